@@ -0,0 +1,814 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Read,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::common::{
+    base::{
+        attributes::Attributes,
+        connection::{CertificateValidation, Credentials, CupsServer},
+        event::EventSender,
+        job::{JobDocument, JobId, JobProgress, JobQuery, JobState, PrinterJob},
+        printer::{
+            DocumentFormat, NewPrinter, PrintOptions, Printer, PrinterCapabilities, PrinterOption, PrinterState,
+            Supply, UnsupportedOption,
+        },
+    },
+    render::text::{render_text, TextOptions},
+    traits::platform::PlatformActions,
+};
+#[cfg(feature = "image")]
+use crate::common::render::image::{render_image, ImagePrintOptions};
+
+const NOT_SUPPORTED: &str = "not supported by the printer backend";
+const BACKEND_TIMEOUT: &str = "the printer backend did not answer in time";
+
+// Most calls of a backend still running after their timeout, the next ones fail at once
+const MAX_WORKERS: usize = 4;
+
+/**
+ * Names of the backends of the system spooler in a Config, cups is libcups and lp the CUPS
+ * command line tools of the backend-lp feature
+ */
+#[cfg(unix)]
+pub const SPOOLER_BACKENDS: [&str; 2] = ["cups", "lp"];
+#[cfg(windows)]
+pub const SPOOLER_BACKENDS: [&str; 1] = ["winspool"];
+
+/**
+ * Backends consulted by the printers api and how long they are waited for, see set_config
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    /**
+     * Names of the enabled backends in the order they are consulted, the ones of
+     * SPOOLER_BACKENDS or of registered backends. None enables the spooler then every
+     * registered backend, in the order they were registered
+     */
+    pub backends: Option<Vec<String>>,
+
+    /**
+     * Longest wait for the printers and the jobs of a backend, the printers of a backend that
+     * did not answer in time are left out of the listing. The call is not interrupted and may
+     * still complete after the timeout was reported, on one of at most four worker threads of
+     * the backend; while all of them are busy the calls fail at once. connect_timeout and
+     * read_timeout end the calls of the cups backend themselves
+     */
+    pub timeouts: BTreeMap<String, Duration>,
+
+    /**
+     * CUPS server used by the cups and lp backends, None for the one of the CUPS client
+     * configuration
+     */
+    pub server: Option<CupsServer>,
+
+    /**
+     * Answer of the cups backend to the password requests of the server, None to only use
+     * the local certificate of the user, as for the requests to the local scheduler
+     */
+    pub credentials: Option<Credentials>,
+
+    /**
+     * Answer the Negotiate challenges of the server with the Kerberos ticket of the user, so
+     * domain joined clients print without a password. The ticket is tried before the
+     * credentials, without credentials nothing is asked on the terminal when it is refused
+     */
+    pub negotiate: bool,
+
+    /**
+     * Check of the certificates of the encrypted connections made by the cups backend
+     */
+    pub certificate_validation: CertificateValidation,
+
+    /**
+     * Longest wait of the cups backend to connect to the server, None for the 30 seconds of
+     * libcups
+     */
+    pub connect_timeout: Option<Duration>,
+
+    /**
+     * Longest wait of the cups backend for an answer of the server once connected, the call
+     * then fails instead of waiting for a queue that stopped answering. None waits as long as
+     * libcups does
+     */
+    pub read_timeout: Option<Duration>,
+}
+
+impl Config {
+    pub fn new() -> Config {
+        return Config::default();
+    }
+
+    /**
+     * Enable only the named backends, consulted in the given order
+     */
+    pub fn backends(mut self, names: &[&str]) -> Self {
+        self.backends = Some(names.iter().map(|n| n.to_string()).collect());
+        return self;
+    }
+
+    /**
+     * Wait at most the given time for the printers and the jobs of the named backend
+     */
+    pub fn timeout(mut self, backend: &str, timeout: Duration) -> Self {
+        self.timeouts.insert(backend.to_string(), timeout);
+        return self;
+    }
+
+    /**
+     * List the printers of the given CUPS server and send the jobs to it
+     */
+    pub fn server(mut self, server: CupsServer) -> Self {
+        self.server = Some(server);
+        return self;
+    }
+
+    /**
+     * Authenticate to the CUPS server with the user name and password
+     */
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        return self;
+    }
+
+    /**
+     * Authenticate with Kerberos (GSSAPI), libcups has to be built with it
+     */
+    pub fn negotiate(mut self) -> Self {
+        self.negotiate = true;
+        return self;
+    }
+
+    /**
+     * Choose how the cups backend checks the certificates of the servers and printers
+     */
+    pub fn certificate_validation(mut self, certificate_validation: CertificateValidation) -> Self {
+        self.certificate_validation = certificate_validation;
+        return self;
+    }
+
+    /**
+     * Give up connecting to the CUPS server after the given time
+     */
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        return self;
+    }
+
+    /**
+     * Fail the calls to the CUPS server that did not get an answer in the given time
+     */
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        return self;
+    }
+}
+
+static CONFIG: RwLock<Config> = RwLock::new(Config {
+    backends: None,
+    timeouts: BTreeMap::new(),
+    server: None,
+    credentials: None,
+    negotiate: false,
+    certificate_validation: CertificateValidation::System,
+    connect_timeout: None,
+    read_timeout: None,
+});
+
+pub(crate) fn set_config(config: Config) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+pub(crate) fn get_config() -> Config {
+    return CONFIG.read().map(|c| c.clone()).unwrap_or_default();
+}
+
+#[cfg(unix)]
+pub(crate) fn cups_server() -> Option<CupsServer> {
+    return CONFIG.read().ok()?.server.clone();
+}
+
+#[cfg(unix)]
+pub(crate) fn credentials() -> Option<Credentials> {
+    return CONFIG.read().ok()?.credentials.clone();
+}
+
+#[cfg(unix)]
+pub(crate) fn negotiate() -> bool {
+    return CONFIG.read().map(|c| c.negotiate).unwrap_or_default();
+}
+
+#[cfg(unix)]
+pub(crate) fn connect_timeout() -> Option<Duration> {
+    return CONFIG.read().ok()?.connect_timeout;
+}
+
+#[cfg(unix)]
+pub(crate) fn read_timeout() -> Option<Duration> {
+    return CONFIG.read().ok()?.read_timeout;
+}
+
+/**
+ * Position of the backend in the configured order, None when it is disabled
+ */
+pub(crate) fn backend_position(name: &str) -> Option<usize> {
+    let config = CONFIG.read().ok()?;
+    return match &config.backends {
+        Some(backends) => backends.iter().position(|b| b == name),
+        None => SPOOLER_BACKENDS.iter().position(|b| *b == name).or(Some(SPOOLER_BACKENDS.len())),
+    };
+}
+
+fn spooler_enabled() -> bool {
+    return SPOOLER_BACKENDS.iter().any(|b| backend_position(b).is_some());
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/**
+ * Threads running the calls of a backend that has a timeout, count includes the busy ones
+ */
+#[derive(Default)]
+struct Workers {
+    idle: Vec<Sender<Task>>,
+    count: usize,
+}
+
+static WORKERS: Mutex<BTreeMap<String, Workers>> = Mutex::new(BTreeMap::new());
+
+/**
+ * Start a thread running the tasks of the backend, it returns itself to the idle workers
+ * after each task
+ */
+fn spawn_worker(backend: String) -> Sender<Task> {
+    let (sender, receiver) = mpsc::channel::<Task>();
+    let worker = sender.clone();
+
+    thread::spawn(move || {
+        for task in receiver {
+            let _ = panic::catch_unwind(AssertUnwindSafe(task));
+            if let Ok(mut workers) = WORKERS.lock() {
+                workers.entry(backend.clone()).or_default().idle.push(worker.clone());
+            }
+        }
+    });
+
+    return sender;
+}
+
+/**
+ * Run the call on a worker of the backend when a timeout is configured for it, returning the
+ * default if it did not finish in time or every worker is still busy with an earlier call.
+ * The call is left running after the timeout and its result is then dropped
+ */
+fn with_timeout<T: Send + 'static>(names: &[&str], default: T, call: impl FnOnce() -> T + Send + 'static) -> T {
+    let timeout = CONFIG
+        .read()
+        .ok()
+        .and_then(|c| names.iter().find_map(|n| c.timeouts.get(*n).map(|t| (n.to_string(), *t))));
+    let Some((backend, timeout)) = timeout else {
+        return call();
+    };
+
+    let worker = {
+        let Ok(mut workers) = WORKERS.lock() else {
+            return default;
+        };
+        let workers = workers.entry(backend.clone()).or_default();
+        match workers.idle.pop() {
+            Some(worker) => worker,
+            None if workers.count < MAX_WORKERS => {
+                workers.count += 1;
+                spawn_worker(backend)
+            }
+            None => return default,
+        }
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let task: Task = Box::new(move || {
+        let _ = sender.send(call());
+    });
+    if worker.send(task).is_err() {
+        return default;
+    }
+    return receiver.recv_timeout(timeout).unwrap_or(default);
+}
+
+/**
+ * Source of printers other than the system spooler, ex a cloud print service or a
+ * proprietary driver. Once registered with register_backend its printers are returned by
+ * get_printers and their print and job methods are handed to it
+ */
+pub trait PrinterBackend: Send + Sync {
+    /**
+     * Name identifying the backend, registering another backend with the same name replaces it
+     */
+    fn name(&self) -> &str;
+
+    /**
+     * Return the printers of the backend, their system names have to be unique among all the
+     * printers of the system
+     */
+    fn printers(&self) -> Vec<Printer>;
+
+    /**
+     * Submit the document read from the reader as a new job of the printer
+     */
+    fn print(
+        &self,
+        printer_name: &str,
+        document: &mut dyn Read,
+        job_name: Option<&str>,
+        options: &PrintOptions,
+    ) -> Result<JobId, &'static str>;
+
+    /**
+     * Return the jobs of the printer matching the query, or why they could not be listed
+     */
+    fn jobs(&self, printer_name: &str, query: &JobQuery) -> Result<Vec<PrinterJob>, &'static str>;
+
+    /**
+     * Cancel the job of the printer
+     */
+    fn cancel_job(&self, printer_name: &str, job_id: JobId) -> Result<(), &'static str>;
+}
+
+struct Registry {
+    backends: Vec<Arc<dyn PrinterBackend>>,
+
+    // Backend name of every printer seen when the backends were listed
+    printers: BTreeMap<String, String>,
+}
+
+static REGISTRY: RwLock<Registry> = RwLock::new(Registry { backends: vec![], printers: BTreeMap::new() });
+
+/**
+ * Add the backend, replacing the one registered with the same name
+ */
+pub(crate) fn register_backend(backend: Arc<dyn PrinterBackend>) {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    let name = backend.name().to_string();
+    registry.backends.retain(|b| b.name() != name);
+    registry.printers.retain(|_, backend_name| *backend_name != name);
+    registry.backends.push(backend);
+}
+
+/**
+ * Remove the backend with the given name, returning false when none was registered
+ */
+pub(crate) fn unregister_backend(name: &str) -> bool {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    let count = registry.backends.len();
+    registry.backends.retain(|b| b.name() != name);
+    registry.printers.retain(|_, backend_name| backend_name != name);
+    return registry.backends.len() != count;
+}
+
+/**
+ * Return the enabled registered backends in the configured order
+ */
+fn backends() -> Vec<Arc<dyn PrinterBackend>> {
+    let mut backends: Vec<(usize, Arc<dyn PrinterBackend>)> = REGISTRY
+        .read()
+        .map(|r| r.backends.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|b| Some((backend_position(b.name())?, b)))
+        .collect();
+
+    backends.sort_by_key(|(position, _)| *position);
+    return backends.into_iter().map(|(_, b)| b).collect();
+}
+
+/**
+ * Position of the spooler among the enabled backends, the one of its first enabled backend
+ */
+fn spooler_position() -> Option<usize> {
+    return SPOOLER_BACKENDS.iter().filter_map(|b| backend_position(b)).min();
+}
+
+fn spooler_printer_by_name(printer_name: &str) -> Option<Printer> {
+    let printer_name = printer_name.to_string();
+    return with_timeout(&SPOOLER_BACKENDS, None, move || crate::Spooler::get_printer_by_name(&printer_name));
+}
+
+/**
+ * Printers of the spooler, None when it did not answer in time
+ */
+fn spooler_printers() -> Option<Vec<Printer>> {
+    if !spooler_enabled() {
+        return Some(vec![]);
+    }
+    return with_timeout(&SPOOLER_BACKENDS, None, || Some(crate::Spooler::get_printers()));
+}
+
+/**
+ * List the printers of the backend and remember it owns them, except the ones with the name
+ * of a printer of a backend consulted before. None when it did not answer in time, the
+ * printers it owned are then kept
+ */
+fn list_printers(backend: &Arc<dyn PrinterBackend>, listed: &[Printer]) -> Option<Vec<Printer>> {
+    let called = backend.clone();
+    let name = backend.name().to_string();
+    let printers: Vec<Printer> = with_timeout(&[&name], None, move || Some(called.printers()))?
+        .into_iter()
+        .filter(|p| !listed.iter().any(|l| l.system_name == p.system_name))
+        .collect();
+
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    registry.printers.retain(|_, backend_name| *backend_name != name);
+    for printer in &printers {
+        registry.printers.insert(printer.system_name.clone(), name.clone());
+    }
+
+    return Some(printers);
+}
+
+/**
+ * List the printers of the enabled backends in their order, with the definition if every
+ * backend answered in time
+ */
+fn collect_printers() -> (Vec<Printer>, bool) {
+    let spooler_position = spooler_position();
+    let mut printers = vec![];
+    let mut spooler_listed = false;
+    let mut answered = true;
+
+    for backend in backends() {
+        if !spooler_listed && spooler_position < backend_position(backend.name()) {
+            let spooler_printers = spooler_printers();
+            answered &= spooler_printers.is_some();
+            printers.extend(spooler_printers.unwrap_or_default());
+            spooler_listed = true;
+        }
+        let backend_printers = list_printers(&backend, &printers);
+        answered &= backend_printers.is_some();
+        printers.extend(backend_printers.unwrap_or_default());
+    }
+
+    if !spooler_listed {
+        let spooler_printers = spooler_printers();
+        answered &= spooler_printers.is_some();
+        let spooler_printers: Vec<Printer> = spooler_printers
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| !printers.iter().any(|l| l.system_name == p.system_name))
+            .collect();
+        printers.extend(spooler_printers);
+    }
+    return (printers, answered);
+}
+
+/**
+ * Return the printers of the enabled backends, or an error when one of them did not answer in
+ * time, so its printers are unknown rather than gone
+ */
+pub(crate) fn get_all_printers() -> Result<Vec<Printer>, &'static str> {
+    let (printers, answered) = collect_printers();
+    return if answered { Ok(printers) } else { Err(BACKEND_TIMEOUT) };
+}
+
+/**
+ * Return the enabled backend the printer was listed from, None for the printers of the spooler
+ */
+fn backend_of(printer_name: &str) -> Option<Arc<dyn PrinterBackend>> {
+    let registry = REGISTRY.read().ok()?;
+    let name = registry.printers.get(printer_name)?;
+    backend_position(name)?;
+    return registry.backends.iter().find(|b| b.name() == name).cloned();
+}
+
+/**
+ * Definition if the spooler of the printer removes the jobs from its listing once they finish,
+ * as the windows spooler does, so a job that is no longer listed has completed. CUPS and the
+ * registered backends keep the finished jobs
+ */
+pub(crate) fn purges_finished_jobs(printer_name: &str) -> bool {
+    return cfg!(windows) && backend_of(printer_name).is_none();
+}
+
+// The printers of the backends only print and list jobs
+macro_rules! spooler_only {
+    ($printer_name:expr, $default:expr) => {
+        if !spooler_enabled() || backend_of($printer_name).is_some() {
+            return $default;
+        }
+    };
+}
+
+/**
+ * Hand the calls for printers of the registered backends to them, and every other call to
+ * the spooler of the platform
+ */
+impl PlatformActions for crate::Platform {
+    fn get_printers() -> Vec<Printer> {
+        return collect_printers().0;
+    }
+
+    fn print(
+        printer_system_name: &str,
+        buffer: &[u8],
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        options.check()?;
+        if let Some(backend) = backend_of(printer_system_name) {
+            let mut buffer = buffer;
+            return backend.print(printer_system_name, &mut buffer, job_name, &options);
+        }
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::print(printer_system_name, buffer, job_name, options);
+    }
+
+    fn print_reader(
+        printer_system_name: &str,
+        reader: &mut dyn Read,
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        options.check()?;
+        if let Some(backend) = backend_of(printer_system_name) {
+            return backend.print(printer_system_name, reader, job_name, &options);
+        }
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::print_reader(printer_system_name, reader, job_name, options);
+    }
+
+    fn print_file(
+        printer_system_name: &str,
+        file_path: &Path,
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        options.check()?;
+        if let Some(backend) = backend_of(printer_system_name) {
+            let mut file = File::open(file_path).map_err(|_| "failed to open the file")?;
+            let job_name = job_name.map(|n| n.to_string()).unwrap_or(file_path.to_string_lossy().to_string());
+            return backend.print(printer_system_name, &mut file, Some(&job_name), &options);
+        }
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::print_file(printer_system_name, file_path, job_name, options);
+    }
+
+    fn print_uri(
+        printer_system_name: &str,
+        uri: &str,
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        options.check()?;
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::print_uri(printer_system_name, uri, job_name, options);
+    }
+
+    fn print_files(
+        printer_system_name: &str,
+        file_paths: &[&Path],
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        options.check()?;
+        if let Some(backend) = backend_of(printer_system_name) {
+            // A job of the backends holds a single document
+            let [file_path] = file_paths else { return Err(NOT_SUPPORTED) };
+            let mut file = File::open(file_path).map_err(|_| "failed to open the file")?;
+            return backend.print(printer_system_name, &mut file, job_name, &options);
+        }
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::print_files(printer_system_name, file_paths, job_name, options);
+    }
+
+    fn print_text(
+        printer_system_name: &str,
+        text: &str,
+        job_name: Option<&str>,
+        text_options: &TextOptions,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        options.check()?;
+        if let Some(backend) = backend_of(printer_system_name) {
+            let document = render_text(text, text_options)?;
+            let mut options = options;
+            options.document_format.get_or_insert(DocumentFormat::Pdf);
+            return backend.print(printer_system_name, &mut &document[..], job_name, &options);
+        }
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::print_text(printer_system_name, text, job_name, text_options, options);
+    }
+
+    #[cfg(feature = "image")]
+    fn print_image(
+        printer_system_name: &str,
+        image: &image::DynamicImage,
+        job_name: Option<&str>,
+        image_options: &ImagePrintOptions,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        options.check()?;
+        if let Some(backend) = backend_of(printer_system_name) {
+            let document = render_image(image, image_options)?;
+            let mut options = options;
+            options.document_format.get_or_insert(DocumentFormat::Pdf);
+            return backend.print(printer_system_name, &mut &document[..], job_name, &options);
+        }
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::print_image(printer_system_name, image, job_name, image_options, options);
+    }
+
+    fn validate_options(printer_system_name: &str, options: &PrintOptions) -> Vec<UnsupportedOption> {
+        spooler_only!(printer_system_name, vec![]);
+        return crate::Spooler::validate_options(printer_system_name, options);
+    }
+
+    fn get_capabilities(printer_system_name: &str) -> Result<PrinterCapabilities, &'static str> {
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::get_capabilities(printer_system_name);
+    }
+
+    fn get_printer_options(printer_system_name: &str) -> Result<Vec<PrinterOption>, &'static str> {
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::get_printer_options(printer_system_name);
+    }
+
+    fn get_default_options(printer_system_name: &str) -> Result<PrintOptions, &'static str> {
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::get_default_options(printer_system_name);
+    }
+
+    fn get_option_conflicts(printer_system_name: &str, options: &PrintOptions, option: &str, value: &str) -> Vec<(String, String)> {
+        spooler_only!(printer_system_name, vec![]);
+        return crate::Spooler::get_option_conflicts(printer_system_name, options, option, value);
+    }
+
+    fn get_supplies(printer_system_name: &str) -> Result<Vec<Supply>, &'static str> {
+        spooler_only!(printer_system_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::get_supplies(printer_system_name);
+    }
+
+    fn probe_device(device_uri: &str) -> Result<Duration, &'static str> {
+        return crate::Spooler::probe_device(device_uri);
+    }
+
+    fn get_printer_jobs(printer_name: &str, query: &JobQuery) -> Result<Vec<PrinterJob>, &'static str> {
+        if let Some(backend) = backend_of(printer_name) {
+            let (name, printer_name, query) = (backend.name().to_string(), printer_name.to_string(), query.clone());
+            return with_timeout(&[&name], Err(BACKEND_TIMEOUT), move || backend.jobs(&printer_name, &query));
+        }
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+
+        let (printer_name, query) = (printer_name.to_string(), query.clone());
+        return with_timeout(&SPOOLER_BACKENDS, Err(BACKEND_TIMEOUT), move || crate::Spooler::get_printer_jobs(&printer_name, &query));
+    }
+
+    fn cancel_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        if let Some(backend) = backend_of(printer_name) {
+            return backend.cancel_job(printer_name, job_id);
+        }
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::cancel_job(printer_name, job_id);
+    }
+
+    fn hold_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::hold_job(printer_name, job_id);
+    }
+
+    fn release_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::release_job(printer_name, job_id);
+    }
+
+    fn restart_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::restart_job(printer_name, job_id);
+    }
+
+    fn move_job(printer_name: &str, job_id: Option<JobId>, target_printer_name: &str) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        spooler_only!(target_printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::move_job(printer_name, job_id, target_printer_name);
+    }
+
+    fn set_job_priority(printer_name: &str, job_id: JobId, priority: u8) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::set_job_priority(printer_name, job_id, priority);
+    }
+
+    fn purge_jobs(printer_name: &str) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::purge_jobs(printer_name);
+    }
+
+    fn pause_printer(printer_name: &str) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::pause_printer(printer_name);
+    }
+
+    fn resume_printer(printer_name: &str) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::resume_printer(printer_name);
+    }
+
+    fn accept_jobs(printer_name: &str) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::accept_jobs(printer_name);
+    }
+
+    fn reject_jobs(printer_name: &str, reason: Option<&str>) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::reject_jobs(printer_name, reason);
+    }
+
+    fn get_job_progress(printer_name: &str, job_id: JobId) -> Result<JobProgress, &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::get_job_progress(printer_name, job_id);
+    }
+
+    fn get_job_attributes(printer_name: &str, job_id: JobId) -> Result<Attributes, &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::get_job_attributes(printer_name, job_id);
+    }
+
+    fn get_job_document(printer_name: &str, job_id: JobId) -> Result<JobDocument, &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::get_job_document(printer_name, job_id);
+    }
+
+    fn get_default_printer() -> Option<Printer> {
+        if !spooler_enabled() {
+            return None;
+        }
+        return crate::Spooler::get_default_printer();
+    }
+
+    fn set_default_printer(printer_name: &str) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::set_default_printer(printer_name);
+    }
+
+    fn set_default_options(printer_name: &str, options: &PrintOptions) -> Result<(), &'static str> {
+        options.check()?;
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::set_default_options(printer_name, options);
+    }
+
+    fn add_printer(printer: &NewPrinter) -> Result<(), &'static str> {
+        if !spooler_enabled() {
+            return Err(NOT_SUPPORTED);
+        }
+        return crate::Spooler::add_printer(printer);
+    }
+
+    fn delete_printer(printer_name: &str) -> Result<(), &'static str> {
+        spooler_only!(printer_name, Err(NOT_SUPPORTED));
+        return crate::Spooler::delete_printer(printer_name);
+    }
+
+    fn get_printer_by_name(printer_name: &str) -> Option<Printer> {
+        if let Some(backend) = backend_of(printer_name) {
+            return list_printers(&backend, &[]).unwrap_or_default().into_iter().find(|p| p.system_name == printer_name);
+        }
+
+        let spooler_position = spooler_position();
+        let mut spooler_asked = spooler_position.is_none();
+        for backend in backends() {
+            if !spooler_asked && spooler_position < backend_position(backend.name()) {
+                if let Some(printer) = spooler_printer_by_name(printer_name) {
+                    return Some(printer);
+                }
+                spooler_asked = true;
+            }
+            let found = list_printers(&backend, &[]).unwrap_or_default().into_iter().find(|p| p.system_name == printer_name || p.name == printer_name);
+            if found.is_some() {
+                return found;
+            }
+        }
+
+        if !spooler_asked {
+            return spooler_printer_by_name(printer_name);
+        }
+        return None;
+    }
+
+    fn watch(sender: EventSender) -> Result<(), &'static str> {
+        return crate::Spooler::watch(sender);
+    }
+
+    fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState {
+        return crate::Spooler::parse_printer_state(platform_state, state_reasons);
+    }
+
+    fn parse_printer_job_state(platform_state: u64) -> JobState {
+        return crate::Spooler::parse_printer_job_state(platform_state);
+    }
+}
@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+
+/**
+ * Value of a raw platform attribute, IPP values without a direct Rust equivalent
+ * (ex dateTime or resolution) are kept as text
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Integer(i32),
+    Boolean(bool),
+    String(String),
+}
+
+/**
+ * Raw platform attributes by name, each attribute can have several values
+ */
+pub type Attributes = HashMap<String, Vec<AttributeValue>>;
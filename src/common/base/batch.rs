@@ -0,0 +1,152 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use super::{
+    job::JobId,
+    printer::{PrintOptions, Printer},
+};
+use crate::common::traits::platform::PlatformActions;
+
+/**
+ * Progress of a batch, the index is the position of the item in the order it was added
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchEvent {
+    /**
+     * The item submission started
+     */
+    Started { index: usize },
+    /**
+     * The item was accepted by the spooler
+     */
+    Submitted { index: usize, job_id: JobId },
+    /**
+     * The item could not be submitted
+     */
+    Failed { index: usize, error: &'static str },
+}
+
+struct BatchItem {
+    printer_system_name: String,
+    file_path: PathBuf,
+    job_name: Option<String>,
+    options: PrintOptions,
+}
+
+/**
+ * Submit many files, each to its own printer with its own options, with bounded concurrency
+ */
+pub struct BatchPrinter {
+    items: Vec<BatchItem>,
+    concurrency: usize,
+}
+
+impl Default for BatchPrinter {
+    fn default() -> Self {
+        return BatchPrinter::new();
+    }
+}
+
+impl BatchPrinter {
+    /**
+     * Create an empty batch submitting up to 4 items at the same time
+     */
+    pub fn new() -> Self {
+        return BatchPrinter {
+            items: vec![],
+            concurrency: 4,
+        };
+    }
+
+    /**
+     * Maximum number of submissions running at the same time (at least 1)
+     */
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        return self;
+    }
+
+    /**
+     * Queue a file to be printed, returning the index reported in the batch events
+     */
+    pub fn add<P: AsRef<Path>>(&mut self, printer: &Printer, file_path: P, job_name: Option<&str>, options: PrintOptions) -> usize {
+        self.items.push(BatchItem {
+            printer_system_name: printer.system_name.clone(),
+            file_path: file_path.as_ref().to_path_buf(),
+            job_name: job_name.map(|n| n.to_string()),
+            options,
+        });
+        return self.items.len() - 1;
+    }
+
+    /**
+     * Number of queued items
+     */
+    pub fn len(&self) -> usize {
+        return self.items.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.items.is_empty();
+    }
+
+    /**
+     * Submit all items, calling on_event from the current thread as each one progresses,
+     * and return the result of every item in the order they were added
+     */
+    pub fn run<F: FnMut(BatchEvent)>(self, mut on_event: F) -> Vec<Result<JobId, &'static str>> {
+        let mut results: Vec<Result<JobId, &'static str>> = vec![Err("not submitted"); self.items.len()];
+        let next = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel::<BatchEvent>();
+        let workers = self.concurrency.min(self.items.len());
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                let sender = sender.clone();
+                let next = &next;
+                let items = &self.items;
+
+                scope.spawn(move || {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::SeqCst);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+
+                        let _ = sender.send(BatchEvent::Started { index });
+                        let result = crate::Platform::print_file(
+                            &item.printer_system_name,
+                            &item.file_path,
+                            item.job_name.as_deref(),
+                            item.options.clone(),
+                        );
+
+                        let _ = sender.send(match result {
+                            Ok(job_id) => BatchEvent::Submitted { index, job_id },
+                            Err(error) => BatchEvent::Failed { index, error },
+                        });
+                    }
+                });
+            }
+
+            // Only the workers keep a sender, so the loop ends once all of them are done
+            drop(sender);
+            for event in receiver {
+                match &event {
+                    BatchEvent::Submitted { index, job_id } => results[*index] = Ok(*job_id),
+                    BatchEvent::Failed { index, error } => results[*index] = Err(*error),
+                    BatchEvent::Started { .. } => {}
+                }
+                on_event(event);
+            }
+        });
+
+        return results;
+    }
+}
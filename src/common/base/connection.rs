@@ -0,0 +1,126 @@
+use std::fmt::{Debug, Error, Formatter};
+
+/**
+ * When the connections to the CUPS server are encrypted with TLS
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Encryption {
+    /**
+     * Upgrade to TLS when the server asks for it (CUPS default)
+     */
+    #[default]
+    IfRequested,
+    Never,
+    /**
+     * Upgrade to TLS with an HTTP Upgrade request, failing when the server cannot
+     */
+    Required,
+    /**
+     * Start the connection with TLS, as for ipps and https uris
+     */
+    Always,
+}
+
+impl Encryption {
+    /**
+     * Return the value of the CUPS_ENCRYPTION environment variable and client.conf
+     */
+    pub fn name(&self) -> &str {
+        return match self {
+            Encryption::IfRequested => "IfRequested",
+            Encryption::Never => "Never",
+            Encryption::Required => "Required",
+            Encryption::Always => "Always",
+        };
+    }
+}
+
+/**
+ * CUPS server the printers are listed from and the jobs sent to, instead of the one of
+ * client.conf or the CUPS_SERVER environment variable (the local scheduler by default)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct CupsServer {
+    /**
+     * Host name or address of the server, or the path of its domain socket
+     */
+    pub host: String,
+    pub port: u16,
+    pub encryption: Encryption,
+}
+
+impl CupsServer {
+    /**
+     * Server on the default IPP port 631, encrypted when it asks for it
+     */
+    pub fn new(host: &str) -> CupsServer {
+        return CupsServer {
+            host: host.to_string(),
+            port: 631,
+            encryption: Encryption::IfRequested,
+        };
+    }
+
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        return self;
+    }
+
+    pub fn encryption(mut self, encryption: Encryption) -> Self {
+        self.encryption = encryption;
+        return self;
+    }
+
+    /**
+     * Return the server as given to cupsSetServer and in CUPS_SERVER, ex cups.local:631,
+     * [fd00::1]:631 or /run/cups/cups.sock
+     */
+    pub fn address(&self) -> String {
+        if self.host.starts_with('/') {
+            return self.host.clone();
+        }
+        if self.host.contains(':') && !self.host.starts_with('[') {
+            return format!("[{}]:{}", self.host, self.port);
+        }
+        return format!("{}:{}", self.host, self.port);
+    }
+}
+
+/**
+ * User name and password answering the authentication requests of the CUPS server, ex for
+ * Get-Jobs and Print-Job on a server with AuthType Basic
+ */
+#[derive(Clone, PartialEq)]
+pub struct Credentials {
+    pub user_name: String,
+    pub password: String,
+}
+
+impl Credentials {
+    pub fn new(user_name: &str, password: &str) -> Credentials {
+        return Credentials {
+            user_name: user_name.to_string(),
+            password: password.to_string(),
+        };
+    }
+}
+
+// The password is left out of the logs
+impl Debug for Credentials {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        return write!(fmt, "Credentials {{ user_name: {:?}, password: \"***\" }}", self.user_name);
+    }
+}
+
+/**
+ * How the certificates of the TLS connections of the cups backend are checked
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CertificateValidation {
+    /**
+     * Let libcups check them against the trusted roots of the system, a server whose certificate
+     * is not signed by one of them is refused
+     */
+    #[default]
+    System,
+}
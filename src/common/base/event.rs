@@ -0,0 +1,419 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::common::{
+    backend::{get_all_printers, purges_finished_jobs},
+    base::{
+        job::{JobId, JobQuery, JobState, PrinterJob, WhichJobs},
+        printer::{Printer, PrinterState},
+    },
+    traits::platform::PlatformActions,
+};
+
+// How often poll_changes compares the printers and jobs
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+// How long watch_job waits for an event before checking the job anyway
+const JOB_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/**
+ * Change of a printer or of one of its jobs, reported by watch
+ */
+#[derive(Debug, Clone)]
+pub enum PrinterEvent {
+    PrinterAdded {
+        printer_name: String,
+    },
+
+    PrinterRemoved {
+        printer_name: String,
+    },
+
+    /**
+     * State, state reasons or configuration of the printer changed
+     */
+    PrinterStateChanged {
+        printer_name: String,
+        state: PrinterState,
+        state_reasons: Vec<String>,
+    },
+
+    /**
+     * Job created, progressing or finished, the state is the one after the change
+     */
+    JobStateChanged {
+        printer_name: String,
+        job_id: JobId,
+        state: JobState,
+    },
+}
+
+/**
+ * Receiving end of a watch, the platform stops listening once it is dropped
+ */
+pub struct EventStream {
+    receiver: Receiver<PrinterEvent>,
+    running: Arc<AtomicBool>,
+}
+
+/**
+ * State a watched job moved to and when it was seen
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobTransition {
+    pub state: JobState,
+    pub at: SystemTime,
+}
+
+/**
+ * Transitions of a job returned by Printer::watch_job, starting with its current state and
+ * ending after a final one (canceled, aborted or completed), or without one when the job is
+ * removed from a spooler that keeps finished jobs
+ */
+pub struct JobWatch {
+    receiver: Receiver<JobTransition>,
+    running: Arc<AtomicBool>,
+}
+
+/**
+ * Sending end given to the listener producing the events
+ */
+pub struct EventSender {
+    sender: Sender<PrinterEvent>,
+    running: Arc<AtomicBool>,
+}
+
+impl EventStream {
+    /**
+     * Create a stream and the sender a listener delivers its events with
+     */
+    pub fn new() -> (EventStream, EventSender) {
+        let (sender, receiver) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+
+        return (
+            EventStream { receiver, running: running.clone() },
+            EventSender { sender, running },
+        );
+    }
+
+    /**
+     * Wait for the next event, None once the listener stopped (ex the spooler went away)
+     */
+    pub fn recv(&self) -> Option<PrinterEvent> {
+        return self.receiver.recv().ok();
+    }
+
+    /**
+     * Wait for the next event up to the timeout
+     */
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<PrinterEvent, RecvTimeoutError> {
+        return self.receiver.recv_timeout(timeout);
+    }
+
+    /**
+     * Return the next event if one was already received
+     */
+    pub fn try_recv(&self) -> Result<PrinterEvent, TryRecvError> {
+        return self.receiver.try_recv();
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = PrinterEvent;
+
+    fn next(&mut self) -> Option<PrinterEvent> {
+        return self.recv();
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl JobWatch {
+    /**
+     * Wait for the next transition, None after the final one
+     */
+    pub fn recv(&self) -> Option<JobTransition> {
+        return self.receiver.recv().ok();
+    }
+
+    /**
+     * Wait for the next transition up to the timeout
+     */
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<JobTransition, RecvTimeoutError> {
+        return self.receiver.recv_timeout(timeout);
+    }
+}
+
+impl Iterator for JobWatch {
+    type Item = JobTransition;
+
+    fn next(&mut self) -> Option<JobTransition> {
+        return self.recv();
+    }
+}
+
+impl Drop for JobWatch {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+impl EventSender {
+    /**
+     * Definition if the stream is still alive, listeners check it between two waits
+     */
+    pub fn is_running(&self) -> bool {
+        return self.running.load(Ordering::Relaxed);
+    }
+
+    /**
+     * Deliver the event, false when the stream was dropped
+     */
+    pub fn send(&self, event: PrinterEvent) -> bool {
+        return self.sender.send(event).is_ok();
+    }
+}
+
+/**
+ * Last known printers and jobs, compared with the current ones by listeners that are only told
+ * that something changed
+ */
+#[derive(Default)]
+pub(crate) struct Snapshot {
+    printers: HashMap<String, (PrinterState, Vec<String>)>,
+    jobs: HashMap<(String, JobId), JobState>,
+}
+
+impl Snapshot {
+    /**
+     * Compare the printers and, when asked, their active jobs with the snapshot. Nothing is
+     * compared when a backend did not answer in time, and the jobs are left as they were when
+     * one of the printers could not list them
+     */
+    pub fn refresh(&mut self, with_jobs: bool) -> Vec<PrinterEvent> {
+        // Its printers would look removed, then added again on the next refresh
+        let Ok(printers) = get_all_printers() else {
+            return vec![];
+        };
+        let mut events = self.printers_changed(&printers);
+
+        if with_jobs {
+            let query = JobQuery::from(WhichJobs::Active);
+            let jobs: Result<Vec<Vec<PrinterJob>>, _> = printers
+                .iter()
+                .map(|p| crate::Platform::get_printer_jobs(&p.system_name, &query))
+                .collect();
+
+            // A failed listing would look like every known job finished, compare on the next refresh
+            if let Ok(jobs) = jobs {
+                events.extend(self.jobs_changed(&jobs.concat()));
+            }
+        }
+
+        return events;
+    }
+
+    /**
+     * Replace the known printers and return the differences as events
+     */
+    pub fn printers_changed(&mut self, printers: &[Printer]) -> Vec<PrinterEvent> {
+        let mut events = vec![];
+        let mut current = HashMap::new();
+
+        for printer in printers {
+            let state = (printer.state.clone(), printer.state_reasons.clone());
+            match self.printers.get(&printer.system_name) {
+                None => events.push(PrinterEvent::PrinterAdded { printer_name: printer.system_name.clone() }),
+                Some(known) if *known != state => events.push(PrinterEvent::PrinterStateChanged {
+                    printer_name: printer.system_name.clone(),
+                    state: state.0.clone(),
+                    state_reasons: state.1.clone(),
+                }),
+                Some(_) => {}
+            }
+            current.insert(printer.system_name.clone(), state);
+        }
+
+        for printer_name in self.printers.keys().filter(|name| !current.contains_key(*name)) {
+            events.push(PrinterEvent::PrinterRemoved { printer_name: printer_name.clone() });
+        }
+
+        self.printers = current;
+        return events;
+    }
+
+    /**
+     * Replace the known jobs with the active ones and return the differences as events, jobs
+     * that left the queue without being seen in a final state are reported as completed
+     */
+    pub fn jobs_changed(&mut self, jobs: &[PrinterJob]) -> Vec<PrinterEvent> {
+        let mut events = vec![];
+        let mut current = HashMap::new();
+
+        for job in jobs {
+            let key = (job.printer_name.clone(), job.id);
+            if self.jobs.get(&key) != Some(&job.state) {
+                events.push(PrinterEvent::JobStateChanged {
+                    printer_name: job.printer_name.clone(),
+                    job_id: job.id,
+                    state: job.state.clone(),
+                });
+            }
+            current.insert(key, job.state.clone());
+        }
+
+        for ((printer_name, job_id), state) in &self.jobs {
+            if !current.contains_key(&(printer_name.clone(), *job_id)) && !state.is_terminal() {
+                events.push(PrinterEvent::JobStateChanged {
+                    printer_name: printer_name.clone(),
+                    job_id: *job_id,
+                    state: JobState::COMPLETED,
+                });
+            }
+        }
+
+        self.jobs = current;
+        return events;
+    }
+}
+
+/**
+ * Compare the printer list on an interval and report the differences as events, so every
+ * application does not have to diff get_printers itself
+ */
+pub struct PrinterWatcher {
+    /**
+     * Time between two comparisons
+     */
+    pub interval: Duration,
+
+    /**
+     * Definition if the active jobs are compared too, reported as JobStateChanged (default is false)
+     */
+    pub with_jobs: bool,
+
+    snapshot: Snapshot,
+}
+
+impl PrinterWatcher {
+    pub fn new(interval: Duration) -> PrinterWatcher {
+        return PrinterWatcher { interval, with_jobs: false, snapshot: Snapshot::default() };
+    }
+
+    /**
+     * Compare the printers with the previous call, the first call reports every printer as added
+     */
+    pub fn poll(&mut self) -> Vec<PrinterEvent> {
+        return self.snapshot.refresh(self.with_jobs);
+    }
+
+    /**
+     * Poll on a thread until the stream is dropped, the printers present when the watcher
+     * starts are not reported
+     */
+    pub fn start(self) -> EventStream {
+        let (stream, sender) = EventStream::new();
+        self.run(sender);
+        return stream;
+    }
+
+    fn run(mut self, sender: EventSender) {
+        thread::spawn(move || {
+            self.poll();
+
+            while sender.is_running() {
+                thread::sleep(self.interval);
+                if !self.poll().into_iter().all(|event| sender.send(event)) {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/**
+ * Compare the printers and jobs on an interval and deliver the changes until the stream is
+ * dropped, used when the platform notifications are not available
+ */
+pub(crate) fn poll_changes(sender: EventSender) {
+    let mut watcher = PrinterWatcher::new(POLL_INTERVAL);
+    watcher.with_jobs = true;
+    watcher.run(sender);
+}
+
+/**
+ * Current state of the job, None when the job is no longer listed, or why the jobs could not
+ * be listed
+ */
+fn job_state(printer_name: &str, job_id: JobId) -> Result<Option<JobState>, &'static str> {
+    return Ok(crate::Platform::get_printer_jobs(printer_name, &JobQuery::from(WhichJobs::All))?
+        .into_iter()
+        .find(|j| j.id == job_id)
+        .map(|j| j.state));
+}
+
+/**
+ * Follow the state of the job, checking it when watch reports a change of the job and at least
+ * every two seconds in case an event was missed. A failed listing is retried on the next check,
+ * a job that disappeared is COMPLETED where the spooler purges finished jobs (windows) and ends
+ * the watch elsewhere
+ */
+pub(crate) fn watch_job(printer_name: &str, job_id: JobId) -> Result<JobWatch, &'static str> {
+    let mut last_state = job_state(printer_name, job_id)?.ok_or("job not found")?;
+    let purges_finished_jobs = purges_finished_jobs(printer_name);
+
+    let (sender, receiver) = mpsc::channel();
+    let running = Arc::new(AtomicBool::new(true));
+    let watch = JobWatch { receiver, running: running.clone() };
+
+    sender.send(JobTransition { state: last_state.clone(), at: SystemTime::now() }).unwrap_or_default();
+    if last_state.is_terminal() {
+        return Ok(watch);
+    }
+
+    let events = crate::watch()?;
+    let printer_name = printer_name.to_string();
+
+    thread::spawn(move || {
+        while running.load(Ordering::Relaxed) {
+            match events.recv_timeout(JOB_CHECK_INTERVAL) {
+                Ok(PrinterEvent::JobStateChanged { printer_name: name, job_id: id, .. })
+                    if name == printer_name && id == job_id => {}
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => thread::sleep(JOB_CHECK_INTERVAL),
+            }
+
+            let state = match job_state(&printer_name, job_id) {
+                Ok(Some(state)) => state,
+                Ok(None) if purges_finished_jobs => JobState::COMPLETED,
+                Ok(None) => break,
+                Err(_) => continue,
+            };
+            if state == last_state {
+                continue;
+            }
+
+            let is_terminal = state.is_terminal();
+            if sender.send(JobTransition { state: state.clone(), at: SystemTime::now() }).is_err() || is_terminal {
+                break;
+            }
+            last_state = state;
+        }
+    });
+
+    return Ok(watch);
+}
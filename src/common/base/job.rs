@@ -1,25 +1,51 @@
 use std::{
     fmt::{Debug, Error, Formatter},
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 use crate::common::traits::platform::{PlatformActions, PlatformPrinterJobGetters};
 
+/**
+ * Identifier of a print job, as returned by the platform spooler
+ */
+pub type JobId = u64;
+
+/**
+ * State of a job, mapped from the IPP job-state on CUPS and the job status flags on Windows
+ */
 #[derive(Debug, Clone, PartialEq)]
-pub enum PrinterJobState {
+pub enum JobState {
+    /**
+     * Waiting in the queue
+     */
     PENDING,
-    PAUSED,
+    /**
+     * Held until released, ex by hold_job or job-hold-until
+     */
+    HELD,
     PROCESSING,
-    CANCELLED,
+    /**
+     * Processing stopped, ex by a paper jam or an offline printer
+     */
+    STOPPED,
+    CANCELED,
+    /**
+     * Stopped by the system because of an error
+     */
+    ABORTED,
     COMPLETED,
     UNKNOWN,
 }
 
+/**
+ * Print job copied from the platform spooler, it owns all its data so it can be sent to other threads
+ */
+#[derive(Clone)]
 pub struct PrinterJob {
     /**
      * Job ID
      */
-    pub id: u64,
+    pub id: JobId,
     /**
      * Visual name/title of job
      */
@@ -27,11 +53,28 @@ pub struct PrinterJob {
     /**
      * Job Status, indicates how the job is currently
      */
-    pub state: PrinterJobState,
+    pub state: JobState,
+    /**
+     * Platform state value the state was mapped from, useful for debugging
+     */
+    pub raw_state: u64,
+    /**
+     * Why the job is in its state, ex media-empty or account-limit-reached on CUPS, and the
+     * JOB_STATUS flags (ex paper-out) plus the driver status text on Windows
+     */
+    pub state_reasons: Vec<String>,
     /**
      * Indicates the job file type, ex application/pdf
      */
     pub media_type: String,
+    /**
+     * Pages (impressions) already printed, when reported by the spooler
+     */
+    pub pages_completed: Option<u32>,
+    /**
+     * Total pages (impressions) of the job, when known
+     */
+    pub pages_total: Option<u32>,
     /**
      * Date when job was created
      */
@@ -48,21 +91,242 @@ pub struct PrinterJob {
      * Name of printer
      */
     pub printer_name: String,
+    /**
+     * Name of the user that submitted the job
+     */
+    pub user_name: String,
+}
+
+/**
+ * Which jobs of a printer are listed
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhichJobs {
+    /**
+     * Jobs still in the queue (pending, held, processing or stopped)
+     */
+    Active,
+    /**
+     * Finished jobs kept in the history (canceled, aborted or completed)
+     */
+    Completed,
+    All,
+}
+
+/**
+ * Whose jobs are listed
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobOwner {
+    Any,
+    /**
+     * Jobs submitted by the user running the process
+     */
+    CurrentUser,
+    /**
+     * Jobs submitted by the named user
+     */
+    User(String),
+}
+
+/**
+ * Filters of a job listing, see Printer::query_jobs
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobQuery {
+    pub which_jobs: WhichJobs,
+    pub owner: JobOwner,
+    /**
+     * Maximum number of jobs returned
+     */
+    pub limit: Option<u32>,
+    /**
+     * Number of matching jobs skipped before the first returned one
+     */
+    pub offset: Option<u32>,
+}
+
+impl Default for JobQuery {
+    fn default() -> Self {
+        return JobQuery::new(WhichJobs::All);
+    }
+}
+
+impl From<WhichJobs> for JobQuery {
+    fn from(which_jobs: WhichJobs) -> Self {
+        return JobQuery::new(which_jobs);
+    }
+}
+
+impl JobQuery {
+    /**
+     * Query the selected jobs of any user
+     */
+    pub fn new(which_jobs: WhichJobs) -> Self {
+        return JobQuery {
+            which_jobs,
+            owner: JobOwner::Any,
+            limit: None,
+            offset: None,
+        };
+    }
+
+    /**
+     * Only list jobs of the user running the process
+     */
+    pub fn current_user(mut self) -> Self {
+        self.owner = JobOwner::CurrentUser;
+        return self;
+    }
+
+    /**
+     * Only list jobs of the named user
+     */
+    pub fn user(mut self, user_name: &str) -> Self {
+        self.owner = JobOwner::User(user_name.to_string());
+        return self;
+    }
+
+    /**
+     * Return at most limit jobs, to fetch a long queue incrementally
+     */
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        return self;
+    }
+
+    /**
+     * Skip the first offset matching jobs
+     */
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = Some(offset);
+        return self;
+    }
+}
+
+/**
+ * Document of a job read back from the spooler
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobDocument {
+    /**
+     * Name of the document, when reported
+     */
+    pub name: String,
+    /**
+     * Format of the data, ex application/pdf on CUPS or RAW on Windows
+     */
+    pub format: String,
+    pub data: Vec<u8>,
+}
+
+/**
+ * Printing progress of a job, counters the printer does not report are None
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct JobProgress {
+    /**
+     * Pages (impressions) already printed
+     */
+    pub pages_completed: Option<u32>,
+    /**
+     * Total pages (impressions) of the job
+     */
+    pub pages_total: Option<u32>,
+    /**
+     * Sheets of media already printed (CUPS only)
+     */
+    pub sheets_completed: Option<u32>,
+    /**
+     * Total sheets of media of the job (CUPS only)
+     */
+    pub sheets_total: Option<u32>,
+}
+
+/**
+ * Final state of a job returned by Printer::wait_for_job
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobCompletion {
+    /**
+     * Terminal state of the job, COMPLETED when the Windows spooler removed the job from its queue
+     */
+    pub state: JobState,
+    /**
+     * State reasons of the last observed state of the job, ex job-completed-with-errors or
+     * document-format-error on CUPS and the JOB_STATUS flags (ex error) on Windows
+     */
+    pub state_reasons: Vec<String>,
+    /**
+     * Date when job was processed or started printing, when still known
+     */
+    pub processed_at: Option<SystemTime>,
+    /**
+     * Date when job was completed, when still known
+     */
+    pub completed_at: Option<SystemTime>,
+    /**
+     * Time spent waiting for the job
+     */
+    pub waited: Duration,
 }
 
 impl PrinterJob {
+    /**
+     * Cancel the job if it has not finished yet
+     */
+    pub fn cancel(&self) -> Result<(), &'static str> {
+        return crate::Platform::cancel_job(self.printer_name.as_str(), self.id);
+    }
+
+    /**
+     * Keep the job from printing until it is released
+     */
+    pub fn hold(&self) -> Result<(), &'static str> {
+        return crate::Platform::hold_job(self.printer_name.as_str(), self.id);
+    }
+
+    /**
+     * Let an held job print
+     */
+    pub fn release(&self) -> Result<(), &'static str> {
+        return crate::Platform::release_job(self.printer_name.as_str(), self.id);
+    }
+
+    /**
+     * Query the job again and update self with its current state
+     */
+    pub fn refresh(&mut self) -> Result<(), &'static str> {
+        let job = crate::Platform::get_printer_jobs(self.printer_name.as_str(), &JobQuery::default())?
+            .into_iter()
+            .find(|j| j.id == self.id);
+
+        return match job {
+            Some(job) => {
+                *self = job;
+                Ok(())
+            }
+            None => Err("job not found"),
+        };
+    }
+
     pub(crate) fn from_platform_printer_job_getters(
         platform_printer_job: &dyn PlatformPrinterJobGetters,
     ) -> Self {
         return PrinterJob {
             id: platform_printer_job.get_id(),
             name: platform_printer_job.get_name(),
-            state: PrinterJobState::from_platform_state(platform_printer_job.get_state()),
+            state: JobState::from_platform_state(platform_printer_job.get_state()),
+            raw_state: platform_printer_job.get_state(),
+            state_reasons: platform_printer_job.get_state_reasons(),
             media_type: platform_printer_job.get_media_type(),
+            pages_completed: platform_printer_job.get_pages_completed(),
+            pages_total: platform_printer_job.get_pages_total(),
             created_at: platform_printer_job.get_created_at(),
             processed_at: platform_printer_job.get_processed_at(),
             completed_at: platform_printer_job.get_completed_at(),
             printer_name: platform_printer_job.get_printer(),
+            user_name: platform_printer_job.get_user(),
         };
     }
 }
@@ -75,25 +339,52 @@ impl Debug for PrinterJob {
                 \r  id: {:?},
                 \r  name: {:?},
                 \r  state: {:?},
+                \r  raw_state: {:?},
+                \r  state_reasons: {:?},
                 \r  media_type: {:?},
+                \r  pages_completed: {:?},
+                \r  pages_total: {:?},
                 \r  created_at: {:?},
                 \r  processed_at: {:?},
                 \r  completed_at: {:?},
                 \r  printer_name: {:?},
+                \r  user_name: {:?},
             \r}}",
             self.id,
             self.name,
             self.state,
+            self.raw_state,
+            self.state_reasons,
             self.media_type,
+            self.pages_completed,
+            self.pages_total,
             self.created_at,
             self.processed_at,
             self.completed_at,
             self.printer_name,
+            self.user_name,
         )
     }
 }
 
-impl PrinterJobState {
+impl JobState {
+    /**
+     * Definition if the job will not change state anymore
+     */
+    pub fn is_terminal(&self) -> bool {
+        return *self == JobState::CANCELED || *self == JobState::ABORTED || *self == JobState::COMPLETED;
+    }
+
+    /**
+     * Definition if the job is still in the queue
+     */
+    pub fn is_active(&self) -> bool {
+        return *self == JobState::PENDING
+            || *self == JobState::HELD
+            || *self == JobState::PROCESSING
+            || *self == JobState::STOPPED;
+    }
+
     pub(crate) fn from_platform_state(platform_state: u64) -> Self {
         return crate::Platform::parse_printer_job_state(platform_state);
     }
@@ -1,2 +1,6 @@
+pub mod attributes;
+pub mod batch;
+pub mod connection;
+pub mod event;
 pub mod job;
 pub mod printer;
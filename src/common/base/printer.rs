@@ -1,10 +1,33 @@
 
-use std::fmt::{Debug, Error, Formatter};
+use std::{
+    fmt::{Debug, Display, Error, Formatter},
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
-use super::job::PrinterJob;
+use super::attributes::Attributes;
+use super::event::{watch_job, JobWatch};
+use super::job::{JobCompletion, JobDocument, JobId, JobProgress, JobQuery, JobState, PrinterJob, WhichJobs};
+use crate::common::backend::purges_finished_jobs;
+use crate::common::render::test_page::render_test_page;
+use crate::common::render::text::TextOptions;
+#[cfg(feature = "preflight")]
+use crate::common::preflight::{preflight_pdf, PreflightReport};
+#[cfg(feature = "snmp")]
+use crate::common::snmp::{query_device, DeviceStatus};
+#[cfg(feature = "image")]
+use crate::common::render::image::ImagePrintOptions;
 use crate::common::traits::platform::{PlatformActions, PlatformPrinterGetters};
 
-#[derive(Debug, Clone)]
+const PRIORITY_OUT_OF_RANGE: &str = "priority must be between 1 and 100";
+
+/**
+ * State of the printer, READY, PRINTING and PAUSED match the idle, processing and stopped ipp states
+ */
+#[derive(Debug, Clone, PartialEq)]
 pub enum PrinterState {
     READY,
     OFFLINE,
@@ -13,17 +36,1196 @@ pub enum PrinterState {
     UNKNOWN,
 }
 
-#[derive(PartialEq)]
-pub enum PrintOrientation {
-    Portrait,
-    Landscape
+/**
+ * Kind of the queue behind a printer, applications can use it to prefer local devices
+ * over the ones shared by other hosts or discovered on the network
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrinterKind {
+    /**
+     * Queue created on this host for a device
+     */
+    Local,
+
+    /**
+     * Queue of another host, ex a printer shared by another CUPS server or a windows network printer
+     */
+    RemoteQueue,
+
+    /**
+     * CUPS class grouping other printers
+     */
+    Class,
+
+    /**
+     * Queue created on demand for a discovered printer, by CUPS or cups-browsed
+     */
+    Temporary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrintOrientation {
+    Portrait,
+    Landscape
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Duplex {
+    OneSided,
+    TwoSidedLongEdge,
+    TwoSidedShortEdge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaSize {
+    A3,
+    A4,
+    A5,
+    A6,
+    Letter,
+    Legal,
+    Executive,
+    Tabloid,
+    Envelope10,
+    EnvelopeDL,
+    /**
+     * Media name as known by the platform, ex "na_index-4x6_4x6in" on CUPS or a form name on Windows
+     */
+    Custom(String),
+    /**
+     * Arbitrary page dimensions, ex for receipt or banner media
+     */
+    CustomSize { width: Dimension, height: Dimension },
+}
+
+// Media sizes with a fixed name on every platform, used to map the platform names back
+pub(crate) const STANDARD_MEDIA_SIZES: [MediaSize; 10] = [
+    MediaSize::A3,
+    MediaSize::A4,
+    MediaSize::A5,
+    MediaSize::A6,
+    MediaSize::Letter,
+    MediaSize::Legal,
+    MediaSize::Executive,
+    MediaSize::Tabloid,
+    MediaSize::Envelope10,
+    MediaSize::EnvelopeDL,
+];
+
+impl MediaSize {
+    /**
+     * Return the (width, height) of the media, None for media names unknown to this crate
+     */
+    pub fn dimensions(&self) -> Option<(Dimension, Dimension)> {
+        let mm = |w: f32, h: f32| Some((Dimension::Millimeters(w), Dimension::Millimeters(h)));
+        let inches = |w: f32, h: f32| Some((Dimension::Inches(w), Dimension::Inches(h)));
+        return match self {
+            MediaSize::A3 => mm(297.0, 420.0),
+            MediaSize::A4 => mm(210.0, 297.0),
+            MediaSize::A5 => mm(148.0, 210.0),
+            MediaSize::A6 => mm(105.0, 148.0),
+            MediaSize::Letter => inches(8.5, 11.0),
+            MediaSize::Legal => inches(8.5, 14.0),
+            MediaSize::Executive => inches(7.25, 10.5),
+            MediaSize::Tabloid => inches(11.0, 17.0),
+            MediaSize::Envelope10 => inches(4.125, 9.5),
+            MediaSize::EnvelopeDL => mm(110.0, 220.0),
+            MediaSize::CustomSize { width, height } => Some((*width, *height)),
+            MediaSize::Custom(_) => None,
+        };
+    }
+
+    /**
+     * Return the PWG 5101.1 self describing media name, ex iso_a4_210x297mm
+     */
+    pub fn pwg_name(&self) -> String {
+        return match self {
+            MediaSize::A3 => "iso_a3_297x420mm".to_string(),
+            MediaSize::A4 => "iso_a4_210x297mm".to_string(),
+            MediaSize::A5 => "iso_a5_148x210mm".to_string(),
+            MediaSize::A6 => "iso_a6_105x148mm".to_string(),
+            MediaSize::Letter => "na_letter_8.5x11in".to_string(),
+            MediaSize::Legal => "na_legal_8.5x14in".to_string(),
+            MediaSize::Executive => "na_executive_7.25x10.5in".to_string(),
+            MediaSize::Tabloid => "na_ledger_11x17in".to_string(),
+            MediaSize::Envelope10 => "na_number-10_4.125x9.5in".to_string(),
+            MediaSize::EnvelopeDL => "iso_dl_110x220mm".to_string(),
+            MediaSize::Custom(name) => name.clone(),
+            // Custom.WIDTHxLENGTH, keeping the unit when both dimensions share it, otherwise in points
+            MediaSize::CustomSize { width, height } => match (width, height) {
+                (Dimension::Millimeters(w), Dimension::Millimeters(h)) => format!("Custom.{}x{}mm", w, h),
+                (Dimension::Inches(w), Dimension::Inches(h)) => format!("Custom.{}x{}in", w, h),
+                _ => format!("Custom.{}x{}", width.to_points().round(), height.to_points().round()),
+            },
+        };
+    }
+}
+
+/**
+ * A length with its unit
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dimension {
+    Millimeters(f32),
+    Inches(f32),
+    Points(f32),
+}
+
+impl Dimension {
+    pub fn to_points(&self) -> f32 {
+        return match self {
+            Dimension::Millimeters(value) => value * 72.0 / 25.4,
+            Dimension::Inches(value) => value * 72.0,
+            Dimension::Points(value) => *value,
+        };
+    }
+
+    pub fn to_millimeters(&self) -> f32 {
+        return match self {
+            Dimension::Millimeters(value) => *value,
+            Dimension::Inches(value) => value * 25.4,
+            Dimension::Points(value) => value * 25.4 / 72.0,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorMode {
+    Color,
+    Monochrome,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrintQuality {
+    Draft,
+    Normal,
+    High,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputOrder {
+    Normal,
+    Reverse,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaSource {
+    Auto,
+    Main,
+    Manual,
+    Tray1,
+    Tray2,
+    Tray3,
+    Envelope,
+    LargeCapacity,
+    /**
+     * Source name as known by the printer, ex "Tray4" on CUPS or a driver bin number on Windows
+     */
+    Custom(String),
+}
+
+impl MediaSource {
+    /**
+     * Return the IPP media-source keyword
+     */
+    pub fn ipp_keyword(&self) -> &str {
+        return match self {
+            MediaSource::Auto => "auto",
+            MediaSource::Main => "main",
+            MediaSource::Manual => "manual",
+            MediaSource::Tray1 => "tray-1",
+            MediaSource::Tray2 => "tray-2",
+            MediaSource::Tray3 => "tray-3",
+            MediaSource::Envelope => "envelope",
+            MediaSource::LargeCapacity => "large-capacity",
+            MediaSource::Custom(name) => name.as_str(),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputBin {
+    Auto,
+    Top,
+    Middle,
+    Bottom,
+    Side,
+    Left,
+    Right,
+    Center,
+    Rear,
+    FaceUp,
+    FaceDown,
+    LargeCapacity,
+    Stacker(u8),
+    Mailbox(u8),
+    Tray(u8),
+    /**
+     * IPP output-bin keyword as reported by the printer
+     */
+    Custom(String),
+}
+
+impl OutputBin {
+    /**
+     * Return the IPP output-bin keyword (PWG 5100.2)
+     */
+    pub fn ipp_keyword(&self) -> String {
+        return match self {
+            OutputBin::Auto => "auto".to_string(),
+            OutputBin::Top => "top".to_string(),
+            OutputBin::Middle => "middle".to_string(),
+            OutputBin::Bottom => "bottom".to_string(),
+            OutputBin::Side => "side".to_string(),
+            OutputBin::Left => "left".to_string(),
+            OutputBin::Right => "right".to_string(),
+            OutputBin::Center => "center".to_string(),
+            OutputBin::Rear => "rear".to_string(),
+            OutputBin::FaceUp => "face-up".to_string(),
+            OutputBin::FaceDown => "face-down".to_string(),
+            OutputBin::LargeCapacity => "large-capacity".to_string(),
+            OutputBin::Stacker(n) => format!("stacker-{}", n),
+            OutputBin::Mailbox(n) => format!("mailbox-{}", n),
+            OutputBin::Tray(n) => format!("tray-{}", n),
+            OutputBin::Custom(keyword) => keyword.clone(),
+        };
+    }
+}
+
+/**
+ * CUPS banner pages, the names match the files in the CUPS banners directory
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobSheet {
+    None,
+    Standard,
+    Classified,
+    Confidential,
+    Secret,
+    TopSecret,
+    Unclassified,
+    Custom(String),
+}
+
+impl JobSheet {
+    fn name(&self) -> &str {
+        return match self {
+            JobSheet::None => "none",
+            JobSheet::Standard => "standard",
+            JobSheet::Classified => "classified",
+            JobSheet::Confidential => "confidential",
+            JobSheet::Secret => "secret",
+            JobSheet::TopSecret => "topsecret",
+            JobSheet::Unclassified => "unclassified",
+            JobSheet::Custom(name) => name.as_str(),
+        };
+    }
+}
+
+/**
+ * Banner printed before and, optionally, after the job
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobSheets {
+    pub start: JobSheet,
+    pub end: Option<JobSheet>,
+}
+
+impl Display for JobSheets {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        return match &self.end {
+            Some(end) => write!(fmt, "{},{}", self.start.name(), end.name()),
+            None => write!(fmt, "{}", self.start.name()),
+        };
+    }
+}
+
+/**
+ * When a held job should be released for printing
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum HoldUntil {
+    Indefinite,
+    DayTime,
+    Evening,
+    Night,
+    Weekend,
+    SecondShift,
+    ThirdShift,
+    /**
+     * Release the job at this time, must be within the next 24 hours
+     */
+    At(SystemTime),
+}
+
+impl Display for HoldUntil {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        return match self {
+            HoldUntil::Indefinite => write!(fmt, "indefinite"),
+            HoldUntil::DayTime => write!(fmt, "day-time"),
+            HoldUntil::Evening => write!(fmt, "evening"),
+            HoldUntil::Night => write!(fmt, "night"),
+            HoldUntil::Weekend => write!(fmt, "weekend"),
+            HoldUntil::SecondShift => write!(fmt, "second-shift"),
+            HoldUntil::ThirdShift => write!(fmt, "third-shift"),
+            HoldUntil::At(time) => {
+                // Time of day in UTC, as expected by job-hold-until
+                let seconds = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % 86400;
+                write!(fmt, "{:02}:{:02}:{:02}", seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+            }
+        };
+    }
+}
+
+/**
+ * Hash algorithm applied to the job password (PWG 5100.11)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobPasswordEncryption {
+    None,
+    Md5,
+    Sha,
+    Sha2_256,
+    Sha2_384,
+    Sha2_512,
+    Custom(String),
+}
+
+impl JobPasswordEncryption {
+    /**
+     * Return the IPP job-password-encryption keyword
+     */
+    pub fn ipp_keyword(&self) -> &str {
+        return match self {
+            JobPasswordEncryption::None => "none",
+            JobPasswordEncryption::Md5 => "md5",
+            JobPasswordEncryption::Sha => "sha",
+            JobPasswordEncryption::Sha2_256 => "sha2-256",
+            JobPasswordEncryption::Sha2_384 => "sha2-384",
+            JobPasswordEncryption::Sha2_512 => "sha2-512",
+            JobPasswordEncryption::Custom(keyword) => keyword.as_str(),
+        };
+    }
+}
+
+/**
+ * Format of the submitted document, sent as the IPP document-format attribute
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentFormat {
+    Pdf,
+    PostScript,
+    PlainText,
+    Jpeg,
+    Png,
+    PwgRaster,
+    UrfRaster,
+    /**
+     * XPS document, rendered by the XPS filter pipeline of the XPSDrv and v4 drivers on windows
+     * and converted to GDI by the spooler for the others
+     */
+    Xps,
+    /**
+     * Data already in the printer language, ex ZPL, ESC/POS or PCL, sent without CUPS filtering
+     * (on windows every format but PlainText and Xps is sent this way)
+     */
+    Raw,
+    Custom(String),
+}
+
+impl DocumentFormat {
+    /**
+     * Return the MIME media type of the format
+     */
+    pub fn mime_type(&self) -> &str {
+        return match self {
+            DocumentFormat::Pdf => "application/pdf",
+            DocumentFormat::PostScript => "application/postscript",
+            DocumentFormat::PlainText => "text/plain",
+            DocumentFormat::Jpeg => "image/jpeg",
+            DocumentFormat::Png => "image/png",
+            DocumentFormat::PwgRaster => "image/pwg-raster",
+            DocumentFormat::UrfRaster => "image/urf",
+            DocumentFormat::Xps => "application/vnd.ms-xpsdocument",
+            DocumentFormat::Raw => "application/vnd.cups-raw",
+            DocumentFormat::Custom(mime_type) => mime_type.as_str(),
+        };
+    }
+}
+
+/**
+ * Finishing processes applied to the printed sheets, as defined by the IPP finishings attribute
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finishing {
+    None,
+    Staple,
+    StapleTopLeft,
+    StapleBottomLeft,
+    StapleTopRight,
+    StapleBottomRight,
+    StapleDualLeft,
+    StapleDualTop,
+    Punch,
+    PunchDualLeft,
+    PunchDualTop,
+    PunchTripleLeft,
+    PunchQuadLeft,
+    Fold,
+    FoldHalf,
+    FoldLetter,
+    FoldZ,
+    Bind,
+    SaddleStitch,
+    BookletMaker,
+    Trim,
+    JogOffset,
+    /**
+     * Raw IPP finishings enum value
+     */
+    Other(u32),
+}
+
+impl Finishing {
+    /**
+     * Return the IPP finishings enum value (PWG 5100.1)
+     */
+    pub fn ipp_value(&self) -> u32 {
+        return match self {
+            Finishing::None => 3,
+            Finishing::Staple => 4,
+            Finishing::Punch => 5,
+            Finishing::Bind => 7,
+            Finishing::SaddleStitch => 8,
+            Finishing::Fold => 10,
+            Finishing::Trim => 11,
+            Finishing::BookletMaker => 13,
+            Finishing::JogOffset => 14,
+            Finishing::StapleTopLeft => 20,
+            Finishing::StapleBottomLeft => 21,
+            Finishing::StapleTopRight => 22,
+            Finishing::StapleBottomRight => 23,
+            Finishing::StapleDualLeft => 28,
+            Finishing::StapleDualTop => 29,
+            Finishing::PunchDualLeft => 74,
+            Finishing::PunchDualTop => 75,
+            Finishing::PunchTripleLeft => 78,
+            Finishing::PunchQuadLeft => 82,
+            Finishing::FoldHalf => 93,
+            Finishing::FoldLetter => 96,
+            Finishing::FoldZ => 100,
+            Finishing::Other(value) => *value,
+        };
+    }
+}
+
+/**
+ * Print resolution in dots per inch
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrintResolution {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl PrintResolution {
+    /**
+     * Same resolution on both axes, ex 300x300dpi
+     */
+    pub fn dpi(value: u32) -> Self {
+        return PrintResolution { x: value, y: value };
+    }
+}
+
+/**
+ * Page margins in points (1/72 inch)
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageMargins {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+impl PageMargins {
+    /**
+     * Same margin on all sides, in points
+     */
+    pub fn points(value: f32) -> Self {
+        return PageMargins { top: value, bottom: value, left: value, right: value };
+    }
+
+    /**
+     * Margins given in any unit
+     */
+    pub fn dimensions(top: Dimension, bottom: Dimension, left: Dimension, right: Dimension) -> Self {
+        return PageMargins {
+            top: top.to_points(),
+            bottom: bottom.to_points(),
+            left: left.to_points(),
+            right: right.to_points(),
+        };
+    }
+
+    /**
+     * Margins given in millimeters
+     */
+    pub fn millimeters(top: f32, bottom: f32, left: f32, right: f32) -> Self {
+        let to_points = |mm: f32| mm * 72.0 / 25.4;
+        return PageMargins {
+            top: to_points(top),
+            bottom: to_points(bottom),
+            left: to_points(left),
+            right: to_points(right),
+        };
+    }
+}
+
+/**
+ * Validated list of 1-based inclusive page ranges, parsed from a string like "1-3,7,9-12"
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRanges {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl PageRanges {
+    /**
+     * Return the (first, last) page of each range
+     */
+    pub fn ranges(&self) -> &[(u32, u32)] {
+        return &self.ranges;
+    }
+}
+
+impl FromStr for PageRanges {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut ranges = vec![];
+
+        for range in value.split(',').map(|r| r.trim()) {
+            let (first, last) = range.split_once('-').unwrap_or((range, range));
+            let first = first.trim().parse::<u32>().map_err(|_| "invalid page number")?;
+            let last = last.trim().parse::<u32>().map_err(|_| "invalid page number")?;
+
+            if first == 0 || last < first {
+                return Err("invalid page range");
+            }
+
+            ranges.push((first, last));
+        }
+
+        return Ok(PageRanges { ranges });
+    }
+}
+
+impl Display for PageRanges {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), Error> {
+        let ranges: Vec<String> = self
+            .ranges
+            .iter()
+            .map(|(first, last)| if first == last { first.to_string() } else { format!("{}-{}", first, last) })
+            .collect();
+        write!(fmt, "{}", ranges.join(","))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PrintOptions {
+    /**
+     * Page orientation of the job
+     */
+    pub orientation: Option<PrintOrientation>,
+
+    /**
+     * Number of copies to print
+     */
+    pub copies: Option<u32>,
+
+    /**
+     * Print on one or both sides of the sheet
+     */
+    pub duplex: Option<Duplex>,
+
+    /**
+     * Paper size of the job
+     */
+    pub media_size: Option<MediaSize>,
+
+    /**
+     * Print only the selected pages (not supported by the Windows RAW spooling)
+     */
+    pub page_ranges: Option<PageRanges>,
+
+    /**
+     * Force color or grayscale output
+     */
+    pub color_mode: Option<ColorMode>,
+
+    /**
+     * Output quality of the job
+     */
+    pub quality: Option<PrintQuality>,
+
+    /**
+     * Requested resolution, on Windows it takes precedence over the quality
+     */
+    pub resolution: Option<PrintResolution>,
+
+    /**
+     * Number of pages per sheet, one of 1, 2, 4, 6, 9 or 16 (CUPS only)
+     */
+    pub number_up: Option<u8>,
+
+    /**
+     * Collate the pages when printing multiple copies
+     */
+    pub collate: Option<bool>,
+
+    /**
+     * Order of the output pages, reverse prints the last page first (CUPS only)
+     */
+    pub output_order: Option<OutputOrder>,
+
+    /**
+     * Finishing processes like staple, punch or fold (CUPS only)
+     */
+    pub finishings: Vec<Finishing>,
+
+    /**
+     * Input tray to take the paper from
+     */
+    pub media_source: Option<MediaSource>,
+
+    /**
+     * Output tray or mailbox that receives the job (CUPS only)
+     */
+    pub output_bin: Option<OutputBin>,
+
+    /**
+     * Banner pages that separate the job from others (CUPS only)
+     */
+    pub job_sheets: Option<JobSheets>,
+
+    /**
+     * Hold the job and print it later (CUPS only)
+     */
+    pub hold_until: Option<HoldUntil>,
+
+    /**
+     * Job priority from 1 (lowest) to 100 (highest), the spooler default is 50 (CUPS only).
+     * Other values fail the print calls, see PrintOptions::check
+     */
+    pub priority: Option<u8>,
+
+    /**
+     * Scale the document to fit the printable area instead of cropping it (CUPS only)
+     */
+    pub fit_to_page: Option<bool>,
+
+    /**
+     * Scaling of the document in percent, 100 keeps the original size
+     */
+    pub scaling: Option<u32>,
+
+    /**
+     * Page margins used when printing text and images (CUPS only)
+     */
+    pub margins: Option<PageMargins>,
+
+    /**
+     * PIN that releases the job at the printer panel. When an encryption other than none is
+     * set, this must already be the hashed password (CUPS only)
+     */
+    pub job_password: Option<String>,
+
+    /**
+     * Hash algorithm used for the job password
+     */
+    pub job_password_encryption: Option<JobPasswordEncryption>,
+
+    /**
+     * Billing information, ex a cost center (CUPS only)
+     */
+    pub job_billing: Option<String>,
+
+    /**
+     * Account charged for the job (CUPS only)
+     */
+    pub job_account_id: Option<String>,
+
+    /**
+     * User charged for the job, when different from the submitting user (CUPS only)
+     */
+    pub job_accounting_user_id: Option<String>,
+
+    /**
+     * User the job is submitted for, its job-originating-user-name, ex the end user of a print
+     * server. The spooler only accepts it from trusted clients and ignores it for authenticated
+     * requests, the job then belongs to the authenticated user (CUPS only)
+     */
+    pub requesting_user: Option<String>,
+
+    /**
+     * Format of the document data, detected by the server when unset. On Windows only
+     * PlainText and Xps change the spooler datatype, everything else is sent RAW and the driver
+     * then applies none of the copies, orientation, duplex, media, color, quality, resolution,
+     * collate and scaling options (Printer::validate reports them)
+     */
+    pub document_format: Option<DocumentFormat>,
+
+    /**
+     * Platform options not modeled by this struct, see PrintOptions::raw
+     */
+    pub raw_options: Vec<(String, String)>,
+}
+
+impl PrintOptions {
+    /**
+     * Append an arbitrary option, ex a vendor PPD keyword. Raw options are applied after the
+     * typed ones, so they win on conflicts. Ignored on Windows where driver private data is opaque
+     */
+    pub fn raw(mut self, name: &str, value: &str) -> Self {
+        self.raw_options.push((name.to_string(), value.to_string()));
+        return self;
+    }
+
+    /**
+     * Check the values and the combinations of the options, the print calls and build() fail
+     * with the same error, so the options set directly on the fields are checked too
+     */
+    pub fn check(&self) -> Result<(), &'static str> {
+        if self.copies == Some(0) {
+            return Err("copies must be greater than zero");
+        }
+
+        if let Some(number_up) = self.number_up
+            && ![1, 2, 4, 6, 9, 16].contains(&number_up)
+        {
+            return Err("number_up must be one of 1, 2, 4, 6, 9 or 16");
+        }
+
+        if let Some(priority) = self.priority
+            && !(1..=100).contains(&priority)
+        {
+            return Err(PRIORITY_OUT_OF_RANGE);
+        }
+
+        if self.scaling == Some(0) {
+            return Err("scaling must be greater than zero");
+        }
+
+        if self.fit_to_page == Some(true) && self.scaling.is_some_and(|s| s != 100) {
+            return Err("fit_to_page can not be combined with a custom scaling");
+        }
+
+        if let Some(margins) = &self.margins
+            && [margins.top, margins.bottom, margins.left, margins.right].iter().any(|m| *m < 0.0)
+        {
+            return Err("margins can not be negative");
+        }
+
+        if self.finishings.contains(&Finishing::None) && self.finishings.len() > 1 {
+            return Err("finishing none can not be combined with other finishings");
+        }
+
+        if self.finishings.contains(&Finishing::BookletMaker) {
+            if self.number_up == Some(1) {
+                return Err("booklet printing requires more than one page per sheet");
+            }
+            if self.duplex == Some(Duplex::OneSided) {
+                return Err("booklet printing requires two sided printing");
+            }
+        }
+
+        if self.job_password_encryption.is_some() && self.job_password.is_none() {
+            return Err("job_password_encryption requires a job_password");
+        }
+
+        return Ok(());
+    }
+
+    /**
+     * Start building print options with validation
+     */
+    pub fn builder() -> PrintOptionsBuilder {
+        return PrintOptionsBuilder::default();
+    }
+}
+
+/**
+ * Builder of PrintOptions with typed setters, build() rejects invalid or contradictory combinations
+ */
+#[derive(Debug, Clone, Default)]
+pub struct PrintOptionsBuilder {
+    options: PrintOptions,
+}
+
+impl PrintOptionsBuilder {
+    pub fn orientation(mut self, orientation: PrintOrientation) -> Self {
+        self.options.orientation = Some(orientation);
+        return self;
+    }
+
+    pub fn copies(mut self, copies: u32) -> Self {
+        self.options.copies = Some(copies);
+        return self;
+    }
+
+    pub fn duplex(mut self, duplex: Duplex) -> Self {
+        self.options.duplex = Some(duplex);
+        return self;
+    }
+
+    pub fn media_size(mut self, media_size: MediaSize) -> Self {
+        self.options.media_size = Some(media_size);
+        return self;
+    }
+
+    pub fn page_ranges(mut self, page_ranges: PageRanges) -> Self {
+        self.options.page_ranges = Some(page_ranges);
+        return self;
+    }
+
+    pub fn color_mode(mut self, color_mode: ColorMode) -> Self {
+        self.options.color_mode = Some(color_mode);
+        return self;
+    }
+
+    pub fn quality(mut self, quality: PrintQuality) -> Self {
+        self.options.quality = Some(quality);
+        return self;
+    }
+
+    pub fn resolution(mut self, resolution: PrintResolution) -> Self {
+        self.options.resolution = Some(resolution);
+        return self;
+    }
+
+    /**
+     * Same resolution on both axes, in dots per inch
+     */
+    pub fn resolution_dpi(self, dpi: u32) -> Self {
+        return self.resolution(PrintResolution::dpi(dpi));
+    }
+
+    pub fn number_up(mut self, number_up: u8) -> Self {
+        self.options.number_up = Some(number_up);
+        return self;
+    }
+
+    pub fn collate(mut self, collate: bool) -> Self {
+        self.options.collate = Some(collate);
+        return self;
+    }
+
+    pub fn output_order(mut self, output_order: OutputOrder) -> Self {
+        self.options.output_order = Some(output_order);
+        return self;
+    }
+
+    pub fn finishing(mut self, finishing: Finishing) -> Self {
+        self.options.finishings.push(finishing);
+        return self;
+    }
+
+    pub fn media_source(mut self, media_source: MediaSource) -> Self {
+        self.options.media_source = Some(media_source);
+        return self;
+    }
+
+    pub fn output_bin(mut self, output_bin: OutputBin) -> Self {
+        self.options.output_bin = Some(output_bin);
+        return self;
+    }
+
+    pub fn job_sheets(mut self, start: JobSheet, end: Option<JobSheet>) -> Self {
+        self.options.job_sheets = Some(JobSheets { start, end });
+        return self;
+    }
+
+    pub fn hold_until(mut self, hold_until: HoldUntil) -> Self {
+        self.options.hold_until = Some(hold_until);
+        return self;
+    }
+
+    pub fn priority(mut self, priority: u8) -> Self {
+        self.options.priority = Some(priority);
+        return self;
+    }
+
+    pub fn fit_to_page(mut self, fit_to_page: bool) -> Self {
+        self.options.fit_to_page = Some(fit_to_page);
+        return self;
+    }
+
+    /**
+     * Scaling in percent
+     */
+    pub fn scaling(mut self, scaling: u32) -> Self {
+        self.options.scaling = Some(scaling);
+        return self;
+    }
+
+    pub fn margins(mut self, margins: PageMargins) -> Self {
+        self.options.margins = Some(margins);
+        return self;
+    }
+
+    /**
+     * Same margin on all sides, in millimeters
+     */
+    pub fn margins_mm(self, value: f32) -> Self {
+        return self.margins(PageMargins::millimeters(value, value, value, value));
+    }
+
+    /**
+     * Same margin on all sides, in points
+     */
+    pub fn margins_points(self, value: f32) -> Self {
+        return self.margins(PageMargins::points(value));
+    }
+
+    pub fn job_password(mut self, job_password: &str, encryption: JobPasswordEncryption) -> Self {
+        self.options.job_password = Some(job_password.to_string());
+        self.options.job_password_encryption = Some(encryption);
+        return self;
+    }
+
+    pub fn job_billing(mut self, job_billing: &str) -> Self {
+        self.options.job_billing = Some(job_billing.to_string());
+        return self;
+    }
+
+    pub fn job_account_id(mut self, job_account_id: &str) -> Self {
+        self.options.job_account_id = Some(job_account_id.to_string());
+        return self;
+    }
+
+    pub fn job_accounting_user_id(mut self, job_accounting_user_id: &str) -> Self {
+        self.options.job_accounting_user_id = Some(job_accounting_user_id.to_string());
+        return self;
+    }
+
+    pub fn requesting_user(mut self, requesting_user: &str) -> Self {
+        self.options.requesting_user = Some(requesting_user.to_string());
+        return self;
+    }
+
+    pub fn document_format(mut self, document_format: DocumentFormat) -> Self {
+        self.options.document_format = Some(document_format);
+        return self;
+    }
+
+    pub fn raw(mut self, name: &str, value: &str) -> Self {
+        self.options = self.options.raw(name, value);
+        return self;
+    }
+
+    /**
+     * Validate and return the print options
+     */
+    pub fn build(self) -> Result<PrintOptions, &'static str> {
+        self.options.check()?;
+        return Ok(self.options);
+    }
+}
+
+/**
+ * Driver of a new printer queue
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrinterDriver {
+    /**
+     * Driverless IPP Everywhere queue on CUPS, the Microsoft IPP Class Driver on Windows
+     */
+    Everywhere,
+    /**
+     * Model as listed by lpinfo -m on CUPS, ex "drv:///sample.drv/generic.ppd",
+     * or the name of an installed driver on Windows
+     */
+    Model(String),
+    /**
+     * PPD file uploaded to the CUPS server, not supported on Windows
+     */
+    PpdFile(PathBuf),
+}
+
+/**
+ * Definition of a printer queue to create with add_printer
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewPrinter {
+    /**
+     * System name of the queue
+     */
+    pub name: String,
+
+    /**
+     * Uri of the device on CUPS, ex socket://10.0.0.5:9100, or the name of an existing port on Windows
+     */
+    pub device_uri: String,
+
+    pub driver: PrinterDriver,
+
+    /**
+     * Description shown to the users (printer-info on CUPS, comment on Windows)
+     */
+    pub info: Option<String>,
+
+    pub location: Option<String>,
+
+    pub is_shared: bool,
+}
+
+impl NewPrinter {
+    pub fn new(name: &str, device_uri: &str, driver: PrinterDriver) -> Self {
+        return NewPrinter {
+            name: name.to_string(),
+            device_uri: device_uri.to_string(),
+            driver,
+            info: None,
+            location: None,
+            is_shared: false,
+        };
+    }
+}
+
+/**
+ * A consumable of the printer, ex a toner cartridge, reported by Printer::supplies
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Supply {
+    pub name: String,
+
+    /**
+     * Kind of the supply as ipp marker-types keyword, ex toner, ink-cartridge or waste-toner
+     */
+    pub kind: String,
+
+    /**
+     * Color of the supply, ex #00FFFF, or "none" when it has no color
+     */
+    pub color: String,
+
+    /**
+     * Remaining level in percent, None when the device does not report it
+     */
+    pub level: Option<u8>,
+
+    /**
+     * Level under which the supply is reported as low
+     */
+    pub low_level: Option<u8>,
+
+    /**
+     * Level over which the supply is reported as almost full, ex for waste containers
+     */
+    pub high_level: Option<u8>,
+}
+
+impl Supply {
+    /**
+     * Check if the level reached the low level of the supply
+     */
+    pub fn is_low(&self) -> bool {
+        return match (self.level, self.low_level) {
+            (Some(level), Some(low_level)) => level <= low_level,
+            _ => false,
+        };
+    }
+}
+
+/**
+ * A value of a printer option with its label
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionChoice {
+    /**
+     * Value to send, ex in PrintOptions::raw
+     */
+    pub value: String,
+
+    /**
+     * Localized label of the value, the value itself when the printer has none
+     */
+    pub label: String,
+}
+
+/**
+ * A configurable option of the printer, reported by Printer::options to build settings dialogs
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrinterOption {
+    /**
+     * Name of the option as known by the platform, ex "sides" on CUPS
+     */
+    pub name: String,
+
+    /**
+     * Localized label of the option
+     */
+    pub label: String,
+
+    /**
+     * Supported values, ranges are reported as a single choice, ex "1-999" for copies
+     */
+    pub choices: Vec<OptionChoice>,
+
+    /**
+     * Value used when the option is not set
+     */
+    pub default: Option<String>,
+}
+
+/**
+ * Choices the printer supports, reported by Printer::capabilities
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PrinterCapabilities {
+    /**
+     * Supported media, names unknown to this crate are kept as MediaSize::Custom
+     */
+    pub media_sizes: Vec<MediaSize>,
+
+    pub duplex_modes: Vec<Duplex>,
+
+    pub color_modes: Vec<ColorMode>,
+
+    pub resolutions: Vec<PrintResolution>,
+
+    /**
+     * Maximum copies of a single job (1 when the printer does not report it)
+     */
+    pub max_copies: u32,
 }
 
-#[derive(Default)]
-pub struct PrintOptions {
-    pub orientation: Option<PrintOrientation>
+/**
+ * A requested option that the printer can not honor, reported by Printer::validate
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsupportedOption {
+    /**
+     * Name of the option as known by the platform, ex "sides" on CUPS
+     */
+    pub option: String,
+
+    /**
+     * Requested value
+     */
+    pub value: String,
+
+    /**
+     * Why the option can not be honored
+     */
+    pub reason: &'static str,
 }
 
+// How often Printer::wait_for_job checks the job state
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 /**
  * Printer is a struct to representation the system printer
  */
@@ -44,10 +1246,26 @@ pub struct Printer {
     pub driver_name: String,
 
     /**
-     * Uri of printer (default is empty string)
+     * Uri of printer, printer-uri-supported on unix (default is empty string)
      */
     pub uri: String,
 
+    /**
+     * Kind of the queue, local, remote, class or temporary
+     */
+    pub kind: PrinterKind,
+
+    /**
+     * Make and model of the device, ex "HP LaserJet 4000 Series" (default is empty string)
+     */
+    pub make_and_model: String,
+
+    /**
+     * Uri of the device behind the queue, ex usb://HP/LaserJet or socket://10.0.0.5:9100,
+     * the port name on windows (default is empty string)
+     */
+    pub device_uri: String,
+
     /**
      * Name of printer port (default is empty string)
      */
@@ -64,7 +1282,7 @@ pub struct Printer {
     pub data_type: String,
 
     /**
-     * Name of printer port (default is empty string)
+     * Description of the printer, printer-info on unix and the comment on windows (default is empty string)
      */
     pub description: String,
 
@@ -83,15 +1301,43 @@ pub struct Printer {
      */
     pub is_shared: bool,
 
+    /**
+     * Definition if the printer queue accepts new jobs
+     */
+    pub is_accepting_jobs: bool,
+
+    /**
+     * Definition if the destination is a CUPS class, jobs sent to it are printed by the first
+     * available member
+     */
+    pub is_class: bool,
+
+    /**
+     * System names of the printers of the class (default is empty)
+     */
+    pub members: Vec<String>,
+
     /**
      * The state of the printer
      */
     pub state: PrinterState,
 
     /**
-     * The state reasons of the printer
+     * The state reasons of the printer as ipp printer-state-reasons keywords,
+     * ex media-empty, marker-supply-low-warning or offline-report ("none" when there is no reason)
      */
     pub state_reasons: Vec<String>,
+
+    /**
+     * Platform flags of the queue, the PRINTER_ATTRIBUTE bits on Windows (ex 0x8 shared or 0x400
+     * work offline) and the printer-type bits on unix (ex 0x1 class or 0x2 remote)
+     */
+    pub attributes: u64,
+
+    /**
+     * Jobs waiting in the queue, when reported (Windows and the ipp backend)
+     */
+    pub jobs_count: Option<u32>,
 }
 
 impl Debug for Printer {
@@ -105,13 +1351,21 @@ impl Debug for Printer {
                 \r  system_name: {:?},
                 \r  is_default: {:?},
                 \r  uri: {:?},
+                \r  kind: {:?},
+                \r  make_and_model: {:?},
+                \r  device_uri: {:?},
                 \r  port_name: {:?},
                 \r  is_shared: {:?},
+                \r  is_accepting_jobs: {:?},
+                \r  is_class: {:?},
+                \r  members: {:?},
                 \r  location: {:?},
                 \r  driver_name: {:?}
                 \r  processor: {:?}
                 \r  data_type: {:?}
                 \r  description: {:?}
+                \r  attributes: {:?}
+                \r  jobs_count: {:?}
             \r}}",
             self.name,
             self.state,
@@ -119,13 +1373,21 @@ impl Debug for Printer {
             self.system_name,
             self.is_default,
             self.uri,
+            self.kind,
+            self.make_and_model,
+            self.device_uri,
             self.port_name,
             self.is_shared,
+            self.is_accepting_jobs,
+            self.is_class,
+            self.members,
             self.location,
             self.driver_name,
             self.processor,
             self.data_type,
             self.description,
+            self.attributes,
+            self.jobs_count,
         )
     }
 }
@@ -137,15 +1399,23 @@ impl Clone for Printer {
             state: self.state.clone(),
             state_reasons: self.state_reasons.clone(),
             uri: self.uri.clone(),
+            kind: self.kind,
+            make_and_model: self.make_and_model.clone(),
+            device_uri: self.device_uri.clone(),
             location: self.location.clone(),
             port_name: self.port_name.clone(),
-            is_default: self.is_default.clone(),
+            is_default: self.is_default,
             system_name: self.system_name.clone(),
             driver_name: self.driver_name.clone(),
-            is_shared: self.is_shared.clone(),
+            is_shared: self.is_shared,
+            is_accepting_jobs: self.is_accepting_jobs,
+            is_class: self.is_class,
+            members: self.members.clone(),
             data_type: self.data_type.clone(),
             description: self.description.clone(),
             processor: self.processor.clone(),
+            attributes: self.attributes,
+            jobs_count: self.jobs_count,
         };
     }
 }
@@ -165,43 +1435,515 @@ impl Printer {
             driver_name: platform_printer.get_marker_and_model(),
             location: platform_printer.get_location(),
             uri: platform_printer.get_uri(),
+            kind: platform_printer.get_kind(),
+            make_and_model: platform_printer.get_marker_and_model(),
+            device_uri: platform_printer.get_device_uri(),
             port_name: platform_printer.get_port_name(),
             is_default: platform_printer.get_is_default(),
             is_shared: platform_printer.get_is_shared(),
+            is_accepting_jobs: platform_printer.get_is_accepting_jobs(),
+            is_class: platform_printer.get_is_class(),
+            members: platform_printer.get_members(),
             data_type: platform_printer.get_data_type(),
             processor: platform_printer.get_processor(),
             description: platform_printer.get_description(),
             state: PrinterState::from_platform_state(platform_printer.get_state(), state_reasons.join(",").as_str()),
-            state_reasons
+            state_reasons,
+            attributes: platform_printer.get_attributes(),
+            jobs_count: platform_printer.get_jobs_count(),
+        };
+    }
+
+    /**
+     * Printer with the given system name, also used as its name, and no other details, for
+     * PrinterBackend implementations to fill in
+     */
+    pub fn new(system_name: &str) -> Printer {
+        return Printer {
+            name: system_name.to_string(),
+            system_name: system_name.to_string(),
+            driver_name: "".to_string(),
+            uri: "".to_string(),
+            kind: PrinterKind::Local,
+            make_and_model: "".to_string(),
+            device_uri: "".to_string(),
+            port_name: "".to_string(),
+            processor: "".to_string(),
+            data_type: "".to_string(),
+            description: "".to_string(),
+            location: "".to_string(),
+            is_default: false,
+            is_shared: false,
+            is_accepting_jobs: true,
+            is_class: false,
+            members: vec![],
+            state: PrinterState::UNKNOWN,
+            state_reasons: vec!["none".to_string()],
+            attributes: 0,
+            jobs_count: None,
         };
     }
 
     /**
-     * Print bytes with self printer instance
+     * Return an owned copy of the system name, the name the free functions of this crate
+     * (ex get_printer_by_name or set_default_printer) expect
      */
-    pub fn print(&self, buffer: &[u8], job_name: Option<&str>, options: PrintOptions) -> Result<(), &'static str> {
+    pub fn clone_name(&self) -> String {
+        return self.system_name.clone();
+    }
+
+    /**
+     * Check if the printer reports the state reason, ignoring the -report, -warning
+     * and -error severity suffixes, ex has_state_reason("media-empty")
+     */
+    pub fn has_state_reason(&self, reason: &str) -> bool {
+        return self.state_reasons.iter().any(|r| {
+            let keyword = r
+                .strip_suffix("-report")
+                .or_else(|| r.strip_suffix("-warning"))
+                .or_else(|| r.strip_suffix("-error"))
+                .unwrap_or(r);
+            keyword == reason
+        });
+    }
+
+    /**
+     * Query the printer again and update self with its current state and information
+     */
+    pub fn refresh(&mut self) -> Result<(), &'static str> {
+        return match crate::Platform::get_printer_by_name(self.system_name.as_str()) {
+            Some(printer) => {
+                *self = printer;
+                Ok(())
+            }
+            None => Err("printer not found"),
+        };
+    }
+
+    /**
+     * Return the toner, ink and other supplies of the printer with their levels, read by CUPS
+     * on unix. With the snmp feature they are read from network devices when the spooler has none
+     */
+    pub fn supplies(&self) -> Result<Vec<Supply>, &'static str> {
+        let supplies = crate::Platform::get_supplies(self.system_name.as_str());
+
+        #[cfg(feature = "snmp")]
+        if !supplies.as_ref().is_ok_and(|s| !s.is_empty())
+            && let Ok(status) = self.device_status()
+        {
+            return Ok(status.supplies);
+        }
+
+        return supplies;
+    }
+
+    /**
+     * Read the state, page counter, trays, supplies and alerts from the Printer MIB of the
+     * network device behind the queue with the "public" SNMP community
+     */
+    #[cfg(feature = "snmp")]
+    pub fn device_status(&self) -> Result<DeviceStatus, &'static str> {
+        let host = crate::common::probe::device_host(&self.device_uri).ok_or("device is not a network printer")?;
+        return query_device(&host, "public");
+    }
+
+    /**
+     * Check that the device behind the queue answers, returning how long it took. IPP devices
+     * are asked for their state, other network devices (socket, lpd) get a port check
+     */
+    pub fn probe(&self) -> Result<Duration, &'static str> {
+        if self.device_uri.is_empty() {
+            return Err("device uri not available");
+        }
+        return crate::Platform::probe_device(self.device_uri.as_str());
+    }
+
+    /**
+     * Store the options as the defaults of the printer, so they apply to future jobs of any
+     * application. Saved in lpoptions on unix and as the user DEVMODE on windows
+     */
+    pub fn set_default_options(&self, options: &PrintOptions) -> Result<(), &'static str> {
+        return crate::Platform::set_default_options(self.system_name.as_str(), options);
+    }
+
+    /**
+     * Return the member printers of a class, members that can not be found are skipped
+     */
+    pub fn get_members(&self) -> Vec<Printer> {
+        return self
+            .members
+            .iter()
+            .filter_map(|name| crate::Platform::get_printer_by_name(name))
+            .collect();
+    }
+
+    /**
+     * Stop printing the queued jobs, new jobs are still accepted. Needs administrator rights
+     */
+    pub fn pause_printer(&self) -> Result<(), &'static str> {
+        return crate::Platform::pause_printer(self.system_name.as_str());
+    }
+
+    /**
+     * Start printing the queued jobs again after a pause
+     */
+    pub fn resume_printer(&self) -> Result<(), &'static str> {
+        return crate::Platform::resume_printer(self.system_name.as_str());
+    }
+
+    /**
+     * Let the queue accept new jobs again after reject_jobs
+     */
+    pub fn accept_jobs(&self) -> Result<(), &'static str> {
+        return crate::Platform::accept_jobs(self.system_name.as_str());
+    }
+
+    /**
+     * Refuse new jobs while the queued ones keep printing, ex to take a device out of service.
+     * Only supported on unix, the reason is shown to the users submitting jobs
+     */
+    pub fn reject_jobs(&self, reason: Option<&str>) -> Result<(), &'static str> {
+        return crate::Platform::reject_jobs(self.system_name.as_str(), reason);
+    }
+
+    /**
+     * Print bytes with self printer instance, returning the id of the created job
+     */
+    pub fn print(&self, buffer: &[u8], job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
         return crate::Platform::print(self.system_name.as_str(), buffer, job_name, options);
     }
 
     /**
-     * Print specific file with self printer instance
+     * Stream the reader data to the printer in chunks, so large documents are never fully buffered,
+     * returning the id of the created job
+     */
+    pub fn print_reader<R: Read>(&self, mut reader: R, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+        return crate::Platform::print_reader(self.system_name.as_str(), &mut reader, job_name, options);
+    }
+
+    /**
+     * Print specific file with self printer instance, returning the id of the created job
      */
-    pub fn print_file(&self, file_path: &str, job_name: Option<&str>, options: PrintOptions) -> Result<(), &'static str> {
-        return crate::Platform::print_file(self.system_name.as_str(), file_path, job_name, options);
+    pub fn print_file<P: AsRef<Path>>(&self, file_path: P, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+        return crate::Platform::print_file(self.system_name.as_str(), file_path.as_ref(), job_name, options);
     }
     
+    /**
+     * Inspect a PDF document before printing, reporting its page count and the page ranges
+     * of the options that exceed it
+     */
+    #[cfg(feature = "preflight")]
+    pub fn preflight(&self, data: &[u8], options: &PrintOptions) -> Result<PreflightReport, &'static str> {
+        return preflight_pdf(data, options);
+    }
+
+    /**
+     * Print a PDF document only when the preflight check passes, so out of range pages are
+     * reported instead of silently dropped, returning the id of the created job
+     */
+    #[cfg(feature = "preflight")]
+    pub fn print_pdf(&self, data: &[u8], job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+        preflight_pdf(data, &options)?.check()?;
+
+        let mut options = options;
+        options.document_format.get_or_insert(DocumentFormat::Pdf);
+        return self.print(data, job_name, options);
+    }
+
+    /**
+     * Print the document at the uri, letting the server fetch it with IPP Print-URI when supported
+     * and downloading it otherwise, returning the id of the created job
+     */
+    pub fn print_uri(&self, uri: &str, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+        return crate::Platform::print_uri(self.system_name.as_str(), uri, job_name, options);
+    }
+
+    /**
+     * Print plain text, returning the id of the created job. The text is rendered to a PDF
+     * document, except on Windows where it is drawn with GDI by the printer driver
+     */
+    pub fn print_text(&self, text: &str, job_name: Option<&str>, text_options: TextOptions, options: PrintOptions) -> Result<JobId, &'static str> {
+        return crate::Platform::print_text(self.system_name.as_str(), text, job_name, &text_options, options);
+    }
+
+    /**
+     * Print a test page with the printer information, an alignment grid and gray and color bars,
+     * returning the id of the created job. On Windows the document is sent RAW, so the printer must accept PDF
+     */
+    pub fn print_test_page(&self) -> Result<JobId, &'static str> {
+        let info = [
+            ("Name", self.name.clone()),
+            ("System name", self.system_name.clone()),
+            ("Make and model", self.make_and_model.clone()),
+            ("Device uri", self.device_uri.clone()),
+            ("Location", self.location.clone()),
+            ("Description", self.description.clone()),
+            ("Printed by", format!("printers {}", env!("CARGO_PKG_VERSION"))),
+        ];
+
+        let document = render_test_page(&info, &MediaSize::A4)?;
+        let options = PrintOptions {
+            document_format: Some(DocumentFormat::Pdf),
+            ..PrintOptions::default()
+        };
+        return self.print(&document, Some("Test page"), options);
+    }
+
+    /**
+     * Print an image, returning the id of the created job. The image is rendered to a PDF
+     * document, except on Windows where it is drawn with GDI by the printer driver
+     */
+    #[cfg(feature = "image")]
+    pub fn print_image(&self, image: &image::DynamicImage, job_name: Option<&str>, image_options: ImagePrintOptions, options: PrintOptions) -> Result<JobId, &'static str> {
+        return crate::Platform::print_image(self.system_name.as_str(), image, job_name, &image_options, options);
+    }
+
+    /**
+     * Print several files as a single job, so they share the banner and accounting entry,
+     * returning the id of the created job. The Windows spooler has no multi-document jobs, the
+     * files are sent one after another as one stream, so there several files are only accepted
+     * with the Raw or PlainText document_format (ex ZPL or ESC/POS data); print PDF, PostScript
+     * and other documents with a header of their own one job per file
+     */
+    pub fn print_files<P: AsRef<Path>>(&self, file_paths: &[P], job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+        let file_paths: Vec<&Path> = file_paths.iter().map(|p| p.as_ref()).collect();
+        return crate::Platform::print_files(self.system_name.as_str(), &file_paths, job_name, options);
+    }
+
+    /**
+     * Return the media sizes, duplex modes, color modes, resolutions and copies the printer supports
+     */
+    pub fn capabilities(&self) -> Result<PrinterCapabilities, &'static str> {
+        return crate::Platform::get_capabilities(self.system_name.as_str());
+    }
+
+    /**
+     * Return every configurable option of the printer with its choices and default
+     */
+    pub fn options(&self) -> Result<Vec<PrinterOption>, &'static str> {
+        return crate::Platform::get_printer_options(self.system_name.as_str());
+    }
+
+    /**
+     * Return the options a job gets when it does not set them, ex to pre-fill a print dialog.
+     * Options the platform does not report are left to None
+     */
+    pub fn default_options(&self) -> Result<PrintOptions, &'static str> {
+        return crate::Platform::get_default_options(self.system_name.as_str());
+    }
+
+    /**
+     * Return the (option, value) pairs of the options that conflict with setting option to value,
+     * ex a duplex mode on a media type that can not be printed on both sides. Windows drivers
+     * resolve their constraints while printing, so there are never conflicts there
+     */
+    pub fn option_conflicts(&self, options: &PrintOptions, option: &str, value: &str) -> Vec<(String, String)> {
+        return crate::Platform::get_option_conflicts(self.system_name.as_str(), options, option, value);
+    }
+
+    /**
+     * Check the options against the printer capabilities before printing
+     */
+    pub fn validate(&self, options: &PrintOptions) -> Result<(), Vec<UnsupportedOption>> {
+        let unsupported = crate::Platform::validate_options(self.system_name.as_str(), options);
+        return if unsupported.is_empty() { Ok(()) } else { Err(unsupported) };
+    }
+
     /**
      * Return vec of active jobs of printer
      */
     pub fn get_active_jobs(&self) -> Vec<PrinterJob> {
-        return crate::Platform::get_printer_jobs(self.system_name.as_str(), true);
+        return self.get_jobs(WhichJobs::Active);
     }
 
     /**
      * Return vec of a historic jobs of printer
      */
     pub fn get_job_history(&self) -> Vec<PrinterJob> {
-        return crate::Platform::get_printer_jobs(self.system_name.as_str(), false);
+        return self.get_jobs(WhichJobs::All);
+    }
+
+    /**
+     * Return vec of finished jobs of printer still kept in the history
+     */
+    pub fn get_completed_jobs(&self) -> Vec<PrinterJob> {
+        return self.get_jobs(WhichJobs::Completed);
+    }
+
+    /**
+     * Return vec of the printer jobs selected by which_jobs, empty when they could not be listed
+     */
+    pub fn get_jobs(&self, which_jobs: WhichJobs) -> Vec<PrinterJob> {
+        return self.query_jobs(&JobQuery::new(which_jobs)).unwrap_or_default();
+    }
+
+    /**
+     * Return vec of the printer jobs matching the filter, a WhichJobs or a full JobQuery,
+     * ex printer.jobs(WhichJobs::Active) or printer.jobs(JobQuery::default().current_user()).
+     * It is empty when the jobs could not be listed, query_jobs reports why
+     */
+    pub fn jobs<Q: Into<JobQuery>>(&self, filter: Q) -> Vec<PrinterJob> {
+        return self.query_jobs(&filter.into()).unwrap_or_default();
+    }
+
+    /**
+     * Return the printer jobs matching the query, ex only the current user jobs, or why they
+     * could not be listed (ex the server is unreachable or refused the request)
+     */
+    pub fn query_jobs(&self, query: &JobQuery) -> Result<Vec<PrinterJob>, &'static str> {
+        return crate::Platform::get_printer_jobs(self.system_name.as_str(), query);
+    }
+
+    /**
+     * Cancel a job of the printer that has not finished yet
+     */
+    pub fn cancel_job(&self, job_id: JobId) -> Result<(), &'static str> {
+        return crate::Platform::cancel_job(self.system_name.as_str(), job_id);
+    }
+
+    /**
+     * Return the position of the job in the printer queue, 1 is the job printing or next to
+     * print. Held and stopped jobs are not in line, so they are not counted
+     */
+    pub fn queue_position(&self, job_id: JobId) -> Result<usize, &'static str> {
+        return self
+            .query_jobs(&JobQuery::new(WhichJobs::Active))?
+            .iter()
+            .filter(|j| j.state == JobState::PENDING || j.state == JobState::PROCESSING)
+            .position(|j| j.id == job_id)
+            .map(|p| p + 1)
+            .ok_or("job is not waiting in the queue");
+    }
+
+    /**
+     * Return every attribute of the job as reported by the platform, for the details
+     * PrinterJob does not model. On Windows the JOB_INFO_1W fields are named after their
+     * IPP equivalents
+     */
+    pub fn get_job_attributes(&self, job_id: JobId) -> Result<Attributes, &'static str> {
+        return crate::Platform::get_job_attributes(self.system_name.as_str(), job_id);
+    }
+
+    /**
+     * Read the submitted document of a job back, ex for auditing. CUPS needs PreserveJobFiles
+     * and Windows the "keep printed documents" printer setting once the job is done
+     */
+    pub fn get_job_document(&self, job_id: JobId) -> Result<JobDocument, &'static str> {
+        return crate::Platform::get_job_document(self.system_name.as_str(), job_id);
+    }
+
+    /**
+     * Return how many pages and sheets of the job were already printed
+     */
+    pub fn get_job_progress(&self, job_id: JobId) -> Result<JobProgress, &'static str> {
+        return crate::Platform::get_job_progress(self.system_name.as_str(), job_id);
+    }
+
+    /**
+     * Block until the job is completed or cancelled, polling the job list, and fail when the
+     * timeout elapses first or the jobs can not be listed. Windows removes finished jobs from
+     * the queue, so there a job that is no longer listed is reported as COMPLETED, elsewhere
+     * it is an error
+     */
+    pub fn wait_for_job(&self, job_id: JobId, timeout: Duration) -> Result<JobCompletion, &'static str> {
+        let started = Instant::now();
+        let purges_finished_jobs = purges_finished_jobs(self.system_name.as_str());
+        let mut last_seen: Option<PrinterJob> = None;
+
+        loop {
+            let job = self.query_jobs(&JobQuery::new(WhichJobs::All))?.into_iter().find(|j| j.id == job_id);
+            match job {
+                Some(job) if job.state.is_terminal() => {
+                    return Ok(JobCompletion {
+                        state: job.state,
+                        state_reasons: job.state_reasons,
+                        processed_at: job.processed_at,
+                        completed_at: job.completed_at,
+                        waited: started.elapsed(),
+                    });
+                }
+                Some(job) => last_seen = Some(job),
+                None if purges_finished_jobs => {
+                    return Ok(JobCompletion {
+                        state: JobState::COMPLETED,
+                        state_reasons: last_seen.as_ref().map(|j| j.state_reasons.clone()).unwrap_or_default(),
+                        processed_at: last_seen.and_then(|j| j.processed_at),
+                        completed_at: None,
+                        waited: started.elapsed(),
+                    });
+                }
+                None => return Err("job not found"),
+            }
+
+            if started.elapsed() >= timeout {
+                return Err("timed out waiting for the job");
+            }
+
+            thread::sleep(WAIT_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())));
+        }
+    }
+
+    /**
+     * Follow the state transitions of the job with their timestamps, using the platform change
+     * notifications when available and checking the job every two seconds otherwise. On Windows
+     * a job that leaves the queue is reported as COMPLETED
+     */
+    pub fn watch_job(&self, job_id: JobId) -> Result<JobWatch, &'static str> {
+        return watch_job(self.system_name.as_str(), job_id);
+    }
+
+    /**
+     * Cancel every job of the printer, ex to clear a stuck queue. On Windows this needs
+     * administrator rights on the printer
+     */
+    pub fn purge_jobs(&self) -> Result<(), &'static str> {
+        return crate::Platform::purge_jobs(self.system_name.as_str());
+    }
+
+    /**
+     * Keep a queued job from printing until it is released
+     */
+    pub fn hold_job(&self, job_id: JobId) -> Result<(), &'static str> {
+        return crate::Platform::hold_job(self.system_name.as_str(), job_id);
+    }
+
+    /**
+     * Let an held job print
+     */
+    pub fn release_job(&self, job_id: JobId) -> Result<(), &'static str> {
+        return crate::Platform::release_job(self.system_name.as_str(), job_id);
+    }
+
+    /**
+     * Print a job again without submitting its documents, ex after a paper jam. On CUPS
+     * this needs a retained job, on Windows the job must still be in the queue
+     */
+    pub fn restart_job(&self, job_id: JobId) -> Result<(), &'static str> {
+        return crate::Platform::restart_job(self.system_name.as_str(), job_id);
+    }
+
+    /**
+     * Change the priority of a queued job, from 1 (lowest) to 100 (highest)
+     */
+    pub fn set_job_priority(&self, job_id: JobId, priority: u8) -> Result<(), &'static str> {
+        if !(1..=100).contains(&priority) {
+            return Err(PRIORITY_OUT_OF_RANGE);
+        }
+        return crate::Platform::set_job_priority(self.system_name.as_str(), job_id, priority);
+    }
+
+    /**
+     * Move a queued job to another printer (CUPS only)
+     */
+    pub fn move_job(&self, job_id: JobId, target: &Printer) -> Result<(), &'static str> {
+        return crate::Platform::move_job(self.system_name.as_str(), Some(job_id), target.system_name.as_str());
+    }
+
+    /**
+     * Move every queued job to another printer, ex when this one jams (CUPS only)
+     */
+    pub fn move_jobs(&self, target: &Printer) -> Result<(), &'static str> {
+        return crate::Platform::move_job(self.system_name.as_str(), None, target.system_name.as_str());
     }
 
 }
@@ -212,3 +1954,30 @@ impl PrinterState {
         return crate::Platform::parse_printer_state(platform_state, state_reasons);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_page_ranges() {
+        let page_ranges = "1-3,5".parse::<PageRanges>().unwrap();
+        assert_eq!(page_ranges.ranges(), &[(1, 3), (5, 5)]);
+        assert_eq!(page_ranges.to_string(), "1-3,5");
+
+        let page_ranges = " 2 - 4 , 7-7 ".parse::<PageRanges>().unwrap();
+        assert_eq!(page_ranges.ranges(), &[(2, 4), (7, 7)]);
+        assert_eq!(page_ranges.to_string().parse::<PageRanges>().unwrap(), page_ranges);
+    }
+
+    #[test]
+    fn parse_invalid_page_ranges() {
+        assert_eq!("0".parse::<PageRanges>(), Err("invalid page range"));
+        assert_eq!("0-2".parse::<PageRanges>(), Err("invalid page range"));
+        assert_eq!("3-1".parse::<PageRanges>(), Err("invalid page range"));
+        assert_eq!("".parse::<PageRanges>(), Err("invalid page number"));
+        assert_eq!("1-".parse::<PageRanges>(), Err("invalid page number"));
+        assert_eq!("1,,2".parse::<PageRanges>(), Err("invalid page number"));
+        assert_eq!("a-b".parse::<PageRanges>(), Err("invalid page number"));
+    }
+}
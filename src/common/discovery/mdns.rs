@@ -0,0 +1,171 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+// DNS record types (RFC 1035, RFC 2782, RFC 3596)
+pub const TYPE_A: u16 = 1;
+pub const TYPE_PTR: u16 = 12;
+pub const TYPE_TXT: u16 = 16;
+pub const TYPE_AAAA: u16 = 28;
+pub const TYPE_SRV: u16 = 33;
+
+const CLASS_IN: u16 = 1;
+
+// Compression pointers followed before a name is considered malformed
+const MAX_NAME_JUMPS: usize = 32;
+
+/**
+ * Resource record of an mDNS response, names are without the trailing dot
+ */
+#[derive(Debug, Clone)]
+pub enum Record {
+    Ptr { name: String, target: String },
+    Srv { name: String, target: String, port: u16 },
+    Txt { name: String, entries: Vec<(String, String)> },
+    Address { name: String, address: IpAddr },
+}
+
+/**
+ * Build a query message with one question of the given type for each name
+ */
+pub fn query(questions: &[(&str, u16)]) -> Vec<u8> {
+    let mut message = vec![0u8; 12];
+    message[4..6].copy_from_slice(&(questions.len() as u16).to_be_bytes());
+
+    for (name, record_type) in questions {
+        // Service instance names may contain dots, only the trailing labels are split
+        for label in split_name(name) {
+            let label = &label.as_bytes()[..label.len().min(63)];
+            message.push(label.len() as u8);
+            message.extend_from_slice(label);
+        }
+        message.push(0);
+        message.extend_from_slice(&record_type.to_be_bytes());
+        message.extend_from_slice(&CLASS_IN.to_be_bytes());
+    }
+
+    return message;
+}
+
+/**
+ * Split a name in labels keeping the instance of "Office Printer 2.0._ipp._tcp.local" whole
+ */
+fn split_name(name: &str) -> Vec<&str> {
+    return match name.find("._") {
+        Some(index) => {
+            let mut labels = vec![&name[..index]];
+            labels.extend(name[index + 1..].split('.').filter(|l| !l.is_empty()));
+            labels
+        }
+        None => name.split('.').filter(|l| !l.is_empty()).collect(),
+    };
+}
+
+/**
+ * Parse the records of a response message, None when it is not a response or is malformed
+ */
+pub fn parse(message: &[u8]) -> Option<Vec<Record>> {
+    if message.len() < 12 || message[2] & 0x80 == 0 {
+        return None;
+    }
+
+    let count = |index: usize| u16::from_be_bytes([message[index], message[index + 1]]) as usize;
+    let questions = count(4);
+    let records = count(6) + count(8) + count(10);
+
+    let mut position = 12;
+    for _ in 0..questions {
+        let (_, next) = read_name(message, position)?;
+        position = next + 4;
+    }
+
+    let mut parsed = Vec::new();
+    for _ in 0..records {
+        let (name, next) = read_name(message, position)?;
+        let header = message.get(next..next + 10)?;
+        let record_type = u16::from_be_bytes([header[0], header[1]]);
+        let length = u16::from_be_bytes([header[8], header[9]]) as usize;
+        let data_start = next + 10;
+        let data = message.get(data_start..data_start + length)?;
+        position = data_start + length;
+
+        let record = match record_type {
+            TYPE_PTR => Record::Ptr { name, target: read_name(message, data_start)?.0 },
+            TYPE_SRV if length > 6 => Record::Srv {
+                name,
+                port: u16::from_be_bytes([data[4], data[5]]),
+                target: read_name(message, data_start + 6)?.0,
+            },
+            TYPE_TXT => Record::Txt { name, entries: parse_txt(data) },
+            TYPE_A if length == 4 => Record::Address {
+                name,
+                address: IpAddr::V4(Ipv4Addr::new(data[0], data[1], data[2], data[3])),
+            },
+            TYPE_AAAA if length == 16 => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(data);
+                Record::Address { name, address: IpAddr::V6(Ipv6Addr::from(octets)) }
+            }
+            _ => continue,
+        };
+        parsed.push(record);
+    }
+
+    return Some(parsed);
+}
+
+/**
+ * Read a possibly compressed name and return it with the position after it
+ */
+fn read_name(message: &[u8], position: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut position = position;
+    let mut end = None;
+
+    for _ in 0..MAX_NAME_JUMPS {
+        loop {
+            let length = *message.get(position)? as usize;
+            if length == 0 {
+                let end = end.unwrap_or(position + 1);
+                return Some((labels.join("."), end));
+            }
+
+            if length & 0xC0 == 0xC0 {
+                let pointer = ((length & 0x3F) << 8) | *message.get(position + 1)? as usize;
+                end.get_or_insert(position + 2);
+                position = pointer;
+                break;
+            }
+
+            let label = message.get(position + 1..position + 1 + length)?;
+            labels.push(String::from_utf8_lossy(label).to_string());
+            position += 1 + length;
+        }
+    }
+
+    return None;
+}
+
+/**
+ * Split the TXT strings in key and value, keys without "=" get an empty value
+ */
+fn parse_txt(data: &[u8]) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    let mut position = 0;
+
+    while position < data.len() {
+        let length = data[position] as usize;
+        let Some(entry) = data.get(position + 1..position + 1 + length) else { break };
+        position += 1 + length;
+
+        let entry = String::from_utf8_lossy(entry);
+        if entry.is_empty() {
+            continue;
+        }
+
+        match entry.split_once('=') {
+            Some((key, value)) => entries.push((key.to_lowercase(), value.to_string())),
+            None => entries.push((entry.to_lowercase(), "".to_string())),
+        }
+    }
+
+    return entries;
+}
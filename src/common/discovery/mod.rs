@@ -0,0 +1,262 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use mdns::Record;
+
+mod mdns;
+#[cfg(target_family = "windows")]
+mod wsd;
+
+const MDNS_ADDRESS: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), 5353);
+
+// IPP services advertised by network printers (PWG 5100.14)
+const IPP_SERVICES: [&str; 2] = ["_ipp._tcp.local", "_ipps._tcp.local"];
+
+/**
+ * Printer announced on the local network, it may have no queue on this system yet
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredPrinter {
+    /**
+     * Service instance name, ex "HP LaserJet MFP M428"
+     */
+    pub name: String,
+
+    /**
+     * Service the printer was found with, "_ipp._tcp", "_ipps._tcp" or "wsd" for WS-Discovery on windows
+     */
+    pub service: String,
+
+    /**
+     * Host name of the printer, ex "NPI2A3B4C.local"
+     */
+    pub host: String,
+
+    pub port: u16,
+
+    /**
+     * Addresses announced for the host (default is empty)
+     */
+    pub addresses: Vec<IpAddr>,
+
+    /**
+     * Uri of the printer, usable as the device uri of a new queue, ex ipp://NPI2A3B4C.local:631/ipp/print,
+     * the web services address for WSD devices
+     */
+    pub uri: String,
+
+    /**
+     * TXT record of the service, keys are lowercase, ex ("ty", "HP LaserJet") or ("color", "T"),
+     * WSD devices report their model as ty, their manufacturer as usb_mfg and their types
+     */
+    pub txt: Vec<(String, String)>,
+}
+
+impl DiscoveredPrinter {
+    /**
+     * Return the value of a TXT key
+     */
+    pub fn txt_value(&self, key: &str) -> Option<&str> {
+        let key = key.to_lowercase();
+        return self.txt.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str());
+    }
+
+    /**
+     * Make and model announced by the printer (ty key)
+     */
+    pub fn make_and_model(&self) -> Option<&str> {
+        return self.txt_value("ty");
+    }
+
+    /**
+     * Document formats accepted by the printer (pdl key), ex application/pdf or image/urf
+     */
+    pub fn document_formats(&self) -> Vec<String> {
+        return self
+            .txt_value("pdl")
+            .map(|pdl| pdl.split(',').map(|f| f.trim().to_string()).filter(|f| !f.is_empty()).collect())
+            .unwrap_or_default();
+    }
+
+    /**
+     * Definition if the printer announces color printing (Color=T)
+     */
+    pub fn supports_color(&self) -> bool {
+        return self.txt_value("color") == Some("T");
+    }
+
+    /**
+     * Definition if the printer announces two sided printing (Duplex=T)
+     */
+    pub fn supports_duplex(&self) -> bool {
+        return self.txt_value("duplex") == Some("T");
+    }
+}
+
+/**
+ * Records received so far, indexed by the lowercase record name as names are case insensitive
+ */
+#[derive(Default)]
+struct Answers {
+    instances: Vec<(String, String)>,
+    services: HashMap<String, (String, u16)>,
+    txts: HashMap<String, Vec<(String, String)>>,
+    addresses: HashMap<String, Vec<IpAddr>>,
+}
+
+impl Answers {
+    fn add(&mut self, record: Record) {
+        match record {
+            Record::Ptr { name, target } => {
+                let service = name.to_lowercase();
+                let known = self.instances.iter().any(|(_, i)| i.eq_ignore_ascii_case(&target));
+                if IPP_SERVICES.contains(&service.as_str()) && !known {
+                    self.instances.push((service, target));
+                }
+            }
+            Record::Srv { name, target, port } => {
+                self.services.insert(name.to_lowercase(), (target, port));
+            }
+            Record::Txt { name, entries } => {
+                self.txts.insert(name.to_lowercase(), entries);
+            }
+            Record::Address { name, address } => {
+                let addresses = self.addresses.entry(name.to_lowercase()).or_default();
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+        }
+    }
+
+    /**
+     * Questions for the records the responders did not send along with their answer
+     */
+    fn missing(&self, asked: &mut HashSet<(String, u16)>) -> Vec<(String, u16)> {
+        let mut questions = Vec::new();
+
+        for (_, instance) in &self.instances {
+            let key = instance.to_lowercase();
+            if !self.services.contains_key(&key) {
+                questions.push((instance.clone(), mdns::TYPE_SRV));
+            }
+            if !self.txts.contains_key(&key) {
+                questions.push((instance.clone(), mdns::TYPE_TXT));
+            }
+        }
+
+        for (host, _) in self.services.values() {
+            if !self.addresses.contains_key(&host.to_lowercase()) {
+                questions.push((host.clone(), mdns::TYPE_A));
+            }
+        }
+
+        questions.retain(|q| asked.insert(q.clone()));
+        return questions;
+    }
+
+    fn printers(&self) -> Vec<DiscoveredPrinter> {
+        let mut printers = Vec::new();
+
+        for (service, instance) in &self.instances {
+            let key = instance.to_lowercase();
+            let Some((host, port)) = self.services.get(&key) else { continue };
+            let txt = self.txts.get(&key).cloned().unwrap_or_default();
+
+            let service = service.trim_end_matches(".local").to_string();
+            let scheme = if service == "_ipps._tcp" { "ipps" } else { "ipp" };
+
+            // rp is the resource path without the leading slash, IPP Everywhere printers use ipp/print
+            let resource = txt
+                .iter()
+                .find(|(k, _)| k == "rp")
+                .map(|(_, v)| v.trim_start_matches('/').to_string())
+                .unwrap_or("ipp/print".to_string());
+
+            printers.push(DiscoveredPrinter {
+                name: instance_name(instance, &service).to_string(),
+                uri: format!("{}://{}:{}/{}", scheme, host, port, resource),
+                addresses: self.addresses.get(&host.to_lowercase()).cloned().unwrap_or_default(),
+                host: host.clone(),
+                port: *port,
+                service,
+                txt,
+            });
+        }
+
+        return printers;
+    }
+}
+
+/**
+ * Strip the service from "HP LaserJet._ipp._tcp.local", names keep the case they were announced with
+ */
+fn instance_name<'a>(instance: &'a str, service: &str) -> &'a str {
+    let suffix = format!(".{}.local", service);
+    let split = instance.len().saturating_sub(suffix.len());
+
+    return match instance.get(split..) {
+        Some(tail) if tail.eq_ignore_ascii_case(&suffix) => &instance[..split],
+        _ => instance,
+    };
+}
+
+/**
+ * Browse the local network for printers during the given time, printers answer in a few hundred
+ * milliseconds so one or two seconds are usually enough. Windows also probes for WSD devices
+ */
+pub fn discover_printers(timeout: Duration) -> Result<Vec<DiscoveredPrinter>, &'static str> {
+    #[cfg(target_family = "windows")]
+    {
+        let wsd = std::thread::spawn(move || wsd::discover(timeout));
+        let mdns = browse_mdns(timeout);
+        let wsd = wsd.join().unwrap_or(Err("WS-Discovery failed"));
+
+        return match (mdns, wsd) {
+            (Err(e), Err(_)) => Err(e),
+            (mdns, wsd) => Ok(mdns.unwrap_or_default().into_iter().chain(wsd.unwrap_or_default()).collect()),
+        };
+    }
+
+    #[cfg(not(target_family = "windows"))]
+    return browse_mdns(timeout);
+}
+
+/**
+ * Ask for the IPP services with mDNS and for the records the responders leave out
+ */
+fn browse_mdns(timeout: Duration) -> Result<Vec<DiscoveredPrinter>, &'static str> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|_| "failed to open the mDNS socket")?;
+    let _ = socket.set_multicast_ttl_v4(255);
+
+    // Queries from a port other than 5353 are answered directly to it (RFC 6762 section 6.7)
+    let services: Vec<(&str, u16)> = IPP_SERVICES.iter().map(|s| (*s, mdns::TYPE_PTR)).collect();
+    socket.send_to(&mdns::query(&services), MDNS_ADDRESS).map_err(|_| "failed to send the mDNS query")?;
+
+    let deadline = Instant::now() + timeout;
+    let mut answers = Answers::default();
+    let mut asked = HashSet::new();
+    let mut buffer = [0u8; 9000];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || socket.set_read_timeout(Some(remaining)).is_err() {
+            break;
+        }
+
+        let Ok((length, _)) = socket.recv_from(&mut buffer) else { continue };
+        let Some(records) = mdns::parse(&buffer[..length]) else { continue };
+        records.into_iter().for_each(|r| answers.add(r));
+
+        let missing = answers.missing(&mut asked);
+        if !missing.is_empty() {
+            let questions: Vec<(&str, u16)> = missing.iter().map(|(n, t)| (n.as_str(), *t)).collect();
+            let _ = socket.send_to(&mdns::query(&questions), MDNS_ADDRESS);
+        }
+    }
+
+    return Ok(answers.printers());
+}
@@ -0,0 +1,216 @@
+use std::{
+    io::{Read, Write},
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket},
+    process,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::common::{discovery::DiscoveredPrinter, probe::device_address};
+
+const WSD_ADDRESS: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(239, 255, 255, 250)), 3702);
+
+// Time given to each device to answer the metadata request
+const METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+
+const NAMESPACES: &str = concat!(
+    r#"xmlns:soap="http://www.w3.org/2003/05/soap-envelope" "#,
+    r#"xmlns:wsa="http://schemas.xmlsoap.org/ws/2004/08/addressing" "#,
+    r#"xmlns:wsd="http://schemas.xmlsoap.org/ws/2005/04/discovery" "#,
+    r#"xmlns:wprt="http://schemas.microsoft.com/windows/2006/08/wdp/print""#,
+);
+
+/**
+ * Device that answered the probe, before its metadata is read
+ */
+struct ProbeMatch {
+    endpoint: String,
+    types: String,
+    xaddr: String,
+    source: IpAddr,
+}
+
+/**
+ * Unique enough message id, responders only use it to relate their answer to the probe
+ */
+fn message_id() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    return format!(
+        "urn:uuid:{:08x}-{:04x}-4{:03x}-8{:03x}-{:012x}",
+        (nanos >> 32) as u32,
+        (nanos >> 16) as u16,
+        nanos as u16 & 0x0FFF,
+        process::id() & 0x0FFF,
+        nanos as u64 & 0xFFFF_FFFF_FFFF
+    );
+}
+
+fn probe_message() -> String {
+    return format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="utf-8"?><soap:Envelope {}><soap:Header>"#,
+            "<wsa:To>urn:schemas-xmlsoap-org:ws:2005:04:discovery</wsa:To>",
+            "<wsa:Action>http://schemas.xmlsoap.org/ws/2005/04/discovery/Probe</wsa:Action>",
+            "<wsa:MessageID>{}</wsa:MessageID></soap:Header>",
+            "<soap:Body><wsd:Probe><wsd:Types>wprt:PrintDeviceType</wsd:Types></wsd:Probe></soap:Body></soap:Envelope>",
+        ),
+        NAMESPACES,
+        message_id()
+    );
+}
+
+fn get_metadata_message(endpoint: &str) -> String {
+    return format!(
+        concat!(
+            r#"<?xml version="1.0" encoding="utf-8"?><soap:Envelope {}><soap:Header>"#,
+            "<wsa:To>{}</wsa:To>",
+            "<wsa:Action>http://schemas.xmlsoap.org/ws/2004/09/transfer/Get</wsa:Action>",
+            "<wsa:MessageID>{}</wsa:MessageID>",
+            "<wsa:ReplyTo><wsa:Address>http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</wsa:Address></wsa:ReplyTo>",
+            "</soap:Header><soap:Body/></soap:Envelope>",
+        ),
+        NAMESPACES,
+        escape(endpoint),
+        message_id()
+    );
+}
+
+fn escape(text: &str) -> String {
+    return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+}
+
+fn unescape(text: &str) -> String {
+    return text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+}
+
+/**
+ * Return the text of the first element with the given local name, whatever its namespace prefix
+ */
+fn element_text(xml: &str, name: &str) -> Option<String> {
+    let mut rest = xml;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let tag_end = rest.find('>')?;
+        let tag = &rest[..tag_end];
+        let tag_name = tag.split([' ', '\t', '\r', '\n', '/']).next().unwrap_or_default();
+        let local_name = tag_name.rsplit(':').next().unwrap_or_default();
+
+        if local_name == name && !tag.starts_with('/') {
+            if tag.ends_with('/') {
+                return Some("".to_string());
+            }
+            let content = &rest[tag_end + 1..];
+            let content_end = content.find('<').unwrap_or(content.len());
+            return Some(unescape(content[..content_end].trim()));
+        }
+    }
+
+    return None;
+}
+
+/**
+ * Split the ProbeMatch elements of a response, a device may answer with several
+ */
+fn parse_probe_matches(xml: &str, source: IpAddr) -> Vec<ProbeMatch> {
+    return xml
+        .split("ProbeMatch>")
+        .filter_map(|part| {
+            let endpoint = element_text(part, "Address")?;
+            let xaddrs = element_text(part, "XAddrs")?;
+            let xaddr = xaddrs.split_whitespace().find(|x| x.starts_with("http"))?.to_string();
+            let types = element_text(part, "Types").unwrap_or_default();
+            return Some(ProbeMatch { endpoint, types, xaddr, source });
+        })
+        .collect();
+}
+
+/**
+ * Ask the device for its friendly name, manufacturer and model with a WS-Transfer Get
+ */
+fn get_metadata(probe_match: &ProbeMatch) -> Option<String> {
+    let (_, host, port) = device_address(&probe_match.xaddr)?;
+    let path = probe_match.xaddr.splitn(4, '/').nth(3).unwrap_or_default();
+    let body = get_metadata_message(&probe_match.endpoint);
+
+    let address = (host.as_str(), port).to_socket_addrs().ok()?.next()?;
+    let mut stream = TcpStream::connect_timeout(&address, METADATA_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(METADATA_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(METADATA_TIMEOUT)).ok()?;
+
+    let request = format!(
+        "POST /{} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/soap+xml; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        port,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    let response = String::from_utf8_lossy(&response);
+    return response.split_once("\r\n\r\n").map(|(_, body)| body.to_string());
+}
+
+/**
+ * Probe the local network for WSD print devices during the given time and read their metadata
+ */
+pub fn discover(timeout: Duration) -> Result<Vec<DiscoveredPrinter>, &'static str> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(|_| "failed to open the WS-Discovery socket")?;
+    socket
+        .send_to(probe_message().as_bytes(), WSD_ADDRESS)
+        .map_err(|_| "failed to send the WS-Discovery probe")?;
+
+    let deadline = Instant::now() + timeout;
+    let mut matches: Vec<ProbeMatch> = Vec::new();
+    let mut buffer = [0u8; 16384];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || socket.set_read_timeout(Some(remaining)).is_err() {
+            break;
+        }
+
+        let Ok((length, source)) = socket.recv_from(&mut buffer) else { continue };
+        let response = String::from_utf8_lossy(&buffer[..length]);
+        for probe_match in parse_probe_matches(&response, source.ip()) {
+            if !matches.iter().any(|m| m.endpoint == probe_match.endpoint) {
+                matches.push(probe_match);
+            }
+        }
+    }
+
+    let mut printers = Vec::new();
+    for probe_match in matches {
+        let Some((_, host, port)) = device_address(&probe_match.xaddr) else { continue };
+        let metadata = get_metadata(&probe_match).unwrap_or_default();
+
+        // The metadata keys follow the mDNS TXT keys so make_and_model works for both
+        let mut txt = vec![("types".to_string(), probe_match.types.clone())];
+        let model = element_text(&metadata, "ModelName");
+        if let Some(manufacturer) = element_text(&metadata, "Manufacturer") {
+            txt.push(("usb_mfg".to_string(), manufacturer));
+        }
+        if let Some(model) = &model {
+            txt.push(("ty".to_string(), model.clone()));
+        }
+
+        printers.push(DiscoveredPrinter {
+            name: element_text(&metadata, "FriendlyName").or(model).unwrap_or(probe_match.endpoint.clone()),
+            service: "wsd".to_string(),
+            host,
+            port,
+            addresses: vec![probe_match.source],
+            uri: probe_match.xaddr,
+            txt,
+        });
+    }
+
+    return Ok(printers);
+}
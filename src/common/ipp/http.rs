@@ -0,0 +1,283 @@
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::common::probe::device_address;
+
+// Size of the chunks the document is sent in
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/**
+ * Host, port and resource path of an ipp:// or http:// uri
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl Endpoint {
+    pub fn parse(uri: &str) -> Result<Endpoint, &'static str> {
+        let (scheme, host, port) = device_address(uri).ok_or("invalid printer uri")?;
+
+        if scheme == "ipps" || scheme == "https" {
+            return Err("encrypted connections are not supported by the ipp backend");
+        }
+
+        if scheme != "ipp" && scheme != "http" {
+            return Err("the printer uri is not an ipp or http uri");
+        }
+
+        let rest = uri.split_once("://").map(|(_, rest)| rest).unwrap_or_default();
+        let path = match rest.find('/') {
+            Some(index) => rest[index..].to_string(),
+            None => "/".to_string(),
+        };
+
+        return Ok(Endpoint { host, port, path });
+    }
+
+    /**
+     * Uri of another resource of the same server, ex /printers/office
+     */
+    pub fn uri(&self, path: &str) -> String {
+        let host = if self.host.contains(':') { format!("[{}]", self.host) } else { self.host.clone() };
+        return format!("ipp://{}:{}{}", host, self.port, path);
+    }
+}
+
+fn write_chunk<S: Write>(stream: &mut S, data: &[u8]) -> std::io::Result<()> {
+    write!(stream, "{:X}\r\n", data.len())?;
+    stream.write_all(data)?;
+    return stream.write_all(b"\r\n");
+}
+
+/**
+ * Decode a chunked body, None until the last chunk was received
+ */
+fn decode_chunked(mut data: &[u8]) -> Option<Vec<u8>> {
+    let mut body = vec![];
+
+    loop {
+        let line_end = data.windows(2).position(|w| w == b"\r\n")?;
+        let size = String::from_utf8_lossy(&data[..line_end]);
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        data = &data[line_end + 2..];
+
+        if size == 0 {
+            return Some(body);
+        }
+
+        body.extend_from_slice(data.get(..size)?);
+        data = data.get(size + 2..)?;
+    }
+}
+
+/**
+ * Post an IPP request over TCP, followed by the document when given, and return the body of the response
+ */
+pub fn post(
+    endpoint: &Endpoint,
+    request: &[u8],
+    document: Option<&mut dyn Read>,
+    timeout: Duration,
+) -> Result<Vec<u8>, &'static str> {
+    let address = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or("failed to resolve the printer host")?;
+
+    let mut stream = TcpStream::connect_timeout(&address, timeout).map_err(|_| "failed to connect to the printer")?;
+    stream.set_read_timeout(Some(timeout)).map_err(|_| "failed to connect to the printer")?;
+    stream.set_write_timeout(Some(timeout)).map_err(|_| "failed to connect to the printer")?;
+
+    return exchange(&mut stream, endpoint, request, document);
+}
+
+/**
+ * Send the request on an open connection and read the response, its end is found with the
+ * Content-Length or the chunked encoding since a USB connection is never closed by the device
+ */
+pub fn exchange<S: Read + Write>(
+    stream: &mut S,
+    endpoint: &Endpoint,
+    request: &[u8],
+    document: Option<&mut dyn Read>,
+) -> Result<Vec<u8>, &'static str> {
+    let header = format!(
+        "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/ipp\r\nTransfer-Encoding: chunked\r\n\r\n",
+        endpoint.path, endpoint.host, endpoint.port,
+    );
+
+    let sent = (|| -> std::io::Result<()> {
+        stream.write_all(header.as_bytes())?;
+        write_chunk(stream, request)?;
+
+        if let Some(document) = document {
+            let mut buffer = vec![0u8; CHUNK_SIZE];
+            loop {
+                let length = document.read(&mut buffer)?;
+                if length == 0 {
+                    break;
+                }
+                write_chunk(stream, &buffer[..length])?;
+            }
+        }
+
+        stream.write_all(b"0\r\n\r\n")?;
+        return stream.flush();
+    })();
+    sent.map_err(|_| "failed to send the request to the printer")?;
+
+    let mut response = vec![];
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut read = |response: &mut Vec<u8>| -> Result<bool, &'static str> {
+        let length = stream.read(&mut buffer).map_err(|_| "failed to read the printer response")?;
+        response.extend_from_slice(&buffer[..length]);
+        return Ok(length > 0);
+    };
+
+    let header_end = loop {
+        if let Some(position) = response.windows(4).position(|w| w == b"\r\n\r\n") {
+            break position;
+        }
+        if !read(&mut response)? {
+            return Err("invalid http response");
+        }
+    };
+
+    let header = String::from_utf8_lossy(&response[..header_end]).to_lowercase();
+    let status = header.split_whitespace().nth(1).unwrap_or_default().to_string();
+    let is_chunked = header.lines().any(|l| l.starts_with("transfer-encoding:") && l.contains("chunked"));
+    let content_length = header
+        .lines()
+        .find_map(|l| l.strip_prefix("content-length:"))
+        .and_then(|l| l.trim().parse::<usize>().ok());
+    response.drain(..header_end + 4);
+
+    let body = loop {
+        if is_chunked {
+            if let Some(body) = decode_chunked(&response) {
+                break body;
+            }
+        } else if let Some(content_length) = content_length
+            && response.len() >= content_length
+        {
+            response.truncate(content_length);
+            break response;
+        }
+
+        if !read(&mut response)? {
+            if is_chunked || content_length.is_some() {
+                return Err("invalid http response");
+            }
+            break response;
+        }
+    };
+
+    return match status.as_str() {
+        "200" => Ok(body),
+        "401" | "403" => Err("the printer requires authentication"),
+        "426" => Err("the printer requires an encrypted connection"),
+        _ => Err("the printer answered with an http error"),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // Connection answering with the bytes of the response, a few at a time as a device does
+    struct Connection {
+        response: Cursor<Vec<u8>>,
+        sent: Vec<u8>,
+    }
+
+    impl Read for Connection {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            let length = buffer.len().min(7);
+            return self.response.read(&mut buffer[..length]);
+        }
+    }
+
+    impl Write for Connection {
+        fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+            return self.sent.write(data);
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    fn exchange_with(response: &[u8], document: Option<&mut dyn Read>) -> (Result<Vec<u8>, &'static str>, Vec<u8>) {
+        let endpoint = Endpoint::parse("ipp://printer.local:631/ipp/print").unwrap();
+        let mut connection = Connection { response: Cursor::new(response.to_vec()), sent: vec![] };
+        let result = exchange(&mut connection, &endpoint, b"request", document);
+        return (result, connection.sent);
+    }
+
+    const CHUNKED_BODY: &[u8] = b"4\r\nWiki\r\n6;name=value\r\npedia \r\nE\r\nin \r\n\r\nchunks.\r\n0\r\n\r\n";
+
+    #[test]
+    fn decode_chunked_body() {
+        assert_eq!(decode_chunked(CHUNKED_BODY).unwrap(), b"Wikipedia in \r\n\r\nchunks.");
+        assert_eq!(decode_chunked(b"0\r\n\r\n").unwrap(), b"");
+    }
+
+    #[test]
+    fn decode_truncated_chunked_body() {
+        let last_chunk = CHUNKED_BODY.len() - 5;
+        for length in 0..last_chunk + 3 {
+            assert!(decode_chunked(&CHUNKED_BODY[..length]).is_none(), "decoded {length} bytes");
+        }
+        assert!(decode_chunked(b"zz\r\nWiki\r\n0\r\n\r\n").is_none());
+    }
+
+    #[test]
+    fn exchange_sends_chunked_request_and_document() {
+        let mut document = Cursor::new(b"%PDF".to_vec());
+        let (result, sent) = exchange_with(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nipp", Some(&mut document));
+
+        assert_eq!(result.unwrap(), b"ipp");
+        let sent = String::from_utf8(sent).unwrap();
+        assert!(sent.starts_with("POST /ipp/print HTTP/1.1\r\nHost: printer.local:631\r\n"));
+        assert!(sent.ends_with("\r\n\r\n7\r\nrequest\r\n4\r\n%PDF\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn exchange_reads_chunked_response() {
+        let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        response.extend_from_slice(CHUNKED_BODY);
+        let (result, _) = exchange_with(&response, None);
+        assert_eq!(result.unwrap(), b"Wikipedia in \r\n\r\nchunks.");
+    }
+
+    #[test]
+    fn exchange_reads_response_until_closed() {
+        let (result, _) = exchange_with(b"HTTP/1.0 200 OK\r\n\r\nipp", None);
+        assert_eq!(result.unwrap(), b"ipp");
+    }
+
+    #[test]
+    fn exchange_with_truncated_response() {
+        let mut response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        response.extend_from_slice(&CHUNKED_BODY[..20]);
+        assert_eq!(exchange_with(&response, None).0, Err("invalid http response"));
+        assert_eq!(exchange_with(b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nipp", None).0, Err("invalid http response"));
+        assert_eq!(exchange_with(b"HTTP/1.1 200 OK\r\n", None).0, Err("invalid http response"));
+    }
+
+    #[test]
+    fn exchange_with_http_error() {
+        let response = b"HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\n\r\n";
+        assert_eq!(exchange_with(response, None).0, Err("the printer requires authentication"));
+        let response = b"HTTP/1.1 426 Upgrade Required\r\nContent-Length: 0\r\n\r\n";
+        assert_eq!(exchange_with(response, None).0, Err("the printer requires an encrypted connection"));
+    }
+}
@@ -0,0 +1,420 @@
+// IPP/1.1 encoding (RFC 8010)
+pub const OPERATION_PRINT_JOB: u16 = 0x0002;
+pub const OPERATION_CANCEL_JOB: u16 = 0x0008;
+pub const OPERATION_GET_JOBS: u16 = 0x000A;
+pub const OPERATION_GET_PRINTER_ATTRIBUTES: u16 = 0x000B;
+pub const OPERATION_CUPS_GET_PRINTERS: u16 = 0x4002;
+
+pub const STATUS_OPERATION_NOT_SUPPORTED: u16 = 0x0501;
+
+pub const GROUP_OPERATION: u8 = 0x01;
+pub const GROUP_JOB: u8 = 0x02;
+const GROUP_END: u8 = 0x03;
+pub const GROUP_PRINTER: u8 = 0x04;
+
+pub const TAG_INTEGER: u8 = 0x21;
+pub const TAG_BOOLEAN: u8 = 0x22;
+pub const TAG_ENUM: u8 = 0x23;
+const TAG_DATE_TIME: u8 = 0x31;
+pub const TAG_RESOLUTION: u8 = 0x32;
+pub const TAG_RANGE: u8 = 0x33;
+const TAG_BEGIN_COLLECTION: u8 = 0x34;
+const TAG_END_COLLECTION: u8 = 0x37;
+pub const TAG_TEXT: u8 = 0x41;
+pub const TAG_NAME: u8 = 0x42;
+pub const TAG_KEYWORD: u8 = 0x44;
+pub const TAG_URI: u8 = 0x45;
+pub const TAG_CHARSET: u8 = 0x47;
+pub const TAG_NATURAL_LANGUAGE: u8 = 0x48;
+pub const TAG_MIME_MEDIA_TYPE: u8 = 0x49;
+
+// Resolution units of the resolution values
+const UNITS_DOTS_PER_INCH: u8 = 3;
+
+const VALUE_TOO_LONG: &str = "an ipp attribute name or value is longer than 65535 bytes";
+
+/**
+ * Value of an attribute, out of band values (unknown, no-value) and collections are Other
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i32),
+    Boolean(bool),
+    String(String),
+    Resolution(i32, i32),
+    Range(i32, i32),
+    Other,
+}
+
+impl Value {
+    pub fn as_integer(&self) -> Option<i32> {
+        return if let Value::Integer(value) = self { Some(*value) } else { None };
+    }
+
+    pub fn as_string(&self) -> Option<&str> {
+        return if let Value::String(value) = self { Some(value.as_str()) } else { None };
+    }
+}
+
+/**
+ * Attribute of a group, with every value of a 1setOf
+ */
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    pub name: String,
+    pub values: Vec<Value>,
+}
+
+/**
+ * Attributes of one group of a response, ex one printer of CUPS-Get-Printers
+ */
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub tag: u8,
+    pub attributes: Vec<Attribute>,
+}
+
+impl Group {
+    pub fn values(&self, name: &str) -> &[Value] {
+        return self.attributes.iter().find(|a| a.name == name).map(|a| a.values.as_slice()).unwrap_or_default();
+    }
+
+    pub fn integer(&self, name: &str) -> Option<i32> {
+        return self.values(name).first().and_then(|v| v.as_integer());
+    }
+
+    pub fn boolean(&self, name: &str) -> Option<bool> {
+        return match self.values(name).first() {
+            Some(Value::Boolean(value)) => Some(*value),
+            _ => None,
+        };
+    }
+
+    pub fn string(&self, name: &str) -> String {
+        return self.values(name).first().and_then(|v| v.as_string()).unwrap_or_default().to_string();
+    }
+
+    pub fn strings(&self, name: &str) -> Vec<String> {
+        return self.values(name).iter().filter_map(|v| v.as_string()).map(|s| s.to_string()).collect();
+    }
+}
+
+/**
+ * Response of an operation
+ */
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub groups: Vec<Group>,
+}
+
+impl Response {
+    /**
+     * Successful status codes are below 0x0100, the others are client or server errors
+     */
+    pub fn is_successful(&self) -> bool {
+        return self.status < 0x0100;
+    }
+
+    /**
+     * Groups with the tag, ex the printer groups of CUPS-Get-Printers
+     */
+    pub fn groups(&self, tag: u8) -> impl Iterator<Item = &Group> {
+        return self.groups.iter().filter(move |g| g.tag == tag);
+    }
+}
+
+/**
+ * Request of an operation, started with the attributes-charset, attributes-natural-language
+ * and, when given, printer-uri operation attributes
+ */
+pub struct Request {
+    buffer: Vec<u8>,
+    group: u8,
+    // Names and values are prefixed with a 16 bits length, a longer one fails finish
+    too_long: bool,
+}
+
+impl Request {
+    pub fn new(operation: u16, request_id: u32, printer_uri: Option<&str>) -> Request {
+        let mut buffer = vec![0x02, 0x00];
+        buffer.extend_from_slice(&operation.to_be_bytes());
+        buffer.extend_from_slice(&request_id.to_be_bytes());
+        buffer.push(GROUP_OPERATION);
+
+        let mut request = Request { buffer, group: GROUP_OPERATION, too_long: false };
+        request.add_string(TAG_CHARSET, "attributes-charset", "utf-8");
+        request.add_string(TAG_NATURAL_LANGUAGE, "attributes-natural-language", "en");
+        if let Some(printer_uri) = printer_uri {
+            request.add_string(TAG_URI, "printer-uri", printer_uri);
+        }
+        return request;
+    }
+
+    /**
+     * Start a new group, attributes added after belong to it
+     */
+    pub fn begin_group(&mut self, group: u8) {
+        if self.group != group {
+            self.buffer.push(group);
+            self.group = group;
+        }
+    }
+
+    fn add_value(&mut self, tag: u8, name: &str, value: &[u8]) {
+        if name.len() > u16::MAX as usize || value.len() > u16::MAX as usize {
+            self.too_long = true;
+            return;
+        }
+
+        self.buffer.push(tag);
+        self.buffer.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        self.buffer.extend_from_slice(name.as_bytes());
+        self.buffer.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        self.buffer.extend_from_slice(value);
+    }
+
+    pub fn add_string(&mut self, tag: u8, name: &str, value: &str) {
+        self.add_value(tag, name, value.as_bytes());
+    }
+
+    /**
+     * Add a 1setOf, the additional values have an empty name
+     */
+    pub fn add_strings(&mut self, tag: u8, name: &str, values: &[&str]) {
+        for (index, value) in values.iter().enumerate() {
+            self.add_string(tag, if index == 0 { name } else { "" }, value);
+        }
+    }
+
+    pub fn add_integer(&mut self, tag: u8, name: &str, value: i32) {
+        self.add_value(tag, name, &value.to_be_bytes());
+    }
+
+    pub fn add_integers(&mut self, tag: u8, name: &str, values: &[i32]) {
+        for (index, value) in values.iter().enumerate() {
+            self.add_integer(tag, if index == 0 { name } else { "" }, *value);
+        }
+    }
+
+    pub fn add_boolean(&mut self, name: &str, value: bool) {
+        self.add_value(TAG_BOOLEAN, name, &[value as u8]);
+    }
+
+    pub fn add_resolution(&mut self, name: &str, x: i32, y: i32) {
+        let mut value = vec![];
+        value.extend_from_slice(&x.to_be_bytes());
+        value.extend_from_slice(&y.to_be_bytes());
+        value.push(UNITS_DOTS_PER_INCH);
+        self.add_value(TAG_RESOLUTION, name, &value);
+    }
+
+    pub fn add_ranges(&mut self, name: &str, ranges: &[(u32, u32)]) {
+        for (index, (start, end)) in ranges.iter().enumerate() {
+            let mut value = vec![];
+            value.extend_from_slice(&(*start as i32).to_be_bytes());
+            value.extend_from_slice(&(*end as i32).to_be_bytes());
+            self.add_value(TAG_RANGE, if index == 0 { name } else { "" }, &value);
+        }
+    }
+
+    /**
+     * End the attributes, the document data of Print-Job follows them. Fails when a name or a
+     * value did not fit its length field, instead of sending a corrupted request
+     */
+    pub fn finish(mut self) -> Result<Vec<u8>, &'static str> {
+        if self.too_long {
+            return Err(VALUE_TOO_LONG);
+        }
+        self.buffer.push(GROUP_END);
+        return Ok(self.buffer);
+    }
+}
+
+fn read_u16(data: &[u8], position: usize) -> Option<u16> {
+    return data.get(position..position + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+}
+
+fn read_i32(data: &[u8]) -> Option<i32> {
+    return data.get(..4).map(|b| i32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+}
+
+fn decode_value(tag: u8, value: &[u8]) -> Value {
+    return match tag {
+        TAG_INTEGER | TAG_ENUM => read_i32(value).map(Value::Integer).unwrap_or(Value::Other),
+        TAG_BOOLEAN => Value::Boolean(value.first().is_some_and(|b| *b != 0)),
+        TAG_RESOLUTION if value.len() == 9 => {
+            Value::Resolution(read_i32(value).unwrap_or_default(), read_i32(&value[4..]).unwrap_or_default())
+        }
+        TAG_RANGE if value.len() == 8 => {
+            Value::Range(read_i32(value).unwrap_or_default(), read_i32(&value[4..]).unwrap_or_default())
+        }
+        TAG_DATE_TIME => Value::Other,
+        // octetString and the character string types, with or without language
+        0x30 | 0x35 | 0x36 | 0x40..=0x4F => Value::String(String::from_utf8_lossy(value).to_string()),
+        _ => Value::Other,
+    };
+}
+
+/**
+ * Parse the attribute groups of a response
+ */
+pub fn parse_response(data: &[u8]) -> Option<Response> {
+    let status = read_u16(data, 2)?;
+    let mut groups: Vec<Group> = vec![];
+    let mut position = 8;
+    let mut collection_depth = 0;
+
+    loop {
+        let tag = *data.get(position)?;
+        position += 1;
+
+        if tag == GROUP_END {
+            break;
+        }
+
+        if tag < 0x10 {
+            groups.push(Group { tag, attributes: vec![] });
+            continue;
+        }
+
+        let name_length = read_u16(data, position)? as usize;
+        let name = data.get(position + 2..position + 2 + name_length)?;
+        position += 2 + name_length;
+        let value_length = read_u16(data, position)? as usize;
+        let value = data.get(position + 2..position + 2 + value_length)?;
+        position += 2 + value_length;
+
+        // Members of a collection are skipped, the collection itself is kept as Other
+        if tag == TAG_BEGIN_COLLECTION {
+            collection_depth += 1;
+            if collection_depth > 1 {
+                continue;
+            }
+        } else if tag == TAG_END_COLLECTION {
+            collection_depth -= 1;
+            continue;
+        } else if collection_depth > 0 {
+            continue;
+        }
+
+        let group = groups.last_mut()?;
+        let value = decode_value(tag, value);
+
+        if name_length == 0 {
+            if let Some(attribute) = group.attributes.last_mut() {
+                attribute.values.push(value);
+            }
+        } else {
+            group.attributes.push(Attribute { name: String::from_utf8_lossy(name).to_string(), values: vec![value] });
+        }
+    }
+
+    return Some(Response { status, groups });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(tag: u8, name: &str, value: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![tag];
+        bytes.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(value);
+        return bytes;
+    }
+
+    #[test]
+    fn request_round_trip() {
+        let mut request = Request::new(OPERATION_PRINT_JOB, 7, Some("ipp://localhost/printers/office"));
+        request.add_string(TAG_NAME, "job-name", "report");
+        request.begin_group(GROUP_JOB);
+        request.add_integer(TAG_INTEGER, "copies", 2);
+        request.add_strings(TAG_KEYWORD, "finishings-col", &["staple", "punch"]);
+        request.add_boolean("job-hold", true);
+        request.add_resolution("printer-resolution", 600, 300);
+        request.add_ranges("page-ranges", &[(1, 3), (5, 5)]);
+
+        let bytes = request.finish().unwrap();
+        assert_eq!(&bytes[..8], &[0x02, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x07]);
+        assert_eq!(bytes.last(), Some(&GROUP_END));
+
+        let response = parse_response(&bytes).unwrap();
+        assert_eq!(response.status, OPERATION_PRINT_JOB);
+        assert_eq!(response.groups.len(), 2);
+
+        let operation = &response.groups[0];
+        assert_eq!(operation.tag, GROUP_OPERATION);
+        assert_eq!(operation.string("attributes-charset"), "utf-8");
+        assert_eq!(operation.string("printer-uri"), "ipp://localhost/printers/office");
+        assert_eq!(operation.string("job-name"), "report");
+
+        let job = &response.groups[1];
+        assert_eq!(job.tag, GROUP_JOB);
+        assert_eq!(job.integer("copies"), Some(2));
+        assert_eq!(job.strings("finishings-col"), vec!["staple", "punch"]);
+        assert_eq!(job.boolean("job-hold"), Some(true));
+        assert_eq!(job.values("printer-resolution"), &[Value::Resolution(600, 300)]);
+        assert_eq!(job.values("page-ranges"), &[Value::Range(1, 3), Value::Range(5, 5)]);
+    }
+
+    #[test]
+    fn request_with_too_long_value_fails() {
+        let mut request = Request::new(OPERATION_PRINT_JOB, 1, None);
+        request.add_string(TAG_NAME, "job-name", &"a".repeat(65536));
+        assert_eq!(request.finish(), Err(VALUE_TOO_LONG));
+
+        let mut request = Request::new(OPERATION_PRINT_JOB, 1, None);
+        request.add_string(TAG_NAME, "job-name", &"a".repeat(65535));
+        assert!(request.finish().is_ok());
+    }
+
+    // Get-Printer-Attributes response with a 1setOf keyword and a 1setOf collection,
+    // whose members are skipped
+    fn printer_response() -> Vec<u8> {
+        let mut bytes = vec![0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, GROUP_OPERATION];
+        bytes.extend(attribute(TAG_CHARSET, "attributes-charset", b"utf-8"));
+        bytes.push(GROUP_PRINTER);
+        bytes.extend(attribute(TAG_NAME, "printer-name", b"office"));
+        bytes.extend(attribute(TAG_KEYWORD, "sides-supported", b"one-sided"));
+        bytes.extend(attribute(TAG_KEYWORD, "", b"two-sided-long-edge"));
+        bytes.extend(attribute(TAG_BEGIN_COLLECTION, "media-col-database", b""));
+        bytes.extend(attribute(0x4A, "", b"media-size"));
+        bytes.extend(attribute(TAG_BEGIN_COLLECTION, "", b""));
+        bytes.extend(attribute(0x4A, "", b"x-dimension"));
+        bytes.extend(attribute(TAG_INTEGER, "", &21000i32.to_be_bytes()));
+        bytes.extend(attribute(TAG_END_COLLECTION, "", b""));
+        bytes.extend(attribute(TAG_END_COLLECTION, "", b""));
+        bytes.extend(attribute(TAG_BEGIN_COLLECTION, "", b""));
+        bytes.extend(attribute(0x4A, "", b"media-type"));
+        bytes.extend(attribute(TAG_KEYWORD, "", b"stationery"));
+        bytes.extend(attribute(TAG_END_COLLECTION, "", b""));
+        bytes.extend(attribute(TAG_ENUM, "printer-state", &3i32.to_be_bytes()));
+        bytes.push(GROUP_END);
+        return bytes;
+    }
+
+    #[test]
+    fn parse_response_with_collection_and_1set_of() {
+        let response = parse_response(&printer_response()).unwrap();
+        assert!(response.is_successful());
+        assert_eq!(response.groups.len(), 2);
+
+        let printer = response.groups(GROUP_PRINTER).next().unwrap();
+        assert_eq!(printer.string("printer-name"), "office");
+        assert_eq!(printer.strings("sides-supported"), vec!["one-sided", "two-sided-long-edge"]);
+        assert_eq!(printer.values("media-col-database"), &[Value::Other, Value::Other]);
+        assert_eq!(printer.integer("printer-state"), Some(3));
+        assert_eq!(printer.attributes.len(), 4);
+    }
+
+    #[test]
+    fn parse_truncated_response() {
+        let bytes = printer_response();
+        for length in 0..bytes.len() {
+            assert!(parse_response(&bytes[..length]).is_none(), "parsed {length} bytes");
+        }
+    }
+}
@@ -0,0 +1,554 @@
+use std::{
+    env,
+    fs::File,
+    io::Read,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::common::{
+    backend::PrinterBackend,
+    base::{
+        job::{JobId, JobOwner, JobQuery, JobState, PrinterJob, WhichJobs},
+        printer::{ColorMode, Duplex, OutputOrder, PrintOptions, PrintOrientation, PrintQuality, Printer, PrinterKind, PrinterState},
+    },
+};
+use http::Endpoint;
+use message::{Group, Request, Response};
+
+mod http;
+mod message;
+#[cfg(target_os = "linux")]
+pub mod usb;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Bits of the CUPS printer-type attribute
+const CUPS_PRINTER_CLASS: i32 = 0x1;
+const CUPS_PRINTER_REMOTE: i32 = 0x2;
+const CUPS_PRINTER_DEFAULT: i32 = 0x20000;
+
+const PRINTER_ATTRIBUTES: [&str; 14] = [
+    "printer-name",
+    "printer-info",
+    "printer-location",
+    "printer-make-and-model",
+    "printer-uri-supported",
+    "device-uri",
+    "printer-state",
+    "printer-state-reasons",
+    "printer-is-accepting-jobs",
+    "printer-is-shared",
+    "printer-is-temporary",
+    "printer-type",
+    "member-names",
+    "queued-job-count",
+];
+
+const JOB_ATTRIBUTES: [&str; 11] = [
+    "job-id",
+    "job-name",
+    "job-state",
+    "job-state-reasons",
+    "job-originating-user-name",
+    "document-format",
+    "job-impressions",
+    "job-impressions-completed",
+    "time-at-creation",
+    "time-at-processing",
+    "time-at-completed",
+];
+
+/**
+ * Connection the requests are sent on
+ */
+#[derive(Debug, Clone)]
+enum Transport {
+    Tcp,
+    #[cfg(target_os = "linux")]
+    Usb(usb::UsbDevice),
+}
+
+/**
+ * Client speaking IPP over HTTP in pure Rust, for systems without libcups (ex Alpine or scratch
+ * containers). The uri is a CUPS server (ipp://cups.local:631) or an IPP Everywhere device
+ * (ipp://printer.local/ipp/print), encrypted ipps connections are not supported
+ */
+#[derive(Debug, Clone)]
+pub struct IppClient {
+    endpoint: Endpoint,
+    transport: Transport,
+
+    /**
+     * Time given to connect and to every read and write (default is 30 seconds)
+     */
+    pub timeout: Duration,
+
+    /**
+     * requesting-user-name of the requests (default is the USER or USERNAME environment variable)
+     */
+    pub user_name: String,
+}
+
+impl IppClient {
+    pub fn new(uri: &str) -> Result<IppClient, &'static str> {
+        return Ok(IppClient {
+            endpoint: Endpoint::parse(uri)?,
+            transport: Transport::Tcp,
+            timeout: DEFAULT_TIMEOUT,
+            user_name: default_user_name(),
+        });
+    }
+
+    /**
+     * Create a client talking to a driverless USB printer over its IPP over USB interface, for
+     * systems where the ipp-usb daemon does not run (linux only, the daemon must be stopped since
+     * it holds the interface). Needs write access to the device in /dev/bus/usb
+     */
+    #[cfg(target_os = "linux")]
+    pub fn usb(device: &usb::UsbDevice) -> IppClient {
+        // IPP over USB devices expect the requests of a local http server
+        let endpoint = Endpoint { host: "localhost".to_string(), port: 80, path: "/ipp/print".to_string() };
+        return IppClient { endpoint, transport: Transport::Usb(device.clone()), timeout: DEFAULT_TIMEOUT, user_name: default_user_name() };
+    }
+
+    /**
+     * List the USB printers that can be reached with IppClient::usb (linux only)
+     */
+    #[cfg(target_os = "linux")]
+    pub fn usb_devices() -> Vec<usb::UsbDevice> {
+        return usb::find_ipp_usb_devices();
+    }
+
+    fn send(&self, request: Request, resource: &str, document: Option<&mut dyn Read>) -> Result<Response, &'static str> {
+        let endpoint = Endpoint { path: resource.to_string(), ..self.endpoint.clone() };
+        let request = request.finish()?;
+        let body = match &self.transport {
+            Transport::Tcp => http::post(&endpoint, &request, document, self.timeout)?,
+            #[cfg(target_os = "linux")]
+            Transport::Usb(device) => {
+                let mut connection = usb::UsbConnection::open(device, self.timeout)?;
+                http::exchange(&mut connection, &endpoint, &request, document)?
+            }
+        };
+        return message::parse_response(&body).ok_or("invalid ipp response");
+    }
+
+    /**
+     * Return the printers of a CUPS server (CUPS-Get-Printers), or the device itself when the uri
+     * is an IPP Everywhere printer (Get-Printer-Attributes)
+     */
+    pub fn get_printers(&self) -> Result<Vec<Printer>, &'static str> {
+        // A uri with a resource path is the one of a printer
+        if self.endpoint.path != "/" {
+            return Ok(vec![self.get_printer()?]);
+        }
+
+        let mut request = Request::new(message::OPERATION_CUPS_GET_PRINTERS, 1, None);
+        request.add_string(message::TAG_NAME, "requesting-user-name", &self.user_name);
+        request.add_strings(message::TAG_KEYWORD, "requested-attributes", &PRINTER_ATTRIBUTES);
+
+        let response = self.send(request, "/", None)?;
+        if response.is_successful() {
+            return Ok(response.groups(message::GROUP_PRINTER).map(printer).collect());
+        }
+
+        // IPP Everywhere devices do not implement the CUPS operations
+        if response.status == message::STATUS_OPERATION_NOT_SUPPORTED {
+            return Ok(vec![self.get_printer()?]);
+        }
+
+        return Err("the server failed to list its printers");
+    }
+
+    /**
+     * Return the printer at the uri of the client (Get-Printer-Attributes)
+     */
+    pub fn get_printer(&self) -> Result<Printer, &'static str> {
+        let printer_uri = self.endpoint.uri(&self.endpoint.path);
+        let mut request = Request::new(message::OPERATION_GET_PRINTER_ATTRIBUTES, 1, Some(&printer_uri));
+        request.add_string(message::TAG_NAME, "requesting-user-name", &self.user_name);
+        request.add_strings(message::TAG_KEYWORD, "requested-attributes", &PRINTER_ATTRIBUTES);
+
+        let response = self.send(request, &self.endpoint.path, None)?;
+
+        if !response.is_successful() {
+            return Err("the printer failed to return its attributes");
+        }
+
+        let group = response.groups(message::GROUP_PRINTER).next().ok_or("the printer returned no attributes")?;
+        let mut printer = printer(group);
+        if printer.uri.is_empty() {
+            printer.uri = printer_uri;
+        }
+        return Ok(printer);
+    }
+
+    /**
+     * Submit the data as a new job (Print-Job)
+     */
+    pub fn print(&self, printer: &Printer, buffer: &[u8], job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+        let mut buffer = buffer;
+        return self.print_reader(printer, &mut buffer, job_name, options);
+    }
+
+    /**
+     * Submit the file as a new job, named after the file unless a job name is given
+     */
+    pub fn print_file(&self, printer: &Printer, file_path: &Path, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+        let mut file = File::open(file_path).map_err(|_| "failed to open the file")?;
+        return self.print_reader(printer, &mut file, Some(job_name.unwrap_or(&file_path.to_string_lossy())), options);
+    }
+
+    /**
+     * Submit the data read from the reader as a new job, the document is streamed to the printer
+     */
+    pub fn print_reader(
+        &self,
+        printer: &Printer,
+        reader: &mut dyn Read,
+        job_name: Option<&str>,
+        options: PrintOptions,
+    ) -> Result<JobId, &'static str> {
+        options.check()?;
+        let document_format = options.document_format.as_ref().map(|f| f.mime_type()).unwrap_or("application/octet-stream");
+
+        let mut request = Request::new(message::OPERATION_PRINT_JOB, 1, Some(&printer.uri));
+        request.add_string(message::TAG_NAME, "requesting-user-name", options.requesting_user.as_ref().unwrap_or(&self.user_name));
+        request.add_string(message::TAG_NAME, "job-name", job_name.unwrap_or("untitled"));
+        request.add_string(message::TAG_MIME_MEDIA_TYPE, "document-format", document_format);
+        add_job_attributes(&mut request, &options);
+
+        let response = self.send(request, &resource(&printer.uri), Some(reader))?;
+        if !response.is_successful() {
+            return Err("the printer refused the job");
+        }
+
+        return response
+            .groups(message::GROUP_JOB)
+            .find_map(|g| g.integer("job-id"))
+            .map(|id| id as JobId)
+            .ok_or("the printer returned no job id");
+    }
+
+    /**
+     * Return the jobs of the printer (Get-Jobs)
+     */
+    pub fn get_jobs(&self, printer: &Printer, which: WhichJobs) -> Result<Vec<PrinterJob>, &'static str> {
+        let which_jobs = match which {
+            WhichJobs::Active => "not-completed",
+            WhichJobs::Completed => "completed",
+            WhichJobs::All => "all",
+        };
+
+        let mut request = Request::new(message::OPERATION_GET_JOBS, 1, Some(&printer.uri));
+        request.add_string(message::TAG_NAME, "requesting-user-name", &self.user_name);
+        request.add_string(message::TAG_KEYWORD, "which-jobs", which_jobs);
+        request.add_strings(message::TAG_KEYWORD, "requested-attributes", &JOB_ATTRIBUTES);
+
+        let response = self.send(request, &resource(&printer.uri), None)?;
+        if !response.is_successful() {
+            return Err("the printer failed to list its jobs");
+        }
+
+        return Ok(response.groups(message::GROUP_JOB).map(|g| job(g, &printer.system_name)).collect());
+    }
+
+    /**
+     * Cancel the job of the printer (Cancel-Job)
+     */
+    pub fn cancel_job(&self, printer: &Printer, job_id: JobId) -> Result<(), &'static str> {
+        let mut request = Request::new(message::OPERATION_CANCEL_JOB, 1, Some(&printer.uri));
+        request.add_integer(message::TAG_INTEGER, "job-id", job_id as i32);
+        request.add_string(message::TAG_NAME, "requesting-user-name", &self.user_name);
+
+        let response = self.send(request, &resource(&printer.uri), None)?;
+        if !response.is_successful() {
+            return Err("the printer failed to cancel the job");
+        }
+        return Ok(());
+    }
+
+    fn find_printer(&self, printer_name: &str) -> Result<Printer, &'static str> {
+        return self.get_printers()?.into_iter().find(|p| p.system_name == printer_name).ok_or("printer not found");
+    }
+}
+
+/**
+ * Registered with register_backend, the printers of the server join the ones of the system
+ * under the backend name "ipp". A second registered client replaces the first one
+ */
+impl PrinterBackend for IppClient {
+    fn name(&self) -> &str {
+        return "ipp";
+    }
+
+    fn printers(&self) -> Vec<Printer> {
+        return self.get_printers().unwrap_or_default();
+    }
+
+    fn print(
+        &self,
+        printer_name: &str,
+        document: &mut dyn Read,
+        job_name: Option<&str>,
+        options: &PrintOptions,
+    ) -> Result<JobId, &'static str> {
+        let printer = self.find_printer(printer_name)?;
+        return self.print_reader(&printer, document, job_name, options.clone());
+    }
+
+    fn jobs(&self, printer_name: &str, query: &JobQuery) -> Result<Vec<PrinterJob>, &'static str> {
+        let printer = self.find_printer(printer_name)?;
+        return Ok(self
+            .get_jobs(&printer, query.which_jobs)?
+            .into_iter()
+            .filter(|j| match &query.owner {
+                JobOwner::Any => true,
+                JobOwner::CurrentUser => j.user_name == self.user_name,
+                JobOwner::User(user_name) => j.user_name == *user_name,
+            })
+            .skip(query.offset.unwrap_or_default() as usize)
+            .take(query.limit.map(|limit| limit as usize).unwrap_or(usize::MAX))
+            .collect());
+    }
+
+    fn cancel_job(&self, printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        let printer = self.find_printer(printer_name)?;
+        return IppClient::cancel_job(self, &printer, job_id);
+    }
+}
+
+fn default_user_name() -> String {
+    return env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or("anonymous".to_string());
+}
+
+/**
+ * Resource path of a printer uri, ex /printers/office
+ */
+fn resource(uri: &str) -> String {
+    let rest = uri.split_once("://").map(|(_, rest)| rest).unwrap_or(uri);
+    return match rest.find('/') {
+        Some(index) => rest[index..].to_string(),
+        None => "/".to_string(),
+    };
+}
+
+fn printer_state(state: Option<i32>, state_reasons: &[String]) -> PrinterState {
+    if state_reasons.iter().any(|r| r == "offline-report") {
+        return PrinterState::OFFLINE;
+    }
+
+    return match state {
+        Some(3) => PrinterState::READY,
+        Some(4) => PrinterState::PRINTING,
+        Some(5) => PrinterState::PAUSED,
+        _ => PrinterState::UNKNOWN,
+    };
+}
+
+fn printer(group: &Group) -> Printer {
+    let printer_type = group.integer("printer-type").unwrap_or_default();
+    let state_reasons = match group.strings("printer-state-reasons") {
+        reasons if reasons.is_empty() => vec!["none".to_string()],
+        reasons => reasons,
+    };
+
+    let uris = group.strings("printer-uri-supported");
+    let uri = uris.iter().find(|u| u.starts_with("ipp://")).or(uris.first()).cloned().unwrap_or_default();
+
+    let kind = if printer_type & CUPS_PRINTER_CLASS != 0 {
+        PrinterKind::Class
+    } else if group.boolean("printer-is-temporary") == Some(true) {
+        PrinterKind::Temporary
+    } else if printer_type & CUPS_PRINTER_REMOTE != 0 {
+        PrinterKind::RemoteQueue
+    } else {
+        PrinterKind::Local
+    };
+
+    let system_name = group.string("printer-name");
+    let info = group.string("printer-info");
+
+    return Printer {
+        name: if info.is_empty() { system_name.clone() } else { info.clone() },
+        system_name,
+        driver_name: group.string("printer-make-and-model"),
+        uri,
+        kind,
+        make_and_model: group.string("printer-make-and-model"),
+        device_uri: group.string("device-uri"),
+        port_name: "".to_string(),
+        processor: "".to_string(),
+        data_type: "".to_string(),
+        description: info,
+        location: group.string("printer-location"),
+        is_default: printer_type & CUPS_PRINTER_DEFAULT != 0,
+        is_shared: group.boolean("printer-is-shared").unwrap_or_default(),
+        is_accepting_jobs: group.boolean("printer-is-accepting-jobs").unwrap_or(true),
+        is_class: printer_type & CUPS_PRINTER_CLASS != 0,
+        members: group.strings("member-names"),
+        state: printer_state(group.integer("printer-state"), &state_reasons),
+        state_reasons,
+        attributes: printer_type as u32 as u64,
+        jobs_count: group.integer("queued-job-count").filter(|c| *c >= 0).map(|c| c as u32),
+    };
+}
+
+fn job_state(state: i32) -> JobState {
+    return match state {
+        3 => JobState::PENDING,
+        4 => JobState::HELD,
+        5 => JobState::PROCESSING,
+        6 => JobState::STOPPED,
+        7 => JobState::CANCELED,
+        8 => JobState::ABORTED,
+        9 => JobState::COMPLETED,
+        _ => JobState::UNKNOWN,
+    };
+}
+
+// CUPS reports the job times in seconds since the epoch, devices send 0 or their uptime
+fn job_time(group: &Group, name: &str) -> Option<SystemTime> {
+    return group
+        .integer(name)
+        .filter(|t| *t > 0)
+        .map(|t| UNIX_EPOCH + Duration::from_secs(t as u64));
+}
+
+fn job(group: &Group, printer_name: &str) -> PrinterJob {
+    let state = group.integer("job-state").unwrap_or_default();
+    return PrinterJob {
+        id: group.integer("job-id").unwrap_or_default() as JobId,
+        name: group.string("job-name"),
+        state: job_state(state),
+        raw_state: state as u64,
+        state_reasons: group.strings("job-state-reasons"),
+        media_type: group.string("document-format"),
+        pages_completed: group.integer("job-impressions-completed").filter(|v| *v >= 0).map(|v| v as u32),
+        pages_total: group.integer("job-impressions").filter(|v| *v >= 0).map(|v| v as u32),
+        created_at: job_time(group, "time-at-creation").unwrap_or(UNIX_EPOCH),
+        processed_at: job_time(group, "time-at-processing"),
+        completed_at: job_time(group, "time-at-completed"),
+        printer_name: printer_name.to_string(),
+        user_name: group.string("job-originating-user-name"),
+    };
+}
+
+/**
+ * Map the print options to Print-Job job attributes, raw options are sent as keywords
+ */
+fn add_job_attributes(request: &mut Request, options: &PrintOptions) {
+    request.begin_group(message::GROUP_JOB);
+
+    if let Some(orientation) = &options.orientation {
+        let value = if *orientation == PrintOrientation::Landscape { 4 } else { 3 };
+        request.add_integer(message::TAG_ENUM, "orientation-requested", value);
+    }
+
+    if let Some(copies) = options.copies {
+        request.add_integer(message::TAG_INTEGER, "copies", copies as i32);
+    }
+
+    if let Some(duplex) = &options.duplex {
+        let value = match duplex {
+            Duplex::OneSided => "one-sided",
+            Duplex::TwoSidedLongEdge => "two-sided-long-edge",
+            Duplex::TwoSidedShortEdge => "two-sided-short-edge",
+        };
+        request.add_string(message::TAG_KEYWORD, "sides", value);
+    }
+
+    if let Some(media_size) = &options.media_size {
+        request.add_string(message::TAG_KEYWORD, "media", &media_size.pwg_name());
+    }
+
+    if let Some(page_ranges) = &options.page_ranges {
+        request.add_ranges("page-ranges", page_ranges.ranges());
+    }
+
+    if let Some(color_mode) = &options.color_mode {
+        let value = if *color_mode == ColorMode::Color { "color" } else { "monochrome" };
+        request.add_string(message::TAG_KEYWORD, "print-color-mode", value);
+    }
+
+    if let Some(quality) = &options.quality {
+        let value = match quality {
+            PrintQuality::Draft => 3,
+            PrintQuality::Normal => 4,
+            PrintQuality::High => 5,
+        };
+        request.add_integer(message::TAG_ENUM, "print-quality", value);
+    }
+
+    if let Some(resolution) = &options.resolution {
+        request.add_resolution("printer-resolution", resolution.x as i32, resolution.y as i32);
+    }
+
+    if let Some(number_up) = options.number_up {
+        request.add_integer(message::TAG_INTEGER, "number-up", number_up as i32);
+    }
+
+    if let Some(collate) = options.collate {
+        let value = if collate { "separate-documents-collated-copies" } else { "separate-documents-uncollated-copies" };
+        request.add_string(message::TAG_KEYWORD, "multiple-document-handling", value);
+    }
+
+    if let Some(output_order) = &options.output_order {
+        let value = if *output_order == OutputOrder::Reverse { "reverse-order" } else { "normal-order" };
+        request.add_string(message::TAG_KEYWORD, "page-delivery", value);
+    }
+
+    if !options.finishings.is_empty() {
+        let values: Vec<i32> = options.finishings.iter().map(|f| f.ipp_value() as i32).collect();
+        request.add_integers(message::TAG_ENUM, "finishings", &values);
+    }
+
+    if let Some(media_source) = &options.media_source {
+        request.add_string(message::TAG_KEYWORD, "media-source", media_source.ipp_keyword());
+    }
+
+    if let Some(output_bin) = &options.output_bin {
+        request.add_string(message::TAG_KEYWORD, "output-bin", &output_bin.ipp_keyword());
+    }
+
+    if let Some(job_sheets) = &options.job_sheets {
+        let value = job_sheets.to_string();
+        request.add_strings(message::TAG_NAME, "job-sheets", &value.split(',').collect::<Vec<&str>>());
+    }
+
+    if let Some(hold_until) = &options.hold_until {
+        request.add_string(message::TAG_KEYWORD, "job-hold-until", &hold_until.to_string());
+    }
+
+    if let Some(priority) = options.priority {
+        request.add_integer(message::TAG_INTEGER, "job-priority", priority as i32);
+    }
+
+    if let Some(fit_to_page) = options.fit_to_page {
+        request.add_string(message::TAG_KEYWORD, "print-scaling", if fit_to_page { "fit" } else { "none" });
+    }
+
+    if let Some(job_password) = &options.job_password {
+        request.add_string(message::TAG_TEXT, "job-password", job_password);
+    }
+
+    if let Some(encryption) = &options.job_password_encryption {
+        request.add_string(message::TAG_KEYWORD, "job-password-encryption", encryption.ipp_keyword());
+    }
+
+    if let Some(job_account_id) = &options.job_account_id {
+        request.add_string(message::TAG_NAME, "job-account-id", job_account_id);
+    }
+
+    if let Some(job_accounting_user_id) = &options.job_accounting_user_id {
+        request.add_string(message::TAG_NAME, "job-accounting-user-id", job_accounting_user_id);
+    }
+
+    for (name, value) in &options.raw_options {
+        match value.as_str() {
+            "true" | "false" => request.add_boolean(name, value == "true"),
+            _ => request.add_string(message::TAG_KEYWORD, name, value),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    ffi::CString,
+    fs,
+    io::{Error, ErrorKind, Read, Write},
+    mem,
+    path::Path,
+    time::Duration,
+};
+
+use libc::{c_int, c_uint, c_ulong, c_void};
+
+// USB printer class interfaces with the IPP over USB protocol (IPP-USB 1.0)
+const USB_CLASS_PRINTER: u8 = 0x07;
+const USB_SUBCLASS_PRINTER: u8 = 0x01;
+const USB_PROTOCOL_IPP_USB: u8 = 0x04;
+
+const USB_DESCRIPTOR_INTERFACE: u8 = 0x04;
+const USB_DESCRIPTOR_ENDPOINT: u8 = 0x05;
+const USB_ENDPOINT_BULK: u8 = 0x02;
+const USB_ENDPOINT_IN: u8 = 0x80;
+
+const SYSFS_USB_DEVICES: &str = "/sys/bus/usb/devices";
+
+// Largest bulk transfer submitted at once
+const TRANSFER_SIZE: usize = 16 * 1024;
+
+#[repr(C)]
+struct UsbdevfsBulkTransfer {
+    ep: c_uint,
+    len: c_uint,
+    timeout: c_uint,
+    data: *mut c_void,
+}
+
+#[repr(C)]
+struct UsbdevfsSetInterface {
+    interface: c_uint,
+    altsetting: c_uint,
+}
+
+#[repr(C)]
+struct UsbdevfsIoctl {
+    ifno: c_int,
+    ioctl_code: c_int,
+    data: *mut c_void,
+}
+
+// ioctl request numbers of linux/usbdevice_fs.h
+const fn ioc(direction: c_ulong, number: c_ulong, size: usize) -> c_ulong {
+    return (direction << 30) | ((size as c_ulong) << 16) | ((b'U' as c_ulong) << 8) | number;
+}
+
+const IOC_NONE: c_ulong = 0;
+const IOC_WRITE: c_ulong = 1;
+const IOC_READ: c_ulong = 2;
+
+const USBDEVFS_BULK: c_ulong = ioc(IOC_READ | IOC_WRITE, 2, mem::size_of::<UsbdevfsBulkTransfer>());
+const USBDEVFS_SETINTERFACE: c_ulong = ioc(IOC_READ, 4, mem::size_of::<UsbdevfsSetInterface>());
+const USBDEVFS_CLAIMINTERFACE: c_ulong = ioc(IOC_READ, 15, mem::size_of::<c_uint>());
+const USBDEVFS_RELEASEINTERFACE: c_ulong = ioc(IOC_READ, 16, mem::size_of::<c_uint>());
+const USBDEVFS_IOCTL: c_ulong = ioc(IOC_READ | IOC_WRITE, 18, mem::size_of::<UsbdevfsIoctl>());
+const USBDEVFS_DISCONNECT: c_ulong = ioc(IOC_NONE, 22, 0);
+const USBDEVFS_CONNECT: c_ulong = ioc(IOC_NONE, 23, 0);
+
+/**
+ * USB printer with an IPP over USB interface, found with find_ipp_usb_devices
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsbDevice {
+    pub bus: u8,
+    pub address: u8,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub manufacturer: String,
+    pub product: String,
+    pub serial: String,
+
+    interface: u8,
+    alternate_setting: u8,
+    endpoint_in: u8,
+    endpoint_out: u8,
+}
+
+fn read_attribute(device: &Path, name: &str) -> String {
+    return fs::read_to_string(device.join(name)).map(|v| v.trim().to_string()).unwrap_or_default();
+}
+
+/**
+ * Find the first IPP over USB interface in the descriptors of the active configuration, returning
+ * its number, alternate setting and bulk in and out endpoints
+ */
+fn find_ipp_interface(descriptors: &[u8]) -> Option<(u8, u8, u8, u8)> {
+    let mut position = 0;
+    let mut current: Option<(u8, u8)> = None;
+    let mut endpoints: (Option<u8>, Option<u8>) = (None, None);
+
+    while position + 2 <= descriptors.len() {
+        let length = descriptors[position] as usize;
+        if length < 2 || position + length > descriptors.len() {
+            break;
+        }
+        let descriptor = &descriptors[position..position + length];
+        position += length;
+
+        match descriptor[1] {
+            USB_DESCRIPTOR_INTERFACE if length >= 9 => {
+                if let (Some((interface, alternate)), (Some(endpoint_in), Some(endpoint_out))) = (current, endpoints) {
+                    return Some((interface, alternate, endpoint_in, endpoint_out));
+                }
+
+                let is_ipp = descriptor[5] == USB_CLASS_PRINTER
+                    && descriptor[6] == USB_SUBCLASS_PRINTER
+                    && descriptor[7] == USB_PROTOCOL_IPP_USB;
+                current = if is_ipp { Some((descriptor[2], descriptor[3])) } else { None };
+                endpoints = (None, None);
+            }
+            USB_DESCRIPTOR_ENDPOINT if length >= 7 && current.is_some() && descriptor[3] & 0x03 == USB_ENDPOINT_BULK => {
+                if descriptor[2] & USB_ENDPOINT_IN != 0 {
+                    endpoints.0 = endpoints.0.or(Some(descriptor[2]));
+                } else {
+                    endpoints.1 = endpoints.1.or(Some(descriptor[2]));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    return match (current, endpoints) {
+        (Some((interface, alternate)), (Some(endpoint_in), Some(endpoint_out))) => {
+            Some((interface, alternate, endpoint_in, endpoint_out))
+        }
+        _ => None,
+    };
+}
+
+/**
+ * List the USB printers with an IPP over USB interface, read from sysfs
+ */
+pub fn find_ipp_usb_devices() -> Vec<UsbDevice> {
+    let Ok(entries) = fs::read_dir(SYSFS_USB_DEVICES) else { return vec![] };
+    let mut devices = vec![];
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        // Interfaces (ex 1-1:1.0) live next to the devices
+        if entry.file_name().to_string_lossy().contains(':') {
+            continue;
+        }
+
+        let Ok(descriptors) = fs::read(path.join("descriptors")) else { continue };
+        // The device descriptor comes before the configuration descriptors
+        let Some((interface, alternate_setting, endpoint_in, endpoint_out)) = descriptors.get(18..).and_then(find_ipp_interface)
+        else {
+            continue;
+        };
+
+        devices.push(UsbDevice {
+            bus: read_attribute(&path, "busnum").parse().unwrap_or_default(),
+            address: read_attribute(&path, "devnum").parse().unwrap_or_default(),
+            vendor_id: u16::from_str_radix(&read_attribute(&path, "idVendor"), 16).unwrap_or_default(),
+            product_id: u16::from_str_radix(&read_attribute(&path, "idProduct"), 16).unwrap_or_default(),
+            manufacturer: read_attribute(&path, "manufacturer"),
+            product: read_attribute(&path, "product"),
+            serial: read_attribute(&path, "serial"),
+            interface,
+            alternate_setting,
+            endpoint_in,
+            endpoint_out,
+        });
+    }
+
+    devices.sort_by_key(|d| (d.bus, d.address));
+    return devices;
+}
+
+/**
+ * Claimed IPP over USB interface, carrying HTTP on its bulk endpoints. The usblp driver is
+ * detached from the interface while it is open and attached again on drop
+ */
+pub struct UsbConnection {
+    fd: c_int,
+    interface: c_uint,
+    endpoint_in: u8,
+    endpoint_out: u8,
+    timeout: c_uint,
+    detached: bool,
+}
+
+impl UsbConnection {
+    pub fn open(device: &UsbDevice, timeout: Duration) -> Result<UsbConnection, &'static str> {
+        let path = format!("/dev/bus/usb/{:03}/{:03}", device.bus, device.address);
+        let path = CString::new(path).map_err(|_| "failed to open the usb device")?;
+
+        let fd = unsafe { libc::open(path.as_ptr(), libc::O_RDWR | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Err("failed to open the usb device, check the permissions of /dev/bus/usb");
+        }
+
+        let mut connection = UsbConnection {
+            fd,
+            interface: device.interface as c_uint,
+            endpoint_in: device.endpoint_in,
+            endpoint_out: device.endpoint_out,
+            timeout: timeout.as_millis().min(c_uint::MAX as u128) as c_uint,
+            detached: false,
+        };
+
+        // Fails when no driver is bound to the interface, which is fine
+        let mut disconnect = UsbdevfsIoctl { ifno: device.interface as c_int, ioctl_code: USBDEVFS_DISCONNECT as c_int, data: std::ptr::null_mut() };
+        connection.detached = unsafe { libc::ioctl(fd, USBDEVFS_IOCTL as _, &mut disconnect) } >= 0;
+
+        let mut interface = connection.interface;
+        if unsafe { libc::ioctl(fd, USBDEVFS_CLAIMINTERFACE as _, &mut interface) } < 0 {
+            return Err("failed to claim the usb interface, is the ipp-usb daemon running?");
+        }
+
+        let mut setting = UsbdevfsSetInterface { interface: connection.interface, altsetting: device.alternate_setting as c_uint };
+        if unsafe { libc::ioctl(fd, USBDEVFS_SETINTERFACE as _, &mut setting) } < 0 {
+            return Err("failed to select the ipp over usb interface");
+        }
+
+        return Ok(connection);
+    }
+
+    fn bulk(&mut self, endpoint: u8, data: *mut c_void, length: usize) -> std::io::Result<usize> {
+        let mut transfer = UsbdevfsBulkTransfer {
+            ep: endpoint as c_uint,
+            len: length.min(TRANSFER_SIZE) as c_uint,
+            timeout: self.timeout,
+            data,
+        };
+
+        let transferred = unsafe { libc::ioctl(self.fd, USBDEVFS_BULK as _, &mut transfer) };
+        if transferred < 0 {
+            let error = Error::last_os_error();
+            return Err(if error.raw_os_error() == Some(libc::ETIMEDOUT) { Error::from(ErrorKind::TimedOut) } else { error });
+        }
+        return Ok(transferred as usize);
+    }
+}
+
+impl Read for UsbConnection {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        // Devices send empty packets while they prepare the response
+        loop {
+            let length = self.bulk(self.endpoint_in, buffer.as_mut_ptr() as *mut c_void, buffer.len())?;
+            if length > 0 || buffer.is_empty() {
+                return Ok(length);
+            }
+        }
+    }
+}
+
+impl Write for UsbConnection {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        return self.bulk(self.endpoint_out, buffer.as_ptr() as *mut c_void, buffer.len());
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        return Ok(());
+    }
+}
+
+impl Drop for UsbConnection {
+    fn drop(&mut self) {
+        unsafe {
+            let mut interface = self.interface;
+            libc::ioctl(self.fd, USBDEVFS_RELEASEINTERFACE as _, &mut interface);
+
+            if self.detached {
+                let mut connect = UsbdevfsIoctl { ifno: self.interface as c_int, ioctl_code: USBDEVFS_CONNECT as c_int, data: std::ptr::null_mut() };
+                libc::ioctl(self.fd, USBDEVFS_IOCTL as _, &mut connect);
+            }
+
+            libc::close(self.fd);
+        }
+    }
+}
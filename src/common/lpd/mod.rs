@@ -0,0 +1,310 @@
+use std::{
+    env,
+    fs::File,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    process,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::common::{
+    base::job::{JobId, JobState, PrinterJob},
+    probe::device_address,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Daemon commands and receive job subcommands (RFC 1179)
+const COMMAND_RECEIVE_JOB: u8 = 0x02;
+const COMMAND_SHORT_QUEUE_STATE: u8 = 0x03;
+const COMMAND_LONG_QUEUE_STATE: u8 = 0x04;
+const COMMAND_REMOVE_JOBS: u8 = 0x05;
+const SUBCOMMAND_CONTROL_FILE: u8 = 0x02;
+const SUBCOMMAND_DATA_FILE: u8 = 0x03;
+
+// Control file lines are limited, RFC 1179 allows 31 characters for the host and 99 for names
+const MAX_HOST_LENGTH: usize = 31;
+const MAX_NAME_LENGTH: usize = 99;
+
+// Job numbers have three digits
+static NEXT_JOB_NUMBER: AtomicU64 = AtomicU64::new(0);
+
+/**
+ * Queue of a printer or print server speaking the LPD protocol (RFC 1179), ex lpd://10.0.0.5/lp.
+ * Servers that only accept connections from the reserved ports 721 to 731 are not supported
+ */
+#[derive(Debug, Clone)]
+pub struct LpdPrinter {
+    pub host: String,
+    pub port: u16,
+    pub queue: String,
+
+    /**
+     * Time given to connect and to every read and write (default is 30 seconds)
+     */
+    pub timeout: Duration,
+
+    /**
+     * User the jobs are submitted and removed as (default is the USER or USERNAME environment variable)
+     */
+    pub user_name: String,
+}
+
+fn local_host_name() -> String {
+    let host = env::var("HOSTNAME").or_else(|_| env::var("COMPUTERNAME")).unwrap_or("localhost".to_string());
+    return host.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '.').take(MAX_HOST_LENGTH).collect();
+}
+
+// Names end at the line feed of the control file line
+fn control_value(value: &str) -> String {
+    return value.chars().filter(|c| !c.is_control()).take(MAX_NAME_LENGTH).collect();
+}
+
+/**
+ * Parse the short queue state of BSD lpd, ex "active  root  12  notes.txt  1024 bytes", the
+ * lines of other formats are skipped
+ */
+fn parse_queue_state(queue: &str, state: &str) -> Vec<PrinterJob> {
+    let mut jobs = vec![];
+
+    for line in state.lines() {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        if columns.len() < 4 {
+            continue;
+        }
+
+        let rank = columns[0];
+        let is_active = rank == "active";
+        let is_ranked = ["st", "nd", "rd", "th"].iter().any(|s| rank.strip_suffix(s).is_some_and(|n| n.parse::<u32>().is_ok()));
+        let Ok(id) = columns[2].parse::<JobId>() else { continue };
+
+        if !is_active && !is_ranked {
+            continue;
+        }
+
+        // The files column may hold spaces, the size is the last two columns
+        let name_end = if columns.len() >= 6 && columns[columns.len() - 1] == "bytes" { columns.len() - 2 } else { columns.len() };
+
+        jobs.push(PrinterJob {
+            id,
+            name: columns[3..name_end].join(" "),
+            state: if is_active { JobState::PROCESSING } else { JobState::PENDING },
+            raw_state: 0,
+            state_reasons: vec![],
+            media_type: "".to_string(),
+            pages_completed: None,
+            pages_total: None,
+            created_at: UNIX_EPOCH,
+            processed_at: None,
+            completed_at: None,
+            printer_name: queue.to_string(),
+            user_name: columns[1].to_string(),
+        });
+    }
+
+    return jobs;
+}
+
+impl LpdPrinter {
+    /**
+     * Create from an lpd uri, ex lpd://10.0.0.5/lp or lpd://server:515/office, the port is 515
+     * when not given
+     */
+    pub fn new(uri: &str) -> Result<LpdPrinter, &'static str> {
+        let (scheme, host, port) = device_address(uri).ok_or("invalid printer uri")?;
+        if scheme != "lpd" {
+            return Err("the printer uri is not an lpd uri");
+        }
+
+        let rest = uri.split_once("://").map(|(_, rest)| rest).unwrap_or_default();
+        let queue = rest.split_once('/').map(|(_, queue)| queue).unwrap_or_default();
+        let queue = queue.split(['?', '/']).next().unwrap_or_default();
+        if queue.is_empty() {
+            return Err("the printer uri has no queue name");
+        }
+
+        let user_name = env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or("anonymous".to_string());
+        return Ok(LpdPrinter { host, port, queue: queue.to_string(), timeout: DEFAULT_TIMEOUT, user_name });
+    }
+
+    fn connect(&self) -> Result<TcpStream, &'static str> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or("failed to resolve the printer host")?;
+
+        let stream = TcpStream::connect_timeout(&address, self.timeout).map_err(|_| "failed to connect to the printer")?;
+        stream.set_read_timeout(Some(self.timeout)).map_err(|_| "failed to connect to the printer")?;
+        stream.set_write_timeout(Some(self.timeout)).map_err(|_| "failed to connect to the printer")?;
+        return Ok(stream);
+    }
+
+    /**
+     * Send a command and read the reply until the server closes the connection
+     */
+    fn command(&self, command: u8, operands: &str) -> Result<String, &'static str> {
+        let mut stream = self.connect()?;
+        let line = format!("{}{}\n", command as char, operands);
+        stream.write_all(line.as_bytes()).map_err(|_| "failed to send the command to the printer")?;
+
+        let mut reply = vec![];
+        stream.read_to_end(&mut reply).map_err(|_| "failed to read the printer reply")?;
+        return Ok(String::from_utf8_lossy(&reply).to_string());
+    }
+
+    /**
+     * Send bytes and wait for the one byte acknowledgement, zero when the server accepted them
+     */
+    fn send_acknowledged(stream: &mut TcpStream, data: &[u8]) -> Result<(), &'static str> {
+        stream.write_all(data).map_err(|_| "failed to send the job to the printer")?;
+
+        let mut acknowledgement = [0u8; 1];
+        stream.read_exact(&mut acknowledgement).map_err(|_| "the printer did not acknowledge the job")?;
+        return if acknowledgement[0] == 0 { Ok(()) } else { Err("the printer refused the job") };
+    }
+
+    /**
+     * Submit the bytes as a new job, printed as they are (the l filter of the control file)
+     */
+    pub fn print(&self, buffer: &[u8], job_name: Option<&str>) -> Result<JobId, &'static str> {
+        return self.print_document(buffer, job_name.unwrap_or("untitled"), 1);
+    }
+
+    /**
+     * Submit the file as a new job, named after the file unless a job name is given
+     */
+    pub fn print_file(&self, file_path: &Path, job_name: Option<&str>) -> Result<JobId, &'static str> {
+        let mut buffer = vec![];
+        File::open(file_path)
+            .and_then(|mut file| file.read_to_end(&mut buffer))
+            .map_err(|_| "failed to read the file")?;
+        return self.print_document(&buffer, job_name.unwrap_or(&file_path.to_string_lossy()), 1);
+    }
+
+    /**
+     * Submit the data read from the reader as a new job, LPD announces the size of the data
+     * first so it is read in memory before being sent
+     */
+    pub fn print_reader(&self, reader: &mut dyn Read, job_name: Option<&str>) -> Result<JobId, &'static str> {
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer).map_err(|_| "failed to read the document")?;
+        return self.print_document(&buffer, job_name.unwrap_or("untitled"), 1);
+    }
+
+    /**
+     * Submit the bytes as a job printed the given number of times
+     */
+    pub fn print_copies(&self, buffer: &[u8], job_name: Option<&str>, copies: u32) -> Result<JobId, &'static str> {
+        return self.print_document(buffer, job_name.unwrap_or("untitled"), copies.max(1));
+    }
+
+    fn print_document(&self, buffer: &[u8], job_name: &str, copies: u32) -> Result<JobId, &'static str> {
+        let job_number = (process::id() as u64 + NEXT_JOB_NUMBER.fetch_add(1, Ordering::Relaxed)) % 1000;
+        let host = local_host_name();
+        let data_file = format!("dfA{:03}{}", job_number, host);
+        let control_file_name = format!("cfA{:03}{}", job_number, host);
+        let job_name = control_value(job_name);
+
+        let mut control = format!("H{}\nP{}\nJ{}\nN{}\n", host, control_value(&self.user_name), job_name, job_name);
+        for _ in 0..copies {
+            control.push_str(&format!("l{}\n", data_file));
+        }
+        control.push_str(&format!("U{}\n", data_file));
+
+        let mut stream = self.connect()?;
+        Self::send_acknowledged(&mut stream, format!("{}{}\n", COMMAND_RECEIVE_JOB as char, self.queue).as_bytes())?;
+
+        let subcommand = format!("{}{} {}\n", SUBCOMMAND_CONTROL_FILE as char, control.len(), control_file_name);
+        Self::send_acknowledged(&mut stream, subcommand.as_bytes())?;
+        Self::send_acknowledged(&mut stream, &[control.as_bytes(), &[0]].concat())?;
+
+        let subcommand = format!("{}{} {}\n", SUBCOMMAND_DATA_FILE as char, buffer.len(), data_file);
+        Self::send_acknowledged(&mut stream, subcommand.as_bytes())?;
+        stream.write_all(buffer).map_err(|_| "failed to send the job to the printer")?;
+        Self::send_acknowledged(&mut stream, &[0])?;
+
+        return Ok(job_number);
+    }
+
+    /**
+     * Return the queue state as sent by the server, long lists more details of each job
+     */
+    pub fn queue_state(&self, long: bool) -> Result<String, &'static str> {
+        let command = if long { COMMAND_LONG_QUEUE_STATE } else { COMMAND_SHORT_QUEUE_STATE };
+        return self.command(command, &self.queue);
+    }
+
+    /**
+     * Return the jobs of the queue, read from the short queue state. Only the BSD lpd format is
+     * understood, the creation time is not reported
+     */
+    pub fn get_jobs(&self) -> Result<Vec<PrinterJob>, &'static str> {
+        let state = self.queue_state(false)?;
+        return Ok(parse_queue_state(&self.queue, &state));
+    }
+
+    /**
+     * Remove the job from the queue, servers only let the user that submitted it remove it
+     */
+    pub fn remove_job(&self, job_id: JobId) -> Result<(), &'static str> {
+        let operands = format!("{} {} {}", self.queue, control_value(&self.user_name), job_id);
+        self.command(COMMAND_REMOVE_JOBS, &operands)?;
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BSD_QUEUE_STATE: &str = "lp is ready and printing
+Rank   Owner      Job  Files                                 Total Size
+active root       12   notes.txt                             1024 bytes
+1st    alice      13   my report.pdf                         20480 bytes
+2nd    bob        14   (standard input)                      512 bytes
+";
+
+    #[test]
+    fn parse_bsd_queue_state() {
+        let jobs = parse_queue_state("lp", BSD_QUEUE_STATE);
+        assert_eq!(jobs.len(), 3);
+
+        assert_eq!(jobs[0].id, 12);
+        assert_eq!(jobs[0].name, "notes.txt");
+        assert_eq!(jobs[0].user_name, "root");
+        assert_eq!(jobs[0].state, JobState::PROCESSING);
+        assert_eq!(jobs[0].printer_name, "lp");
+
+        assert_eq!(jobs[1].id, 13);
+        assert_eq!(jobs[1].name, "my report.pdf");
+        assert_eq!(jobs[1].user_name, "alice");
+        assert_eq!(jobs[1].state, JobState::PENDING);
+
+        assert_eq!(jobs[2].id, 14);
+        assert_eq!(jobs[2].name, "(standard input)");
+        assert_eq!(jobs[2].state, JobState::PENDING);
+    }
+
+    #[test]
+    fn parse_empty_queue_state() {
+        assert!(parse_queue_state("lp", "no entries\n").is_empty());
+        assert!(parse_queue_state("lp", "").is_empty());
+    }
+
+    #[test]
+    fn parse_truncated_queue_state() {
+        let jobs = parse_queue_state("lp", "Rank   Owner      Job  Files\n3rd    carol      15   draft\n4th    dave  1");
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, 15);
+        assert_eq!(jobs[0].name, "draft");
+
+        for length in 0..BSD_QUEUE_STATE.len() {
+            let jobs = parse_queue_state("lp", &BSD_QUEUE_STATE[..length]);
+            assert!(jobs.len() <= 3);
+            assert!(jobs.iter().all(|j| (12..=14).contains(&j.id)));
+        }
+    }
+}
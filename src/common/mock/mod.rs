@@ -0,0 +1,293 @@
+use std::{
+    env,
+    io::Read,
+    sync::{Arc, Mutex, MutexGuard},
+    time::SystemTime,
+};
+
+use crate::common::{
+    backend::PrinterBackend,
+    base::{
+        job::{JobId, JobOwner, JobQuery, JobState, PrinterJob, WhichJobs},
+        printer::{PrintOptions, Printer, PrinterState},
+    },
+};
+
+/**
+ * Job received by a MockBackend, with the data that was printed
+ */
+#[derive(Debug, Clone)]
+pub struct SubmittedJob {
+    pub job_id: JobId,
+    pub printer_name: String,
+    pub job_name: Option<String>,
+    pub options: PrintOptions,
+    pub data: Vec<u8>,
+}
+
+struct MockJob {
+    job: PrinterJob,
+
+    // Index in the script of the current state
+    step: usize,
+
+    // Listed at least once in the current step
+    listed: bool,
+}
+
+struct State {
+    printers: Vec<Printer>,
+    jobs: Vec<MockJob>,
+    submitted: Vec<SubmittedJob>,
+    script: Vec<JobState>,
+    print_error: Option<&'static str>,
+    next_job_id: JobId,
+}
+
+/**
+ * Backend of virtual printers for the tests of applications, where no spooler exists. Clones
+ * share their printers and jobs, so a clone can be registered with register_backend while the
+ * test keeps another one to check what was printed
+ */
+#[derive(Clone)]
+pub struct MockBackend {
+    name: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        return MockBackend::new("mock");
+    }
+}
+
+fn set_state(job: &mut PrinterJob, state: JobState) {
+    if state == JobState::PROCESSING && job.processed_at.is_none() {
+        job.processed_at = Some(SystemTime::now());
+    }
+    if state.is_terminal() && job.completed_at.is_none() {
+        job.completed_at = Some(SystemTime::now());
+    }
+    job.state = state;
+}
+
+impl MockBackend {
+    /**
+     * Create a backend without printers, named as given in the registry of backends. New jobs
+     * go through PENDING, PROCESSING and COMPLETED, see set_job_script
+     */
+    pub fn new(name: &str) -> MockBackend {
+        return MockBackend {
+            name: name.to_string(),
+            state: Arc::new(Mutex::new(State {
+                printers: vec![],
+                jobs: vec![],
+                submitted: vec![],
+                script: vec![JobState::PENDING, JobState::PROCESSING, JobState::COMPLETED],
+                print_error: None,
+                next_job_id: 1,
+            })),
+        };
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        return self.state.lock().unwrap_or_else(|e| e.into_inner());
+    }
+
+    /**
+     * Add a printer, replacing the one with the same system name. Printer::new gives a printer
+     * in the UNKNOWN state, set its state to READY to look like an idle queue
+     */
+    pub fn add_printer(&self, printer: Printer) {
+        let mut state = self.lock();
+        state.printers.retain(|p| p.system_name != printer.system_name);
+        state.printers.push(printer);
+    }
+
+    /**
+     * Remove the printer and its jobs
+     */
+    pub fn remove_printer(&self, printer_name: &str) {
+        let mut state = self.lock();
+        state.printers.retain(|p| p.system_name != printer_name);
+        state.jobs.retain(|j| j.job.printer_name != printer_name);
+    }
+
+    /**
+     * Change the state of a printer, ex to PAUSED or OFFLINE, with its state reasons
+     */
+    pub fn set_printer_state(&self, printer_name: &str, printer_state: PrinterState, state_reasons: &[&str]) {
+        let mut state = self.lock();
+        if let Some(printer) = state.printers.iter_mut().find(|p| p.system_name == printer_name) {
+            printer.state = printer_state;
+            printer.state_reasons = match state_reasons {
+                [] => vec!["none".to_string()],
+                reasons => reasons.iter().map(|r| r.to_string()).collect(),
+            };
+        }
+    }
+
+    /**
+     * Set the states the jobs go through, ex [PENDING, PROCESSING, ABORTED]. A job is
+     * listed once in each state and stays in the last one
+     */
+    pub fn set_job_script(&self, script: &[JobState]) {
+        self.lock().script = script.to_vec();
+    }
+
+    /**
+     * Change the state of a job right away, it only follows its script again once it reached
+     * a later state of it
+     */
+    pub fn set_job_state(&self, job_id: JobId, job_state: JobState) -> Result<(), &'static str> {
+        let mut state = self.lock();
+        let job = state.jobs.iter_mut().find(|j| j.job.id == job_id).ok_or("job not found")?;
+        set_state(&mut job.job, job_state);
+        job.listed = false;
+        return Ok(());
+    }
+
+    /**
+     * Make the next print fail with the given error instead of creating a job
+     */
+    pub fn fail_next_print(&self, error: &'static str) {
+        self.lock().print_error = Some(error);
+    }
+
+    /**
+     * Return the jobs received so far with their data, in the order they were printed
+     */
+    pub fn submitted(&self) -> Vec<SubmittedJob> {
+        return self.lock().submitted.clone();
+    }
+
+    /**
+     * Forget the jobs and the submitted data, the printers are kept
+     */
+    pub fn clear_jobs(&self) {
+        let mut state = self.lock();
+        state.jobs.clear();
+        state.submitted.clear();
+    }
+}
+
+impl PrinterBackend for MockBackend {
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    fn printers(&self) -> Vec<Printer> {
+        return self.lock().printers.clone();
+    }
+
+    fn print(
+        &self,
+        printer_name: &str,
+        document: &mut dyn Read,
+        job_name: Option<&str>,
+        options: &PrintOptions,
+    ) -> Result<JobId, &'static str> {
+        let mut data = vec![];
+        document.read_to_end(&mut data).map_err(|_| "failed to read the document")?;
+
+        let mut state = self.lock();
+        if let Some(error) = state.print_error.take() {
+            return Err(error);
+        }
+
+        let printer = state.printers.iter().find(|p| p.system_name == printer_name).ok_or("printer not found")?;
+        if !printer.is_accepting_jobs {
+            return Err("the printer is not accepting jobs");
+        }
+
+        let job_id = state.next_job_id;
+        state.next_job_id += 1;
+
+        let mut job = PrinterJob {
+            id: job_id,
+            name: job_name.unwrap_or_default().to_string(),
+            state: JobState::PENDING,
+            raw_state: 0,
+            state_reasons: vec![],
+            media_type: options.document_format.as_ref().map(|f| f.mime_type().to_string()).unwrap_or_default(),
+            pages_completed: None,
+            pages_total: None,
+            created_at: SystemTime::now(),
+            processed_at: None,
+            completed_at: None,
+            printer_name: printer_name.to_string(),
+            user_name: match &options.requesting_user {
+                Some(requesting_user) => requesting_user.clone(),
+                None => env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_default(),
+            },
+        };
+        if let Some(first) = state.script.first() {
+            set_state(&mut job, first.clone());
+        }
+
+        state.jobs.push(MockJob { job, step: 0, listed: false });
+        state.submitted.push(SubmittedJob {
+            job_id,
+            printer_name: printer_name.to_string(),
+            job_name: job_name.map(|n| n.to_string()),
+            options: options.clone(),
+            data,
+        });
+
+        return Ok(job_id);
+    }
+
+    fn jobs(&self, printer_name: &str, query: &JobQuery) -> Result<Vec<PrinterJob>, &'static str> {
+        let mut state = self.lock();
+        let State { jobs, script, .. } = &mut *state;
+
+        // A job is seen once in every state of its script, then moves one step further on the next listing
+        for job in jobs.iter_mut().filter(|j| j.job.printer_name == printer_name && !j.job.state.is_terminal()) {
+            if !job.listed {
+                job.listed = true;
+                continue;
+            }
+            let current = script.iter().position(|s| *s == job.job.state).unwrap_or(job.step);
+            job.step = job.step.max(current);
+            if let Some(next) = script.get(job.step + 1) {
+                job.step += 1;
+                set_state(&mut job.job, next.clone());
+            }
+        }
+
+        let current_user = env::var("USER").or_else(|_| env::var("USERNAME")).unwrap_or_default();
+        return Ok(jobs
+            .iter()
+            .map(|j| &j.job)
+            .filter(|j| j.printer_name == printer_name)
+            .filter(|j| match query.which_jobs {
+                WhichJobs::Active => j.state.is_active(),
+                WhichJobs::Completed => j.state.is_terminal(),
+                WhichJobs::All => true,
+            })
+            .filter(|j| match &query.owner {
+                JobOwner::Any => true,
+                JobOwner::CurrentUser => j.user_name == current_user,
+                JobOwner::User(user_name) => j.user_name == *user_name,
+            })
+            .skip(query.offset.unwrap_or_default() as usize)
+            .take(query.limit.map(|limit| limit as usize).unwrap_or(usize::MAX))
+            .cloned()
+            .collect());
+    }
+
+    fn cancel_job(&self, printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        let mut state = self.lock();
+        let job = state
+            .jobs
+            .iter_mut()
+            .find(|j| j.job.id == job_id && j.job.printer_name == printer_name)
+            .ok_or("job not found")?;
+
+        if job.job.state.is_terminal() {
+            return Err("the job is already finished");
+        }
+        set_state(&mut job.job, JobState::CANCELED);
+        return Ok(());
+    }
+}
@@ -1,2 +1,19 @@
+pub mod backend;
 pub mod base;
-pub(crate) mod traits;
+#[cfg(feature = "discovery")]
+pub mod discovery;
+#[cfg(feature = "backend-ipp")]
+pub mod ipp;
+#[cfg(feature = "backend-lpd")]
+pub mod lpd;
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "preflight")]
+pub mod preflight;
+pub(crate) mod probe;
+pub mod render;
+#[cfg(feature = "backend-socket")]
+pub mod socket;
+#[cfg(feature = "snmp")]
+pub mod snmp;
+pub(crate) mod traits;
\ No newline at end of file
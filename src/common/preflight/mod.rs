@@ -0,0 +1,86 @@
+use lopdf::Document;
+
+use crate::common::base::printer::PrintOptions;
+
+/**
+ * Result of inspecting a PDF document against the print options
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreflightReport {
+    /**
+     * Number of pages in the document
+     */
+    pub page_count: u32,
+
+    /**
+     * Number of document pages selected by the page ranges, for each copy
+     */
+    pub selected_pages: u32,
+
+    /**
+     * Requested (first, last) ranges that reach past the last page
+     */
+    pub out_of_range: Vec<(u32, u32)>,
+
+    /**
+     * Definition if the document is encrypted, printers may refuse it
+     */
+    pub is_encrypted: bool,
+}
+
+impl PreflightReport {
+    /**
+     * Fail when the document would not print as requested
+     */
+    pub fn check(&self) -> Result<(), &'static str> {
+        if self.page_count == 0 {
+            return Err("document has no pages");
+        }
+
+        if !self.out_of_range.is_empty() {
+            return Err("page ranges exceed the document page count");
+        }
+
+        if self.selected_pages == 0 {
+            return Err("page ranges select no pages");
+        }
+
+        return Ok(());
+    }
+}
+
+/**
+ * Parse the PDF data and compare its pages with the page ranges of the options
+ */
+pub fn preflight_pdf(data: &[u8], options: &PrintOptions) -> Result<PreflightReport, &'static str> {
+    let document = Document::load_mem(data).map_err(|_| "invalid PDF document")?;
+    let page_count = document.get_pages().len() as u32;
+
+    let (selected_pages, out_of_range) = match &options.page_ranges {
+        Some(page_ranges) => {
+            let mut selected = vec![false; page_count as usize];
+            for (first, last) in page_ranges.ranges() {
+                for page in *first..=(*last).min(page_count) {
+                    selected[page as usize - 1] = true;
+                }
+            }
+
+            let out_of_range = page_ranges
+                .ranges()
+                .iter()
+                .filter(|(_, last)| *last > page_count)
+                .copied()
+                .collect();
+
+            (selected.iter().filter(|s| **s).count() as u32, out_of_range)
+        }
+        None => (page_count, vec![]),
+    };
+
+    return Ok(PreflightReport {
+        page_count,
+        selected_pages,
+        out_of_range,
+        is_encrypted: document.is_encrypted(),
+    });
+}
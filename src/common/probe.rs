@@ -0,0 +1,83 @@
+use std::{
+    io::ErrorKind,
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/**
+ * Split a network device uri (ex socket://10.0.0.5:9100) in scheme, host and port,
+ * using the default port of the scheme when the uri has none
+ */
+pub fn device_address(device_uri: &str) -> Option<(String, String, u16)> {
+    let (scheme, rest) = device_uri.split_once("://")?;
+    let authority = rest.split(['/', '?']).next()?;
+    let authority = authority.rsplit('@').next()?;
+
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(ipv6) => {
+            let (host, rest) = ipv6.split_once(']')?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+
+    let default_port = match scheme {
+        "socket" => 9100,
+        "lpd" => 515,
+        "ipp" | "ipps" => 631,
+        "http" => 80,
+        "https" => 443,
+        _ => return None,
+    };
+
+    let port = match port {
+        Some(port) => port.parse::<u16>().ok()?,
+        None => default_port,
+    };
+
+    return if host.is_empty() { None } else { Some((scheme.to_string(), host.to_string(), port)) };
+}
+
+/**
+ * Open a TCP connection to the device and return how long it took, name resolution excluded
+ */
+pub fn probe_tcp(host: &str, port: u16) -> Result<Duration, &'static str> {
+    let addresses = (host, port).to_socket_addrs().map_err(|_| "failed to resolve the device host")?;
+
+    let started = Instant::now();
+    let mut error = "failed to resolve the device host";
+    for address in addresses {
+        error = match TcpStream::connect_timeout(&address, PROBE_TIMEOUT) {
+            Ok(_) => return Ok(started.elapsed()),
+            Err(e) if e.kind() == ErrorKind::TimedOut => "timed out connecting to the device",
+            Err(e) if e.kind() == ErrorKind::ConnectionRefused => "the device refused the connection",
+            Err(_) => "failed to connect to the device",
+        };
+    }
+
+    return Err(error);
+}
+
+/**
+ * Host of a network device, from its uri or from a windows standard TCP/IP port name (ex IP_10.0.0.5)
+ */
+#[cfg(feature = "snmp")]
+pub fn device_host(device_uri: &str) -> Option<String> {
+    return match device_uri.strip_prefix("IP_") {
+        Some(host) => Some(host.to_string()),
+        None => device_address(device_uri).map(|(_, host, _)| host),
+    };
+}
+
+/**
+ * Probe the port of a network device uri
+ */
+pub fn probe_device_port(device_uri: &str) -> Result<Duration, &'static str> {
+    let (_, host, port) = device_address(device_uri).ok_or("probing is only supported for network devices")?;
+    return probe_tcp(&host, port);
+}
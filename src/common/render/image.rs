@@ -0,0 +1,200 @@
+use image::{codecs::jpeg::JpegEncoder, DynamicImage};
+
+use crate::common::{
+    base::printer::{MediaSize, PageMargins},
+    render::pdf::PdfDocument,
+};
+
+/**
+ * How the image size is computed on the page
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageScale {
+    /**
+     * Scale to fit the printable area, keeping the aspect ratio
+     */
+    Fit,
+    /**
+     * Scale to cover the printable area, keeping the aspect ratio and cropping the overflow
+     */
+    Fill,
+    /**
+     * Keep the image pixels at the given dots per inch
+     */
+    Dpi(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageRotation {
+    None,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /**
+     * Rotate 90 degrees when the image and the page orientations differ
+     */
+    Auto,
+}
+
+/**
+ * Layout of images printed by Printer::print_image
+ */
+#[derive(Debug, Clone)]
+pub struct ImagePrintOptions {
+    pub scale: ImageScale,
+    pub rotation: ImageRotation,
+
+    /**
+     * Center the image on the printable area, otherwise it is placed at the top left corner
+     */
+    pub center: bool,
+
+    /**
+     * Page size, a named media name without known dimensions falls back to A4
+     */
+    pub media_size: MediaSize,
+
+    pub margins: PageMargins,
+
+    /**
+     * Quality of the JPEG embedded in the document, from 1 to 100
+     */
+    pub jpeg_quality: u8,
+}
+
+impl Default for ImagePrintOptions {
+    fn default() -> Self {
+        return ImagePrintOptions {
+            scale: ImageScale::Fit,
+            rotation: ImageRotation::None,
+            center: true,
+            media_size: MediaSize::A4,
+            margins: PageMargins::points(18.0),
+            jpeg_quality: 90,
+        };
+    }
+}
+
+/**
+ * Image rotated as asked and its place on the page, in points from the bottom left corner
+ */
+pub(crate) struct ImagePlacement {
+    pub image: DynamicImage,
+    pub width: f32,
+    pub height: f32,
+    pub x: f32,
+    pub y: f32,
+    pub draw_width: f32,
+    pub draw_height: f32,
+}
+
+/**
+ * Rotate and scale the image in the printable area of the page
+ */
+pub(crate) fn place_image(image: &DynamicImage, options: &ImagePrintOptions) -> Result<ImagePlacement, &'static str> {
+    let (width, height) = options
+        .media_size
+        .dimensions()
+        .or(MediaSize::A4.dimensions())
+        .map(|(w, h)| (w.to_points(), h.to_points()))
+        .unwrap();
+
+    let margins = &options.margins;
+    let area_width = width - margins.left - margins.right;
+    let area_height = height - margins.top - margins.bottom;
+
+    if area_width <= 0.0 || area_height <= 0.0 {
+        return Err("margins leave no room for the image");
+    }
+
+    let image = match options.rotation {
+        ImageRotation::None => image.clone(),
+        ImageRotation::Rotate90 => image.rotate90(),
+        ImageRotation::Rotate180 => image.rotate180(),
+        ImageRotation::Rotate270 => image.rotate270(),
+        ImageRotation::Auto => {
+            if (image.width() > image.height()) != (area_width > area_height) {
+                image.rotate90()
+            } else {
+                image.clone()
+            }
+        }
+    };
+
+    if image.width() == 0 || image.height() == 0 {
+        return Err("image is empty");
+    }
+
+    let (pixels_width, pixels_height) = (image.width() as f32, image.height() as f32);
+    let points_per_pixel = match options.scale {
+        ImageScale::Fit => (area_width / pixels_width).min(area_height / pixels_height),
+        ImageScale::Fill => (area_width / pixels_width).max(area_height / pixels_height),
+        ImageScale::Dpi(0) => return Err("dpi must be greater than zero"),
+        ImageScale::Dpi(dpi) => 72.0 / dpi as f32,
+    };
+
+    let draw_width = pixels_width * points_per_pixel;
+    let draw_height = pixels_height * points_per_pixel;
+    let (x, y) = if options.center {
+        (
+            margins.left + (area_width - draw_width) / 2.0,
+            margins.bottom + (area_height - draw_height) / 2.0,
+        )
+    } else {
+        (margins.left, margins.bottom + area_height - draw_height)
+    };
+
+    return Ok(ImagePlacement {
+        image,
+        width,
+        height,
+        x,
+        y,
+        draw_width,
+        draw_height,
+    });
+}
+
+/**
+ * Render the image as a single page PDF document
+ */
+pub fn render_image(image: &DynamicImage, options: &ImagePrintOptions) -> Result<Vec<u8>, &'static str> {
+    let placement = place_image(image, options)?;
+    let margins = &options.margins;
+    let area_width = placement.width - margins.left - margins.right;
+    let area_height = placement.height - margins.top - margins.bottom;
+
+    let rgb = placement.image.to_rgb8();
+    let mut jpeg = vec![];
+    JpegEncoder::new_with_quality(&mut jpeg, options.jpeg_quality.clamp(1, 100))
+        .encode_image(&rgb)
+        .map_err(|_| "failed to encode the image")?;
+
+    let mut document = PdfDocument::new();
+    let image_id = document.add_stream(
+        &format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /DCTDecode",
+            rgb.width(),
+            rgb.height()
+        ),
+        &jpeg,
+    );
+
+    let resources = format!("<< /XObject << /Im1 {} 0 R >> >>", image_id);
+    // Clip to the printable area, so filled images do not overflow the margins
+    let content = format!(
+        "q\n{:.2} {:.2} {:.2} {:.2} re W n\n{:.2} 0 0 {:.2} {:.2} {:.2} cm\n/Im1 Do\nQ",
+        margins.left,
+        margins.bottom,
+        area_width,
+        area_height,
+        placement.draw_width,
+        placement.draw_height,
+        placement.x,
+        placement.y
+    );
+    document.add_page(placement.width, placement.height, &resources, content.as_bytes());
+
+    return Ok(document.into_bytes());
+}
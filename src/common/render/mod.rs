@@ -0,0 +1,5 @@
+#[cfg(feature = "image")]
+pub mod image;
+pub(crate) mod pdf;
+pub(crate) mod test_page;
+pub mod text;
@@ -0,0 +1,90 @@
+use std::io::Write;
+
+/**
+ * Minimal PDF 1.4 writer, enough to render text and images for printing
+ */
+pub struct PdfDocument {
+    objects: Vec<Vec<u8>>,
+    pages: Vec<usize>,
+}
+
+// Objects reserved for the catalog and page tree, written on finish
+const CATALOG_ID: usize = 1;
+const PAGES_ID: usize = 2;
+
+impl PdfDocument {
+    pub fn new() -> Self {
+        return PdfDocument {
+            objects: vec![vec![], vec![]],
+            pages: vec![],
+        };
+    }
+
+    /**
+     * Add an object and return its id
+     */
+    pub fn add_object(&mut self, body: &[u8]) -> usize {
+        self.objects.push(body.to_vec());
+        return self.objects.len();
+    }
+
+    /**
+     * Add a stream object, dict holds the entries besides /Length
+     */
+    pub fn add_stream(&mut self, dict: &str, data: &[u8]) -> usize {
+        let mut body = format!("<< {} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+        return self.add_object(&body);
+    }
+
+    /**
+     * Add a page of width x height points, with its resources dict and content stream
+     */
+    pub fn add_page(&mut self, width: f32, height: f32, resources: &str, content: &[u8]) -> usize {
+        let content_id = self.add_stream("", content);
+        let page = format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources {} /Contents {} 0 R >>",
+            PAGES_ID, width, height, resources, content_id
+        );
+        let page_id = self.add_object(page.as_bytes());
+        self.pages.push(page_id);
+        return page_id;
+    }
+
+    pub fn into_bytes(mut self) -> Vec<u8> {
+        let kids: Vec<String> = self.pages.iter().map(|id| format!("{} 0 R", id)).collect();
+        self.objects[CATALOG_ID - 1] = format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES_ID).into_bytes();
+        self.objects[PAGES_ID - 1] = format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            self.pages.len()
+        )
+        .into_bytes();
+
+        let mut output: Vec<u8> = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = vec![];
+
+        for (i, body) in self.objects.iter().enumerate() {
+            offsets.push(output.len());
+            let _ = writeln!(output, "{} 0 obj", i + 1);
+            output.extend_from_slice(body);
+            output.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = output.len();
+        let _ = write!(output, "xref\n0 {}\n0000000000 65535 f \n", self.objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(output, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            output,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.objects.len() + 1,
+            CATALOG_ID,
+            xref_offset
+        );
+
+        return output;
+    }
+}
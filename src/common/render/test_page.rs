@@ -0,0 +1,141 @@
+use crate::common::{
+    base::printer::MediaSize,
+    render::{
+        pdf::PdfDocument,
+        text::{escape, win_ansi_byte},
+    },
+};
+
+const MARGIN: f32 = 36.0;
+// Alignment grid step, 10 mm in points
+const GRID_STEP: f32 = 72.0 / 2.54;
+const BAR_SIZE: f32 = 48.0;
+
+// Gray levels and rgb colors of the bars, each bar is labeled below
+const GRAY_BARS: [(f32, &str); 5] = [(1.0, "0%"), (0.75, "25%"), (0.5, "50%"), (0.25, "75%"), (0.0, "100%")];
+const COLOR_BARS: [((f32, f32, f32), &str); 6] = [
+    ((0.0, 1.0, 1.0), "Cyan"),
+    ((1.0, 0.0, 1.0), "Magenta"),
+    ((1.0, 1.0, 0.0), "Yellow"),
+    ((1.0, 0.0, 0.0), "Red"),
+    ((0.0, 1.0, 0.0), "Green"),
+    ((0.0, 0.0, 1.0), "Blue"),
+];
+
+fn text(content: &mut Vec<u8>, font: &str, size: f32, x: f32, y: f32, value: &str) {
+    let encoded: Vec<u8> = value.chars().filter(|c| !c.is_control()).map(win_ansi_byte).collect();
+    content.extend(format!("BT\n/{} {:.2} Tf\n{:.2} {:.2} Td\n(", font, size, x, y).into_bytes());
+    content.extend(escape(&encoded));
+    content.extend_from_slice(b") Tj\nET\n");
+}
+
+/**
+ * Render a test page as a PDF document: the information lines, a border at the margins,
+ * a 10 mm alignment grid and gray and color bars
+ */
+pub fn render_test_page(info: &[(&str, String)], media_size: &MediaSize) -> Result<Vec<u8>, &'static str> {
+    let (width, height) = media_size
+        .dimensions()
+        .or(MediaSize::A4.dimensions())
+        .map(|(w, h)| (w.to_points(), h.to_points()))
+        .unwrap();
+
+    if width <= MARGIN * 4.0 || height <= MARGIN * 4.0 {
+        return Err("media size is too small for the test page");
+    }
+
+    let mut content: Vec<u8> = vec![];
+
+    // Alignment grid, starting at the bottom left margin
+    content.extend_from_slice(b"0.8 G 0.25 w\n");
+    let mut x = MARGIN;
+    while x <= width - MARGIN {
+        content.extend(format!("{:.2} {:.2} m {:.2} {:.2} l\n", x, MARGIN, x, height - MARGIN).into_bytes());
+        x += GRID_STEP;
+    }
+    let mut y = MARGIN;
+    while y <= height - MARGIN {
+        content.extend(format!("{:.2} {:.2} m {:.2} {:.2} l\n", MARGIN, y, width - MARGIN, y).into_bytes());
+        y += GRID_STEP;
+    }
+    content.extend_from_slice(b"S\n");
+
+    // Border and center cross
+    content.extend(
+        format!(
+            "0 G 1 w\n{:.2} {:.2} {:.2} {:.2} re S\n{:.2} {:.2} m {:.2} {:.2} l {:.2} {:.2} m {:.2} {:.2} l S\n",
+            MARGIN,
+            MARGIN,
+            width - MARGIN * 2.0,
+            height - MARGIN * 2.0,
+            width / 2.0 - GRID_STEP,
+            height / 2.0,
+            width / 2.0 + GRID_STEP,
+            height / 2.0,
+            width / 2.0,
+            height / 2.0 - GRID_STEP,
+            width / 2.0,
+            height / 2.0 + GRID_STEP
+        )
+        .into_bytes(),
+    );
+
+    // White panel behind the text, so the grid does not get in the way
+    let line_height = 16.0;
+    let panel_height = 48.0 + info.len() as f32 * line_height;
+    let panel_top = height - MARGIN - GRID_STEP;
+    content.extend(
+        format!(
+            "1 g {:.2} {:.2} {:.2} {:.2} re f 0 g\n",
+            MARGIN + GRID_STEP,
+            panel_top - panel_height,
+            width - (MARGIN + GRID_STEP) * 2.0,
+            panel_height
+        )
+        .into_bytes(),
+    );
+
+    let text_x = MARGIN + GRID_STEP * 1.5;
+    text(&mut content, "F2", 22.0, text_x, panel_top - 28.0, "Printer test page");
+    for (i, (label, value)) in info.iter().enumerate() {
+        let y = panel_top - 52.0 - i as f32 * line_height;
+        text(&mut content, "F2", 10.0, text_x, y, label);
+        text(&mut content, "F1", 10.0, text_x + 110.0, y, value);
+    }
+
+    // Gray and color bars at the bottom
+    let bars_x = MARGIN + GRID_STEP * 1.5;
+    let color_bars_y = MARGIN + GRID_STEP * 2.0;
+    let gray_bars_y = color_bars_y + BAR_SIZE + 28.0;
+
+    for (i, (gray, label)) in GRAY_BARS.iter().enumerate() {
+        let x = bars_x + i as f32 * (BAR_SIZE + 8.0);
+        let bar = format!("{:.2} {:.2} {:.2} {:.2} re", x, gray_bars_y, BAR_SIZE, BAR_SIZE);
+        content.extend(format!("{:.2} g {} f 0 G 0.5 w {} S 0 g\n", gray, bar, bar).into_bytes());
+        text(&mut content, "F1", 8.0, x, gray_bars_y - 10.0, label);
+    }
+
+    for (i, ((r, g, b), label)) in COLOR_BARS.iter().enumerate() {
+        let x = bars_x + i as f32 * (BAR_SIZE + 8.0);
+        let bar = format!("{:.2} {:.2} {:.2} {:.2} re", x, color_bars_y, BAR_SIZE, BAR_SIZE);
+        content.extend(format!("{:.2} {:.2} {:.2} rg {} f 0 g\n", r, g, b, bar).into_bytes());
+        text(&mut content, "F1", 8.0, x, color_bars_y - 10.0, label);
+    }
+
+    text(
+        &mut content,
+        "F1",
+        8.0,
+        MARGIN + 4.0,
+        MARGIN + 6.0,
+        "The border should be fully visible and the grid squares should measure 10 mm",
+    );
+
+    let mut document = PdfDocument::new();
+    let regular_id = document.add_object(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>");
+    let bold_id = document.add_object(b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>");
+    let resources = format!("<< /Font << /F1 {} 0 R /F2 {} 0 R >> >>", regular_id, bold_id);
+    document.add_page(width, height, &resources, &content);
+
+    return Ok(document.into_bytes());
+}
@@ -0,0 +1,206 @@
+use crate::common::{
+    base::printer::{MediaSize, PageMargins},
+    render::pdf::PdfDocument,
+};
+
+// Courier glyphs are all 600/1000 em wide
+pub(crate) const COURIER_CHAR_WIDTH: f32 = 0.6;
+const TAB_SIZE: usize = 4;
+
+/**
+ * Layout of text rendered by Printer::print_text
+ */
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    /**
+     * Font size in points, the font is always monospaced (Courier)
+     */
+    pub font_size: f32,
+
+    /**
+     * Line height as a factor of the font size
+     */
+    pub line_spacing: f32,
+
+    /**
+     * Page size, a named media name without known dimensions falls back to A4
+     */
+    pub media_size: MediaSize,
+
+    /**
+     * Space around the text
+     */
+    pub margins: PageMargins,
+
+    /**
+     * Wrap lines longer than the page width, otherwise they are cut
+     */
+    pub wrap: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        return TextOptions {
+            font_size: 10.0,
+            line_spacing: 1.2,
+            media_size: MediaSize::A4,
+            margins: PageMargins::points(36.0),
+            wrap: true,
+        };
+    }
+}
+
+/**
+ * Convert a char to the WinAnsiEncoding used by the standard PDF fonts
+ */
+pub(crate) fn win_ansi_byte(c: char) -> u8 {
+    return match c as u32 {
+        0x20..=0x7E | 0xA0..=0xFF => c as u8,
+        _ => match c {
+            '€' => 0x80,
+            '‚' => 0x82,
+            '„' => 0x84,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            '™' => 0x99,
+            _ => b'?',
+        },
+    };
+}
+
+/**
+ * Text split in pages of lines fitting the page, sizes are in points
+ */
+pub(crate) struct TextLayout {
+    pub width: f32,
+    pub height: f32,
+    pub leading: f32,
+    pub pages: Vec<Vec<String>>,
+}
+
+/**
+ * Split the text in pages of lines, each line holding at most columns chars
+ */
+fn layout(text: &str, columns: usize, rows: usize, wrap: bool) -> Vec<Vec<String>> {
+    let mut pages = vec![];
+    let mut page: Vec<String> = vec![];
+
+    // Form feeds force a page break
+    for (i, section) in text.split('\u{0C}').enumerate() {
+        if i > 0 {
+            pages.push(page);
+            page = vec![];
+        }
+
+        for line in section.lines() {
+            let mut expanded = vec![];
+            for c in line.chars() {
+                if c == '\t' {
+                    let spaces = TAB_SIZE - (expanded.len() % TAB_SIZE);
+                    expanded.extend(std::iter::repeat_n(' ', spaces));
+                } else if !c.is_control() {
+                    expanded.push(c);
+                }
+            }
+
+            let parts: Vec<String> = if expanded.is_empty() {
+                vec![String::new()]
+            } else if wrap {
+                expanded.chunks(columns).map(|c| c.iter().collect()).collect()
+            } else {
+                vec![expanded.into_iter().take(columns).collect()]
+            };
+
+            for part in parts {
+                if page.len() == rows {
+                    pages.push(page);
+                    page = vec![];
+                }
+                page.push(part);
+            }
+        }
+    }
+
+    pages.push(page);
+    return pages;
+}
+
+pub(crate) fn escape(line: &[u8]) -> Vec<u8> {
+    let mut escaped = vec![];
+    for byte in line {
+        if matches!(byte, b'(' | b')' | b'\\') {
+            escaped.push(b'\\');
+        }
+        escaped.push(*byte);
+    }
+    return escaped;
+}
+
+/**
+ * Lay the text out on the pages of the options, in the monospaced font
+ */
+pub(crate) fn layout_text(text: &str, options: &TextOptions) -> Result<TextLayout, &'static str> {
+    let (width, height) = options
+        .media_size
+        .dimensions()
+        .or(MediaSize::A4.dimensions())
+        .map(|(w, h)| (w.to_points(), h.to_points()))
+        .unwrap();
+
+    if options.font_size <= 0.0 || options.line_spacing <= 0.0 {
+        return Err("font size and line spacing must be greater than zero");
+    }
+
+    let margins = &options.margins;
+    let leading = options.font_size * options.line_spacing;
+    let columns = ((width - margins.left - margins.right) / (options.font_size * COURIER_CHAR_WIDTH)).floor();
+    let rows = ((height - margins.top - margins.bottom) / leading).floor();
+
+    if columns < 1.0 || rows < 1.0 {
+        return Err("margins and font size leave no room for text");
+    }
+
+    let pages = layout(text, columns as usize, rows as usize, options.wrap);
+    return Ok(TextLayout { width, height, leading, pages });
+}
+
+/**
+ * Render the text as a PDF document
+ */
+pub fn render_text(text: &str, options: &TextOptions) -> Result<Vec<u8>, &'static str> {
+    let layout = layout_text(text, options)?;
+    let margins = &options.margins;
+
+    let mut document = PdfDocument::new();
+    let font_id = document.add_object(b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>");
+    let resources = format!("<< /Font << /F1 {} 0 R >> >>", font_id);
+
+    for page in layout.pages {
+        let mut content = format!(
+            "BT\n/F1 {:.2} Tf\n{:.2} TL\n{:.2} {:.2} Td\n",
+            options.font_size,
+            layout.leading,
+            margins.left,
+            layout.height - margins.top - options.font_size
+        )
+        .into_bytes();
+
+        for line in page {
+            let encoded: Vec<u8> = line.chars().map(win_ansi_byte).collect();
+            content.push(b'(');
+            content.extend(escape(&encoded));
+            content.extend_from_slice(b") Tj T*\n");
+        }
+        content.extend_from_slice(b"ET");
+
+        document.add_page(layout.width, layout.height, &resources, &content);
+    }
+
+    return Ok(document.into_bytes());
+}
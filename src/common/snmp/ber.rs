@@ -0,0 +1,294 @@
+// BER tags of the SNMP messages (RFC 1157, RFC 3416)
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_COUNTER32: u8 = 0x41;
+const TAG_GAUGE32: u8 = 0x42;
+const TAG_TIMETICKS: u8 = 0x43;
+const TAG_COUNTER64: u8 = 0x46;
+const TAG_GET_NEXT_REQUEST: u8 = 0xA1;
+const TAG_RESPONSE: u8 = 0xA2;
+
+// SNMPv2c, the version every network printer answers
+const SNMP_VERSION_2C: i64 = 1;
+
+/**
+ * Oid and value of a variable binding
+ */
+pub type Variable = (Vec<u32>, Value);
+
+/**
+ * Value of a variable binding, noSuchObject, noSuchInstance and endOfMibView are None
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    String(Vec<u8>),
+    Oid(Vec<u32>),
+    None,
+}
+
+impl Value {
+    pub fn as_integer(&self) -> Option<i64> {
+        return if let Value::Integer(value) = self { Some(*value) } else { None };
+    }
+
+    pub fn as_string(&self) -> Option<String> {
+        return match self {
+            Value::String(value) => Some(String::from_utf8_lossy(value).trim_end_matches('\0').trim().to_string()),
+            _ => None,
+        };
+    }
+}
+
+fn push_length(buffer: &mut Vec<u8>, length: usize) {
+    if length < 0x80 {
+        buffer.push(length as u8);
+    } else if length <= 0xFF {
+        buffer.extend_from_slice(&[0x81, length as u8]);
+    } else {
+        buffer.extend_from_slice(&[0x82, (length >> 8) as u8, length as u8]);
+    }
+}
+
+fn push_tlv(buffer: &mut Vec<u8>, tag: u8, value: &[u8]) {
+    buffer.push(tag);
+    push_length(buffer, value.len());
+    buffer.extend_from_slice(value);
+}
+
+fn encode_integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+
+    // Keep the shortest two's complement form
+    while start < 7
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0) || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+
+    return bytes[start..].to_vec();
+}
+
+fn encode_oid(oid: &[u32]) -> Vec<u8> {
+    let mut encoded = vec![(oid.first().copied().unwrap_or(1) * 40 + oid.get(1).copied().unwrap_or(3)) as u8];
+
+    for sub_identifier in oid.iter().skip(2) {
+        let mut chunk = vec![(*sub_identifier & 0x7F) as u8];
+        let mut rest = *sub_identifier >> 7;
+        while rest > 0 {
+            chunk.insert(0, (rest & 0x7F) as u8 | 0x80);
+            rest >>= 7;
+        }
+        encoded.extend(chunk);
+    }
+
+    return encoded;
+}
+
+/**
+ * Build a GetNextRequest for the oid
+ */
+pub fn get_next_request(community: &str, request_id: i32, oid: &[u32]) -> Vec<u8> {
+    let mut binding = vec![];
+    push_tlv(&mut binding, TAG_OID, &encode_oid(oid));
+    push_tlv(&mut binding, TAG_NULL, &[]);
+
+    let mut bindings = vec![];
+    push_tlv(&mut bindings, TAG_SEQUENCE, &binding);
+
+    let mut pdu = vec![];
+    push_tlv(&mut pdu, TAG_INTEGER, &encode_integer(request_id as i64));
+    push_tlv(&mut pdu, TAG_INTEGER, &[0]);
+    push_tlv(&mut pdu, TAG_INTEGER, &[0]);
+    push_tlv(&mut pdu, TAG_SEQUENCE, &bindings);
+
+    let mut message = vec![];
+    push_tlv(&mut message, TAG_INTEGER, &encode_integer(SNMP_VERSION_2C));
+    push_tlv(&mut message, TAG_OCTET_STRING, community.as_bytes());
+    push_tlv(&mut message, TAG_GET_NEXT_REQUEST, &pdu);
+
+    let mut packet = vec![];
+    push_tlv(&mut packet, TAG_SEQUENCE, &message);
+    return packet;
+}
+
+/**
+ * Read a tag, length and value at the position and return them with the position after the value
+ */
+fn read_tlv(data: &[u8], position: usize) -> Option<(u8, &[u8], usize)> {
+    let tag = *data.get(position)?;
+    let first = *data.get(position + 1)? as usize;
+
+    let (length, start) = if first & 0x80 == 0 {
+        (first, position + 2)
+    } else {
+        let count = first & 0x7F;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let bytes = data.get(position + 2..position + 2 + count)?;
+        (bytes.iter().fold(0usize, |length, b| (length << 8) | *b as usize), position + 2 + count)
+    };
+
+    let value = data.get(start..start + length)?;
+    return Some((tag, value, start + length));
+}
+
+fn decode_integer(value: &[u8]) -> i64 {
+    let initial = if value.first().is_some_and(|b| b & 0x80 != 0) { -1 } else { 0 };
+    return value.iter().fold(initial, |number: i64, b| (number << 8) | *b as i64);
+}
+
+fn decode_unsigned(value: &[u8]) -> i64 {
+    return value.iter().fold(0u64, |number, b| (number << 8) | *b as u64) as i64;
+}
+
+fn decode_oid(value: &[u8]) -> Vec<u32> {
+    let Some(first) = value.first() else { return vec![] };
+    let mut oid = vec![(*first / 40) as u32, (*first % 40) as u32];
+
+    let mut sub_identifier = 0u32;
+    for byte in &value[1..] {
+        sub_identifier = (sub_identifier << 7) | (*byte & 0x7F) as u32;
+        if byte & 0x80 == 0 {
+            oid.push(sub_identifier);
+            sub_identifier = 0;
+        }
+    }
+
+    return oid;
+}
+
+/**
+ * Parse a Response and return its request id with the first variable binding, Err when the
+ * agent reported an error status
+ */
+pub fn parse_response(packet: &[u8]) -> Option<(i32, Result<Variable, i64>)> {
+    let (TAG_SEQUENCE, message, _) = read_tlv(packet, 0)? else { return None };
+    let (_, _, position) = read_tlv(message, 0)?;
+    let (_, _, position) = read_tlv(message, position)?;
+    let (TAG_RESPONSE, pdu, _) = read_tlv(message, position)? else { return None };
+
+    let (_, request_id, position) = read_tlv(pdu, 0)?;
+    let request_id = decode_integer(request_id) as i32;
+    let (_, error_status, position) = read_tlv(pdu, position)?;
+    let error_status = decode_integer(error_status);
+    let (_, _, position) = read_tlv(pdu, position)?;
+
+    if error_status != 0 {
+        return Some((request_id, Err(error_status)));
+    }
+
+    let (_, bindings, _) = read_tlv(pdu, position)?;
+    let (_, binding, _) = read_tlv(bindings, 0)?;
+    let (_, oid, position) = read_tlv(binding, 0)?;
+    let (tag, value, _) = read_tlv(binding, position)?;
+
+    let value = match tag {
+        TAG_INTEGER => Value::Integer(decode_integer(value)),
+        TAG_COUNTER32 | TAG_GAUGE32 | TAG_TIMETICKS | TAG_COUNTER64 => Value::Integer(decode_unsigned(value)),
+        TAG_OCTET_STRING => Value::String(value.to_vec()),
+        TAG_OID => Value::Oid(decode_oid(value)),
+        _ => Value::None,
+    };
+
+    return Some((request_id, Ok((decode_oid(oid), value))));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SYS_DESCR: [u32; 9] = [1, 3, 6, 1, 2, 1, 1, 1, 0];
+
+    // GetResponse of request 0x1234 with sysDescr.0 = "HP LaserJet"
+    const RESPONSE: &[u8] = &[
+        0x30, 0x32, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xA2, 0x25, 0x02, 0x02, 0x12, 0x34,
+        0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x19, 0x30, 0x17, 0x06, 0x08, 0x2B, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01,
+        0x00, 0x04, 0x0B, b'H', b'P', b' ', b'L', b'a', b's', b'e', b'r', b'J', b'e', b't',
+    ];
+
+    #[test]
+    fn parse_get_response() {
+        let (request_id, variable) = parse_response(RESPONSE).unwrap();
+        assert_eq!(request_id, 0x1234);
+
+        let (oid, value) = variable.unwrap();
+        assert_eq!(oid, SYS_DESCR);
+        assert_eq!(value.as_string().unwrap(), "HP LaserJet");
+    }
+
+    #[test]
+    fn parse_get_response_with_gauge() {
+        let mut response = RESPONSE[..39].to_vec();
+        response.extend_from_slice(&[0x42, 0x05, 0x00, 0xFF, 0xFF, 0xFF, 0xFF]);
+        for (position, length) in [(1, 0x2C), (14, 0x1F), (26, 0x13), (28, 0x11)] {
+            response[position] = length;
+        }
+
+        let (_, variable) = parse_response(&response).unwrap();
+        assert_eq!(variable.unwrap().1, Value::Integer(0xFFFF_FFFF));
+    }
+
+    #[test]
+    fn parse_get_response_with_error_status() {
+        let mut response = RESPONSE.to_vec();
+        // noSuchName at index 1
+        response[21] = 0x02;
+        response[24] = 0x01;
+        assert_eq!(parse_response(&response), Some((0x1234, Err(2))));
+    }
+
+    #[test]
+    fn parse_truncated_response() {
+        for length in 0..RESPONSE.len() {
+            assert!(parse_response(&RESPONSE[..length]).is_none(), "parsed {length} bytes");
+        }
+
+        let mut response = RESPONSE.to_vec();
+        response[1] = 0x84;
+        assert!(parse_response(&response).is_none());
+    }
+
+    #[test]
+    fn get_next_request_round_trip() {
+        let mut request = get_next_request("public", 0x1234, &SYS_DESCR);
+        assert_eq!(&request[2..13], &RESPONSE[2..13]);
+        assert_eq!(request[13], TAG_GET_NEXT_REQUEST);
+
+        request[13] = TAG_RESPONSE;
+        let (request_id, variable) = parse_response(&request).unwrap();
+        assert_eq!(request_id, 0x1234);
+        assert_eq!(variable.unwrap(), (SYS_DESCR.to_vec(), Value::None));
+    }
+
+    #[test]
+    fn encode_integer_round_trip() {
+        assert_eq!(encode_integer(0), [0x00]);
+        assert_eq!(encode_integer(127), [0x7F]);
+        assert_eq!(encode_integer(128), [0x00, 0x80]);
+        assert_eq!(encode_integer(256), [0x01, 0x00]);
+        assert_eq!(encode_integer(-1), [0xFF]);
+        assert_eq!(encode_integer(-128), [0x80]);
+        assert_eq!(encode_integer(-129), [0xFF, 0x7F]);
+
+        for value in [0, 1, -1, 127, 128, -128, -129, 65535, i32::MAX as i64, i32::MIN as i64, i64::MAX, i64::MIN] {
+            assert_eq!(decode_integer(&encode_integer(value)), value);
+        }
+    }
+
+    #[test]
+    fn encode_oid_round_trip() {
+        assert_eq!(encode_oid(&SYS_DESCR), RESPONSE[31..39]);
+        assert_eq!(encode_oid(&[1, 3, 6, 1, 4, 1, 11, 128, 16383, 16384]), [
+            0x2B, 0x06, 0x01, 0x04, 0x01, 0x0B, 0x81, 0x00, 0xFF, 0x7F, 0x81, 0x80, 0x00
+        ]);
+
+        let oid = [1, 3, 6, 1, 2, 1, 43, 11, 1, 1, 9, 1, u32::MAX];
+        assert_eq!(decode_oid(&encode_oid(&oid)), oid);
+    }
+}
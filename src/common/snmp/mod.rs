@@ -0,0 +1,336 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    net::{ToSocketAddrs, UdpSocket},
+    time::Duration,
+};
+
+use crate::common::base::printer::{PrinterState, Supply};
+use ber::Value;
+
+mod ber;
+
+const SNMP_PORT: u16 = 161;
+
+// Time given to the agent for each request, a request is sent twice before giving up
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(1500);
+const REQUEST_ATTEMPTS: usize = 2;
+
+// Rows read from a table, printers have a few trays, supplies and alerts
+const MAX_TABLE_ROWS: usize = 128;
+
+// Host Resources MIB (RFC 2790) and Printer MIB (RFC 3805) columns
+const HR_DEVICE_ENTRY: [u32; 10] = [1, 3, 6, 1, 2, 1, 25, 3, 2, 1];
+const HR_PRINTER_STATUS: [u32; 11] = [1, 3, 6, 1, 2, 1, 25, 3, 5, 1, 1];
+const PRT_MARKER_LIFE_COUNT: [u32; 11] = [1, 3, 6, 1, 2, 1, 43, 10, 2, 1, 4];
+const PRT_INPUT_ENTRY: [u32; 10] = [1, 3, 6, 1, 2, 1, 43, 8, 2, 1];
+const PRT_MARKER_SUPPLIES_ENTRY: [u32; 10] = [1, 3, 6, 1, 2, 1, 43, 11, 1, 1];
+const PRT_MARKER_COLORANT_VALUE: [u32; 11] = [1, 3, 6, 1, 2, 1, 43, 12, 1, 1, 4];
+const PRT_ALERT_ENTRY: [u32; 10] = [1, 3, 6, 1, 2, 1, 43, 18, 1, 1];
+
+/**
+ * Paper input of the device, ex a tray or the manual feeder
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputTray {
+    pub name: String,
+
+    /**
+     * Sheets left, None when the device does not know (some trays only report "not empty")
+     */
+    pub level: Option<u32>,
+
+    /**
+     * Sheets the tray holds when full
+     */
+    pub max_capacity: Option<u32>,
+}
+
+impl InputTray {
+    pub fn is_empty(&self) -> bool {
+        return self.level == Some(0);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertSeverity {
+    Critical,
+    Warning,
+    Other,
+}
+
+/**
+ * Active alert of the device, ex a paper jam or an open cover
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceAlert {
+    pub severity: AlertSeverity,
+
+    /**
+     * Description given by the device, ex "Tray 2 empty"
+     */
+    pub description: String,
+}
+
+/**
+ * Status read from the device with SNMP, without going through the spooler
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceStatus {
+    /**
+     * State of the device, None when it reports neither the printer nor the device status
+     */
+    pub state: Option<PrinterState>,
+
+    /**
+     * Pages printed over the life of the device
+     */
+    pub page_count: Option<u64>,
+
+    pub trays: Vec<InputTray>,
+
+    pub supplies: Vec<Supply>,
+
+    pub alerts: Vec<DeviceAlert>,
+}
+
+/**
+ * SNMPv2c session with the agent of a device
+ */
+struct Session {
+    socket: UdpSocket,
+    community: String,
+    request_id: i32,
+}
+
+impl Session {
+    fn open(host: &str, community: &str) -> Result<Session, &'static str> {
+        let address = (host, SNMP_PORT)
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addresses| addresses.next())
+            .ok_or("failed to resolve the device host")?;
+
+        let local = if address.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(local).map_err(|_| "failed to open the SNMP socket")?;
+        socket.connect(address).map_err(|_| "failed to open the SNMP socket")?;
+        socket.set_read_timeout(Some(REQUEST_TIMEOUT)).map_err(|_| "failed to open the SNMP socket")?;
+
+        return Ok(Session { socket, community: community.to_string(), request_id: 0 });
+    }
+
+    /**
+     * Return the variable following the oid, None at the end of the MIB or on an error status
+     */
+    fn get_next(&mut self, oid: &[u32]) -> Result<Option<(Vec<u32>, Value)>, &'static str> {
+        self.request_id = self.request_id.wrapping_add(1);
+        let request = ber::get_next_request(&self.community, self.request_id, oid);
+        let mut buffer = [0u8; 4096];
+
+        for _ in 0..REQUEST_ATTEMPTS {
+            self.socket.send(&request).map_err(|_| "failed to send the SNMP request")?;
+
+            while let Ok(length) = self.socket.recv(&mut buffer) {
+                match ber::parse_response(&buffer[..length]) {
+                    Some((request_id, Ok(variable))) if request_id == self.request_id => return Ok(Some(variable)),
+                    Some((request_id, Err(_))) if request_id == self.request_id => return Ok(None),
+                    _ => continue,
+                }
+            }
+        }
+
+        return Err("the device did not answer the SNMP request");
+    }
+
+    /**
+     * Read the values of a column by row index, the index being the sub identifiers after the column
+     */
+    fn walk(&mut self, column: &[u32]) -> Result<BTreeMap<Vec<u32>, Value>, &'static str> {
+        let mut values = BTreeMap::new();
+        let mut oid = column.to_vec();
+
+        for _ in 0..MAX_TABLE_ROWS {
+            let Some((next, value)) = self.get_next(&oid)? else { break };
+            if !next.starts_with(column) || next.len() <= column.len() || value == Value::None {
+                break;
+            }
+
+            values.insert(next[column.len()..].to_vec(), value);
+            oid = next;
+        }
+
+        return Ok(values);
+    }
+
+    /**
+     * Read a column of a table entry
+     */
+    fn column(&mut self, entry: &[u32], column: u32) -> Result<BTreeMap<Vec<u32>, Value>, &'static str> {
+        let mut oid = entry.to_vec();
+        oid.push(column);
+        return self.walk(&oid);
+    }
+}
+
+fn integer(values: &BTreeMap<Vec<u32>, Value>, index: &[u32]) -> Option<i64> {
+    return values.get(index).and_then(|v| v.as_integer());
+}
+
+fn string(values: &BTreeMap<Vec<u32>, Value>, index: &[u32]) -> Option<String> {
+    return values.get(index).and_then(|v| v.as_string()).filter(|s| !s.is_empty());
+}
+
+/**
+ * Map the hrPrinterStatus and hrDeviceStatus values, a down device is offline whatever it prints
+ */
+fn printer_state(printer_status: Option<i64>, device_status: Option<i64>) -> Option<PrinterState> {
+    if device_status == Some(5) {
+        return Some(PrinterState::OFFLINE);
+    }
+
+    return match printer_status {
+        Some(3) => Some(PrinterState::READY),
+        Some(4) | Some(5) => Some(PrinterState::PRINTING),
+        Some(_) => Some(PrinterState::UNKNOWN),
+        None => device_status.map(|_| PrinterState::UNKNOWN),
+    };
+}
+
+/**
+ * Map a prtMarkerSuppliesType to the ipp marker-types keyword
+ */
+fn supply_kind(supply_type: Option<i64>) -> String {
+    let kind = match supply_type {
+        Some(3) => "toner",
+        Some(4) => "waste-toner",
+        Some(5) => "ink",
+        Some(6) => "ink-cartridge",
+        Some(7) => "ink-ribbon",
+        Some(8) => "waste-ink",
+        Some(9) => "opc",
+        Some(10) => "developer",
+        Some(11) => "fuser-oil",
+        Some(15) => "fuser",
+        Some(20) => "transfer-unit",
+        Some(21) => "toner-cartridge",
+        Some(22) => "fuser-oiler",
+        _ => "other",
+    };
+    return kind.to_string();
+}
+
+/**
+ * Map a prtMarkerColorantValue, ex cyan, to the color format of marker-colors
+ */
+fn colorant_color(colorant: Option<String>) -> String {
+    let color = match colorant.map(|c| c.to_lowercase()).as_deref() {
+        Some("black") => "#000000",
+        Some("cyan") => "#00FFFF",
+        Some("magenta") => "#FF00FF",
+        Some("yellow") => "#FFFF00",
+        Some("red") => "#FF0000",
+        Some("green") => "#00FF00",
+        Some("blue") => "#0000FF",
+        _ => "none",
+    };
+    return color.to_string();
+}
+
+// Negative levels and capacities mean other (-1), unknown (-2) or "some remaining" (-3)
+fn percent(level: Option<i64>, max_capacity: Option<i64>) -> Option<u8> {
+    return match (level, max_capacity) {
+        (Some(level), Some(max)) if level >= 0 && max > 0 => Some((level * 100 / max).min(100) as u8),
+        _ => None,
+    };
+}
+
+fn read_supplies(session: &mut Session) -> Result<Vec<Supply>, &'static str> {
+    let colorant_indexes = session.column(&PRT_MARKER_SUPPLIES_ENTRY, 3)?;
+    let descriptions = session.column(&PRT_MARKER_SUPPLIES_ENTRY, 6)?;
+    let types = session.column(&PRT_MARKER_SUPPLIES_ENTRY, 5)?;
+    let max_capacities = session.column(&PRT_MARKER_SUPPLIES_ENTRY, 8)?;
+    let levels = session.column(&PRT_MARKER_SUPPLIES_ENTRY, 9)?;
+    let colorants = session.walk(&PRT_MARKER_COLORANT_VALUE)?;
+
+    return Ok(descriptions
+        .keys()
+        .map(|index| {
+            // Colorants share the device index of the supply, the supply column gives their index
+            let colorant = integer(&colorant_indexes, index).and_then(|colorant_index| {
+                let colorant_key = vec![*index.first()?, colorant_index as u32];
+                return string(&colorants, &colorant_key);
+            });
+
+            return Supply {
+                name: string(&descriptions, index).unwrap_or_default(),
+                kind: supply_kind(integer(&types, index)),
+                color: colorant_color(colorant),
+                level: percent(integer(&levels, index), integer(&max_capacities, index)),
+                low_level: None,
+                high_level: None,
+            };
+        })
+        .collect());
+}
+
+fn read_trays(session: &mut Session) -> Result<Vec<InputTray>, &'static str> {
+    let names = session.column(&PRT_INPUT_ENTRY, 13)?;
+    let descriptions = session.column(&PRT_INPUT_ENTRY, 18)?;
+    let max_capacities = session.column(&PRT_INPUT_ENTRY, 9)?;
+    let levels = session.column(&PRT_INPUT_ENTRY, 10)?;
+
+    let count = |value: Option<i64>| value.filter(|v| *v >= 0).map(|v| v as u32);
+    let indexes: BTreeSet<&Vec<u32>> = max_capacities.keys().chain(levels.keys()).collect();
+
+    return Ok(indexes
+        .into_iter()
+        .map(|index| InputTray {
+            name: string(&names, index).or(string(&descriptions, index)).unwrap_or_default(),
+            level: count(integer(&levels, index)),
+            max_capacity: count(integer(&max_capacities, index)),
+        })
+        .collect());
+}
+
+fn read_alerts(session: &mut Session) -> Result<Vec<DeviceAlert>, &'static str> {
+    let severities = session.column(&PRT_ALERT_ENTRY, 2)?;
+    let descriptions = session.column(&PRT_ALERT_ENTRY, 8)?;
+
+    return Ok(severities
+        .keys()
+        .map(|index| DeviceAlert {
+            severity: match integer(&severities, index) {
+                Some(3) => AlertSeverity::Critical,
+                Some(4) | Some(5) => AlertSeverity::Warning,
+                _ => AlertSeverity::Other,
+            },
+            description: string(&descriptions, index).unwrap_or_default(),
+        })
+        .collect());
+}
+
+/**
+ * Read the state, page counter, trays, supplies and alerts of a network printer from its
+ * Printer MIB, the community is usually "public"
+ */
+pub fn query_device(host: &str, community: &str) -> Result<DeviceStatus, &'static str> {
+    let mut session = Session::open(host, community)?;
+
+    // The first answer tells if the agent is there at all, the other tables may be missing
+    let printer_status = session.walk(&HR_PRINTER_STATUS)?;
+    let device_status = session.column(&HR_DEVICE_ENTRY, 5).unwrap_or_default();
+    let page_counts = session.walk(&PRT_MARKER_LIFE_COUNT).unwrap_or_default();
+
+    // Both tables are indexed by hrDeviceIndex, the printer is the first device with a printer status
+    let printer_index = printer_status.keys().next();
+
+    return Ok(DeviceStatus {
+        state: printer_state(
+            printer_index.and_then(|index| integer(&printer_status, index)),
+            printer_index.and_then(|index| integer(&device_status, index)),
+        ),
+        page_count: page_counts.values().next().and_then(|v| v.as_integer()).map(|v| v as u64),
+        trays: read_trays(&mut session).unwrap_or_default(),
+        supplies: read_supplies(&mut session).unwrap_or_default(),
+        alerts: read_alerts(&mut session).unwrap_or_default(),
+    });
+}
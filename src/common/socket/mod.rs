@@ -0,0 +1,173 @@
+use std::{
+    fs::File,
+    io::{ErrorKind, Read, Write},
+    net::{Shutdown, TcpStream, ToSocketAddrs},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::common::probe::{device_address, probe_tcp};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_COMPLETION_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Size of the writes the document is sent in
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/**
+ * Printer reached with the raw AppSocket (JetDirect) protocol, the bytes are sent as they are,
+ * already in the printer language (ex ZPL, ESC/POS or PCL), without a spooler queue
+ */
+#[derive(Debug, Clone)]
+pub struct SocketPrinter {
+    pub host: String,
+    pub port: u16,
+
+    /**
+     * Time given to connect and to every write (default is 10 seconds)
+     */
+    pub timeout: Duration,
+
+    /**
+     * Time the printer is given to close the connection once all the data was sent, which is
+     * how it acknowledges the job (default is 30 seconds)
+     */
+    pub completion_timeout: Duration,
+}
+
+/**
+ * Result of a job sent to a SocketPrinter
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SocketJob {
+    pub bytes_sent: u64,
+
+    /**
+     * Definition if the printer closed the connection after reading the data, false when it
+     * was still open after the completion timeout (many receipt printers keep it open)
+     */
+    pub acknowledged: bool,
+
+    /**
+     * Bytes the printer sent back on the connection, ex a PJL or ZPL status answer
+     */
+    pub response: Vec<u8>,
+
+    /**
+     * Time from the connection to the acknowledgement or the completion timeout
+     */
+    pub elapsed: Duration,
+}
+
+impl SocketPrinter {
+    /**
+     * Create from an address, ex "10.0.0.5", "10.0.0.5:9100" or "socket://10.0.0.5:9100", the
+     * port is 9100 when not given
+     */
+    pub fn new(address: &str) -> Result<SocketPrinter, &'static str> {
+        let (host, port) = if address.contains("://") {
+            let (scheme, host, port) = device_address(address).ok_or("invalid printer address")?;
+            if scheme != "socket" {
+                return Err("the printer address is not a socket uri");
+            }
+            (host, port)
+        } else {
+            match device_address(&format!("socket://{}", address)) {
+                Some((_, host, port)) => (host, port),
+                None => return Err("invalid printer address"),
+            }
+        };
+
+        return Ok(SocketPrinter {
+            host,
+            port,
+            timeout: DEFAULT_TIMEOUT,
+            completion_timeout: DEFAULT_COMPLETION_TIMEOUT,
+        });
+    }
+
+    /**
+     * Check that the printer accepts connections and return how long it took, a printer busy
+     * with another connection usually refuses or does not answer
+     */
+    pub fn status(&self) -> Result<Duration, &'static str> {
+        return probe_tcp(&self.host, self.port);
+    }
+
+    fn connect(&self) -> Result<TcpStream, &'static str> {
+        let addresses = (self.host.as_str(), self.port).to_socket_addrs().map_err(|_| "failed to resolve the printer host")?;
+
+        let mut error = "failed to resolve the printer host";
+        for address in addresses {
+            error = match TcpStream::connect_timeout(&address, self.timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) if e.kind() == ErrorKind::TimedOut => "timed out connecting to the printer",
+                Err(e) if e.kind() == ErrorKind::ConnectionRefused => "the printer refused the connection",
+                Err(_) => "failed to connect to the printer",
+            };
+        }
+
+        return Err(error);
+    }
+
+    /**
+     * Send the bytes to the printer
+     */
+    pub fn print(&self, buffer: &[u8]) -> Result<SocketJob, &'static str> {
+        let mut buffer = buffer;
+        return self.print_reader(&mut buffer);
+    }
+
+    /**
+     * Send the content of the file to the printer
+     */
+    pub fn print_file(&self, file_path: &Path) -> Result<SocketJob, &'static str> {
+        let mut file = File::open(file_path).map_err(|_| "failed to open the file")?;
+        return self.print_reader(&mut file);
+    }
+
+    /**
+     * Stream the data read from the reader to the printer, then close the sending side and wait
+     * for the printer to close the connection
+     */
+    pub fn print_reader(&self, reader: &mut dyn Read) -> Result<SocketJob, &'static str> {
+        let started = Instant::now();
+        let mut stream = self.connect()?;
+        stream.set_write_timeout(Some(self.timeout)).map_err(|_| "failed to connect to the printer")?;
+
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        let mut bytes_sent = 0u64;
+        loop {
+            let length = reader.read(&mut buffer).map_err(|_| "failed to read the document")?;
+            if length == 0 {
+                break;
+            }
+            stream.write_all(&buffer[..length]).map_err(|_| "the printer stopped receiving data")?;
+            bytes_sent += length as u64;
+        }
+
+        stream.flush().map_err(|_| "the printer stopped receiving data")?;
+        stream.shutdown(Shutdown::Write).map_err(|_| "the printer stopped receiving data")?;
+
+        // The printer closes its side once it read everything, sending status bytes before
+        let deadline = Instant::now() + self.completion_timeout;
+        let mut response = vec![];
+        let mut acknowledged = false;
+
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()).filter(|r| !r.is_zero()) {
+            stream.set_read_timeout(Some(remaining)).map_err(|_| "failed to read the printer response")?;
+
+            match stream.read(&mut buffer) {
+                Ok(0) => {
+                    acknowledged = true;
+                    break;
+                }
+                Ok(length) => response.extend_from_slice(&buffer[..length]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+
+        return Ok(SocketJob { bytes_sent, acknowledged, response, elapsed: started.elapsed() });
+    }
+}
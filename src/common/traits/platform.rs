@@ -1,8 +1,13 @@
-use std::time::SystemTime;
+use std::{io::Read, path::Path, time::{Duration, SystemTime}};
 use crate::common::base::{
-    job::PrinterJobState, 
-    printer::{Printer, PrinterState, PrintOptions}
+    attributes::Attributes,
+    event::EventSender,
+    job::{JobDocument, JobId, JobProgress, JobQuery, JobState},
+    printer::{NewPrinter, Printer, PrinterCapabilities, PrinterKind, PrinterOption, PrinterState, PrintOptions, Supply, UnsupportedOption}
 };
+use crate::common::render::text::TextOptions;
+#[cfg(feature = "image")]
+use crate::common::render::image::ImagePrintOptions;
 
 pub trait PlatformPrinterGetters {
     fn get_name(&self) -> String;
@@ -10,7 +15,12 @@ pub trait PlatformPrinterGetters {
     fn get_system_name(&self) -> String;
     fn get_marker_and_model(&self) -> String;
     fn get_is_shared(&self) -> bool;
+    fn get_is_accepting_jobs(&self) -> bool;
+    fn get_is_class(&self) -> bool;
+    fn get_members(&self) -> Vec<String>;
     fn get_uri(&self) -> String;
+    fn get_kind(&self) -> PrinterKind;
+    fn get_device_uri(&self) -> String;
     fn get_location(&self) -> String;
     fn get_state(&self) -> u64;
     fn get_state_reasons(&self) -> Vec<String>;
@@ -18,14 +28,20 @@ pub trait PlatformPrinterGetters {
     fn get_processor(&self) -> String;
     fn get_description(&self) -> String;
     fn get_data_type(&self) -> String;
+    fn get_attributes(&self) -> u64;
+    fn get_jobs_count(&self) -> Option<u32>;
 }
 
 pub trait PlatformPrinterJobGetters {
     fn get_id(&self) -> u64;
     fn get_name(&self) -> String;
     fn get_state(&self) -> u64;
+    fn get_state_reasons(&self) -> Vec<String>;
     fn get_printer(&self) -> String;
+    fn get_user(&self) -> String;
     fn get_media_type(&self) -> String;
+    fn get_pages_completed(&self) -> Option<u32>;
+    fn get_pages_total(&self) -> Option<u32>;
     fn get_created_at(&self) -> SystemTime;
     fn get_processed_at(&self) -> Option<SystemTime>;
     fn get_completed_at(&self) -> Option<SystemTime>;
@@ -33,11 +49,43 @@ pub trait PlatformPrinterJobGetters {
 
 pub trait PlatformActions {
     fn get_printers() -> Vec<Printer>;
-    fn print(printer_system_name: &str, buffer: &[u8], job_name: Option<&str>, options: PrintOptions) -> Result<(), &'static str>;
-    fn print_file(printer_system_name: &str, file_path: &str, job_name: Option<&str>, options: PrintOptions) -> Result<(), &'static str>;
-    fn get_printer_jobs(printer_name: &str, active_only: bool) -> Vec<crate::common::base::job::PrinterJob>;
+    fn print(printer_system_name: &str, buffer: &[u8], job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str>;
+    fn print_reader(printer_system_name: &str, reader: &mut dyn Read, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str>;
+    fn print_file(printer_system_name: &str, file_path: &Path, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str>;
+    fn print_uri(printer_system_name: &str, uri: &str, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str>;
+    fn print_files(printer_system_name: &str, file_paths: &[&Path], job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str>;
+    fn print_text(printer_system_name: &str, text: &str, job_name: Option<&str>, text_options: &TextOptions, options: PrintOptions) -> Result<JobId, &'static str>;
+    #[cfg(feature = "image")]
+    fn print_image(printer_system_name: &str, image: &image::DynamicImage, job_name: Option<&str>, image_options: &ImagePrintOptions, options: PrintOptions) -> Result<JobId, &'static str>;
+    fn validate_options(printer_system_name: &str, options: &PrintOptions) -> Vec<UnsupportedOption>;
+    fn get_capabilities(printer_system_name: &str) -> Result<PrinterCapabilities, &'static str>;
+    fn get_printer_options(printer_system_name: &str) -> Result<Vec<PrinterOption>, &'static str>;
+    fn get_default_options(printer_system_name: &str) -> Result<PrintOptions, &'static str>;
+    fn get_option_conflicts(printer_system_name: &str, options: &PrintOptions, option: &str, value: &str) -> Vec<(String, String)>;
+    fn get_supplies(printer_system_name: &str) -> Result<Vec<Supply>, &'static str>;
+    fn probe_device(device_uri: &str) -> Result<Duration, &'static str>;
+    fn get_printer_jobs(printer_name: &str, query: &JobQuery) -> Result<Vec<crate::common::base::job::PrinterJob>, &'static str>;
+    fn cancel_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str>;
+    fn hold_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str>;
+    fn release_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str>;
+    fn restart_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str>;
+    fn move_job(printer_name: &str, job_id: Option<JobId>, target_printer_name: &str) -> Result<(), &'static str>;
+    fn set_job_priority(printer_name: &str, job_id: JobId, priority: u8) -> Result<(), &'static str>;
+    fn purge_jobs(printer_name: &str) -> Result<(), &'static str>;
+    fn pause_printer(printer_name: &str) -> Result<(), &'static str>;
+    fn resume_printer(printer_name: &str) -> Result<(), &'static str>;
+    fn accept_jobs(printer_name: &str) -> Result<(), &'static str>;
+    fn reject_jobs(printer_name: &str, reason: Option<&str>) -> Result<(), &'static str>;
+    fn get_job_progress(printer_name: &str, job_id: JobId) -> Result<JobProgress, &'static str>;
+    fn get_job_attributes(printer_name: &str, job_id: JobId) -> Result<Attributes, &'static str>;
+    fn get_job_document(printer_name: &str, job_id: JobId) -> Result<JobDocument, &'static str>;
     fn get_default_printer() -> Option<Printer>;
+    fn set_default_printer(printer_name: &str) -> Result<(), &'static str>;
+    fn set_default_options(printer_name: &str, options: &PrintOptions) -> Result<(), &'static str>;
+    fn add_printer(printer: &NewPrinter) -> Result<(), &'static str>;
+    fn delete_printer(printer_name: &str) -> Result<(), &'static str>;
     fn get_printer_by_name(printer_name: &str) -> Option<Printer>;
+    fn watch(sender: EventSender) -> Result<(), &'static str>;
     fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState;
-    fn parse_printer_job_state(platform_state: u64) -> PrinterJobState;
+    fn parse_printer_job_state(platform_state: u64) -> JobState;
 }
\ No newline at end of file
@@ -6,6 +6,7 @@
 //!
 //! ```rust
 //! use printers::{get_printer_by_name, get_default_printer, get_printers};
+//! use printers::common::base::printer::PrintOptions;
 //!
 //! fn main() {
 //!
@@ -17,15 +18,15 @@
 //!    // Get a printer by the name
 //!    let my_printer = get_printer_by_name("my_printer");
 //!    if my_printer.is_some() {
-//!        my_printer.unwrap().print_file("notes.txt", None);
-//!        // Err("cupsPrintFile failed")
+//!        let job_id = my_printer.unwrap().print_file("notes.txt", None, PrintOptions::default());
+//!        // Err("cupsPrintFile failed") or Ok(42)
 //!    }
 //!
 //!    // Use the default printer
 //!    let default_printer = get_default_printer();
 //!    if default_printer.is_some() {
-//!        default_printer.unwrap().print("dlrow olleh".as_bytes(), Some("My Job"));
-//!        // Ok(())
+//!        let job_id = default_printer.unwrap().print("dlrow olleh".as_bytes(), Some("My Job"), PrintOptions::default());
+//!        // Ok(43)
 //!    }
 //!
 //! }
@@ -33,7 +34,12 @@
 //!
 //!
 
+#![allow(clippy::needless_return)]
+
+// Dispatch between the spooler and the registered backends, see common::backend
 struct Platform;
+// CUPS on unix, winspool on windows
+struct Spooler;
 
 pub mod common;
 
@@ -43,7 +49,7 @@ mod unix;
 #[cfg(target_family = "windows")]
 mod windows;
 
-use common::{traits::platform::PlatformActions, base::printer::Printer};
+use common::{traits::platform::PlatformActions, backend::{Config, PrinterBackend}, base::{event::EventStream, printer::{NewPrinter, Printer}}};
 
 /**
  * Return all available printers on system
@@ -52,6 +58,13 @@ pub fn get_printers() -> Vec<Printer> {
     return Platform::get_printers();
 }
 
+/**
+ * Return the CUPS classes, printers with is_class that group other printers
+ */
+pub fn get_printer_classes() -> Vec<Printer> {
+    return Platform::get_printers().into_iter().filter(|p| p.is_class).collect();
+}
+
 /**
  * If you known the printer nme you can try get the printer directly
  */
@@ -64,4 +77,77 @@ pub fn get_printer_by_name(printer_name: &str) -> Option<Printer> {
  */
 pub fn get_default_printer() -> Option<Printer> {
     return Platform::get_default_printer();
-}
\ No newline at end of file
+}
+
+/**
+ * Browse the local network for IPP printers announced with mDNS (Bonjour) and, on windows,
+ * WSD devices, including the ones without a queue on this system
+ */
+#[cfg(feature = "discovery")]
+pub fn discover_printers(timeout: std::time::Duration) -> Result<Vec<common::discovery::DiscoveredPrinter>, &'static str> {
+    return common::discovery::discover_printers(timeout);
+}
+
+/**
+ * Listen to printer and job changes instead of polling get_printers, events are delivered
+ * until the stream is dropped. They come from the CUPS D-Bus notifier with the dbus feature, then
+ * from an IPP subscription on CUPS and from the printer change notifications on windows. When
+ * none is available the printers are compared every two seconds
+ */
+pub fn watch() -> Result<EventStream, &'static str> {
+    let (stream, sender) = EventStream::new();
+    Platform::watch(sender)?;
+    return Ok(stream);
+}
+
+/**
+ * Make the printer with the given system name the default one, on unix this is the
+ * user default stored in lpoptions
+ */
+pub fn set_default_printer(printer_name: &str) -> Result<(), &'static str> {
+    return Platform::set_default_printer(printer_name);
+}
+
+/**
+ * Create a printer queue, this needs administrator rights
+ */
+pub fn add_printer(printer: &NewPrinter) -> Result<(), &'static str> {
+    return Platform::add_printer(printer);
+}
+
+/**
+ * Delete the printer queue with the given system name, this needs administrator rights
+ */
+pub fn delete_printer(printer_name: &str) -> Result<(), &'static str> {
+    return Platform::delete_printer(printer_name);
+}
+
+/**
+ * Add a custom backend, its printers are returned with the ones of the system by get_printers.
+ * Registering a backend with the name of a registered one replaces it
+ */
+pub fn register_backend(backend: std::sync::Arc<dyn PrinterBackend>) {
+    common::backend::register_backend(backend);
+}
+
+/**
+ * Remove the custom backend with the given name, returning false when none was registered
+ */
+pub fn unregister_backend(name: &str) -> bool {
+    return common::backend::unregister_backend(name);
+}
+
+/**
+ * Choose the backends consulted by the printers api, their order and how long they are waited
+ * for. It applies to the calls made after it, from every thread
+ */
+pub fn set_config(config: Config) {
+    common::backend::set_config(config);
+}
+
+/**
+ * Return the Config set with set_config, the default one enables every backend
+ */
+pub fn get_config() -> Config {
+    return common::backend::get_config();
+}
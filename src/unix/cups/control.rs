@@ -0,0 +1,89 @@
+use crate::common::base::printer::{NewPrinter, PrinterDriver};
+use crate::unix::cups::{
+    ipp::{self, IppRequest},
+    server,
+};
+use libc::c_int;
+
+/**
+ * Send a printer operation with its printer-uri to the administration resource
+ */
+fn printer_request(operation: c_int, printer_name: &str) -> IppRequest {
+    let mut request = IppRequest::new(operation);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", &ipp::printer_uri(printer_name));
+    return request;
+}
+
+/**
+ * Stop processing the queue with IPP Pause-Printer, jobs are still accepted
+ */
+pub fn pause_printer(printer_name: &str) -> Result<(), &'static str> {
+    return printer_request(ipp::IPP_OP_PAUSE_PRINTER, printer_name).send(server::http(), "/admin/").map(|_| ());
+}
+
+/**
+ * Start processing the queue again with IPP Resume-Printer
+ */
+pub fn resume_printer(printer_name: &str) -> Result<(), &'static str> {
+    return printer_request(ipp::IPP_OP_RESUME_PRINTER, printer_name).send(server::http(), "/admin/").map(|_| ());
+}
+
+/**
+ * Accept new jobs on the queue with CUPS-Accept-Jobs
+ */
+pub fn accept_jobs(printer_name: &str) -> Result<(), &'static str> {
+    return printer_request(ipp::IPP_OP_CUPS_ACCEPT_JOBS, printer_name).send(server::http(), "/admin/").map(|_| ());
+}
+
+/**
+ * Refuse new jobs on the queue with CUPS-Reject-Jobs, the reason is shown as printer-state-message
+ */
+pub fn reject_jobs(printer_name: &str, reason: Option<&str>) -> Result<(), &'static str> {
+    let mut request = printer_request(ipp::IPP_OP_CUPS_REJECT_JOBS, printer_name);
+    if let Some(reason) = reason {
+        request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_TEXT, "printer-state-message", reason);
+    }
+    return request.send(server::http(), "/admin/").map(|_| ());
+}
+
+// printer-state of an enabled queue
+const IPP_PRINTER_IDLE: i32 = 3;
+
+/**
+ * Create or modify the queue with CUPS-Add-Modify-Printer, enabled and accepting jobs
+ */
+pub fn add_printer(printer: &NewPrinter) -> Result<(), &'static str> {
+    let mut request = printer_request(ipp::IPP_OP_CUPS_ADD_MODIFY_PRINTER, &printer.name);
+    request.add_string(ipp::IPP_TAG_PRINTER, ipp::IPP_TAG_URI, "device-uri", &printer.device_uri);
+
+    let ppd_file = match &printer.driver {
+        PrinterDriver::Everywhere => {
+            request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_NAME, "ppd-name", "everywhere");
+            None
+        }
+        PrinterDriver::Model(model) => {
+            request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_NAME, "ppd-name", model);
+            None
+        }
+        PrinterDriver::PpdFile(path) => Some(path.as_path()),
+    };
+
+    if let Some(info) = &printer.info {
+        request.add_string(ipp::IPP_TAG_PRINTER, ipp::IPP_TAG_TEXT, "printer-info", info);
+    }
+    if let Some(location) = &printer.location {
+        request.add_string(ipp::IPP_TAG_PRINTER, ipp::IPP_TAG_TEXT, "printer-location", location);
+    }
+    request.add_boolean(ipp::IPP_TAG_PRINTER, "printer-is-shared", printer.is_shared);
+    request.add_boolean(ipp::IPP_TAG_PRINTER, "printer-is-accepting-jobs", true);
+    request.add_integer(ipp::IPP_TAG_PRINTER, ipp::IPP_TAG_ENUM, "printer-state", IPP_PRINTER_IDLE);
+
+    return request.send_file(server::http(), "/admin/", ppd_file).map(|_| ());
+}
+
+/**
+ * Delete the queue with CUPS-Delete-Printer, its pending jobs are canceled
+ */
+pub fn delete_printer(printer_name: &str) -> Result<(), &'static str> {
+    return printer_request(ipp::IPP_OP_CUPS_DELETE_PRINTER, printer_name).send(server::http(), "/admin/").map(|_| ());
+}
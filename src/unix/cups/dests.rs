@@ -1,12 +1,24 @@
 use crate::{
-    common::traits::platform::PlatformPrinterGetters, unix::utils::strings::c_char_to_string,
+    common::{base::printer::PrinterKind, traits::platform::PlatformPrinterGetters},
+    unix::utils::strings::{c_char_to_string, str_to_cstring},
 };
-use libc::{c_char, c_int};
+use libc::{c_char, c_int, c_void};
 use std::{ffi::CString, ptr, slice};
 
-#[link(name = "cups")]
-unsafe extern "C" {
-    unsafe fn cupsGetDests(dests: *mut *mut CupsDestT) -> c_int;
+cups_functions! {
+    unsafe fn cupsGetDests2(http: *mut c_void, dests: *mut *mut CupsDestT) -> c_int;
+    unsafe fn cupsGetNamedDest(
+        http: *mut c_void,
+        name: *const c_char,
+        instance: *const c_char,
+    ) -> *mut CupsDestT;
+    unsafe fn cupsAddOption(
+        name: *const c_char,
+        value: *const c_char,
+        num_options: c_int,
+        options: *mut *mut CupsOptionT,
+    ) -> c_int;
+    unsafe fn cupsSetDests2(http: *mut c_void, num_dests: c_int, dests: *mut CupsDestT) -> c_int;
     unsafe fn cupsFreeDests(num_dests: c_int, dests: *const CupsDestT);
     unsafe fn cupsGetOption(
         name: *const c_char,
@@ -15,6 +27,10 @@ unsafe extern "C" {
     ) -> *const c_char;
 }
 
+// printer-type bits (cups_ptype_e)
+const CUPS_PRINTER_CLASS: u32 = 0x0001;
+const CUPS_PRINTER_REMOTE: u32 = 0x0002;
+
 /**
  * The CUPS option struct (cups_option_s)
  * https://www.cups.org/doc/cupspm.html#cups_option_s
@@ -41,12 +57,11 @@ pub struct CupsDestT {
 }
 
 impl CupsDestT {
-    fn get_option(&self, key: &str) -> String {
+    pub fn get_option(&self, key: &str) -> String {
         let key = CString::new(key);
         let mut value = "".to_string();
 
-        if !self.options.is_null() && key.is_ok() {
-            let option_key = key.unwrap();
+        if !self.options.is_null() && let Ok(option_key) = key {
             unsafe {
                 let option_value = cupsGetOption(option_key.as_ptr(), self.num_options, self.options);
                 if !option_value.is_null() {
@@ -84,10 +99,56 @@ impl PlatformPrinterGetters for CupsDestT {
         return self.get_option("printer-is-shared") == "true";
     }
 
+    fn get_is_accepting_jobs(&self) -> bool {
+        return self.get_option("printer-is-accepting-jobs") != "false";
+    }
+
+    fn get_is_class(&self) -> bool {
+        let printer_type = self.get_option("printer-type").parse::<u32>().unwrap_or_default();
+        return printer_type & CUPS_PRINTER_CLASS != 0;
+    }
+
+    fn get_members(&self) -> Vec<String> {
+        // member-uris lists the printers of a class, ex ipp://localhost/printers/office
+        return self
+            .get_option("member-uris")
+            .split(",")
+            .filter_map(|uri| uri.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .collect();
+    }
+
     fn get_uri(&self) -> String {
         return self.get_option("printer-uri-supported");
     }
 
+    fn get_kind(&self) -> PrinterKind {
+        let printer_type = self.get_option("printer-type").parse::<u32>().unwrap_or_default();
+        if printer_type & CUPS_PRINTER_CLASS != 0 {
+            return PrinterKind::Class;
+        }
+
+        // Discovered destinations have no printer uri until CUPS creates their queue, cups-browsed
+        // queues print through its implicitclass backend
+        if self.get_option("printer-is-temporary") == "true"
+            || self.get_option("printer-uri-supported").is_empty()
+            || self.get_option("device-uri").starts_with("implicitclass://")
+        {
+            return PrinterKind::Temporary;
+        }
+
+        if printer_type & CUPS_PRINTER_REMOTE != 0 {
+            return PrinterKind::RemoteQueue;
+        }
+
+        return PrinterKind::Local;
+    }
+
+    fn get_device_uri(&self) -> String {
+        return self.get_option("device-uri");
+    }
+
     fn get_location(&self) -> String {
         return self.get_option("printer-location");
     }
@@ -113,22 +174,31 @@ impl PlatformPrinterGetters for CupsDestT {
     }
 
     fn get_description(&self) -> String {
-        return "".to_string();
+        return self.get_option("printer-info");
     }
 
     fn get_data_type(&self) -> String {
         return self.get_option("media");
     }
+
+    fn get_attributes(&self) -> u64 {
+        return self.get_option("printer-type").parse::<u64>().unwrap_or_default();
+    }
+
+    fn get_jobs_count(&self) -> Option<u32> {
+        // The destinations do not carry queued-job-count
+        return None;
+    }
 }
 
 /**
  * Returns a vector of CupsDestT (cups_dest_s) struct with all available destinations
- * Using cupsGetDests
+ * Using cupsGetDests2
  */
 pub fn get_dests() -> Option<&'static [CupsDestT]> {
     unsafe {
         let mut dests_ptr: *mut CupsDestT = ptr::null_mut();
-        let dests_count: i32 = cupsGetDests(&mut dests_ptr);
+        let dests_count: i32 = cupsGetDests2(super::server::http(), &mut dests_ptr);
         return if dests_count > 0 {
             Some(slice::from_raw_parts(dests_ptr, dests_count as usize))
         } else {
@@ -137,13 +207,99 @@ pub fn get_dests() -> Option<&'static [CupsDestT]> {
     }
 }
 
+/**
+ * Load the destinations, let update change them and save the list using cupsSetDests2,
+ * which writes the lpoptions file of the user (or /etc/cups/lpoptions for root)
+ */
+fn update_dests(
+    name: &str,
+    update: impl FnOnce(&mut [CupsDestT]) -> Result<(), &'static str>,
+) -> Result<(), &'static str> {
+    unsafe {
+        let mut dests_ptr: *mut CupsDestT = ptr::null_mut();
+        let dests_count = cupsGetDests2(super::server::http(), &mut dests_ptr);
+        if dests_count <= 0 {
+            return Err("printer not found");
+        }
+
+        let dests = slice::from_raw_parts_mut(dests_ptr, dests_count as usize);
+        let result = if dests.iter().any(|d| c_char_to_string(d.name) == name) {
+            update(dests).and_then(|_| {
+                if cupsSetDests2(super::server::http(), dests_count, dests_ptr) == 0 {
+                    Ok(())
+                } else {
+                    Err("cupsSetDests2 failed")
+                }
+            })
+        } else {
+            Err("printer not found")
+        };
+
+        cupsFreeDests(dests_count, dests_ptr);
+        return result;
+    }
+}
+
+/**
+ * Mark the destination as the default one
+ */
+pub fn set_default_dest(name: &str) -> Result<(), &'static str> {
+    return update_dests(name, |dests| {
+        for dest in dests.iter_mut() {
+            dest.is_default = (dest.instance.is_null() && c_char_to_string(dest.name) == name) as c_int;
+        }
+        return Ok(());
+    });
+}
+
+/**
+ * Store the options as the defaults of the destination, used by every application that
+ * prints to it without setting them
+ */
+pub fn set_dest_options(name: &str, options: &[(String, String)]) -> Result<(), &'static str> {
+    return update_dests(name, |dests| {
+        let dest = dests
+            .iter_mut()
+            .find(|d| d.instance.is_null() && c_char_to_string(d.name) == name)
+            .ok_or("printer not found")?;
+
+        for (option_name, value) in options {
+            let option_name = str_to_cstring(option_name);
+            let value = str_to_cstring(value);
+            dest.num_options =
+                unsafe { cupsAddOption(option_name.as_ptr(), value.as_ptr(), dest.num_options, &mut dest.options) };
+        }
+        return Ok(());
+    });
+}
+
 /**
  * Free dests memory
  */
 pub fn free(dests: &'static [CupsDestT]) {
-    if dests.len() > 0 {
+    if !dests.is_empty() {
         unsafe {
             cupsFreeDests(dests.len() as i32, dests.as_ptr() as *mut CupsDestT);
         }
     }
 }
+
+/**
+ * Returns the destination with the given name using cupsGetNamedDest
+ */
+pub fn get_named_dest(name: &str) -> Option<&'static CupsDestT> {
+    let name = str_to_cstring(name);
+    return unsafe {
+        let dest_ptr = cupsGetNamedDest(super::server::http(), name.as_ptr(), ptr::null());
+        dest_ptr.as_ref()
+    };
+}
+
+/**
+ * Free a single dest memory
+ */
+pub fn free_dest(dest: &'static CupsDestT) {
+    unsafe {
+        cupsFreeDests(1, dest as *const CupsDestT);
+    }
+}
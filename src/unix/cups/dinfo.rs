@@ -0,0 +1,415 @@
+use crate::{
+    common::base::{
+        attributes::AttributeValue,
+        printer::{OptionChoice, PrintOptions, PrintResolution, PrinterCapabilities, PrinterOption, UnsupportedOption},
+    },
+    unix::{
+        cups::{
+            dests::{free_dest, get_named_dest, CupsDestT, CupsOptionT},
+            ipp::{self, IppAttributeT},
+            options::CupsOptions,
+        },
+        options,
+        utils::strings::{c_char_to_string, str_to_cstring},
+    },
+};
+use libc::{c_char, c_int, c_void};
+use std::ptr;
+
+cups_functions! {
+    unsafe fn cupsCopyDestInfo(http: *mut c_void, dest: *mut CupsDestT) -> *mut CupsDinfoT;
+    unsafe fn cupsFreeDestInfo(dinfo: *mut CupsDinfoT);
+    unsafe fn cupsCheckDestSupported(
+        http: *mut c_void,
+        dest: *mut CupsDestT,
+        dinfo: *mut CupsDinfoT,
+        option: *const c_char,
+        value: *const c_char,
+    ) -> c_int;
+    unsafe fn cupsFindDestDefault(
+        http: *mut c_void,
+        dest: *mut CupsDestT,
+        dinfo: *mut CupsDinfoT,
+        option: *const c_char,
+    ) -> *mut IppAttributeT;
+    unsafe fn cupsLocalizeDestOption(
+        http: *mut c_void,
+        dest: *mut CupsDestT,
+        dinfo: *mut CupsDinfoT,
+        option: *const c_char,
+    ) -> *const c_char;
+    unsafe fn cupsLocalizeDestValue(
+        http: *mut c_void,
+        dest: *mut CupsDestT,
+        dinfo: *mut CupsDinfoT,
+        option: *const c_char,
+        value: *const c_char,
+    ) -> *const c_char;
+    unsafe fn cupsCopyDestConflicts(
+        http: *mut c_void,
+        dest: *mut CupsDestT,
+        dinfo: *mut CupsDinfoT,
+        num_options: c_int,
+        options: *mut CupsOptionT,
+        new_option: *const c_char,
+        new_value: *const c_char,
+        num_conflicts: *mut c_int,
+        conflicts: *mut *mut CupsOptionT,
+        num_resolved: *mut c_int,
+        resolved: *mut *mut CupsOptionT,
+    ) -> c_int;
+    unsafe fn cupsFindDestSupported(
+        http: *mut c_void,
+        dest: *mut CupsDestT,
+        dinfo: *mut CupsDinfoT,
+        option: *const c_char,
+    ) -> *mut IppAttributeT;
+}
+
+/**
+ * Opaque CUPS destination information (cups_dinfo_t)
+ * https://www.cups.org/doc/cupspm.html#cups_dinfo_t
+ */
+#[repr(C)]
+pub struct CupsDinfoT {
+    _private: [u8; 0],
+}
+
+// IPP attributes with a matching "-supported" attribute on the destination. PPD keywords and raw
+// options have no supported values to check against, so they are not validated
+const VALIDATED_OPTIONS: [&str; 12] = [
+    "copies",
+    "finishings",
+    "media",
+    "media-source",
+    "number-up",
+    "orientation-requested",
+    "output-bin",
+    "page-ranges",
+    "print-color-mode",
+    "print-quality",
+    "printer-resolution",
+    "sides",
+];
+
+// Options whose value is a comma separated list of values
+const MULTI_VALUE_OPTIONS: [&str; 1] = ["finishings"];
+
+/**
+ * Destination with its supported options, copied with cupsCopyDestInfo
+ */
+pub struct DestInfo {
+    dest: &'static CupsDestT,
+    dinfo: *mut CupsDinfoT,
+}
+
+impl DestInfo {
+    pub fn new(printer_name: &str) -> Option<Self> {
+        let dest = get_named_dest(printer_name)?;
+        let dinfo = unsafe { cupsCopyDestInfo(super::server::http(), dest as *const CupsDestT as *mut CupsDestT) };
+
+        return if dinfo.is_null() {
+            free_dest(dest);
+            None
+        } else {
+            Some(DestInfo { dest, dinfo })
+        };
+    }
+
+    pub fn dest_ptr(&self) -> *mut CupsDestT {
+        return self.dest as *const CupsDestT as *mut CupsDestT;
+    }
+
+    /**
+     * Check if the option value is supported with cupsCheckDestSupported
+     */
+    pub fn is_supported(&self, option: &str, value: &str) -> bool {
+        let option = str_to_cstring(option);
+        let value = str_to_cstring(value);
+        return unsafe {
+            cupsCheckDestSupported(super::server::http(), self.dest_ptr(), self.dinfo, option.as_ptr(), value.as_ptr()) != 0
+        };
+    }
+
+    /**
+     * Return the "-supported" attribute of the option with cupsFindDestSupported,
+     * the attribute is owned by the destination information
+     */
+    pub fn find_supported(&self, option: &str) -> Option<*mut IppAttributeT> {
+        let option = str_to_cstring(option);
+        let attr = unsafe { cupsFindDestSupported(super::server::http(), self.dest_ptr(), self.dinfo, option.as_ptr()) };
+        return if attr.is_null() { None } else { Some(attr) };
+    }
+
+    /**
+     * Return the default value of the option with cupsFindDestDefault
+     */
+    pub fn default_value(&self, option: &str) -> Option<String> {
+        let option = str_to_cstring(option);
+        let attr = unsafe { cupsFindDestDefault(super::server::http(), self.dest_ptr(), self.dinfo, option.as_ptr()) };
+        if attr.is_null() {
+            return None;
+        }
+        return unsafe { ipp::attribute_values(attr) }.first().map(value_to_string);
+    }
+
+    /**
+     * Return the localized label of the option, or of one of its values
+     */
+    pub fn label(&self, option: &str, value: Option<&str>) -> String {
+        let option = str_to_cstring(option);
+        let label = match value {
+            Some(value) => {
+                let value = str_to_cstring(value);
+                unsafe {
+                    cupsLocalizeDestValue(super::server::http(), self.dest_ptr(), self.dinfo, option.as_ptr(), value.as_ptr())
+                }
+            }
+            None => unsafe { cupsLocalizeDestOption(super::server::http(), self.dest_ptr(), self.dinfo, option.as_ptr()) },
+        };
+        return c_char_to_string(label);
+    }
+
+    /**
+     * Return the keyword values supported for the option
+     */
+    pub fn supported_keywords(&self, option: &str) -> Vec<String> {
+        let values = self
+            .find_supported(option)
+            .map(|attr| unsafe { ipp::attribute_values(attr) })
+            .unwrap_or_default();
+
+        return values
+            .into_iter()
+            .filter_map(|v| match v {
+                AttributeValue::String(value) => Some(value),
+                _ => None,
+            })
+            .collect();
+    }
+}
+
+impl Drop for DestInfo {
+    fn drop(&mut self) {
+        unsafe { cupsFreeDestInfo(self.dinfo) };
+        free_dest(self.dest);
+    }
+}
+
+/**
+ * Return the options the destination can not honor
+ */
+pub fn validate_options(printer_name: &str, options: &PrintOptions) -> Vec<UnsupportedOption> {
+    let dest_info = match DestInfo::new(printer_name) {
+        Some(dest_info) => dest_info,
+        None => {
+            return vec![UnsupportedOption {
+                option: "printer-name".to_string(),
+                value: printer_name.to_string(),
+                reason: "destination information not available",
+            }];
+        }
+    };
+
+    let mut unsupported = vec![];
+
+    for (name, value) in CupsOptions::from(options).to_vec() {
+        if !VALIDATED_OPTIONS.contains(&name.as_str()) {
+            continue;
+        }
+
+        let values: Vec<&str> = if MULTI_VALUE_OPTIONS.contains(&name.as_str()) {
+            value.split(',').collect()
+        } else {
+            vec![value.as_str()]
+        };
+
+        for value in values {
+            if !dest_info.is_supported(&name, value) {
+                unsupported.push(UnsupportedOption {
+                    option: name.clone(),
+                    value: value.to_string(),
+                    reason: "not supported by the destination",
+                });
+            }
+        }
+    }
+
+    return unsupported;
+}
+
+/**
+ * Return the choices supported by the destination, read from its "-supported" attributes
+ */
+pub fn get_capabilities(printer_name: &str) -> Result<PrinterCapabilities, &'static str> {
+    let dest_info = DestInfo::new(printer_name).ok_or("destination information not available")?;
+
+    let mut color_modes = vec![];
+    for color_mode in dest_info
+        .supported_keywords("print-color-mode")
+        .iter()
+        .filter_map(|v| options::color_mode_from_keyword(v))
+    {
+        if !color_modes.contains(&color_mode) {
+            color_modes.push(color_mode);
+        }
+    }
+
+    let resolutions = dest_info
+        .find_supported("printer-resolution")
+        .map(|attr| unsafe { ipp::attribute_resolutions(attr) })
+        .unwrap_or_default();
+
+    let max_copies = dest_info
+        .find_supported("copies")
+        .map(|attr| unsafe { ipp::attribute_range_upper(attr) })
+        .unwrap_or_default();
+
+    return Ok(PrinterCapabilities {
+        media_sizes: dest_info
+            .supported_keywords("media")
+            .iter()
+            .map(|v| options::media_size_from_name(v))
+            .collect(),
+        duplex_modes: dest_info
+            .supported_keywords("sides")
+            .iter()
+            .filter_map(|v| options::duplex_from_keyword(v))
+            .collect(),
+        color_modes,
+        resolutions: resolutions
+            .into_iter()
+            .map(|(x, y)| PrintResolution { x: x.max(0) as u32, y: y.max(0) as u32 })
+            .collect(),
+        max_copies: max_copies.max(1) as u32,
+    });
+}
+
+fn value_to_string(value: &AttributeValue) -> String {
+    return match value {
+        AttributeValue::Integer(value) => value.to_string(),
+        AttributeValue::Boolean(value) => value.to_string(),
+        AttributeValue::String(value) => value.clone(),
+    };
+}
+
+// Collection options have no values that could be listed as choices
+const COLLECTION_OPTIONS: [&str; 3] = ["media-col", "finishings-col", "job-sheets-col"];
+
+/**
+ * Return the options listed in job-creation-attributes-supported with their supported
+ * values, defaults and localized labels
+ */
+pub fn get_printer_options(printer_name: &str) -> Result<Vec<PrinterOption>, &'static str> {
+    let dest_info = DestInfo::new(printer_name).ok_or("destination information not available")?;
+
+    let mut printer_options = vec![];
+    for name in dest_info.supported_keywords("job-creation-attributes") {
+        if COLLECTION_OPTIONS.contains(&name.as_str()) {
+            continue;
+        }
+
+        let values = match dest_info.find_supported(&name) {
+            Some(attr) => unsafe { ipp::attribute_values(attr) },
+            None => continue,
+        };
+
+        let choices = values
+            .iter()
+            .map(value_to_string)
+            .map(|value| OptionChoice {
+                label: dest_info.label(&name, Some(&value)),
+                value,
+            })
+            .collect();
+
+        printer_options.push(PrinterOption {
+            label: dest_info.label(&name, None),
+            default: dest_info.default_value(&name),
+            choices,
+            name,
+        });
+    }
+
+    return Ok(printer_options);
+}
+
+/**
+ * Return the options that conflict with the new option value using cupsCopyDestConflicts
+ */
+pub fn get_option_conflicts(printer_name: &str, options: &PrintOptions, option: &str, value: &str) -> Vec<(String, String)> {
+    let dest_info = match DestInfo::new(printer_name) {
+        Some(dest_info) => dest_info,
+        None => return vec![],
+    };
+
+    let cups_options = CupsOptions::from(options);
+    let option = str_to_cstring(option);
+    let value = str_to_cstring(value);
+    let mut num_conflicts: c_int = 0;
+    let mut conflicts: *mut CupsOptionT = ptr::null_mut();
+
+    let has_conflicts = unsafe {
+        cupsCopyDestConflicts(
+            super::server::http(),
+            dest_info.dest_ptr(),
+            dest_info.dinfo,
+            cups_options.len(),
+            cups_options.as_ptr(),
+            option.as_ptr(),
+            value.as_ptr(),
+            &mut num_conflicts,
+            &mut conflicts,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+
+    if has_conflicts != 1 {
+        return vec![];
+    }
+    return unsafe { CupsOptions::from_raw(num_conflicts, conflicts) }.to_vec();
+}
+
+// Options read back by get_default_options
+const DEFAULT_OPTIONS: [&str; 7] = [
+    "copies",
+    "media",
+    "sides",
+    "print-color-mode",
+    "print-quality",
+    "printer-resolution",
+    "orientation-requested",
+];
+
+/**
+ * Return the options a job gets when it does not set them, the lpoptions defaults of the user
+ * take precedence over the queue defaults as they do for lp
+ */
+pub fn get_default_options(printer_name: &str) -> Result<PrintOptions, &'static str> {
+    let dest_info = DestInfo::new(printer_name).ok_or("destination information not available")?;
+    let mut options = PrintOptions::default();
+
+    for name in DEFAULT_OPTIONS {
+        let value = match dest_info.dest.get_option(name) {
+            value if !value.is_empty() => value,
+            _ => match dest_info.default_value(name) {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+
+        match name {
+            "copies" => options.copies = value.parse().ok(),
+            "media" => options.media_size = Some(options::media_size_from_name(&value)),
+            "sides" => options.duplex = options::duplex_from_keyword(&value),
+            "print-color-mode" => options.color_mode = options::color_mode_from_keyword(&value),
+            "print-quality" => options.quality = options::quality_from_value(&value),
+            "printer-resolution" => options.resolution = options::resolution_from_value(&value),
+            "orientation-requested" => options.orientation = options::orientation_from_value(&value),
+            _ => {}
+        }
+    }
+
+    return Ok(options);
+}
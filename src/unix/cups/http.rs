@@ -0,0 +1,263 @@
+use crate::unix::utils::strings::{c_char_to_string, str_to_cstring};
+use libc::{c_char, c_double, c_int, c_void, size_t, ssize_t};
+use std::{io::Read, ptr};
+
+cups_functions! {
+    unsafe fn httpSeparateURI(
+        decoding: c_int,
+        uri: *const c_char,
+        scheme: *mut c_char,
+        schemelen: c_int,
+        username: *mut c_char,
+        usernamelen: c_int,
+        host: *mut c_char,
+        hostlen: c_int,
+        port: *mut c_int,
+        resource: *mut c_char,
+        resourcelen: c_int,
+    ) -> c_int;
+    unsafe fn httpConnect2(
+        host: *const c_char,
+        port: c_int,
+        addrlist: *mut c_void,
+        family: c_int,
+        encryption: c_int,
+        blocking: c_int,
+        msec: c_int,
+        cancel: *mut c_int,
+    ) -> *mut c_void;
+    unsafe fn httpClose(http: *mut c_void);
+    unsafe fn httpClearFields(http: *mut c_void);
+    unsafe fn httpGet(http: *mut c_void, uri: *const c_char) -> c_int;
+    unsafe fn httpUpdate(http: *mut c_void) -> c_int;
+    unsafe fn cupsServer() -> *const c_char;
+    unsafe fn ippPort() -> c_int;
+    unsafe fn cupsEncryption() -> c_int;
+    unsafe fn httpRead2(http: *mut c_void, buffer: *mut c_char, length: size_t) -> ssize_t;
+    unsafe fn httpSetTimeout(http: *mut c_void, timeout: c_double, callback: Option<TimeoutCallback>, user_data: *mut c_void);
+    unsafe fn httpGetFd(http: *mut c_void) -> c_int;
+    unsafe fn httpError(http: *mut c_void) -> c_int;
+}
+
+type TimeoutCallback = unsafe extern "C" fn(*mut c_void, *mut c_void) -> c_int;
+
+// Based on:
+// https://github.com/apple/cups/blob/a8968fc4257322b1e4e191c4bccedea98d7b053e/cups/http.h
+const HTTP_URI_CODING_ALL: c_int = 0x0F;
+const HTTP_ENCRYPTION_IF_REQUESTED: c_int = 0;
+const HTTP_ENCRYPTION_ALWAYS: c_int = 3;
+const HTTP_STATUS_CONTINUE: c_int = 100;
+const HTTP_STATUS_OK: c_int = 200;
+const AF_UNSPEC: c_int = 0;
+const CONNECT_TIMEOUT_MSEC: c_int = 30000;
+
+/**
+ * Called by libcups when the server did not answer within the read timeout, 0 stops waiting
+ */
+unsafe extern "C" fn stop_waiting(_http: *mut c_void, _user_data: *mut c_void) -> c_int {
+    return 0;
+}
+
+/**
+ * Parts of an uri split with httpSeparateURI
+ */
+pub struct Uri {
+    pub scheme: String,
+    pub host: String,
+    pub port: c_int,
+    pub resource: String,
+}
+
+impl Uri {
+    pub fn parse(uri: &str) -> Result<Self, &'static str> {
+        let uri = str_to_cstring(uri);
+        let mut scheme = [0 as c_char; 32];
+        let mut username = [0 as c_char; 256];
+        let mut host = [0 as c_char; 256];
+        let mut resource = [0 as c_char; 1024];
+        let mut port: c_int = 0;
+
+        let status = unsafe {
+            httpSeparateURI(
+                HTTP_URI_CODING_ALL,
+                uri.as_ptr(),
+                scheme.as_mut_ptr(),
+                scheme.len() as c_int,
+                username.as_mut_ptr(),
+                username.len() as c_int,
+                host.as_mut_ptr(),
+                host.len() as c_int,
+                &mut port,
+                resource.as_mut_ptr(),
+                resource.len() as c_int,
+            )
+        };
+
+        return if status < 0 {
+            Err("invalid uri")
+        } else {
+            Ok(Uri {
+                scheme: c_char_to_string(scheme.as_ptr()),
+                host: c_char_to_string(host.as_ptr()),
+                port,
+                resource: c_char_to_string(resource.as_ptr()),
+            })
+        };
+    }
+
+    /**
+     * Secure schemes always use TLS
+     */
+    pub fn encryption(&self) -> c_int {
+        return if self.scheme == "https" || self.scheme == "ipps" {
+            HTTP_ENCRYPTION_ALWAYS
+        } else {
+            HTTP_ENCRYPTION_IF_REQUESTED
+        };
+    }
+}
+
+/**
+ * Connection opened with httpConnect2, closed on drop
+ */
+pub struct HttpConnection {
+    http: *mut c_void,
+}
+
+impl HttpConnection {
+    pub fn connect(uri: &Uri) -> Result<Self, &'static str> {
+        return HttpConnection::open(&uri.host, uri.port, uri.encryption());
+    }
+
+    /**
+     * Connect to the CUPS server used by the default connection, cupsServer may be a domain socket path
+     */
+    pub fn connect_server() -> Result<Self, &'static str> {
+        let host = c_char_to_string(unsafe { cupsServer() });
+        return HttpConnection::open(&host, unsafe { ippPort() }, unsafe { cupsEncryption() });
+    }
+
+    /**
+     * Connect within the connect_timeout of the Config, the reads then fail after its read_timeout
+     */
+    fn open(host: &str, port: c_int, encryption: c_int) -> Result<Self, &'static str> {
+        let connect_timeout = crate::common::backend::connect_timeout()
+            .map(|t| t.as_millis().clamp(1, c_int::MAX as u128) as c_int)
+            .unwrap_or(CONNECT_TIMEOUT_MSEC);
+        let host = str_to_cstring(host);
+        let http = unsafe {
+            httpConnect2(
+                host.as_ptr(),
+                port,
+                ptr::null_mut(),
+                AF_UNSPEC,
+                encryption,
+                1,
+                connect_timeout,
+                ptr::null_mut(),
+            )
+        };
+
+        if http.is_null() {
+            return Err("httpConnect2 failed");
+        }
+        if let Some(read_timeout) = crate::common::backend::read_timeout() {
+            unsafe { httpSetTimeout(http, read_timeout.as_secs_f64(), Some(stop_waiting), ptr::null_mut()) };
+        }
+        return Ok(HttpConnection { http });
+    }
+
+    pub fn as_ptr(&self) -> *mut c_void {
+        return self.http;
+    }
+
+    /**
+     * Definition if the connection cannot be reused, a call on it failed (ex it timed out) or
+     * the server closed it after its keep alive timeout
+     */
+    pub fn is_broken(&self) -> bool {
+        if unsafe { httpError(self.http) } != 0 {
+            return true;
+        }
+
+        let mut byte = 0u8;
+        let read = unsafe {
+            libc::recv(httpGetFd(self.http), &mut byte as *mut u8 as *mut c_void, 1, libc::MSG_PEEK | libc::MSG_DONTWAIT)
+        };
+        let error = std::io::Error::last_os_error().kind();
+        return read == 0 || read < 0 && error != std::io::ErrorKind::WouldBlock && error != std::io::ErrorKind::Interrupted;
+    }
+}
+
+impl Drop for HttpConnection {
+    fn drop(&mut self) {
+        unsafe { httpClose(self.http) };
+    }
+}
+
+/**
+ * Definition if the last call on the connection failed since the server did not answer within
+ * the read timeout
+ */
+pub fn timed_out(http: *mut c_void) -> bool {
+    return !http.is_null() && unsafe { httpError(http) } == libc::ETIMEDOUT;
+}
+
+/**
+ * Body of an HTTP response, read with httpRead2
+ */
+pub struct HttpReader {
+    connection: HttpConnection,
+}
+
+impl HttpReader {
+    /**
+     * Read the rest of the current response of the connection
+     */
+    pub fn new(connection: HttpConnection) -> Self {
+        return HttpReader { connection };
+    }
+}
+
+impl Read for HttpReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = unsafe { httpRead2(self.connection.as_ptr(), buf.as_mut_ptr() as *mut c_char, buf.len()) };
+        return if read < 0 {
+            Err(std::io::Error::other("httpRead2 failed"))
+        } else {
+            Ok(read as usize)
+        };
+    }
+}
+
+/**
+ * Start downloading the http or https uri
+ */
+pub fn get(uri: &str) -> Result<HttpReader, &'static str> {
+    let uri = Uri::parse(uri)?;
+    if uri.scheme != "http" && uri.scheme != "https" {
+        return Err("only http and https uris can be downloaded");
+    }
+
+    let connection = HttpConnection::connect(&uri)?;
+    let resource = str_to_cstring(&uri.resource);
+
+    let status = unsafe {
+        httpClearFields(connection.as_ptr());
+        if httpGet(connection.as_ptr(), resource.as_ptr()) != 0 {
+            return Err("httpGet failed");
+        }
+
+        let mut status = httpUpdate(connection.as_ptr());
+        while status == HTTP_STATUS_CONTINUE {
+            status = httpUpdate(connection.as_ptr());
+        }
+        status
+    };
+
+    return if status == HTTP_STATUS_OK {
+        Ok(HttpReader { connection })
+    } else {
+        Err("document download failed")
+    };
+}
@@ -0,0 +1,432 @@
+use crate::common::base::attributes::{AttributeValue, Attributes};
+use crate::unix::{
+    cups::{
+        http::{self, HttpConnection, HttpReader},
+        options::CupsOptions,
+    },
+    utils::strings::{c_char_to_string, path_to_cstring, str_to_cstring},
+};
+use libc::{c_char, c_int, c_void, size_t};
+use std::{collections::HashMap, path::Path, ptr};
+
+cups_functions! {
+    unsafe fn ippNewRequest(op: c_int) -> *mut IppT;
+    unsafe fn ippDelete(ipp: *mut IppT);
+    unsafe fn ippAddString(
+        ipp: *mut IppT,
+        group: c_int,
+        value_tag: c_int,
+        name: *const c_char,
+        language: *const c_char,
+        value: *const c_char,
+    ) -> *mut IppAttributeT;
+    unsafe fn ippAddInteger(
+        ipp: *mut IppT,
+        group: c_int,
+        value_tag: c_int,
+        name: *const c_char,
+        value: c_int,
+    ) -> *mut IppAttributeT;
+    unsafe fn ippAddStrings(
+        ipp: *mut IppT,
+        group: c_int,
+        value_tag: c_int,
+        name: *const c_char,
+        num_values: c_int,
+        language: *const c_char,
+        values: *const *const c_char,
+    ) -> *mut IppAttributeT;
+    unsafe fn ippAddBoolean(ipp: *mut IppT, group: c_int, name: *const c_char, value: c_char) -> *mut IppAttributeT;
+    unsafe fn ippFirstAttribute(ipp: *mut IppT) -> *mut IppAttributeT;
+    unsafe fn ippNextAttribute(ipp: *mut IppT) -> *mut IppAttributeT;
+    unsafe fn ippGetName(attr: *mut IppAttributeT) -> *const c_char;
+    unsafe fn ippGetGroupTag(attr: *mut IppAttributeT) -> c_int;
+    unsafe fn ippGetValueTag(attr: *mut IppAttributeT) -> c_int;
+    unsafe fn ippGetBoolean(attr: *mut IppAttributeT, element: c_int) -> c_int;
+    unsafe fn ippAttributeString(attr: *mut IppAttributeT, buffer: *mut c_char, bufsize: size_t) -> size_t;
+    unsafe fn ippDeleteAttribute(ipp: *mut IppT, attr: *mut IppAttributeT);
+    unsafe fn ippFindAttribute(ipp: *mut IppT, name: *const c_char, value_tag: c_int) -> *mut IppAttributeT;
+    unsafe fn ippGetStatusCode(ipp: *mut IppT) -> c_int;
+    unsafe fn ippGetCount(attr: *mut IppAttributeT) -> c_int;
+    unsafe fn ippGetInteger(attr: *mut IppAttributeT, element: c_int) -> c_int;
+    unsafe fn ippGetRange(attr: *mut IppAttributeT, element: c_int, upper: *mut c_int) -> c_int;
+    unsafe fn ippGetResolution(attr: *mut IppAttributeT, element: c_int, yres: *mut c_int, units: *mut c_int) -> c_int;
+    unsafe fn ippGetString(attr: *mut IppAttributeT, element: c_int, language: *mut *const c_char) -> *const c_char;
+    unsafe fn cupsEncodeOptions2(ipp: *mut IppT, num_options: c_int, options: *mut c_void, group_tag: c_int);
+    unsafe fn cupsSendRequest(http: *mut c_void, request: *mut IppT, resource: *const c_char, length: size_t) -> c_int;
+    unsafe fn cupsGetResponse(http: *mut c_void, resource: *const c_char) -> *mut IppT;
+    unsafe fn cupsDoFileRequest(
+        http: *mut c_void,
+        request: *mut IppT,
+        resource: *const c_char,
+        filename: *const c_char,
+    ) -> *mut IppT;
+    unsafe fn cupsLastError() -> c_int;
+    unsafe fn cupsUser() -> *const c_char;
+}
+
+/**
+ * Opaque IPP message (ipp_t)
+ */
+#[repr(C)]
+pub struct IppT {
+    _private: [u8; 0],
+}
+
+/**
+ * Opaque IPP attribute (ipp_attribute_t)
+ */
+#[repr(C)]
+pub struct IppAttributeT {
+    _private: [u8; 0],
+}
+
+// Based on:
+// https://github.com/apple/cups/blob/a8968fc4257322b1e4e191c4bccedea98d7b053e/cups/ipp.h
+pub const IPP_TAG_ZERO: c_int = 0x00;
+pub const IPP_TAG_OPERATION: c_int = 0x01;
+pub const IPP_TAG_JOB: c_int = 0x02;
+pub const IPP_TAG_PRINTER: c_int = 0x04;
+pub const IPP_TAG_INTEGER: c_int = 0x21;
+const IPP_TAG_BOOLEAN: c_int = 0x22;
+pub const IPP_TAG_ENUM: c_int = 0x23;
+// Out of band values (unsupported, unknown, no-value...) are below this tag
+const IPP_TAG_INTEGER_FIRST: c_int = 0x20;
+const IPP_TAG_TEXTLANG: c_int = 0x35;
+const IPP_TAG_NAMELANG: c_int = 0x36;
+const IPP_TAG_TEXT_FIRST: c_int = 0x41;
+pub const IPP_TAG_TEXT: c_int = 0x41;
+const IPP_TAG_MIMETYPE: c_int = 0x49;
+pub const IPP_TAG_NAME: c_int = 0x42;
+pub const IPP_TAG_KEYWORD: c_int = 0x44;
+pub const IPP_TAG_URI: c_int = 0x45;
+
+pub const IPP_OP_PRINT_URI: c_int = 0x0003;
+pub const IPP_OP_CREATE_JOB: c_int = 0x0005;
+pub const IPP_OP_GET_JOB_ATTRIBUTES: c_int = 0x0009;
+pub const IPP_OP_GET_JOBS: c_int = 0x000A;
+pub const IPP_OP_GET_PRINTER_ATTRIBUTES: c_int = 0x000B;
+pub const IPP_OP_HOLD_JOB: c_int = 0x000C;
+pub const IPP_OP_RELEASE_JOB: c_int = 0x000D;
+pub const IPP_OP_RESTART_JOB: c_int = 0x000E;
+pub const IPP_OP_PAUSE_PRINTER: c_int = 0x0010;
+pub const IPP_OP_RESUME_PRINTER: c_int = 0x0011;
+pub const IPP_OP_SET_JOB_ATTRIBUTES: c_int = 0x0014;
+pub const IPP_OP_CUPS_ADD_MODIFY_PRINTER: c_int = 0x4003;
+pub const IPP_OP_CUPS_DELETE_PRINTER: c_int = 0x4004;
+pub const IPP_OP_CUPS_ACCEPT_JOBS: c_int = 0x4008;
+pub const IPP_OP_CUPS_REJECT_JOBS: c_int = 0x4009;
+pub const IPP_OP_CUPS_MOVE_JOB: c_int = 0x400D;
+pub const IPP_OP_CUPS_GET_DOCUMENT: c_int = 0x4027;
+
+const IPP_RES_PER_CM: c_int = 4;
+
+const HTTP_STATUS_CONTINUE: c_int = 100;
+// Any ipp_status_t above this value is an error
+const IPP_STATUS_OK_EVENTS_COMPLETE: c_int = 0x0007;
+
+const SERVER_TIMEOUT: &str = "the CUPS server did not answer in time";
+
+/**
+ * Describe an ipp_status_t error
+ */
+pub fn status_message(status: c_int) -> &'static str {
+    return match status {
+        0x0400 => "bad request",
+        0x0401 => "forbidden",
+        0x0402 => "not authenticated",
+        0x0403 => "not authorized",
+        0x0404 => "not possible",
+        0x0405 => "request timeout",
+        0x0406 => "not found",
+        0x0407 => "gone",
+        0x040B => "document format not supported",
+        0x040C => "attributes or values not supported",
+        0x0501 => "operation not supported",
+        0x0502 => "service unavailable",
+        0x0506 => "printer is not accepting jobs",
+        _ => "IPP request failed",
+    };
+}
+
+/**
+ * Printer uri of a local CUPS queue, used as the target of IPP requests
+ */
+pub fn printer_uri(printer_name: &str) -> String {
+    return format!("ipp://localhost/printers/{}", printer_name);
+}
+
+/**
+ * Job uri of a CUPS job, used as the target of job operations
+ */
+pub fn job_uri(job_id: u64) -> String {
+    return format!("ipp://localhost/jobs/{}", job_id);
+}
+
+/**
+ * Name of the current CUPS user, sent as requesting-user-name
+ */
+pub fn current_user() -> String {
+    return c_char_to_string(unsafe { cupsUser() });
+}
+
+/**
+ * IPP request built with ippNewRequest, released on drop unless sent
+ */
+pub struct IppRequest {
+    ipp: *mut IppT,
+}
+
+impl IppRequest {
+    /**
+     * Create the request with the requesting-user-name already set
+     */
+    pub fn new(operation: c_int) -> Self {
+        let mut request = IppRequest {
+            ipp: unsafe { ippNewRequest(operation) },
+        };
+        request.add_string(IPP_TAG_OPERATION, IPP_TAG_NAME, "requesting-user-name", &current_user());
+        return request;
+    }
+
+    pub fn add_string(&mut self, group: c_int, value_tag: c_int, name: &str, value: &str) {
+        let name = str_to_cstring(name);
+        let value = str_to_cstring(value);
+        unsafe { ippAddString(self.ipp, group, value_tag, name.as_ptr(), ptr::null(), value.as_ptr()) };
+    }
+
+    pub fn add_integer(&mut self, group: c_int, value_tag: c_int, name: &str, value: i32) {
+        let name = str_to_cstring(name);
+        unsafe { ippAddInteger(self.ipp, group, value_tag, name.as_ptr(), value) };
+    }
+
+    pub fn add_strings(&mut self, group: c_int, value_tag: c_int, name: &str, values: &[&str]) {
+        let name = str_to_cstring(name);
+        let values: Vec<_> = values.iter().map(|v| str_to_cstring(v)).collect();
+        let values_ptrs: Vec<*const c_char> = values.iter().map(|v| v.as_ptr()).collect();
+        unsafe {
+            ippAddStrings(
+                self.ipp,
+                group,
+                value_tag,
+                name.as_ptr(),
+                values_ptrs.len() as c_int,
+                ptr::null(),
+                values_ptrs.as_ptr(),
+            )
+        };
+    }
+
+    pub fn add_boolean(&mut self, group: c_int, name: &str, value: bool) {
+        let name = str_to_cstring(name);
+        unsafe { ippAddBoolean(self.ipp, group, name.as_ptr(), value as c_char) };
+    }
+
+    /**
+     * Replace the requesting-user-name, ex to list the jobs of another user
+     */
+    pub fn set_user(&mut self, user_name: &str) {
+        let name = str_to_cstring("requesting-user-name");
+        unsafe {
+            let attr = ippFindAttribute(self.ipp, name.as_ptr(), IPP_TAG_NAME);
+            if !attr.is_null() {
+                ippDeleteAttribute(self.ipp, attr);
+            }
+        }
+        self.add_string(IPP_TAG_OPERATION, IPP_TAG_NAME, "requesting-user-name", user_name);
+    }
+
+    /**
+     * Encode the CUPS options as attributes of the group with cupsEncodeOptions2
+     */
+    pub fn add_options(&mut self, options: &CupsOptions, group: c_int) {
+        unsafe { cupsEncodeOptions2(self.ipp, options.len(), options.as_ptr() as *mut c_void, group) };
+    }
+
+    /**
+     * Send the request, a null http uses the default CUPS server connection
+     */
+    pub fn send(self, http: *mut c_void, resource: &str) -> Result<IppResponse, &'static str> {
+        return self.send_file(http, resource, None);
+    }
+
+    /**
+     * Send the request with cupsDoFileRequest, the file (ex a PPD) is sent after the attributes
+     */
+    pub fn send_file(mut self, http: *mut c_void, resource: &str, file_path: Option<&Path>) -> Result<IppResponse, &'static str> {
+        let resource = str_to_cstring(resource);
+        let file_path = file_path.map(path_to_cstring).transpose()?;
+        let file_path_ptr = file_path.as_ref().map_or(ptr::null(), |p| p.as_ptr());
+        let ipp = std::mem::replace(&mut self.ipp, ptr::null_mut());
+        let response = unsafe { cupsDoFileRequest(http, ipp, resource.as_ptr(), file_path_ptr) };
+
+        if response.is_null() && http::timed_out(http) {
+            return Err(SERVER_TIMEOUT);
+        }
+        if response.is_null() {
+            return Err(status_message(unsafe { cupsLastError() }));
+        }
+
+        let response = IppResponse { ipp: response };
+        let status = response.status();
+        return if status > IPP_STATUS_OK_EVENTS_COMPLETE {
+            Err(status_message(status))
+        } else {
+            Ok(response)
+        };
+    }
+
+    /**
+     * Send the request with cupsSendRequest and read its response, leaving the connection at
+     * the data that follows the response, ex the document of CUPS-Get-Document
+     */
+    pub fn send_for_data(self, connection: HttpConnection, resource: &str) -> Result<(IppResponse, HttpReader), &'static str> {
+        let resource = str_to_cstring(resource);
+
+        unsafe {
+            if cupsSendRequest(connection.as_ptr(), self.ipp, resource.as_ptr(), 0) != HTTP_STATUS_CONTINUE {
+                return Err(status_message(cupsLastError()));
+            }
+
+            let response = cupsGetResponse(connection.as_ptr(), resource.as_ptr());
+            if response.is_null() {
+                return Err(status_message(cupsLastError()));
+            }
+
+            let response = IppResponse { ipp: response };
+            let status = response.status();
+            return if status > IPP_STATUS_OK_EVENTS_COMPLETE {
+                Err(status_message(status))
+            } else {
+                Ok((response, HttpReader::new(connection)))
+            };
+        }
+    }
+}
+
+impl Drop for IppRequest {
+    fn drop(&mut self) {
+        if !self.ipp.is_null() {
+            unsafe { ippDelete(self.ipp) };
+        }
+    }
+}
+
+/**
+ * IPP response returned by cupsDoRequest, released on drop
+ */
+pub struct IppResponse {
+    ipp: *mut IppT,
+}
+
+impl IppResponse {
+    pub fn status(&self) -> c_int {
+        return unsafe { ippGetStatusCode(self.ipp) };
+    }
+
+    fn find(&self, name: &str) -> Option<*mut IppAttributeT> {
+        let name = str_to_cstring(name);
+        let attr = unsafe { ippFindAttribute(self.ipp, name.as_ptr(), IPP_TAG_ZERO) };
+        return if attr.is_null() { None } else { Some(attr) };
+    }
+
+    /**
+     * Return the first value of an integer or enum attribute
+     */
+    pub fn find_integer(&self, name: &str) -> Option<i32> {
+        return self.find(name).map(|attr| unsafe { ippGetInteger(attr, 0) });
+    }
+
+    /**
+     * Copy the attributes of every group with the tag, ex each job of a Get-Jobs response
+     */
+    pub fn groups(&self, group_tag: c_int) -> Vec<Attributes> {
+        let mut groups = vec![];
+        let mut current: Option<Attributes> = None;
+
+        unsafe {
+            let mut attr = ippFirstAttribute(self.ipp);
+            while !attr.is_null() {
+                let name = ippGetName(attr);
+
+                // Groups are split by a separator attribute without name
+                if ippGetGroupTag(attr) != group_tag || name.is_null() {
+                    if let Some(group) = current.take() {
+                        groups.push(group);
+                    }
+                } else {
+                    current
+                        .get_or_insert_with(HashMap::new)
+                        .insert(c_char_to_string(name), attribute_values(attr));
+                }
+
+                attr = ippNextAttribute(self.ipp);
+            }
+        }
+
+        if let Some(group) = current {
+            groups.push(group);
+        }
+        return groups;
+    }
+}
+
+impl Drop for IppResponse {
+    fn drop(&mut self) {
+        unsafe { ippDelete(self.ipp) };
+    }
+}
+
+/**
+ * Copy the values of an attribute
+ */
+pub unsafe fn attribute_values(attr: *mut IppAttributeT) -> Vec<AttributeValue> {
+    let value_tag = unsafe { ippGetValueTag(attr) };
+    let count = unsafe { ippGetCount(attr) };
+
+    return match value_tag {
+        tag if tag < IPP_TAG_INTEGER_FIRST => vec![],
+        IPP_TAG_INTEGER | IPP_TAG_ENUM => (0..count)
+            .map(|i| AttributeValue::Integer(unsafe { ippGetInteger(attr, i) }))
+            .collect(),
+        IPP_TAG_BOOLEAN => (0..count)
+            .map(|i| AttributeValue::Boolean(unsafe { ippGetBoolean(attr, i) } != 0))
+            .collect(),
+        IPP_TAG_TEXTLANG | IPP_TAG_NAMELANG | IPP_TAG_TEXT_FIRST..=IPP_TAG_MIMETYPE => (0..count)
+            .map(|i| AttributeValue::String(c_char_to_string(unsafe { ippGetString(attr, i, ptr::null_mut()) })))
+            .collect(),
+        _ => {
+            let mut buffer = [0 as c_char; 1024];
+            unsafe { ippAttributeString(attr, buffer.as_mut_ptr(), buffer.len()) };
+            vec![AttributeValue::String(c_char_to_string(buffer.as_ptr()))]
+        }
+    };
+}
+
+/**
+ * Return the upper bound of the first rangeOfInteger value, ex 999 for copies-supported 1-999
+ */
+pub unsafe fn attribute_range_upper(attr: *mut IppAttributeT) -> i32 {
+    let mut upper: c_int = 0;
+    unsafe { ippGetRange(attr, 0, &mut upper) };
+    return upper;
+}
+
+/**
+ * Return the (x, y) resolutions of the attribute in dots per inch
+ */
+pub unsafe fn attribute_resolutions(attr: *mut IppAttributeT) -> Vec<(i32, i32)> {
+    let count = unsafe { ippGetCount(attr) };
+    return (0..count)
+        .map(|i| {
+            let mut y: c_int = 0;
+            let mut units: c_int = 0;
+            let x = unsafe { ippGetResolution(attr, i, &mut y, &mut units) };
+            if units == IPP_RES_PER_CM {
+                ((x as f32 * 2.54).round() as i32, (y as f32 * 2.54).round() as i32)
+            } else {
+                (x, y)
+            }
+        })
+        .collect();
+}
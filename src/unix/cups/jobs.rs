@@ -1,136 +1,468 @@
-use crate::common::base::printer::{PrintOptions, PrintOrientation};
-use crate::unix::cups::dests::CupsOptionT;
+use crate::common::base::job::{JobDocument, JobId, JobOwner, JobProgress, JobQuery, WhichJobs};
+use crate::common::base::attributes::{AttributeValue, Attributes};
+use crate::common::base::printer::PrintOptions;
+use crate::unix::cups::{
+    http,
+    ipp::{self, IppRequest},
+    options::CupsOptions,
+    server::{self, RequestingUser},
+};
 use crate::{
     common::traits::platform::PlatformPrinterJobGetters,
-    unix::utils::{date::time_t_to_system_time, strings::{c_char_to_string, str_to_cstring}},
+    unix::utils::{date::time_t_to_system_time, strings::str_to_cstring},
+};
+use libc::{c_char, c_int, c_void, size_t, time_t};
+use std::{
+    fs::File,
+    io::Read,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
 };
-use libc::{c_char, c_int, time_t};
-use std::ffi::CStr;
-use std::{slice, time::SystemTime};
-
-#[link(name = "cups")]
-unsafe extern "C" {
-
-    unsafe fn cupsPrintFile(
-        printer_name: *const c_char,
-        filename: *const c_char,
-        title: *const c_char,
-        num_options: c_int,
-        options: *const CupsOptionT,
-    ) -> i32;
-
-    unsafe fn cupsGetJobs(
-        jobs: *mut *mut CupsJobsS,
+
+cups_functions! {
+
+    unsafe fn cupsStartDocument(
+        http: *mut c_void,
         name: *const c_char,
-        myjobs: c_int,
-        whichjobs: c_int,
+        job_id: c_int,
+        docname: *const c_char,
+        format: *const c_char,
+        last_document: c_int,
     ) -> c_int;
 
+    unsafe fn cupsWriteRequestData(http: *mut c_void, buffer: *const c_char, length: size_t) -> c_int;
+
+    unsafe fn cupsFinishDocument(http: *mut c_void, name: *const c_char) -> c_int;
+
+    unsafe fn cupsCancelJob2(http: *mut c_void, name: *const c_char, job_id: c_int, purge: c_int) -> c_int;
+
 }
 
-#[derive(Debug)]
-#[repr(C)]
-pub struct CupsJobsS {
-    id: c_int,
-    dest: *const c_char,
-    title: *const c_char,
-    user: *const c_char,
-    format: *const c_char,
-    state: c_int,
-    size: c_int,
-    priority: c_int,
-    completed_time: time_t,
-    creation_time: time_t,
-    processing_time: time_t,
+/**
+ * Job attributes copied from an IPP Get-Jobs response
+ */
+pub struct IppJob {
+    attributes: Attributes,
 }
 
-impl PlatformPrinterJobGetters for CupsJobsS {
+impl IppJob {
+    fn integer(&self, name: &str) -> Option<i32> {
+        return match self.attributes.get(name).and_then(|v| v.first()) {
+            Some(AttributeValue::Integer(value)) => Some(*value),
+            _ => None,
+        };
+    }
+
+    fn strings(&self, name: &str) -> Vec<String> {
+        return self
+            .attributes
+            .get(name)
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| if let AttributeValue::String(s) = v { Some(s.clone()) } else { None })
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
+
+    fn string(&self, name: &str) -> String {
+        return self.strings(name).into_iter().next().unwrap_or_default();
+    }
+
+    fn time(&self, name: &str) -> Option<SystemTime> {
+        return time_t_to_system_time(self.integer(name).unwrap_or_default() as time_t);
+    }
+}
+
+impl PlatformPrinterJobGetters for IppJob {
     fn get_id(&self) -> u64 {
-       return self.id as u64;
+        return self.integer("job-id").unwrap_or_default() as u64;
     }
 
     fn get_name(&self) -> String {
-        return c_char_to_string(self.title);
+        return self.string("job-name");
     }
 
     fn get_state(&self) -> u64 {
-        return self.state as u64;
+        return self.integer("job-state").unwrap_or_default() as u64;
+    }
+
+    fn get_state_reasons(&self) -> Vec<String> {
+        return self.strings("job-state-reasons").into_iter().filter(|r| r != "none").collect();
     }
 
     fn get_printer(&self) -> String {
-        return c_char_to_string(self.dest);
+        // ipp://localhost/printers/<name>
+        let uri = self.string("job-printer-uri");
+        return uri.rsplit('/').next().unwrap_or_default().to_string();
+    }
+
+    fn get_user(&self) -> String {
+        return self.string("job-originating-user-name");
     }
 
     fn get_media_type(&self) -> String {
-        return c_char_to_string(self.format);
+        return self.string("document-format");
+    }
+
+    fn get_pages_completed(&self) -> Option<u32> {
+        return self.integer("job-impressions-completed").filter(|v| *v >= 0).map(|v| v as u32);
+    }
+
+    fn get_pages_total(&self) -> Option<u32> {
+        return self.integer("job-impressions").filter(|v| *v >= 0).map(|v| v as u32);
     }
 
     fn get_created_at(&self) -> SystemTime {
-        return time_t_to_system_time(self.creation_time).unwrap();
+        return self.time("time-at-creation").unwrap_or(UNIX_EPOCH);
     }
 
     fn get_processed_at(&self) -> Option<SystemTime> {
-        return time_t_to_system_time(self.processing_time);
+        return self.time("time-at-processing");
     }
 
     fn get_completed_at(&self) -> Option<SystemTime> {
-        return time_t_to_system_time(self.completed_time);
+        return self.time("time-at-completed");
     }
 }
 
+// Attributes read by the PlatformPrinterJobGetters of IppJob
+const JOB_ATTRIBUTES: [&str; 12] = [
+    "job-id",
+    "job-name",
+    "job-state",
+    "job-state-reasons",
+    "job-printer-uri",
+    "job-originating-user-name",
+    "document-format",
+    "job-impressions",
+    "job-impressions-completed",
+    "time-at-creation",
+    "time-at-processing",
+    "time-at-completed",
+];
+
 /**
- * Return the printer jobs
+ * Return the printer jobs with IPP Get-Jobs. The offset is sent as first-index, which is 1 based
  */
-pub fn get_printer_jobs(printer_name: &str, active_only: bool) -> Option<&'static [CupsJobsS]> {
-    let mut jobs_ptr: *mut CupsJobsS = std::ptr::null_mut();
-    let whichjobs = if active_only { 0 } else { -1 };
-    let name = str_to_cstring(printer_name);
+pub fn get_printer_jobs(printer_name: &str, query: &JobQuery) -> Result<Vec<IppJob>, &'static str> {
+    let which_jobs = match query.which_jobs {
+        WhichJobs::Active => "not-completed",
+        WhichJobs::Completed => "completed",
+        WhichJobs::All => "all",
+    };
 
-    return unsafe {
-        let jobs_count = cupsGetJobs(&mut jobs_ptr, name.as_ptr(), 0, whichjobs);
-        if jobs_count > 0 {
-            Some(slice::from_raw_parts(jobs_ptr, jobs_count as usize))
-        } else {
-            None
+    let mut request = IppRequest::new(ipp::IPP_OP_GET_JOBS);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", &ipp::printer_uri(printer_name));
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_KEYWORD, "which-jobs", which_jobs);
+    request.add_strings(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_KEYWORD, "requested-attributes", &JOB_ATTRIBUTES);
+
+    match &query.owner {
+        JobOwner::Any => {}
+        JobOwner::CurrentUser => request.add_boolean(ipp::IPP_TAG_OPERATION, "my-jobs", true),
+        JobOwner::User(user_name) => {
+            request.set_user(user_name);
+            request.add_boolean(ipp::IPP_TAG_OPERATION, "my-jobs", true);
         }
-    };
+    }
+
+    if let Some(limit) = query.limit {
+        request.add_integer(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_INTEGER, "limit", limit.min(i32::MAX as u32) as i32);
+    }
+
+    if let Some(offset) = query.offset {
+        request.add_integer(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_INTEGER, "first-index", offset.saturating_add(1).min(i32::MAX as u32) as i32);
+    }
+
+    let response = request.send(server::http(), "/")?;
+    return Ok(response
+        .groups(ipp::IPP_TAG_JOB)
+        .into_iter()
+        .map(|attributes| IppJob { attributes })
+        .collect());
+}
+
+/**
+ * Send an file to printer, returning the id of the created job
+ */
+pub fn print_file(printer_name: &str, file_path: &Path, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+    return print_files(printer_name, &[file_path], job_name, options);
+}
+
+/**
+ * Send several files to printer as documents of a single job, returning the id of the created job
+ */
+pub fn print_files(printer_name: &str, file_paths: &[&Path], job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+    if file_paths.is_empty() {
+        return Err("no files to print");
+    }
+
+    let mut files = vec![];
+    for file_path in file_paths {
+        let file = File::open(file_path).map_err(|_| "failed to open the file")?;
+        let name = file_path.file_name().unwrap_or(file_path.as_os_str()).to_string_lossy().to_string();
+        files.push((name, file));
+    }
+
+    let title = job_name.map(|n| n.to_string()).unwrap_or(file_paths[0].to_string_lossy().to_string());
+    let mut documents: Vec<(&str, &mut dyn Read)> = files.iter_mut().map(|(name, file)| (name.as_str(), file as &mut dyn Read)).collect();
+    return print_documents(printer_name, &title, &mut documents, &options);
 }
 
 // Based on:
-// https://github.com/apple/cups/blob/a8968fc4257322b1e4e191c4bccedea98d7b053e/cups/cups.h#L166
-const CUPS_ORIENTATION: &CStr = c"orientation-requested";
-const CUPS_ORIENTATION_PORTRAIT: &CStr = c"3";
-const CUPS_ORIENTATION_LANDSCAPE: &CStr = c"4";
-
-/**
- * Send an file to printer
- */
-pub fn print_file(printer_name: &str, file_path: &str, job_name: Option<&str>, options: PrintOptions) -> Result<(), &'static str> {
-    let mut options_vec = vec![];
-    
-    if let Some(orientation) = options.orientation {
-        let value = if orientation == PrintOrientation::Landscape {
-            CUPS_ORIENTATION_LANDSCAPE.as_ptr() as _
-        } else {
-            CUPS_ORIENTATION_PORTRAIT.as_ptr() as _
-        };
-        
-        options_vec.push(CupsOptionT {
-            name: CUPS_ORIENTATION.as_ptr() as _,
-            value
-        })
-    }
-    
-    unsafe {        
-        let printer = &str_to_cstring(printer_name);
-        let filename = str_to_cstring(file_path);
-        let title = str_to_cstring(job_name.unwrap_or(file_path));
- 
-        let result = cupsPrintFile(printer.as_ptr(), filename.as_ptr(), title.as_ptr(), options_vec.len() as _, options_vec.as_ptr());
-        return if result == 0 {
-            Err("cupsPrintFile failed")
-        } else {
-            Ok(())
+// https://github.com/apple/cups/blob/a8968fc4257322b1e4e191c4bccedea98d7b053e/cups/http.h
+const HTTP_STATUS_CONTINUE: c_int = 100;
+// Any ipp_status_t above this value is an error
+const IPP_STATUS_OK_EVENTS_COMPLETE: c_int = 0x0007;
+// The CUPS_FORMAT_AUTO, lets the scheduler detect the document format
+const CUPS_FORMAT_AUTO: &str = "application/octet-stream";
+const WRITE_CHUNK_SIZE: usize = 64 * 1024;
+
+/**
+ * Stream the reader data to printer with cupsStartDocument and cupsWriteRequestData, returning the id of the created job
+ */
+pub fn print_reader(printer_name: &str, reader: &mut dyn Read, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+    let title = job_name.unwrap_or("untitled");
+    return print_documents(printer_name, title, &mut [(title, reader)], &options);
+}
+
+/**
+ * Create the job with an IPP Create-Job request and stream the documents to it, the last one
+ * closes the job. cupsCreateJob and cupsPrintFiles are not used since they look the queue up
+ * on connections of their own, which the timeouts of the Config do not apply to
+ */
+fn print_documents(
+    printer_name: &str,
+    title: &str,
+    documents: &mut [(&str, &mut dyn Read)],
+    options: &PrintOptions,
+) -> Result<JobId, &'static str> {
+    let cups_options = CupsOptions::from(options);
+    let _requesting_user = RequestingUser::set(options.requesting_user.as_deref());
+    let printer = str_to_cstring(printer_name);
+    let format = str_to_cstring(options.document_format.as_ref().map_or(CUPS_FORMAT_AUTO, |f| f.mime_type()));
+
+    let mut request = IppRequest::new(ipp::IPP_OP_CREATE_JOB);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", &ipp::printer_uri(printer_name));
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_NAME, "job-name", title);
+    request.add_options(&cups_options, ipp::IPP_TAG_OPERATION);
+    request.add_options(&cups_options, ipp::IPP_TAG_JOB);
+
+    let response = request.send(server::http(), &format!("/printers/{}", printer_name))?;
+    let job_id = response.find_integer("job-id").ok_or("cupsCreateJob failed")?;
+
+    let count = documents.len();
+    for (index, (name, reader)) in documents.iter_mut().enumerate() {
+        let name = str_to_cstring(name);
+        let last = index + 1 == count;
+
+        unsafe {
+            if cupsStartDocument(server::http(), printer.as_ptr(), job_id, name.as_ptr(), format.as_ptr(), last as c_int) != HTTP_STATUS_CONTINUE {
+                cupsCancelJob2(server::http(), printer.as_ptr(), job_id, 0);
+                return Err("cupsStartDocument failed");
+            }
+
+            let mut chunk = vec![0u8; WRITE_CHUNK_SIZE];
+            loop {
+                let read = match reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(_) => {
+                        cupsFinishDocument(server::http(), printer.as_ptr());
+                        cupsCancelJob2(server::http(), printer.as_ptr(), job_id, 0);
+                        return Err("failed to read print data");
+                    }
+                };
+
+                if cupsWriteRequestData(server::http(), chunk.as_ptr() as *const c_char, read) != HTTP_STATUS_CONTINUE {
+                    cupsFinishDocument(server::http(), printer.as_ptr());
+                    cupsCancelJob2(server::http(), printer.as_ptr(), job_id, 0);
+                    return Err("cupsWriteRequestData failed");
+                }
+            }
+
+            // A job left open waits for its other documents, so it is canceled
+            if cupsFinishDocument(server::http(), printer.as_ptr()) > IPP_STATUS_OK_EVENTS_COMPLETE {
+                if !last {
+                    cupsCancelJob2(server::http(), printer.as_ptr(), job_id, 0);
+                }
+                return Err("cupsFinishDocument failed");
+            }
         }
     }
+
+    return Ok(job_id as JobId);
+}
+
+/**
+ * Ask the server to fetch and print the document at the uri with the IPP Print-URI operation,
+ * downloading it and streaming it as a regular job when the operation is not supported.
+ * The CUPS scheduler does not implement Print-URI, so local queues always use the fallback
+ */
+pub fn print_uri(printer_name: &str, uri: &str, job_name: Option<&str>, options: PrintOptions) -> Result<JobId, &'static str> {
+    let cups_options = CupsOptions::from(&options);
+    let resource = format!("/printers/{}", printer_name);
+    let requesting_user = RequestingUser::set(options.requesting_user.as_deref());
+
+    let mut request = IppRequest::new(ipp::IPP_OP_PRINT_URI);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", &ipp::printer_uri(printer_name));
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_NAME, "job-name", job_name.unwrap_or(uri));
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "document-uri", uri);
+    request.add_options(&cups_options, ipp::IPP_TAG_OPERATION);
+    request.add_options(&cups_options, ipp::IPP_TAG_JOB);
+
+    if let Ok(response) = request.send(server::http(), &resource)
+        && let Some(job_id) = response.find_integer("job-id")
+    {
+        return Ok(job_id as JobId);
+    }
+
+    drop(requesting_user);
+    let mut reader = http::get(uri)?;
+    return print_reader(printer_name, &mut reader, Some(job_name.unwrap_or(uri)), options);
+}
+
+/**
+ * Cancel the job with cupsCancelJob2, keeping it in the job history
+ */
+pub fn cancel_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+    let printer = str_to_cstring(printer_name);
+    let status = unsafe { cupsCancelJob2(server::http(), printer.as_ptr(), job_id as c_int, 0) };
+
+    return if status > IPP_STATUS_OK_EVENTS_COMPLETE {
+        Err(ipp::status_message(status))
+    } else {
+        Ok(())
+    };
+}
+
+/**
+ * Start an IPP request targeting a single job, sent to the /jobs resource
+ */
+fn job_request(operation: c_int, job_id: JobId) -> IppRequest {
+    let mut request = IppRequest::new(operation);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "job-uri", &ipp::job_uri(job_id));
+    return request;
+}
+
+/**
+ * Hold the job with IPP Hold-Job until it is released
+ */
+pub fn hold_job(job_id: JobId) -> Result<(), &'static str> {
+    let mut request = job_request(ipp::IPP_OP_HOLD_JOB, job_id);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_KEYWORD, "job-hold-until", "indefinite");
+    return request.send(server::http(), "/jobs").map(|_| ());
+}
+
+/**
+ * Release an held job with IPP Release-Job
+ */
+pub fn release_job(job_id: JobId) -> Result<(), &'static str> {
+    return job_request(ipp::IPP_OP_RELEASE_JOB, job_id).send(server::http(), "/jobs").map(|_| ());
+}
+
+/**
+ * Print a finished job again with IPP Restart-Job, the scheduler must still have its
+ * documents (PreserveJobFiles)
+ */
+pub fn restart_job(job_id: JobId) -> Result<(), &'static str> {
+    return job_request(ipp::IPP_OP_RESTART_JOB, job_id).send(server::http(), "/jobs").map(|_| ());
+}
+
+/**
+ * Move one job, or every job of the source printer when job_id is None, to another printer
+ * with CUPS-Move-Job
+ */
+pub fn move_job(printer_name: &str, job_id: Option<JobId>, target_printer_name: &str) -> Result<(), &'static str> {
+    let mut request = match job_id {
+        Some(job_id) => job_request(ipp::IPP_OP_CUPS_MOVE_JOB, job_id),
+        None => {
+            let mut request = IppRequest::new(ipp::IPP_OP_CUPS_MOVE_JOB);
+            request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", &ipp::printer_uri(printer_name));
+            request
+        }
+    };
+
+    request.add_string(ipp::IPP_TAG_JOB, ipp::IPP_TAG_URI, "job-printer-uri", &ipp::printer_uri(target_printer_name));
+    return request.send(server::http(), "/jobs").map(|_| ());
+}
+
+/**
+ * Change the priority of a queued job with IPP Set-Job-Attributes
+ */
+pub fn set_job_priority(job_id: JobId, priority: u8) -> Result<(), &'static str> {
+    let mut request = job_request(ipp::IPP_OP_SET_JOB_ATTRIBUTES, job_id);
+    request.add_integer(ipp::IPP_TAG_JOB, ipp::IPP_TAG_INTEGER, "job-priority", priority as i32);
+    return request.send(server::http(), "/jobs").map(|_| ());
+}
+
+// Based on:
+// https://github.com/apple/cups/blob/a8968fc4257322b1e4e191c4bccedea98d7b053e/cups/cups.h
+const CUPS_JOBID_ALL: c_int = -1;
+
+/**
+ * Cancel every job of the printer with cupsCancelJob2, also removing them from the job history
+ */
+pub fn purge_jobs(printer_name: &str) -> Result<(), &'static str> {
+    let printer = str_to_cstring(printer_name);
+    let status = unsafe { cupsCancelJob2(server::http(), printer.as_ptr(), CUPS_JOBID_ALL, 1) };
+
+    return if status > IPP_STATUS_OK_EVENTS_COMPLETE {
+        Err(ipp::status_message(status))
+    } else {
+        Ok(())
+    };
+}
+
+/**
+ * Read the job counters with IPP Get-Job-Attributes
+ */
+pub fn get_job_progress(job_id: JobId) -> Result<JobProgress, &'static str> {
+    let response = job_request(ipp::IPP_OP_GET_JOB_ATTRIBUTES, job_id).send(server::http(), "/jobs")?;
+    let counter = |name: &str| response.find_integer(name).filter(|v| *v >= 0).map(|v| v as u32);
+
+    return Ok(JobProgress {
+        pages_completed: counter("job-impressions-completed"),
+        pages_total: counter("job-impressions"),
+        sheets_completed: counter("job-media-sheets-completed"),
+        sheets_total: counter("job-media-sheets"),
+    });
+}
+
+/**
+ * Read every attribute of a job with IPP Get-Job-Attributes
+ */
+pub fn get_job_attributes(job_id: JobId) -> Result<Attributes, &'static str> {
+    let mut request = job_request(ipp::IPP_OP_GET_JOB_ATTRIBUTES, job_id);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_KEYWORD, "requested-attributes", "all");
+
+    let response = request.send(server::http(), "/jobs")?;
+    return response.groups(ipp::IPP_TAG_JOB).into_iter().next().ok_or("job not found");
+}
+
+/**
+ * Read the first document of a job back with CUPS-Get-Document, the scheduler only keeps
+ * documents with PreserveJobFiles enabled
+ */
+pub fn get_job_document(printer_name: &str, job_id: JobId) -> Result<JobDocument, &'static str> {
+    let mut request = IppRequest::new(ipp::IPP_OP_CUPS_GET_DOCUMENT);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", &ipp::printer_uri(printer_name));
+    request.add_integer(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_INTEGER, "job-id", job_id as i32);
+    request.add_integer(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_INTEGER, "document-number", 1);
+
+    let connection = http::HttpConnection::connect_server()?;
+    let (response, mut reader) = request.send_for_data(connection, "/")?;
+
+    let mut data = vec![];
+    reader.read_to_end(&mut data).map_err(|_| "failed to read the job document")?;
+
+    let attributes = response.groups(ipp::IPP_TAG_OPERATION).into_iter().next().unwrap_or_default();
+    let operation = IppJob { attributes };
+    return Ok(JobDocument {
+        name: operation.string("document-name"),
+        format: operation.string("document-format"),
+        data,
+    });
 }
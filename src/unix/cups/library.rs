@@ -0,0 +1,75 @@
+use libc::c_void;
+use std::{ffi::CString, sync::OnceLock};
+
+// Names libcups is installed under, the runtime packages only ship the versioned ones
+#[cfg(target_os = "macos")]
+const LIBRARY_NAMES: [&str; 3] = ["/usr/lib/libcups.2.dylib", "libcups.2.dylib", "libcups.dylib"];
+#[cfg(not(target_os = "macos"))]
+const LIBRARY_NAMES: [&str; 2] = ["libcups.so.2", "libcups.so"];
+
+// Address of the dlopen handle, zero when libcups could not be loaded
+static LIBRARY: OnceLock<usize> = OnceLock::new();
+
+/**
+ * Declare libcups functions with the syntax of an extern block. Each one is resolved with
+ * dlsym on its first call, libcups is not linked so the binary starts without it
+ */
+macro_rules! cups_functions {
+    ($(unsafe fn $name:ident($($arg:ident: $type:ty),* $(,)?) $(-> $return:ty)?;)*) => {
+        pub const SYMBOLS: &[&str] = &[$(stringify!($name)),*];
+
+        $(
+            #[allow(non_snake_case, clippy::too_many_arguments)]
+            unsafe fn $name($($arg: $type),*) $(-> $return)? {
+                static ADDRESS: std::sync::OnceLock<usize> = std::sync::OnceLock::new();
+                let address = *ADDRESS.get_or_init(|| $crate::unix::cups::library::symbol(stringify!($name)));
+                assert!(address != 0, concat!("libcups is not loaded, ", stringify!($name), " called"));
+
+                let function: unsafe extern "C" fn($($type),*) $(-> $return)? = unsafe { std::mem::transmute(address) };
+                return unsafe { function($($arg),*) };
+            }
+        )*
+    };
+}
+
+fn open() -> usize {
+    for name in LIBRARY_NAMES {
+        let Ok(name) = CString::new(name) else { continue };
+        let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            continue;
+        }
+
+        // An older libcups without one of the functions is treated as missing
+        let handle = handle as usize;
+        if super::SYMBOLS.iter().flat_map(|symbols| symbols.iter()).all(|s| resolve(handle, s) != 0) {
+            return handle;
+        }
+        unsafe { libc::dlclose(handle as *mut c_void) };
+    }
+
+    return 0;
+}
+
+fn resolve(handle: usize, name: &str) -> usize {
+    let Ok(name) = CString::new(name) else { return 0 };
+    return unsafe { libc::dlsym(handle as *mut c_void, name.as_ptr()) } as usize;
+}
+
+/**
+ * Definition if libcups was found and has every function used by this crate, it is loaded
+ * on the first call and stays loaded
+ */
+pub fn is_loaded() -> bool {
+    return *LIBRARY.get_or_init(open) != 0;
+}
+
+/**
+ * Address of the libcups function, zero when libcups is not loaded
+ */
+pub fn symbol(name: &str) -> usize {
+    return match *LIBRARY.get_or_init(open) {
+        0 => 0,
+        handle => resolve(handle, name),
+    };
+}
@@ -1,2 +1,24 @@
+#[macro_use]
+pub mod library;
+
+pub mod control;
 pub mod dests;
-pub mod jobs;
\ No newline at end of file
+pub mod dinfo;
+pub mod http;
+pub mod ipp;
+pub mod jobs;
+pub mod options;
+pub mod printer;
+pub mod server;
+pub mod subscriptions;
+
+// Functions of every module, all of them have to resolve for libcups to be used
+const SYMBOLS: [&[&str]; 7] = [
+    dests::SYMBOLS,
+    dinfo::SYMBOLS,
+    http::SYMBOLS,
+    ipp::SYMBOLS,
+    jobs::SYMBOLS,
+    options::SYMBOLS,
+    server::SYMBOLS,
+];
@@ -0,0 +1,95 @@
+use crate::{
+    common::base::printer::PrintOptions,
+    unix::{
+        cups::dests::CupsOptionT,
+        options,
+        utils::strings::{c_char_to_string, str_to_cstring},
+    },
+};
+use libc::{c_char, c_int};
+use std::{ptr, slice};
+
+cups_functions! {
+    unsafe fn cupsAddOption(
+        name: *const c_char,
+        value: *const c_char,
+        num_options: c_int,
+        options: *mut *mut CupsOptionT,
+    ) -> c_int;
+    unsafe fn cupsFreeOptions(num_options: c_int, options: *mut CupsOptionT);
+}
+
+/**
+ * Owned array of CUPS options, allocated with cupsAddOption and released on drop
+ */
+pub struct CupsOptions {
+    num_options: c_int,
+    options: *mut CupsOptionT,
+}
+
+impl CupsOptions {
+    pub fn new() -> Self {
+        return CupsOptions {
+            num_options: 0,
+            options: ptr::null_mut(),
+        };
+    }
+
+    /**
+     * Take ownership of an options array allocated by CUPS
+     */
+    pub unsafe fn from_raw(num_options: c_int, options: *mut CupsOptionT) -> Self {
+        return CupsOptions { num_options, options };
+    }
+
+    /**
+     * Add or replace an option
+     */
+    pub fn add(&mut self, name: &str, value: &str) {
+        let name = str_to_cstring(name);
+        let value = str_to_cstring(value);
+        self.num_options = unsafe {
+            cupsAddOption(name.as_ptr(), value.as_ptr(), self.num_options, &mut self.options)
+        };
+    }
+
+    pub fn len(&self) -> c_int {
+        return self.num_options;
+    }
+
+    pub fn as_ptr(&self) -> *mut CupsOptionT {
+        return self.options;
+    }
+
+    /**
+     * Return the (name, value) pairs of the options
+     */
+    pub fn to_vec(&self) -> Vec<(String, String)> {
+        if self.options.is_null() {
+            return vec![];
+        }
+
+        return unsafe { slice::from_raw_parts(self.options, self.num_options as usize) }
+            .iter()
+            .map(|o| (c_char_to_string(o.name), c_char_to_string(o.value)))
+            .collect();
+    }
+}
+
+impl Drop for CupsOptions {
+    fn drop(&mut self) {
+        if !self.options.is_null() {
+            unsafe { cupsFreeOptions(self.num_options, self.options) };
+        }
+    }
+}
+
+impl From<&PrintOptions> for CupsOptions {
+    fn from(options: &PrintOptions) -> Self {
+        let mut cups_options = CupsOptions::new();
+        for (name, value) in options::option_pairs(options) {
+            cups_options.add(&name, &value);
+        }
+        return cups_options;
+    }
+}
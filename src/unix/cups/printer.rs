@@ -0,0 +1,105 @@
+use crate::common::base::{
+    attributes::{AttributeValue, Attributes},
+    printer::Supply,
+};
+use crate::unix::cups::{
+    http::{HttpConnection, Uri},
+    ipp::{self, IppRequest},
+    server,
+};
+use std::{
+    time::{Duration, Instant},
+};
+
+const MARKER_ATTRIBUTES: [&str; 6] = [
+    "marker-names",
+    "marker-types",
+    "marker-colors",
+    "marker-levels",
+    "marker-low-levels",
+    "marker-high-levels",
+];
+
+/**
+ * Read the printer attributes with IPP Get-Printer-Attributes
+ */
+pub fn get_printer_attributes(printer_name: &str, requested_attributes: &[&str]) -> Result<Attributes, &'static str> {
+    let mut request = IppRequest::new(ipp::IPP_OP_GET_PRINTER_ATTRIBUTES);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", &ipp::printer_uri(printer_name));
+    request.add_strings(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_KEYWORD, "requested-attributes", requested_attributes);
+
+    let response = request.send(server::http(), "/")?;
+    return response.groups(ipp::IPP_TAG_PRINTER).into_iter().next().ok_or("printer not found");
+}
+
+pub fn integers(attributes: &Attributes, name: &str) -> Vec<i32> {
+    return attributes
+        .get(name)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| if let AttributeValue::Integer(i) = v { Some(*i) } else { None })
+                .collect()
+        })
+        .unwrap_or_default();
+}
+
+pub fn strings(attributes: &Attributes, name: &str) -> Vec<String> {
+    return attributes
+        .get(name)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| if let AttributeValue::String(s) = v { Some(s.clone()) } else { None })
+                .collect()
+        })
+        .unwrap_or_default();
+}
+
+// Negative marker levels mean the level is unavailable, unknown or just "not empty"
+fn percent(level: Option<&i32>) -> Option<u8> {
+    return level.filter(|l| (0..=100).contains(*l)).map(|l| *l as u8);
+}
+
+/**
+ * Return the printer supplies from the marker-* attributes, the CUPS backends fill them
+ * from the device (ex over SNMP for network printers)
+ */
+pub fn get_supplies(printer_name: &str) -> Result<Vec<Supply>, &'static str> {
+    let attributes = get_printer_attributes(printer_name, &MARKER_ATTRIBUTES)?;
+
+    let types = strings(&attributes, "marker-types");
+    let colors = strings(&attributes, "marker-colors");
+    let levels = integers(&attributes, "marker-levels");
+    let low_levels = integers(&attributes, "marker-low-levels");
+    let high_levels = integers(&attributes, "marker-high-levels");
+
+    return Ok(strings(&attributes, "marker-names")
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| Supply {
+            name,
+            kind: types.get(i).cloned().unwrap_or_default(),
+            color: colors.get(i).cloned().unwrap_or_default(),
+            level: percent(levels.get(i)),
+            low_level: percent(low_levels.get(i)),
+            high_level: percent(high_levels.get(i)),
+        })
+        .collect());
+}
+
+/**
+ * Ask the device itself for its state with IPP Get-Printer-Attributes, returning how long it took to answer
+ */
+pub fn probe_ipp_device(device_uri: &str) -> Result<Duration, &'static str> {
+    let uri = Uri::parse(device_uri)?;
+    let started = Instant::now();
+    let connection = HttpConnection::connect(&uri)?;
+
+    let mut request = IppRequest::new(ipp::IPP_OP_GET_PRINTER_ATTRIBUTES);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", device_uri);
+    request.add_strings(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_KEYWORD, "requested-attributes", &["printer-state"]);
+    request.send(connection.as_ptr(), &uri.resource)?;
+
+    return Ok(started.elapsed());
+}
@@ -0,0 +1,235 @@
+use crate::{
+    common::{
+        backend::Config,
+        base::connection::{Credentials, CupsServer, Encryption},
+    },
+    unix::cups::http::HttpConnection,
+};
+use libc::{c_char, c_int, c_void};
+use std::{
+    cell::{Cell, RefCell},
+    ffi::CString,
+    ptr,
+};
+
+type PasswordCallback = unsafe extern "C" fn(*const c_char, *mut c_void, *const c_char, *const c_char, *mut c_void) -> *const c_char;
+
+cups_functions! {
+    unsafe fn cupsSetServer(server: *const c_char);
+    unsafe fn cupsSetEncryption(encryption: c_int);
+    unsafe fn cupsSetUser(user: *const c_char);
+    unsafe fn cupsSetPasswordCB2(callback: Option<PasswordCallback>, user_data: *mut c_void);
+}
+
+const NEGOTIATE_NOT_SUPPORTED: &str = "libcups was built without Kerberos (GSSAPI) support";
+const SERVER_UNREACHABLE: &str = "failed to connect to the CUPS server";
+
+// Only exported by the libcups built with GSSAPI
+const NEGOTIATE_SYMBOL: &str = "_cupsSetNegotiateAuthString";
+
+// Based on:
+// https://github.com/apple/cups/blob/a8968fc4257322b1e4e191c4bccedea98d7b053e/cups/http.h
+const HTTP_ENCRYPTION_IF_REQUESTED: c_int = 0;
+const HTTP_ENCRYPTION_NEVER: c_int = 1;
+const HTTP_ENCRYPTION_REQUIRED: c_int = 2;
+const HTTP_ENCRYPTION_ALWAYS: c_int = 3;
+
+// A refused password is given again this many times in a call before the request fails
+const PASSWORD_TRIES: u32 = 3;
+
+thread_local! {
+    // Server set on this thread, None until the Config had one
+    static APPLIED_SERVER: RefCell<Option<Option<CupsServer>>> = const { RefCell::new(None) };
+
+    // Credentials and negotiate set on this thread, None until the Config had some
+    static APPLIED_CREDENTIALS: RefCell<Option<(Option<Credentials>, bool)>> = const { RefCell::new(None) };
+
+    // Password returned to libcups, it has to stay valid after the callback returned
+    static PASSWORD: RefCell<Option<CString>> = const { RefCell::new(None) };
+
+    // User name of the credentials, None for the default user of libcups
+    static USER_NAME: RefCell<Option<CString>> = const { RefCell::new(None) };
+
+    static PASSWORD_ASKED: Cell<u32> = const { Cell::new(0) };
+
+    // Connection of this thread when the Config has timeouts, with the Config it was opened for
+    static CONNECTION: RefCell<Option<(Config, HttpConnection)>> = const { RefCell::new(None) };
+
+    // Reason the last apply on this thread failed
+    static ERROR: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+fn encryption(encryption: Encryption) -> c_int {
+    return match encryption {
+        Encryption::IfRequested => HTTP_ENCRYPTION_IF_REQUESTED,
+        Encryption::Never => HTTP_ENCRYPTION_NEVER,
+        Encryption::Required => HTTP_ENCRYPTION_REQUIRED,
+        Encryption::Always => HTTP_ENCRYPTION_ALWAYS,
+    };
+}
+
+/**
+ * Called by libcups on this thread when the server asks for a password, NULL cancels the request
+ */
+unsafe extern "C" fn password_callback(
+    _prompt: *const c_char,
+    _http: *mut c_void,
+    _method: *const c_char,
+    _resource: *const c_char,
+    _user_data: *mut c_void,
+) -> *const c_char {
+    let asked = PASSWORD_ASKED.get();
+    if asked >= PASSWORD_TRIES {
+        return ptr::null();
+    }
+    PASSWORD_ASKED.set(asked + 1);
+
+    // libcups sends the password with the current user name, which may be a requesting user
+    USER_NAME.with_borrow(|user_name| {
+        if let Some(user_name) = user_name {
+            unsafe { cupsSetUser(user_name.as_ptr()) };
+        }
+    });
+    return PASSWORD.with_borrow(|password| password.as_ref().map(|p| p.as_ptr()).unwrap_or(ptr::null()));
+}
+
+/**
+ * Submit the requests of this thread as the given user (the requesting-user-name of the jobs)
+ * until dropped, libcups then uses the user of the credentials or the process again
+ */
+pub struct RequestingUser {
+    changed: bool,
+}
+
+impl RequestingUser {
+    pub fn set(requesting_user: Option<&str>) -> RequestingUser {
+        let Some(requesting_user) = requesting_user.and_then(|u| CString::new(u).ok()) else {
+            return RequestingUser { changed: false };
+        };
+        unsafe { cupsSetUser(requesting_user.as_ptr()) };
+        return RequestingUser { changed: true };
+    }
+}
+
+impl Drop for RequestingUser {
+    fn drop(&mut self) {
+        if self.changed {
+            USER_NAME.with_borrow(|user_name| unsafe { cupsSetUser(user_name.as_ref().map(|u| u.as_ptr()).unwrap_or(ptr::null())) });
+        }
+    }
+}
+
+/**
+ * Return why the last apply on this thread failed
+ */
+pub fn error() -> Option<&'static str> {
+    return ERROR.get();
+}
+
+fn apply_server(server: Option<CupsServer>) {
+    APPLIED_SERVER.with_borrow_mut(|applied| {
+        if applied.is_none() && server.is_none() || applied.as_ref() == Some(&server) {
+            return;
+        }
+
+        match &server {
+            Some(server) => {
+                let Ok(address) = CString::new(server.address()) else { return };
+                unsafe {
+                    cupsSetServer(address.as_ptr());
+                    cupsSetEncryption(encryption(server.encryption));
+                }
+            }
+            // Back to the server of client.conf and CUPS_SERVER
+            None => unsafe {
+                cupsSetServer(ptr::null());
+                cupsSetEncryption(HTTP_ENCRYPTION_IF_REQUESTED);
+            },
+        }
+        *applied = Some(server);
+    });
+}
+
+fn apply_credentials(credentials: Option<Credentials>, negotiate: bool) -> Result<(), &'static str> {
+    PASSWORD_ASKED.set(0);
+
+    // libcups answers the Negotiate challenges itself with the Kerberos ticket of the user
+    if negotiate && super::library::symbol(NEGOTIATE_SYMBOL) == 0 {
+        return Err(NEGOTIATE_NOT_SUPPORTED);
+    }
+
+    APPLIED_CREDENTIALS.with_borrow_mut(|applied| {
+        let settings = (credentials, negotiate);
+        if applied.is_none() && settings == (None, false) || applied.as_ref() == Some(&settings) {
+            return;
+        }
+
+        let (credentials, negotiate) = &settings;
+        let user_name = credentials.as_ref().and_then(|c| CString::new(c.user_name.as_str()).ok());
+        let password = credentials.as_ref().and_then(|c| CString::new(c.password.as_str()).ok());
+        PASSWORD.set(password);
+
+        // Without credentials libcups asks on the terminal as before, except with negotiate
+        // where the password requests after a refused ticket are canceled
+        unsafe {
+            cupsSetUser(user_name.as_ref().map(|u| u.as_ptr()).unwrap_or(ptr::null()));
+            USER_NAME.set(user_name);
+            match credentials.is_some() || *negotiate {
+                true => cupsSetPasswordCB2(Some(password_callback), ptr::null_mut()),
+                false => cupsSetPasswordCB2(None, ptr::null_mut()),
+            }
+        }
+        *applied = Some(settings);
+    });
+    return Ok(());
+}
+
+/**
+ * Open the connection of this thread with the timeouts of the Config, it is kept until the
+ * Config changes or the connection breaks. Without timeouts the default connection of libcups
+ * is used, which waits as long as libcups does
+ */
+fn apply_timeouts(config: Config) -> Result<(), &'static str> {
+    return CONNECTION.with_borrow_mut(|connection| {
+        if config.connect_timeout.is_none() && config.read_timeout.is_none() {
+            *connection = None;
+            return Ok(());
+        }
+
+        if let Some((opened_for, opened)) = connection.as_ref()
+            && *opened_for == config
+            && !opened.is_broken()
+        {
+            return Ok(());
+        }
+
+        *connection = None;
+        let opened = HttpConnection::connect_server().map_err(|_| SERVER_UNREACHABLE)?;
+        *connection = Some((config, opened));
+        return Ok(());
+    });
+}
+
+/**
+ * Connection the calls of this thread are made on, NULL for the default connection of libcups
+ * (CUPS_HTTP_DEFAULT)
+ */
+pub fn http() -> *mut c_void {
+    return CONNECTION.with_borrow(|connection| connection.as_ref().map(|(_, c)| c.as_ptr()).unwrap_or(ptr::null_mut()));
+}
+
+/**
+ * Point libcups to the server, the credentials and the timeouts of the
+ * Config. The libcups settings are kept per thread, so this runs before the calls of every
+ * thread, the default connection is reopened when the server changed
+ */
+pub fn apply() -> Result<(), &'static str> {
+    apply_server(crate::common::backend::cups_server());
+    let mut result = apply_credentials(crate::common::backend::credentials(), crate::common::backend::negotiate());
+    if result.is_ok() {
+        result = apply_timeouts(crate::common::backend::get_config());
+    }
+
+    ERROR.set(result.err());
+    return result;
+}
@@ -0,0 +1,168 @@
+use crate::{
+    common::{
+        base::{
+            attributes::Attributes,
+            event::{EventSender, PrinterEvent},
+        },
+        traits::platform::PlatformActions,
+    },
+    unix::cups::{
+        ipp::{self, IppRequest},
+        printer::{integers, strings},
+        server,
+    },
+};
+use libc::c_int;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+const IPP_TAG_SUBSCRIPTION: c_int = 0x06;
+const IPP_TAG_EVENT_NOTIFICATION: c_int = 0x07;
+
+const IPP_OP_CREATE_PRINTER_SUBSCRIPTIONS: c_int = 0x0016;
+const IPP_OP_RENEW_SUBSCRIPTION: c_int = 0x001A;
+const IPP_OP_CANCEL_SUBSCRIPTION: c_int = 0x001B;
+const IPP_OP_GET_NOTIFICATIONS: c_int = 0x001C;
+
+/**
+ * Lease of the subscriptions made by watch, renewed at half its duration so they go away soon
+ * after a crash
+ */
+pub const SUBSCRIPTION_LEASE: Duration = Duration::from_secs(600);
+
+// How often the pulled notifications are asked for
+const GET_NOTIFICATIONS_INTERVAL: Duration = Duration::from_secs(1);
+
+// Server wide subscriptions target the CUPS server itself
+const SERVER_URI: &str = "ipp://localhost/";
+
+// Events of every printer and job a subscription asks CUPS for
+const NOTIFY_EVENTS: [&str; 4] = ["printer-added", "printer-deleted", "printer-state-changed", "job-state-changed"];
+
+/**
+ * Subscribe to the server wide printer and job events (RFC 3995), CUPS sends them to the recipient
+ * (ex dbus:// for its D-Bus notifier) or, without one, keeps them for Get-Notifications (RFC 3996)
+ * until the lease expires
+ */
+pub fn create_subscription(recipient_uri: Option<&str>, lease: Duration) -> Result<i32, &'static str> {
+    let mut request = IppRequest::new(IPP_OP_CREATE_PRINTER_SUBSCRIPTIONS);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", SERVER_URI);
+    match recipient_uri {
+        Some(recipient_uri) => {
+            request.add_string(IPP_TAG_SUBSCRIPTION, ipp::IPP_TAG_URI, "notify-recipient-uri", recipient_uri)
+        }
+        None => request.add_string(IPP_TAG_SUBSCRIPTION, ipp::IPP_TAG_KEYWORD, "notify-pull-method", "ippget"),
+    }
+    request.add_strings(IPP_TAG_SUBSCRIPTION, ipp::IPP_TAG_KEYWORD, "notify-events", &NOTIFY_EVENTS);
+    request.add_integer(IPP_TAG_SUBSCRIPTION, ipp::IPP_TAG_INTEGER, "notify-lease-duration", lease.as_secs() as i32);
+
+    let response = request.send(server::http(), "/")?;
+    return response.find_integer("notify-subscription-id").ok_or("subscription id not returned");
+}
+
+/**
+ * Extend the lease of the subscription
+ */
+pub fn renew_subscription(subscription_id: i32, lease: Duration) -> Result<(), &'static str> {
+    let mut request = IppRequest::new(IPP_OP_RENEW_SUBSCRIPTION);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", SERVER_URI);
+    request.add_integer(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_INTEGER, "notify-subscription-id", subscription_id);
+    request.add_integer(IPP_TAG_SUBSCRIPTION, ipp::IPP_TAG_INTEGER, "notify-lease-duration", lease.as_secs() as i32);
+    return request.send(server::http(), "/").map(|_| ());
+}
+
+/**
+ * Remove the subscription so CUPS stops sending its events
+ */
+pub fn cancel_subscription(subscription_id: i32) -> Result<(), &'static str> {
+    let mut request = IppRequest::new(IPP_OP_CANCEL_SUBSCRIPTION);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", SERVER_URI);
+    request.add_integer(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_INTEGER, "notify-subscription-id", subscription_id);
+    return request.send(server::http(), "/").map(|_| ());
+}
+
+/**
+ * Return the events of the subscription from the sequence number on
+ */
+pub fn get_notifications(subscription_id: i32, sequence_number: i32) -> Result<Vec<Attributes>, &'static str> {
+    let mut request = IppRequest::new(IPP_OP_GET_NOTIFICATIONS);
+    request.add_string(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_URI, "printer-uri", SERVER_URI);
+    request.add_integer(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_INTEGER, "notify-subscription-ids", subscription_id);
+    request.add_integer(ipp::IPP_TAG_OPERATION, ipp::IPP_TAG_INTEGER, "notify-sequence-numbers", sequence_number);
+
+    let response = request.send(server::http(), "/")?;
+    return Ok(response.groups(IPP_TAG_EVENT_NOTIFICATION));
+}
+
+/**
+ * Map an event notification to an event, the printer name is taken from the printer uri
+ * when the notification has none
+ */
+fn notification_event(notification: &Attributes) -> Option<PrinterEvent> {
+    let event = strings(notification, "notify-subscribed-event").into_iter().next()?;
+    let printer_name = strings(notification, "printer-name").into_iter().next().or_else(|| {
+        let uri = strings(notification, "notify-printer-uri").into_iter().next()?;
+        return uri.rsplit('/').next().map(|name| name.to_string());
+    })?;
+
+    let state_reasons = strings(notification, "printer-state-reasons");
+    let integer = |name: &str| integers(notification, name).first().map_or(0, |i| *i as u64);
+
+    return match event.as_str() {
+        "printer-added" => Some(PrinterEvent::PrinterAdded { printer_name }),
+        "printer-deleted" => Some(PrinterEvent::PrinterRemoved { printer_name }),
+        "printer-state-changed" => Some(PrinterEvent::PrinterStateChanged {
+            state: crate::Platform::parse_printer_state(integer("printer-state"), &state_reasons.join(",")),
+            state_reasons: state_reasons.into_iter().filter(|r| r != "none").collect(),
+            printer_name,
+        }),
+        event if event.starts_with("job-") => Some(PrinterEvent::JobStateChanged {
+            job_id: integer("notify-job-id"),
+            state: crate::Platform::parse_printer_job_state(integer("job-state")),
+            printer_name,
+        }),
+        _ => None,
+    };
+}
+
+/**
+ * Pull the events of a subscription every second until the stream is dropped, it works with
+ * any CUPS server (ex remote ones or in containers without D-Bus). The sender is given back when
+ * the server refuses the subscription
+ */
+pub fn watch(sender: EventSender) -> Result<(), EventSender> {
+    let Ok(subscription_id) = create_subscription(None, SUBSCRIPTION_LEASE) else { return Err(sender) };
+
+    thread::spawn(move || {
+        let _ = super::server::apply();
+        let mut sequence_number = 1;
+        let mut renewed_at = Instant::now();
+
+        while sender.is_running() {
+            thread::sleep(GET_NOTIFICATIONS_INTERVAL);
+
+            for notification in get_notifications(subscription_id, sequence_number).unwrap_or_default() {
+                if let Some(number) = integers(&notification, "notify-sequence-number").first() {
+                    sequence_number = sequence_number.max(number + 1);
+                }
+
+                if let Some(event) = notification_event(&notification)
+                    && !sender.send(event)
+                {
+                    break;
+                }
+            }
+
+            if renewed_at.elapsed() > SUBSCRIPTION_LEASE / 2 {
+                let _ = renew_subscription(subscription_id, SUBSCRIPTION_LEASE);
+                renewed_at = Instant::now();
+            }
+        }
+
+        let _ = cancel_subscription(subscription_id);
+    });
+
+    return Ok(());
+}
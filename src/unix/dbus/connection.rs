@@ -0,0 +1,194 @@
+use crate::unix::utils::strings::{c_char_to_string, str_to_cstring};
+use libc::{c_char, c_int, c_uint, c_void};
+use std::ptr;
+
+#[link(name = "dbus-1")]
+unsafe extern "C" {
+    unsafe fn dbus_error_init(error: *mut DBusError);
+    unsafe fn dbus_error_free(error: *mut DBusError);
+    unsafe fn dbus_error_is_set(error: *const DBusError) -> c_uint;
+    unsafe fn dbus_bus_get(bus_type: c_int, error: *mut DBusError) -> *mut c_void;
+    unsafe fn dbus_bus_add_match(connection: *mut c_void, rule: *const c_char, error: *mut DBusError);
+    unsafe fn dbus_connection_read_write(connection: *mut c_void, timeout_milliseconds: c_int) -> c_uint;
+    unsafe fn dbus_connection_pop_message(connection: *mut c_void) -> *mut c_void;
+    unsafe fn dbus_connection_unref(connection: *mut c_void);
+    unsafe fn dbus_message_get_type(message: *mut c_void) -> c_int;
+    unsafe fn dbus_message_get_interface(message: *mut c_void) -> *const c_char;
+    unsafe fn dbus_message_get_member(message: *mut c_void) -> *const c_char;
+    unsafe fn dbus_message_iter_init(message: *mut c_void, iter: *mut DBusMessageIter) -> c_uint;
+    unsafe fn dbus_message_iter_get_arg_type(iter: *mut DBusMessageIter) -> c_int;
+    unsafe fn dbus_message_iter_get_basic(iter: *mut DBusMessageIter, value: *mut c_void);
+    unsafe fn dbus_message_iter_next(iter: *mut DBusMessageIter) -> c_uint;
+    unsafe fn dbus_message_unref(message: *mut c_void);
+}
+
+const DBUS_BUS_SYSTEM: c_int = 1;
+const DBUS_MESSAGE_TYPE_SIGNAL: c_int = 4;
+
+const DBUS_TYPE_STRING: c_int = b's' as c_int;
+const DBUS_TYPE_UINT32: c_int = b'u' as c_int;
+const DBUS_TYPE_INT32: c_int = b'i' as c_int;
+const DBUS_TYPE_BOOLEAN: c_int = b'b' as c_int;
+
+/**
+ * The D-Bus error struct (DBusError)
+ */
+#[repr(C)]
+struct DBusError {
+    name: *const c_char,
+    message: *const c_char,
+    dummy: c_uint,
+    padding: *mut c_void,
+}
+
+/**
+ * Iterator over the arguments of a message (DBusMessageIter), only filled by libdbus
+ */
+#[repr(C)]
+struct DBusMessageIter {
+    _private: [u64; 16],
+}
+
+/**
+ * Argument of a signal, containers and other types are kept as Other so positions still match
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Other,
+}
+
+/**
+ * Signal read from the bus
+ */
+pub struct Signal {
+    pub interface: String,
+    pub member: String,
+    pub arguments: Vec<Argument>,
+}
+
+/**
+ * Shared connection to the system bus
+ */
+pub struct Connection {
+    connection: *mut c_void,
+}
+
+impl Connection {
+    pub fn system() -> Result<Connection, &'static str> {
+        let mut error = new_error();
+        let connection = unsafe { dbus_bus_get(DBUS_BUS_SYSTEM, &mut error) };
+        let failed = unsafe { dbus_error_is_set(&error) } != 0;
+        unsafe { dbus_error_free(&mut error) };
+
+        return if failed || connection.is_null() {
+            Err("failed to connect to the D-Bus system bus")
+        } else {
+            Ok(Connection { connection })
+        };
+    }
+
+    /**
+     * Receive the messages matching the rule, ex type='signal',interface='org.cups.cupsd.Notifier'
+     */
+    pub fn add_match(&self, rule: &str) -> Result<(), &'static str> {
+        let rule = str_to_cstring(rule);
+        let mut error = new_error();
+        unsafe { dbus_bus_add_match(self.connection, rule.as_ptr(), &mut error) };
+        let failed = unsafe { dbus_error_is_set(&error) } != 0;
+        unsafe { dbus_error_free(&mut error) };
+
+        return if failed { Err("invalid D-Bus match rule") } else { Ok(()) };
+    }
+
+    /**
+     * Wait up to the timeout for data and return the signals received, Err once disconnected
+     */
+    pub fn read_signals(&self, timeout_milliseconds: i32) -> Result<Vec<Signal>, &'static str> {
+        if unsafe { dbus_connection_read_write(self.connection, timeout_milliseconds) } == 0 {
+            return Err("disconnected from the D-Bus system bus");
+        }
+
+        let mut signals = vec![];
+        loop {
+            let message = unsafe { dbus_connection_pop_message(self.connection) };
+            if message.is_null() {
+                break;
+            }
+
+            unsafe {
+                if dbus_message_get_type(message) == DBUS_MESSAGE_TYPE_SIGNAL {
+                    signals.push(Signal {
+                        interface: c_char_to_string(dbus_message_get_interface(message)),
+                        member: c_char_to_string(dbus_message_get_member(message)),
+                        arguments: read_arguments(message),
+                    });
+                }
+                dbus_message_unref(message);
+            }
+        }
+
+        return Ok(signals);
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        unsafe { dbus_connection_unref(self.connection) };
+    }
+}
+
+fn new_error() -> DBusError {
+    let mut error = DBusError { name: ptr::null(), message: ptr::null(), dummy: 0, padding: ptr::null_mut() };
+    unsafe { dbus_error_init(&mut error) };
+    return error;
+}
+
+/**
+ * Copy the basic arguments of the message
+ */
+unsafe fn read_arguments(message: *mut c_void) -> Vec<Argument> {
+    let mut arguments = vec![];
+    let mut iter = DBusMessageIter { _private: [0; 16] };
+
+    if unsafe { dbus_message_iter_init(message, &mut iter) } == 0 {
+        return arguments;
+    }
+
+    loop {
+        let argument = unsafe {
+            match dbus_message_iter_get_arg_type(&mut iter) {
+                DBUS_TYPE_STRING => {
+                    let mut value: *const c_char = ptr::null();
+                    dbus_message_iter_get_basic(&mut iter, &mut value as *mut _ as *mut c_void);
+                    Argument::String(c_char_to_string(value))
+                }
+                DBUS_TYPE_UINT32 => {
+                    let mut value: u32 = 0;
+                    dbus_message_iter_get_basic(&mut iter, &mut value as *mut _ as *mut c_void);
+                    Argument::Integer(value as i64)
+                }
+                DBUS_TYPE_INT32 => {
+                    let mut value: i32 = 0;
+                    dbus_message_iter_get_basic(&mut iter, &mut value as *mut _ as *mut c_void);
+                    Argument::Integer(value as i64)
+                }
+                DBUS_TYPE_BOOLEAN => {
+                    let mut value: c_uint = 0;
+                    dbus_message_iter_get_basic(&mut iter, &mut value as *mut _ as *mut c_void);
+                    Argument::Boolean(value != 0)
+                }
+                _ => Argument::Other,
+            }
+        };
+        arguments.push(argument);
+
+        if unsafe { dbus_message_iter_next(&mut iter) } == 0 {
+            break;
+        }
+    }
+
+    return arguments;
+}
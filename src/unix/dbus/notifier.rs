@@ -0,0 +1,117 @@
+use std::{
+    sync::mpsc,
+    thread,
+    time::Instant,
+};
+
+use crate::{
+    common::{
+        base::event::{EventSender, PrinterEvent},
+        traits::platform::PlatformActions,
+    },
+    unix::{
+        cups::subscriptions::{self, SUBSCRIPTION_LEASE},
+        dbus::connection::{Argument, Connection, Signal},
+    },
+};
+
+const NOTIFIER_INTERFACE: &str = "org.cups.cupsd.Notifier";
+
+// How long a read waits before checking if the stream was dropped
+const READ_TIMEOUT_MILLISECONDS: i32 = 500;
+
+fn string_argument(arguments: &[Argument], index: usize) -> String {
+    return match arguments.get(index) {
+        Some(Argument::String(value)) => value.clone(),
+        _ => "".to_string(),
+    };
+}
+
+fn integer_argument(arguments: &[Argument], index: usize) -> u64 {
+    return match arguments.get(index) {
+        Some(Argument::Integer(value)) => *value as u64,
+        _ => 0,
+    };
+}
+
+/**
+ * Map a notifier signal to an event, the arguments are notify-text, printer-uri, printer-name,
+ * printer-state, printer-state-reasons and printer-is-accepting-jobs followed for job events by
+ * job-id, job-state, job-state-reasons, job-name and job-impressions-completed
+ */
+fn signal_event(signal: &Signal) -> Option<PrinterEvent> {
+    if signal.interface != NOTIFIER_INTERFACE {
+        return None;
+    }
+
+    let arguments = &signal.arguments;
+    let printer_name = string_argument(arguments, 2);
+
+    return match signal.member.as_str() {
+        "PrinterAdded" => Some(PrinterEvent::PrinterAdded { printer_name }),
+        "PrinterDeleted" => Some(PrinterEvent::PrinterRemoved { printer_name }),
+        "PrinterStateChanged" | "PrinterStopped" | "PrinterRestarted" | "PrinterShutdown" | "PrinterModified" => {
+            let state_reasons = string_argument(arguments, 4);
+            Some(PrinterEvent::PrinterStateChanged {
+                state: crate::Platform::parse_printer_state(integer_argument(arguments, 3), &state_reasons),
+                state_reasons: state_reasons.split(',').filter(|r| !r.is_empty()).map(|r| r.to_string()).collect(),
+                printer_name,
+            })
+        }
+        "JobCreated" | "JobCompleted" | "JobStopped" | "JobState" | "JobProgress" | "JobConfigChanged" => {
+            Some(PrinterEvent::JobStateChanged {
+                job_id: integer_argument(arguments, 6),
+                state: crate::Platform::parse_printer_job_state(integer_argument(arguments, 7)),
+                printer_name,
+            })
+        }
+        _ => None,
+    };
+}
+
+/**
+ * Subscribe CUPS to its D-Bus notifier and deliver the signals as events until the stream is dropped,
+ * the sender is given back when the system bus or the subscription are not available
+ */
+pub fn watch(sender: EventSender) -> Result<(), EventSender> {
+    let (ready_sender, ready) = mpsc::channel();
+
+    // The connection is not thread safe, it lives on the listener thread
+    thread::spawn(move || {
+        let connection = match Connection::system() {
+            Ok(connection) => connection,
+            Err(_) => return ready_sender.send(Err(sender)).unwrap_or_default(),
+        };
+
+        let rule = format!("type='signal',interface='{}'", NOTIFIER_INTERFACE);
+        if connection.add_match(&rule).is_err() {
+            return ready_sender.send(Err(sender)).unwrap_or_default();
+        }
+
+        // CUPS only sends the signals for subscriptions with a dbus:// recipient
+        let subscription_id = match subscriptions::create_subscription(Some("dbus://"), SUBSCRIPTION_LEASE) {
+            Ok(subscription_id) => subscription_id,
+            Err(_) => return ready_sender.send(Err(sender)).unwrap_or_default(),
+        };
+        ready_sender.send(Ok(())).unwrap_or_default();
+
+        let mut renewed_at = Instant::now();
+        while sender.is_running() {
+            let Ok(signals) = connection.read_signals(READ_TIMEOUT_MILLISECONDS) else { break };
+
+            let delivered = signals.iter().filter_map(signal_event).all(|event| sender.send(event));
+            if !delivered {
+                break;
+            }
+
+            if renewed_at.elapsed() > SUBSCRIPTION_LEASE / 2 {
+                let _ = subscriptions::renew_subscription(subscription_id, SUBSCRIPTION_LEASE);
+                renewed_at = Instant::now();
+            }
+        }
+
+        let _ = subscriptions::cancel_subscription(subscription_id);
+    });
+
+    return ready.recv().unwrap_or(Ok(()));
+}
@@ -0,0 +1,144 @@
+use std::{
+    ffi::OsStr,
+    io::{self, Read},
+    process::{Command, Output, Stdio},
+};
+
+const TOOLS_MISSING: &str = "failed to run the CUPS command line tools, are lp and lpstat installed?";
+
+fn command<S: AsRef<OsStr>>(program: &str, args: &[S]) -> Command {
+    // The parsed messages and dates are the untranslated ones in the C locale
+    let mut command = Command::new(program);
+    command.args(args).env("LC_ALL", "C");
+
+    if let Some(server) = crate::common::backend::cups_server() {
+        command.env("CUPS_SERVER", server.address()).env("CUPS_ENCRYPTION", server.encryption.name());
+    }
+    return command;
+}
+
+fn stdout(output: Output, error: &'static str) -> Result<String, &'static str> {
+    if !output.status.success() {
+        return Err(error);
+    }
+    return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+}
+
+/**
+ * Run the tool and return its standard output, the error is returned when it exits with a failure
+ */
+pub fn run<S: AsRef<OsStr>>(program: &str, args: &[S], error: &'static str) -> Result<String, &'static str> {
+    let output = command(program, args).stdin(Stdio::null()).output().map_err(|_| TOOLS_MISSING)?;
+    return stdout(output, error);
+}
+
+/**
+ * Run the tool with the data read from the reader as its standard input
+ */
+pub fn run_with_input<S: AsRef<OsStr>>(
+    program: &str,
+    args: &[S],
+    reader: &mut dyn Read,
+    error: &'static str,
+) -> Result<String, &'static str> {
+    let mut child = command(program, args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| TOOLS_MISSING)?;
+
+    // Dropping the pipe closes it, which ends the document, the job is not submitted when
+    // the tool is killed before
+    let copied = child.stdin.take().map(|mut stdin| io::copy(reader, &mut stdin));
+    if !matches!(copied, Some(Ok(_))) {
+        let _ = child.kill();
+        let _ = child.wait();
+        return Err("failed to send the document to lp");
+    }
+
+    let output = child.wait_with_output().map_err(|_| error)?;
+    return stdout(output, error);
+}
+
+/**
+ * Split the options printed by lpoptions, ex "copies=1 printer-info='Office Printer'", values
+ * are quoted or escaped with backslashes as cupsParseOptions expects them
+ */
+pub fn parse_options(text: &str) -> Vec<(String, String)> {
+    let mut options = vec![];
+    let mut chars = text.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            name.push(c);
+        }
+        if name.is_empty() {
+            break;
+        }
+
+        if chars.next_if_eq(&'=').is_none() {
+            options.push((name, "true".to_string()));
+            continue;
+        }
+
+        let mut value = String::new();
+        let mut quote = None;
+        while let Some(c) = chars.next() {
+            match (c, quote) {
+                ('\\', _) => value.extend(chars.next()),
+                ('\'' | '"', None) => quote = Some(c),
+                (c, Some(q)) if c == q => quote = None,
+                (c, None) if c.is_whitespace() => break,
+                (c, _) => value.push(c),
+            }
+        }
+        options.push((name, value));
+    }
+
+    return options;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        return pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+    }
+
+    #[test]
+    fn parse_lpoptions_output() {
+        let text = "copies=1 device-uri=ipp://10.0.0.5/ipp/print printer-info='Office Printer' \
+                    printer-location=\"2nd floor\" printer-make-and-model=HP\\ LaserJet\\ 400 job-hold\n";
+        assert_eq!(
+            parse_options(text),
+            options(&[
+                ("copies", "1"),
+                ("device-uri", "ipp://10.0.0.5/ipp/print"),
+                ("printer-info", "Office Printer"),
+                ("printer-location", "2nd floor"),
+                ("printer-make-and-model", "HP LaserJet 400"),
+                ("job-hold", "true"),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_quotes_and_escapes_in_values() {
+        let text = "a='it\\'s' b=\"say 'hi'\" c='' d=x'y z'w";
+        assert_eq!(parse_options(text), options(&[("a", "it's"), ("b", "say 'hi'"), ("c", ""), ("d", "xy zw")]));
+    }
+
+    #[test]
+    fn parse_truncated_options() {
+        assert!(parse_options("").is_empty());
+        assert!(parse_options("  \n").is_empty());
+        assert_eq!(parse_options("copies="), options(&[("copies", "")]));
+        assert_eq!(parse_options("printer-info='Office Pri"), options(&[("printer-info", "Office Pri")]));
+        assert_eq!(parse_options("printer-info=Office\\"), options(&[("printer-info", "Office")]));
+    }
+}
@@ -0,0 +1,70 @@
+use std::ffi::OsString;
+
+use crate::{
+    common::base::printer::{NewPrinter, PrinterDriver},
+    unix::lp::command,
+};
+
+/**
+ * Stop processing the queue with cupsdisable, jobs are still accepted
+ */
+pub fn pause_printer(printer_name: &str) -> Result<(), &'static str> {
+    return command::run("cupsdisable", &[printer_name], "cupsdisable failed").map(|_| ());
+}
+
+/**
+ * Start processing the queue again with cupsenable
+ */
+pub fn resume_printer(printer_name: &str) -> Result<(), &'static str> {
+    return command::run("cupsenable", &[printer_name], "cupsenable failed").map(|_| ());
+}
+
+/**
+ * Accept new jobs on the queue with cupsaccept
+ */
+pub fn accept_jobs(printer_name: &str) -> Result<(), &'static str> {
+    return command::run("cupsaccept", &[printer_name], "cupsaccept failed").map(|_| ());
+}
+
+/**
+ * Refuse new jobs on the queue with cupsreject, the reason is shown as printer-state-message
+ */
+pub fn reject_jobs(printer_name: &str, reason: Option<&str>) -> Result<(), &'static str> {
+    let mut args = vec![];
+    if let Some(reason) = reason {
+        args.push("-r");
+        args.push(reason);
+    }
+    args.push(printer_name);
+    return command::run("cupsreject", &args, "cupsreject failed").map(|_| ());
+}
+
+/**
+ * Create or modify the queue with lpadmin, enabled and accepting jobs
+ */
+pub fn add_printer(printer: &NewPrinter) -> Result<(), &'static str> {
+    let mut args: Vec<OsString> = vec!["-p".into(), (&printer.name).into(), "-E".into(), "-v".into(), (&printer.device_uri).into()];
+
+    match &printer.driver {
+        PrinterDriver::Everywhere => args.extend(["-m".into(), "everywhere".into()]),
+        PrinterDriver::Model(model) => args.extend(["-m".into(), model.into()]),
+        PrinterDriver::PpdFile(path) => args.extend(["-P".into(), path.into()]),
+    }
+
+    if let Some(info) = &printer.info {
+        args.extend(["-D".into(), info.into()]);
+    }
+    if let Some(location) = &printer.location {
+        args.extend(["-L".into(), location.into()]);
+    }
+    args.extend(["-o".into(), format!("printer-is-shared={}", printer.is_shared).into()]);
+
+    return command::run("lpadmin", &args, "lpadmin failed to add the printer").map(|_| ());
+}
+
+/**
+ * Delete the queue with lpadmin -x, its pending jobs are canceled
+ */
+pub fn delete_printer(printer_name: &str) -> Result<(), &'static str> {
+    return command::run("lpadmin", &["-x", printer_name], "lpadmin failed to delete the printer").map(|_| ());
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use crate::{
+    common::{
+        base::printer::{OptionChoice, PrintOptions, PrinterKind, PrinterOption, Supply},
+        traits::platform::PlatformPrinterGetters,
+    },
+    unix::{lp::command, options},
+};
+
+// printer-type bits (cups_ptype_e)
+const CUPS_PRINTER_CLASS: u32 = 0x0001;
+const CUPS_PRINTER_REMOTE: u32 = 0x0002;
+
+/**
+ * Destination as printed by lpoptions -p, which lists the same options cupsGetNamedDest returns
+ */
+#[derive(Debug, Clone)]
+pub struct LpDest {
+    name: String,
+    is_default: bool,
+    options: HashMap<String, String>,
+}
+
+impl LpDest {
+    pub fn get_option(&self, key: &str) -> String {
+        return self.options.get(key).cloned().unwrap_or_default();
+    }
+}
+
+impl PlatformPrinterGetters for LpDest {
+    fn get_name(&self) -> String {
+        return self.get_option("printer-info").trim().to_string();
+    }
+
+    fn get_is_default(&self) -> bool {
+        return self.is_default;
+    }
+
+    fn get_system_name(&self) -> String {
+        return self.name.clone();
+    }
+
+    fn get_marker_and_model(&self) -> String {
+        return self.get_option("printer-make-and-model");
+    }
+
+    fn get_is_shared(&self) -> bool {
+        return self.get_option("printer-is-shared") == "true";
+    }
+
+    fn get_is_accepting_jobs(&self) -> bool {
+        return self.get_option("printer-is-accepting-jobs") != "false";
+    }
+
+    fn get_is_class(&self) -> bool {
+        let printer_type = self.get_option("printer-type").parse::<u32>().unwrap_or_default();
+        return printer_type & CUPS_PRINTER_CLASS != 0;
+    }
+
+    fn get_members(&self) -> Vec<String> {
+        return self
+            .get_option("member-uris")
+            .split(",")
+            .filter_map(|uri| uri.rsplit('/').next())
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .collect();
+    }
+
+    fn get_uri(&self) -> String {
+        return self.get_option("printer-uri-supported");
+    }
+
+    fn get_kind(&self) -> PrinterKind {
+        let printer_type = self.get_option("printer-type").parse::<u32>().unwrap_or_default();
+        if printer_type & CUPS_PRINTER_CLASS != 0 {
+            return PrinterKind::Class;
+        }
+
+        if self.get_option("printer-is-temporary") == "true"
+            || self.get_option("printer-uri-supported").is_empty()
+            || self.get_option("device-uri").starts_with("implicitclass://")
+        {
+            return PrinterKind::Temporary;
+        }
+
+        if printer_type & CUPS_PRINTER_REMOTE != 0 {
+            return PrinterKind::RemoteQueue;
+        }
+
+        return PrinterKind::Local;
+    }
+
+    fn get_device_uri(&self) -> String {
+        return self.get_option("device-uri");
+    }
+
+    fn get_location(&self) -> String {
+        return self.get_option("printer-location");
+    }
+
+    fn get_state(&self) -> u64 {
+        return self.get_option("printer-state").parse::<u64>().unwrap_or_default();
+    }
+
+    fn get_state_reasons(&self) -> Vec<String> {
+        return self
+            .get_option("printer-state-reasons")
+            .split(",")
+            .filter_map(|v| if v.is_empty() { None } else { Some(v.to_string()) })
+            .collect();
+    }
+
+    fn get_port_name(&self) -> String {
+        return self.get_option("device-uri");
+    }
+
+    fn get_processor(&self) -> String {
+        return "".to_string();
+    }
+
+    fn get_description(&self) -> String {
+        return self.get_option("printer-info");
+    }
+
+    fn get_data_type(&self) -> String {
+        return self.get_option("media");
+    }
+
+    fn get_attributes(&self) -> u64 {
+        return self.get_option("printer-type").parse::<u64>().unwrap_or_default();
+    }
+
+    fn get_jobs_count(&self) -> Option<u32> {
+        // The destinations do not carry queued-job-count
+        return None;
+    }
+}
+
+/**
+ * Return the name of the default destination from lpstat -d, which follows the LPDEST and
+ * PRINTER variables and the lpoptions of the user
+ */
+pub fn get_default_name() -> Option<String> {
+    let output = command::run("lpstat", &["-d"], "lpstat failed").ok()?;
+    let name = output.lines().find_map(|l| l.strip_prefix("system default destination:"))?;
+    return Some(name.trim().to_string()).filter(|name| !name.is_empty());
+}
+
+fn read_dest(name: &str, default_name: Option<&str>) -> Option<LpDest> {
+    let output = command::run("lpoptions", &["-p", name], "lpoptions failed").ok()?;
+    return Some(LpDest {
+        name: name.to_string(),
+        is_default: default_name == Some(name),
+        options: command::parse_options(&output).into_iter().collect(),
+    });
+}
+
+/**
+ * Return all the destinations, listed with lpstat -e
+ */
+pub fn get_dests() -> Vec<LpDest> {
+    let Ok(output) = command::run("lpstat", &["-e"], "lpstat failed") else { return vec![] };
+    let default_name = get_default_name();
+
+    return output
+        .lines()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty() && !name.contains('/'))
+        .filter_map(|name| read_dest(name, default_name.as_deref()))
+        .collect();
+}
+
+/**
+ * Return the destination with the given system name
+ */
+pub fn get_named_dest(name: &str) -> Option<LpDest> {
+    return read_dest(name, get_default_name().as_deref());
+}
+
+/**
+ * Mark the destination as the default one in the lpoptions file of the user
+ */
+pub fn set_default_dest(name: &str) -> Result<(), &'static str> {
+    return command::run("lpoptions", &["-d", name], "lpoptions failed to set the default printer").map(|_| ());
+}
+
+/**
+ * Store the options as the defaults of the destination in the lpoptions file of the user
+ */
+pub fn set_dest_options(name: &str, options: &[(String, String)]) -> Result<(), &'static str> {
+    let mut args = vec!["-p".to_string(), name.to_string()];
+    for (option_name, value) in options {
+        args.push("-o".to_string());
+        args.push(format!("{}={}", option_name, value));
+    }
+    return command::run("lpoptions", &args, "lpoptions failed to set the printer options").map(|_| ());
+}
+
+// Negative marker levels mean the level is unavailable, unknown or just "not empty"
+fn percent(level: Option<&String>) -> Option<u8> {
+    return level.and_then(|l| l.parse::<u8>().ok()).filter(|l| *l <= 100);
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    return value.split(",").filter(|v| !v.is_empty()).map(|v| v.to_string()).collect();
+}
+
+/**
+ * Return the printer supplies from the marker-* options of the destination
+ */
+pub fn get_supplies(printer_name: &str) -> Result<Vec<Supply>, &'static str> {
+    let dest = get_named_dest(printer_name).ok_or("printer not found")?;
+    let types = split_list(&dest.get_option("marker-types"));
+    let colors = split_list(&dest.get_option("marker-colors"));
+    let levels = split_list(&dest.get_option("marker-levels"));
+    let low_levels = split_list(&dest.get_option("marker-low-levels"));
+    let high_levels = split_list(&dest.get_option("marker-high-levels"));
+
+    return Ok(split_list(&dest.get_option("marker-names"))
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| Supply {
+            name,
+            kind: types.get(i).cloned().unwrap_or_default(),
+            color: colors.get(i).cloned().unwrap_or_default(),
+            level: percent(levels.get(i)),
+            low_level: percent(low_levels.get(i)),
+            high_level: percent(high_levels.get(i)),
+        })
+        .collect());
+}
+
+/**
+ * Return the driver options listed by lpoptions -l, ex "PageSize/Media Size: Letter *A4", the
+ * default choice is the one marked with a star
+ */
+pub fn get_printer_options(printer_name: &str) -> Result<Vec<PrinterOption>, &'static str> {
+    let output = command::run("lpoptions", &["-p", printer_name, "-l"], "lpoptions failed to list the printer options")?;
+    let mut printer_options = vec![];
+
+    for line in output.lines() {
+        let Some((option, choices)) = line.split_once(':') else { continue };
+        let (name, label) = option.split_once('/').unwrap_or((option, option));
+
+        let mut default = None;
+        let choices = choices
+            .split_whitespace()
+            .map(|choice| {
+                let value = match choice.strip_prefix('*') {
+                    Some(value) => {
+                        default = Some(value.to_string());
+                        value
+                    }
+                    None => choice,
+                };
+                return OptionChoice { value: value.to_string(), label: value.to_string() };
+            })
+            .collect();
+
+        printer_options.push(PrinterOption { name: name.to_string(), label: label.to_string(), choices, default });
+    }
+
+    return Ok(printer_options);
+}
+
+/**
+ * Return the options a job gets when it does not set them, read from the options of the
+ * destination (which hold the lpoptions defaults of the user)
+ */
+pub fn get_default_options(printer_name: &str) -> Result<PrintOptions, &'static str> {
+    let dest = get_named_dest(printer_name).ok_or("printer not found")?;
+    let mut print_options = PrintOptions::default();
+
+    for (name, value) in &dest.options {
+        match name.as_str() {
+            "copies" => print_options.copies = value.parse().ok(),
+            "media" => print_options.media_size = Some(options::media_size_from_name(value)),
+            "sides" => print_options.duplex = options::duplex_from_keyword(value),
+            "print-color-mode" => print_options.color_mode = options::color_mode_from_keyword(value),
+            "print-quality" => print_options.quality = options::quality_from_value(value),
+            "printer-resolution" => print_options.resolution = options::resolution_from_value(value),
+            "orientation-requested" => print_options.orientation = options::orientation_from_value(value),
+            _ => {}
+        }
+    }
+
+    return Ok(print_options);
+}
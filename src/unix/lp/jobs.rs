@@ -0,0 +1,323 @@
+use std::{
+    env,
+    ffi::OsStr,
+    io::Read,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    common::{
+        base::{
+            job::{JobId, JobOwner, JobQuery, WhichJobs},
+            printer::PrintOptions,
+        },
+        traits::platform::PlatformPrinterJobGetters,
+    },
+    unix::{lp::command, options},
+};
+
+// job-state values
+const IPP_JOB_PENDING: u64 = 3;
+const IPP_JOB_HELD: u64 = 4;
+const IPP_JOB_PROCESSING: u64 = 5;
+const IPP_JOB_CANCELED: u64 = 7;
+const IPP_JOB_ABORTED: u64 = 8;
+const IPP_JOB_COMPLETED: u64 = 9;
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/**
+ * Job as listed by lpstat -l -o, which reports no job name nor document format
+ */
+#[derive(Debug, Clone)]
+pub struct LpJob {
+    id: JobId,
+    printer: String,
+    user: String,
+    state: u64,
+    state_reasons: Vec<String>,
+    created_at: SystemTime,
+}
+
+impl PlatformPrinterJobGetters for LpJob {
+    fn get_id(&self) -> u64 {
+        return self.id;
+    }
+
+    fn get_name(&self) -> String {
+        return "".to_string();
+    }
+
+    fn get_state(&self) -> u64 {
+        return self.state;
+    }
+
+    fn get_state_reasons(&self) -> Vec<String> {
+        return self.state_reasons.clone();
+    }
+
+    fn get_printer(&self) -> String {
+        return self.printer.clone();
+    }
+
+    fn get_user(&self) -> String {
+        return self.user.clone();
+    }
+
+    fn get_media_type(&self) -> String {
+        return "".to_string();
+    }
+
+    fn get_pages_completed(&self) -> Option<u32> {
+        return None;
+    }
+
+    fn get_pages_total(&self) -> Option<u32> {
+        return None;
+    }
+
+    fn get_created_at(&self) -> SystemTime {
+        return self.created_at;
+    }
+
+    fn get_processed_at(&self) -> Option<SystemTime> {
+        return None;
+    }
+
+    fn get_completed_at(&self) -> Option<SystemTime> {
+        return None;
+    }
+}
+
+/**
+ * Parse a date printed in the C locale, ex "Tue Oct  1 09:00:00 2024", as local time
+ */
+fn parse_date(columns: &[&str]) -> Option<SystemTime> {
+    let [_, month, day, time, year] = columns else { return None };
+    let month = MONTHS.iter().position(|m| m == month)?;
+    let mut time = time.split(':').map(|v| v.parse::<i32>().ok());
+
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = year.parse::<i32>().ok()? - 1900;
+    tm.tm_mon = month as i32;
+    tm.tm_mday = day.parse().ok()?;
+    tm.tm_hour = time.next()??;
+    tm.tm_min = time.next()??;
+    tm.tm_sec = time.next()??;
+    tm.tm_isdst = -1;
+
+    let seconds = unsafe { libc::mktime(&mut tm) };
+    return if seconds > 0 { Some(UNIX_EPOCH + Duration::from_secs(seconds as u64)) } else { None };
+}
+
+fn job_state(which_jobs: &WhichJobs, reasons: &[String]) -> u64 {
+    let has = |reason: &str| reasons.iter().any(|r| r.starts_with(reason));
+
+    if let WhichJobs::Completed = which_jobs {
+        return if has("job-canceled") {
+            IPP_JOB_CANCELED
+        } else if has("aborted-by-system") || has("job-aborted") {
+            IPP_JOB_ABORTED
+        } else {
+            IPP_JOB_COMPLETED
+        };
+    }
+
+    return if has("job-printing") {
+        IPP_JOB_PROCESSING
+    } else if has("job-hold-until") {
+        IPP_JOB_HELD
+    } else {
+        IPP_JOB_PENDING
+    };
+}
+
+/**
+ * Parse the jobs of lpstat -l -o, ex "office-12  alice  1024  Tue Oct  1 09:00:00 2024"
+ * followed by indented Status, Alerts and "queued for" lines
+ */
+fn parse_jobs(output: &str, which_jobs: &WhichJobs) -> Vec<LpJob> {
+    let mut jobs: Vec<LpJob> = vec![];
+
+    for line in output.lines() {
+        if line.starts_with(char::is_whitespace) {
+            let Some(job) = jobs.last_mut() else { continue };
+            let line = line.trim();
+            if let Some(alerts) = line.strip_prefix("Alerts:") {
+                job.state_reasons = alerts.split_whitespace().map(|r| r.to_string()).collect();
+            } else if let Some(printer) = line.strip_prefix("queued for ") {
+                job.printer = printer.trim().to_string();
+            }
+            continue;
+        }
+
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        if columns.len() < 3 {
+            continue;
+        }
+
+        let Some((printer, id)) = columns[0].rsplit_once('-') else { continue };
+        let Ok(id) = id.parse::<JobId>() else { continue };
+
+        jobs.push(LpJob {
+            id,
+            printer: printer.to_string(),
+            user: columns[1].to_string(),
+            state: IPP_JOB_PENDING,
+            state_reasons: vec![],
+            created_at: parse_date(columns.get(3..).unwrap_or_default()).unwrap_or(UNIX_EPOCH),
+        });
+    }
+
+    for job in jobs.iter_mut() {
+        job.state = job_state(which_jobs, &job.state_reasons);
+    }
+
+    return jobs;
+}
+
+/**
+ * Return the jobs of the printer, the owner, limit and offset are applied to the listing
+ */
+pub fn get_printer_jobs(printer_name: &str, query: &JobQuery) -> Result<Vec<LpJob>, &'static str> {
+    let listings: &[WhichJobs] = match query.which_jobs {
+        WhichJobs::Active => &[WhichJobs::Active],
+        WhichJobs::Completed => &[WhichJobs::Completed],
+        WhichJobs::All => &[WhichJobs::Active, WhichJobs::Completed],
+    };
+
+    let owner = match &query.owner {
+        JobOwner::Any => None,
+        JobOwner::CurrentUser => Some(env::var("USER").unwrap_or_default()),
+        JobOwner::User(user_name) => Some(user_name.clone()),
+    };
+
+    let mut jobs = vec![];
+    for which_jobs in listings {
+        let which = if let WhichJobs::Completed = which_jobs { "completed" } else { "not-completed" };
+        let output = command::run("lpstat", &["-l", "-W", which, "-o", printer_name], "lpstat failed")?;
+        jobs.extend(parse_jobs(&output, which_jobs));
+    }
+
+    return Ok(jobs
+        .into_iter()
+        .filter(|job| owner.as_ref().is_none_or(|owner| job.user == *owner))
+        .skip(query.offset.unwrap_or_default() as usize)
+        .take(query.limit.map(|limit| limit as usize).unwrap_or(usize::MAX))
+        .collect());
+}
+
+fn lp_args(printer_name: &str, job_name: Option<&str>, options: &PrintOptions) -> Vec<String> {
+    let mut args = vec!["-d".to_string(), printer_name.to_string()];
+
+    if let Some(job_name) = job_name {
+        args.push("-t".to_string());
+        args.push(job_name.to_string());
+    }
+
+    if let Some(requesting_user) = &options.requesting_user {
+        args.push("-U".to_string());
+        args.push(requesting_user.to_string());
+    }
+
+    for (name, value) in options::option_pairs(options) {
+        args.push("-o".to_string());
+        args.push(format!("{}={}", name, value));
+    }
+
+    return args;
+}
+
+/**
+ * Read the job id of "request id is office-12 (1 file(s))"
+ */
+fn parse_request_id(output: &str) -> Result<JobId, &'static str> {
+    return output
+        .split_whitespace()
+        .find_map(|word| word.rsplit_once('-').and_then(|(_, id)| id.parse::<JobId>().ok()))
+        .ok_or("lp did not report the job id");
+}
+
+/**
+ * Submit the files as one job with lp
+ */
+pub fn print_files(
+    printer_name: &str,
+    file_paths: &[&Path],
+    job_name: Option<&str>,
+    options: PrintOptions,
+) -> Result<JobId, &'static str> {
+    let mut args: Vec<&OsStr> = vec![];
+    let lp_args = lp_args(printer_name, job_name, &options);
+    args.extend(lp_args.iter().map(OsStr::new));
+    args.push(OsStr::new("--"));
+    args.extend(file_paths.iter().map(|p| p.as_os_str()));
+
+    let output = command::run("lp", &args, "lp failed to submit the job")?;
+    return parse_request_id(&output);
+}
+
+/**
+ * Submit the data read from the reader as the standard input of lp
+ */
+pub fn print_reader(
+    printer_name: &str,
+    reader: &mut dyn Read,
+    job_name: Option<&str>,
+    options: PrintOptions,
+) -> Result<JobId, &'static str> {
+    let args = lp_args(printer_name, job_name, &options);
+    let output = command::run_with_input("lp", &args, reader, "lp failed to submit the job")?;
+    return parse_request_id(&output);
+}
+
+/**
+ * Change a queued job with lp -i, ex "-H hold" or "-q 80"
+ */
+fn alter_job(job_id: JobId, option: &str, value: &str) -> Result<(), &'static str> {
+    let job_id = job_id.to_string();
+    return command::run("lp", &["-i", &job_id, option, value], "lp failed to change the job").map(|_| ());
+}
+
+pub fn cancel_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+    let job = format!("{}-{}", printer_name, job_id);
+    return command::run("cancel", &[job.as_str()], "cancel failed").map(|_| ());
+}
+
+pub fn hold_job(job_id: JobId) -> Result<(), &'static str> {
+    return alter_job(job_id, "-H", "hold");
+}
+
+pub fn release_job(job_id: JobId) -> Result<(), &'static str> {
+    return alter_job(job_id, "-H", "resume");
+}
+
+pub fn restart_job(job_id: JobId) -> Result<(), &'static str> {
+    return alter_job(job_id, "-H", "restart");
+}
+
+/**
+ * Set the job-priority of the job, lp takes priorities from 1 to 100
+ */
+pub fn set_job_priority(job_id: JobId, priority: u8) -> Result<(), &'static str> {
+    return alter_job(job_id, "-q", &priority.to_string());
+}
+
+/**
+ * Move the job, or every job of the printer, with lpmove
+ */
+pub fn move_job(printer_name: &str, job_id: Option<JobId>, target_printer_name: &str) -> Result<(), &'static str> {
+    let source = match job_id {
+        Some(job_id) => format!("{}-{}", printer_name, job_id),
+        None => printer_name.to_string(),
+    };
+    return command::run("lpmove", &[source.as_str(), target_printer_name], "lpmove failed").map(|_| ());
+}
+
+/**
+ * Cancel every job of the printer with cancel -a
+ */
+pub fn purge_jobs(printer_name: &str) -> Result<(), &'static str> {
+    return command::run("cancel", &["-a", printer_name], "cancel failed").map(|_| ());
+}
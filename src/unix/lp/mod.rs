@@ -0,0 +1,251 @@
+use std::{io::Read, path::Path, time::Duration};
+
+use crate::common::{
+    base::{
+        attributes::Attributes,
+        event::{poll_changes, EventSender},
+        job::{JobDocument, JobId, JobProgress, JobQuery, JobState, PrinterJob},
+        printer::{
+            DocumentFormat, NewPrinter, PrintOptions, Printer, PrinterCapabilities, PrinterOption, PrinterState,
+            Supply, UnsupportedOption,
+        },
+    },
+    render::text::{render_text, TextOptions},
+    traits::platform::{PlatformActions, PlatformPrinterGetters},
+};
+#[cfg(feature = "image")]
+use crate::common::render::image::{render_image, ImagePrintOptions};
+
+pub mod command;
+pub mod control;
+pub mod dests;
+pub mod jobs;
+
+const NOT_SUPPORTED: &str = "not supported by the lp backend";
+
+/**
+ * Backend running the CUPS command line tools (lp, lpstat, lpoptions, cancel, lpadmin...),
+ * used when libcups could not be loaded. The details only available over IPP are not reported
+ */
+pub struct LpBackend;
+
+impl PlatformActions for LpBackend {
+    fn get_printers() -> Vec<Printer> {
+        return dests::get_dests().iter().map(|d| Printer::from_platform_printer_getters(d)).collect();
+    }
+
+    fn print(
+        printer_system_name: &str,
+        buffer: &[u8],
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        let mut buffer = buffer;
+        return jobs::print_reader(printer_system_name, &mut buffer, job_name, options);
+    }
+
+    fn print_reader(
+        printer_system_name: &str,
+        reader: &mut dyn Read,
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        return jobs::print_reader(printer_system_name, reader, job_name, options);
+    }
+
+    fn print_file(
+        printer_system_name: &str,
+        file_path: &Path,
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        return jobs::print_files(printer_system_name, &[file_path], job_name, options);
+    }
+
+    fn print_uri(
+        _printer_system_name: &str,
+        _uri: &str,
+        _job_name: Option<&str>,
+        _options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        return Err(NOT_SUPPORTED);
+    }
+
+    fn print_files(
+        printer_system_name: &str,
+        file_paths: &[&Path],
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        return jobs::print_files(printer_system_name, file_paths, job_name, options);
+    }
+
+    fn print_text(
+        printer_system_name: &str,
+        text: &str,
+        job_name: Option<&str>,
+        text_options: &TextOptions,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        let document = render_text(text, text_options)?;
+        let mut options = options;
+        options.document_format.get_or_insert(DocumentFormat::Pdf);
+        return Self::print(printer_system_name, &document, job_name, options);
+    }
+
+    #[cfg(feature = "image")]
+    fn print_image(
+        printer_system_name: &str,
+        image: &image::DynamicImage,
+        job_name: Option<&str>,
+        image_options: &ImagePrintOptions,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        let document = render_image(image, image_options)?;
+        let mut options = options;
+        options.document_format.get_or_insert(DocumentFormat::Pdf);
+        return Self::print(printer_system_name, &document, job_name, options);
+    }
+
+    fn validate_options(_printer_system_name: &str, _options: &PrintOptions) -> Vec<UnsupportedOption> {
+        // The supported values are only known over IPP, lp applies what it can
+        return vec![];
+    }
+
+    fn get_capabilities(_printer_system_name: &str) -> Result<PrinterCapabilities, &'static str> {
+        return Err(NOT_SUPPORTED);
+    }
+
+    fn get_printer_options(printer_system_name: &str) -> Result<Vec<PrinterOption>, &'static str> {
+        return dests::get_printer_options(printer_system_name);
+    }
+
+    fn get_default_options(printer_system_name: &str) -> Result<PrintOptions, &'static str> {
+        return dests::get_default_options(printer_system_name);
+    }
+
+    fn get_option_conflicts(_printer_system_name: &str, _options: &PrintOptions, _option: &str, _value: &str) -> Vec<(String, String)> {
+        return vec![];
+    }
+
+    fn get_supplies(printer_system_name: &str) -> Result<Vec<Supply>, &'static str> {
+        return dests::get_supplies(printer_system_name);
+    }
+
+    fn probe_device(device_uri: &str) -> Result<Duration, &'static str> {
+        return crate::common::probe::probe_device_port(device_uri);
+    }
+
+    fn get_printer_jobs(printer_name: &str, query: &JobQuery) -> Result<Vec<PrinterJob>, &'static str> {
+        return Ok(jobs::get_printer_jobs(printer_name, query)?
+            .iter()
+            .map(|j| PrinterJob::from_platform_printer_job_getters(j))
+            .collect());
+    }
+
+    fn cancel_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        return jobs::cancel_job(printer_name, job_id);
+    }
+
+    fn hold_job(_printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        return jobs::hold_job(job_id);
+    }
+
+    fn release_job(_printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        return jobs::release_job(job_id);
+    }
+
+    fn restart_job(_printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        return jobs::restart_job(job_id);
+    }
+
+    fn move_job(printer_name: &str, job_id: Option<JobId>, target_printer_name: &str) -> Result<(), &'static str> {
+        return jobs::move_job(printer_name, job_id, target_printer_name);
+    }
+
+    fn set_job_priority(_printer_name: &str, job_id: JobId, priority: u8) -> Result<(), &'static str> {
+        return jobs::set_job_priority(job_id, priority);
+    }
+
+    fn purge_jobs(printer_name: &str) -> Result<(), &'static str> {
+        return jobs::purge_jobs(printer_name);
+    }
+
+    fn pause_printer(printer_name: &str) -> Result<(), &'static str> {
+        return control::pause_printer(printer_name);
+    }
+
+    fn resume_printer(printer_name: &str) -> Result<(), &'static str> {
+        return control::resume_printer(printer_name);
+    }
+
+    fn accept_jobs(printer_name: &str) -> Result<(), &'static str> {
+        return control::accept_jobs(printer_name);
+    }
+
+    fn reject_jobs(printer_name: &str, reason: Option<&str>) -> Result<(), &'static str> {
+        return control::reject_jobs(printer_name, reason);
+    }
+
+    fn get_job_progress(_printer_name: &str, _job_id: JobId) -> Result<JobProgress, &'static str> {
+        return Err(NOT_SUPPORTED);
+    }
+
+    fn get_job_attributes(_printer_name: &str, _job_id: JobId) -> Result<Attributes, &'static str> {
+        return Err(NOT_SUPPORTED);
+    }
+
+    fn get_job_document(_printer_name: &str, _job_id: JobId) -> Result<JobDocument, &'static str> {
+        return Err(NOT_SUPPORTED);
+    }
+
+    fn get_default_printer() -> Option<Printer> {
+        let name = dests::get_default_name()?;
+        return dests::get_named_dest(&name).map(|d| Printer::from_platform_printer_getters(&d));
+    }
+
+    fn set_default_printer(printer_name: &str) -> Result<(), &'static str> {
+        return dests::set_default_dest(printer_name);
+    }
+
+    fn set_default_options(printer_name: &str, options: &PrintOptions) -> Result<(), &'static str> {
+        // A default document format would disable the auto typing of every other application
+        let options: Vec<(String, String)> = crate::unix::options::option_pairs(options)
+            .into_iter()
+            .filter(|(name, _)| name != "document-format")
+            .collect();
+        return dests::set_dest_options(printer_name, &options);
+    }
+
+    fn add_printer(printer: &NewPrinter) -> Result<(), &'static str> {
+        return control::add_printer(printer);
+    }
+
+    fn delete_printer(printer_name: &str) -> Result<(), &'static str> {
+        return control::delete_printer(printer_name);
+    }
+
+    fn get_printer_by_name(printer_name: &str) -> Option<Printer> {
+        if let Some(dest) = dests::get_named_dest(printer_name) {
+            return Some(Printer::from_platform_printer_getters(&dest));
+        }
+
+        return dests::get_dests()
+            .iter()
+            .find(|d| d.get_name() == printer_name)
+            .map(|d| Printer::from_platform_printer_getters(d));
+    }
+
+    fn watch(sender: EventSender) -> Result<(), &'static str> {
+        poll_changes(sender);
+        return Ok(());
+    }
+
+    fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState {
+        return crate::unix::printer_state(platform_state, state_reasons);
+    }
+
+    fn parse_printer_job_state(platform_state: u64) -> JobState {
+        return crate::unix::job_state(platform_state);
+    }
+}
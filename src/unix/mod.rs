@@ -1,23 +1,109 @@
 use cups::dests::get_dests;
-use std::str;
+use std::{io::Read, path::Path, str, time::Duration};
 
 use crate::common::{
+    backend::backend_position,
     base::{
-        job::{PrinterJob, PrinterJobState},
-        printer::{Printer, PrinterState},
+        attributes::Attributes,
+        event::{poll_changes, EventSender},
+        job::{JobDocument, JobId, JobProgress, JobQuery, JobState, PrinterJob},
+        printer::{DocumentFormat, Printer, PrinterState},
     },
+    render::text::{render_text, TextOptions},
     traits::platform::{PlatformActions, PlatformPrinterGetters},
 };
-use crate::common::base::printer::PrintOptions;
+use crate::common::base::printer::{NewPrinter, PrintOptions, PrinterCapabilities, PrinterOption, Supply, UnsupportedOption};
+#[cfg(feature = "image")]
+use crate::common::render::image::{render_image, ImagePrintOptions};
+#[cfg(feature = "backend-lp")]
+use lp::LpBackend;
 
 mod cups;
+#[cfg(feature = "dbus")]
+mod dbus;
+#[cfg(feature = "backend-lp")]
+mod lp;
+mod options;
 mod utils;
 
-impl PlatformActions for crate::Platform {
+const LIBCUPS_NOT_LOADED: &str = "libcups is not installed";
+
+/**
+ * Error of the calls that could not use libcups
+ */
+fn libcups_error() -> &'static str {
+    return cups::server::error().unwrap_or(LIBCUPS_NOT_LOADED);
+}
+
+/**
+ * Return early when libcups is not used, with the lp backend when it is enabled or with the
+ * default otherwise
+ */
+macro_rules! without_libcups {
+    ($fallback:expr, $default:expr) => {
+        match use_libcups() {
+            Ok(true) => {}
+            #[cfg(feature = "backend-lp")]
+            Ok(false) if backend_position("lp").is_some() => return $fallback,
+            _ => return $default,
+        }
+    };
+}
+
+/**
+ * Definition if libcups is enabled ahead of the lp backend in the Config and could be loaded,
+ * it is then pointed to the server of the Config. The error tells why the Config could not be
+ * applied, ex libcups without Kerberos support
+ */
+fn use_libcups() -> Result<bool, &'static str> {
+    let lp = backend_position("lp");
+    let used = match backend_position("cups") {
+        Some(cups) => lp.is_none_or(|lp| cups < lp) && cups::library::is_loaded(),
+        None => false,
+    };
+
+    if used {
+        cups::server::apply()?;
+    }
+    return Ok(used);
+}
+
+/**
+ * Map the CUPS printer-state, the offline-report reason wins over the state
+ */
+fn printer_state(platform_state: u64, state_reasons: &str) -> PrinterState {
+    if state_reasons.contains("offline-report") {
+        return PrinterState::OFFLINE;
+    }
+
+    match platform_state {
+        3 => PrinterState::READY,
+        4 => PrinterState::PRINTING,
+        5 => PrinterState::PAUSED,
+        _ => PrinterState::UNKNOWN,
+    }
+}
+
+fn job_state(platform_state: u64) -> JobState {
+    match platform_state {
+        3  => JobState::PENDING,
+        4  => JobState::HELD,
+        5  => JobState::PROCESSING,
+        6  => JobState::STOPPED,
+        7  => JobState::CANCELED,
+        8  => JobState::ABORTED,
+        9  => JobState::COMPLETED,
+        _ => JobState::UNKNOWN,
+    }
+}
+
+impl PlatformActions for crate::Spooler {
     fn get_printers() -> Vec<Printer> {
+        without_libcups!(LpBackend::get_printers(), vec![]);
+
         let dests = cups::dests::get_dests().unwrap_or_default();
         let printers = dests
-            .into_iter()
+            .iter()
             .filter(|p| p.is_valid())
             .map(|p| Printer::from_platform_printer_getters(p))
             .collect();
@@ -31,37 +117,228 @@ impl PlatformActions for crate::Platform {
         buffer: &[u8],
         job_name: Option<&str>,
         options: PrintOptions
-    ) -> Result<(), &'static str> {
-        let path = crate::unix::utils::file::save_tmp_file(buffer);
-        return if path.is_some() {
-            let file_path = path.unwrap();
-            return Self::print_file(printer_system_name, file_path.to_str().unwrap(), job_name, options);
-        } else {
-            Err("Failed to create temp file")
-        };
+    ) -> Result<JobId, &'static str> {
+        without_libcups!(LpBackend::print(printer_system_name, buffer, job_name, options), Err(libcups_error()));
+
+        let mut buffer = buffer;
+        return cups::jobs::print_reader(printer_system_name, &mut buffer, job_name, options);
+    }
+
+    fn print_reader(
+        printer_system_name: &str,
+        reader: &mut dyn Read,
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        without_libcups!(LpBackend::print_reader(printer_system_name, reader, job_name, options), Err(libcups_error()));
+
+        return cups::jobs::print_reader(printer_system_name, reader, job_name, options);
     }
 
     fn print_file(
         printer_system_name: &str,
-        file_path: &str,
+        file_path: &Path,
         job_name: Option<&str>,
         options: PrintOptions
-    ) -> Result<(), &'static str> {
+    ) -> Result<JobId, &'static str> {
+        without_libcups!(LpBackend::print_file(printer_system_name, file_path, job_name, options), Err(libcups_error()));
+
         return cups::jobs::print_file(printer_system_name, file_path, job_name, options);
     }
 
-    fn get_printer_jobs(printer_name: &str, active_only: bool) -> Vec<PrinterJob> {
-        return cups::jobs::get_printer_jobs(printer_name, active_only)
-            .unwrap_or_default()
-            .into_iter()
+    fn print_uri(
+        printer_system_name: &str,
+        uri: &str,
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        without_libcups!(LpBackend::print_uri(printer_system_name, uri, job_name, options), Err(libcups_error()));
+
+        return cups::jobs::print_uri(printer_system_name, uri, job_name, options);
+    }
+
+    fn print_files(
+        printer_system_name: &str,
+        file_paths: &[&Path],
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        without_libcups!(LpBackend::print_files(printer_system_name, file_paths, job_name, options), Err(libcups_error()));
+
+        return cups::jobs::print_files(printer_system_name, file_paths, job_name, options);
+    }
+
+    fn print_text(
+        printer_system_name: &str,
+        text: &str,
+        job_name: Option<&str>,
+        text_options: &TextOptions,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        let document = render_text(text, text_options)?;
+        let mut options = options;
+        options.document_format.get_or_insert(DocumentFormat::Pdf);
+        return Self::print(printer_system_name, &document, job_name, options);
+    }
+
+    #[cfg(feature = "image")]
+    fn print_image(
+        printer_system_name: &str,
+        image: &image::DynamicImage,
+        job_name: Option<&str>,
+        image_options: &ImagePrintOptions,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        let document = render_image(image, image_options)?;
+        let mut options = options;
+        options.document_format.get_or_insert(DocumentFormat::Pdf);
+        return Self::print(printer_system_name, &document, job_name, options);
+    }
+
+    fn validate_options(printer_system_name: &str, options: &PrintOptions) -> Vec<UnsupportedOption> {
+        without_libcups!(LpBackend::validate_options(printer_system_name, options), vec![]);
+
+        return cups::dinfo::validate_options(printer_system_name, options);
+    }
+
+    fn get_capabilities(printer_system_name: &str) -> Result<PrinterCapabilities, &'static str> {
+        without_libcups!(LpBackend::get_capabilities(printer_system_name), Err(libcups_error()));
+
+        return cups::dinfo::get_capabilities(printer_system_name);
+    }
+
+    fn get_printer_options(printer_system_name: &str) -> Result<Vec<PrinterOption>, &'static str> {
+        without_libcups!(LpBackend::get_printer_options(printer_system_name), Err(libcups_error()));
+
+        return cups::dinfo::get_printer_options(printer_system_name);
+    }
+
+    fn get_default_options(printer_system_name: &str) -> Result<PrintOptions, &'static str> {
+        without_libcups!(LpBackend::get_default_options(printer_system_name), Err(libcups_error()));
+
+        return cups::dinfo::get_default_options(printer_system_name);
+    }
+
+    fn get_option_conflicts(printer_system_name: &str, options: &PrintOptions, option: &str, value: &str) -> Vec<(String, String)> {
+        without_libcups!(LpBackend::get_option_conflicts(printer_system_name, options, option, value), vec![]);
+
+        return cups::dinfo::get_option_conflicts(printer_system_name, options, option, value);
+    }
+
+    fn get_supplies(printer_system_name: &str) -> Result<Vec<Supply>, &'static str> {
+        without_libcups!(LpBackend::get_supplies(printer_system_name), Err(libcups_error()));
+
+        return cups::printer::get_supplies(printer_system_name);
+    }
+
+    fn probe_device(device_uri: &str) -> Result<Duration, &'static str> {
+        without_libcups!(LpBackend::probe_device(device_uri), crate::common::probe::probe_device_port(device_uri));
+
+        return match device_uri.split_once("://").map(|(scheme, _)| scheme) {
+            Some("ipp" | "ipps" | "http" | "https") => cups::printer::probe_ipp_device(device_uri),
+            _ => crate::common::probe::probe_device_port(device_uri),
+        };
+    }
+
+    fn get_printer_jobs(printer_name: &str, query: &JobQuery) -> Result<Vec<PrinterJob>, &'static str> {
+        without_libcups!(LpBackend::get_printer_jobs(printer_name, query), Err(libcups_error()));
+
+        return Ok(cups::jobs::get_printer_jobs(printer_name, query)?
+            .iter()
             .map(|j| PrinterJob::from_platform_printer_job_getters(j))
-            .collect();
+            .collect());
+    }
+
+    fn cancel_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::cancel_job(printer_name, job_id), Err(libcups_error()));
+
+        return cups::jobs::cancel_job(printer_name, job_id);
+    }
+
+    fn hold_job(_printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::hold_job(_printer_name, job_id), Err(libcups_error()));
+
+        return cups::jobs::hold_job(job_id);
+    }
+
+    fn release_job(_printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::release_job(_printer_name, job_id), Err(libcups_error()));
+
+        return cups::jobs::release_job(job_id);
+    }
+
+    fn restart_job(_printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::restart_job(_printer_name, job_id), Err(libcups_error()));
+
+        return cups::jobs::restart_job(job_id);
+    }
+
+    fn move_job(printer_name: &str, job_id: Option<JobId>, target_printer_name: &str) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::move_job(printer_name, job_id, target_printer_name), Err(libcups_error()));
+
+        return cups::jobs::move_job(printer_name, job_id, target_printer_name);
+    }
+
+    fn set_job_priority(_printer_name: &str, job_id: JobId, priority: u8) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::set_job_priority(_printer_name, job_id, priority), Err(libcups_error()));
+
+        return cups::jobs::set_job_priority(job_id, priority);
+    }
+
+    fn purge_jobs(printer_name: &str) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::purge_jobs(printer_name), Err(libcups_error()));
+
+        return cups::jobs::purge_jobs(printer_name);
+    }
+
+    fn pause_printer(printer_name: &str) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::pause_printer(printer_name), Err(libcups_error()));
+
+        return cups::control::pause_printer(printer_name);
+    }
+
+    fn resume_printer(printer_name: &str) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::resume_printer(printer_name), Err(libcups_error()));
+
+        return cups::control::resume_printer(printer_name);
+    }
+
+    fn accept_jobs(printer_name: &str) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::accept_jobs(printer_name), Err(libcups_error()));
+
+        return cups::control::accept_jobs(printer_name);
+    }
+
+    fn reject_jobs(printer_name: &str, reason: Option<&str>) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::reject_jobs(printer_name, reason), Err(libcups_error()));
+
+        return cups::control::reject_jobs(printer_name, reason);
+    }
+
+    fn get_job_progress(_printer_name: &str, job_id: JobId) -> Result<JobProgress, &'static str> {
+        without_libcups!(LpBackend::get_job_progress(_printer_name, job_id), Err(libcups_error()));
+
+        return cups::jobs::get_job_progress(job_id);
+    }
+
+    fn get_job_attributes(_printer_name: &str, job_id: JobId) -> Result<Attributes, &'static str> {
+        without_libcups!(LpBackend::get_job_attributes(_printer_name, job_id), Err(libcups_error()));
+
+        return cups::jobs::get_job_attributes(job_id);
+    }
+
+    fn get_job_document(printer_name: &str, job_id: JobId) -> Result<JobDocument, &'static str> {
+        without_libcups!(LpBackend::get_job_document(printer_name, job_id), Err(libcups_error()));
+
+        return cups::jobs::get_job_document(printer_name, job_id);
     }
 
     fn get_default_printer() -> Option<Printer> {
+        without_libcups!(LpBackend::get_default_printer(), None);
+
         let dests = get_dests().unwrap_or_default();
         let dest = dests
-            .into_iter()
+            .iter()
             .find(|d| d.get_is_default())
             .map(|d| Printer::from_platform_printer_getters(d));
 
@@ -69,10 +346,49 @@ impl PlatformActions for crate::Platform {
         return dest;
     }
 
+    fn set_default_printer(printer_name: &str) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::set_default_printer(printer_name), Err(libcups_error()));
+
+        return cups::dests::set_default_dest(printer_name);
+    }
+
+    fn set_default_options(printer_name: &str, options: &PrintOptions) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::set_default_options(printer_name, options), Err(libcups_error()));
+
+        // A default document format would disable the auto typing of every other application
+        let options: Vec<(String, String)> = cups::options::CupsOptions::from(options)
+            .to_vec()
+            .into_iter()
+            .filter(|(name, _)| name != "document-format")
+            .collect();
+        return cups::dests::set_dest_options(printer_name, &options);
+    }
+
+    fn add_printer(printer: &NewPrinter) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::add_printer(printer), Err(libcups_error()));
+
+        return cups::control::add_printer(printer);
+    }
+
+    fn delete_printer(printer_name: &str) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::delete_printer(printer_name), Err(libcups_error()));
+
+        return cups::control::delete_printer(printer_name);
+    }
+
     fn get_printer_by_name(printer_name: &str) -> Option<Printer> {
+        without_libcups!(LpBackend::get_printer_by_name(printer_name), None);
+
+        // Resolve the system name directly, display names still need the full list
+        if let Some(dest) = cups::dests::get_named_dest(printer_name) {
+            let printer = Printer::from_platform_printer_getters(dest);
+            cups::dests::free_dest(dest);
+            return Some(printer);
+        }
+
         let dests = get_dests().unwrap_or_default();
         let dest = dests
-            .into_iter()
+            .iter()
             .find(|d| d.get_name() == printer_name || d.get_system_name() == printer_name)
             .map(|d| Printer::from_platform_printer_getters(d));
 
@@ -80,28 +396,30 @@ impl PlatformActions for crate::Platform {
         return dest;
     }
 
-    fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState {
+    fn watch(sender: EventSender) -> Result<(), &'static str> {
+        without_libcups!(LpBackend::watch(sender), Err(libcups_error()));
 
-        if state_reasons.contains("offline-report") {
-            return PrinterState::OFFLINE;
-        }
+        // The D-Bus signals are only sent by the local scheduler
+        #[cfg(feature = "dbus")]
+        let sender = match crate::common::backend::cups_server() {
+            Some(_) => sender,
+            None => match dbus::notifier::watch(sender) {
+                Ok(()) => return Ok(()),
+                Err(sender) => sender,
+            },
+        };
 
-        match platform_state {
-            3 => PrinterState::READY,
-            4 => PrinterState::PRINTING,
-            5 => PrinterState::PAUSED,
-            _ => PrinterState::UNKNOWN,
+        if let Err(sender) = cups::subscriptions::watch(sender) {
+            poll_changes(sender);
         }
+        return Ok(());
     }
 
-    fn parse_printer_job_state(platform_state: u64) -> PrinterJobState {
-        match platform_state {            
-            3  => PrinterJobState::PENDING,
-            4 | 6  => PrinterJobState::PAUSED,
-            5  => PrinterJobState::PROCESSING,
-            7 | 8  => PrinterJobState::CANCELLED,
-            9  => PrinterJobState::COMPLETED,
-            _ => PrinterJobState::UNKNOWN,
-        }
+    fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState {
+        return printer_state(platform_state, state_reasons);
+    }
+
+    fn parse_printer_job_state(platform_state: u64) -> JobState {
+        return job_state(platform_state);
     }
 }
@@ -0,0 +1,305 @@
+use crate::common::base::printer::{
+    ColorMode, Duplex, MediaSize, MediaSource, OutputOrder, PrintOptions, PrintOrientation, PrintQuality,
+    PrintResolution, STANDARD_MEDIA_SIZES,
+};
+
+// Based on:
+// https://github.com/apple/cups/blob/a8968fc4257322b1e4e191c4bccedea98d7b053e/cups/cups.h#L166
+const CUPS_ORIENTATION: &str = "orientation-requested";
+const CUPS_ORIENTATION_PORTRAIT: &str = "3";
+const CUPS_ORIENTATION_LANDSCAPE: &str = "4";
+const CUPS_COPIES: &str = "copies";
+const CUPS_SIDES: &str = "sides";
+const CUPS_SIDES_ONE_SIDED: &str = "one-sided";
+const CUPS_SIDES_TWO_SIDED_PORTRAIT: &str = "two-sided-long-edge";
+const CUPS_SIDES_TWO_SIDED_LANDSCAPE: &str = "two-sided-short-edge";
+const CUPS_MEDIA: &str = "media";
+const CUPS_PAGE_RANGES: &str = "page-ranges";
+const CUPS_PRINT_COLOR_MODE: &str = "print-color-mode";
+const CUPS_PRINT_COLOR_MODE_COLOR: &str = "color";
+const CUPS_PRINT_COLOR_MODE_MONOCHROME: &str = "monochrome";
+// PPD based drivers read the ColorModel keyword instead of the IPP attribute
+const CUPS_COLOR_MODEL: &str = "ColorModel";
+const CUPS_COLOR_MODEL_COLOR: &str = "RGB";
+const CUPS_COLOR_MODEL_GRAY: &str = "Gray";
+const CUPS_PRINT_QUALITY: &str = "print-quality";
+const CUPS_PRINT_QUALITY_DRAFT: &str = "3";
+const CUPS_PRINT_QUALITY_NORMAL: &str = "4";
+const CUPS_PRINT_QUALITY_HIGH: &str = "5";
+const CUPS_PRINTER_RESOLUTION: &str = "printer-resolution";
+// PPD based drivers read the Resolution keyword instead of the IPP attribute
+const CUPS_RESOLUTION: &str = "Resolution";
+const CUPS_NUMBER_UP: &str = "number-up";
+const CUPS_COLLATE: &str = "collate";
+const CUPS_OUTPUT_ORDER: &str = "outputorder";
+const CUPS_OUTPUT_ORDER_NORMAL: &str = "normal";
+const CUPS_OUTPUT_ORDER_REVERSE: &str = "reverse";
+const CUPS_FINISHINGS: &str = "finishings";
+const CUPS_MEDIA_SOURCE: &str = "media-source";
+// PPD based drivers read the InputSlot keyword instead of the IPP attribute
+const CUPS_INPUT_SLOT: &str = "InputSlot";
+const CUPS_OUTPUT_BIN: &str = "output-bin";
+const CUPS_JOB_SHEETS: &str = "job-sheets";
+const CUPS_JOB_HOLD_UNTIL: &str = "job-hold-until";
+const CUPS_JOB_PRIORITY: &str = "job-priority";
+const CUPS_FIT_TO_PAGE: &str = "fit-to-page";
+const CUPS_PRINT_SCALING: &str = "print-scaling";
+const CUPS_PRINT_SCALING_FIT: &str = "fit";
+const CUPS_PRINT_SCALING_NONE: &str = "none";
+const CUPS_SCALING: &str = "scaling";
+const CUPS_PAGE_TOP: &str = "page-top";
+const CUPS_PAGE_BOTTOM: &str = "page-bottom";
+const CUPS_PAGE_LEFT: &str = "page-left";
+const CUPS_PAGE_RIGHT: &str = "page-right";
+const CUPS_JOB_PASSWORD: &str = "job-password";
+const CUPS_JOB_PASSWORD_ENCRYPTION: &str = "job-password-encryption";
+const CUPS_JOB_BILLING: &str = "job-billing";
+const CUPS_JOB_ACCOUNT_ID: &str = "job-account-id";
+const CUPS_JOB_ACCOUNTING_USER_ID: &str = "job-accounting-user-id";
+// Read by cupsPrintFile instead of detecting the format from the file content
+const CUPS_DOCUMENT_FORMAT: &str = "document-format";
+
+// IPP media-source keyword and the usual PPD InputSlot choice
+fn media_source_names(media_source: &MediaSource) -> (&str, &str) {
+    let slot = match media_source {
+        MediaSource::Auto => "Auto",
+        MediaSource::Main => "Main",
+        MediaSource::Manual => "Manual",
+        MediaSource::Tray1 => "Tray1",
+        MediaSource::Tray2 => "Tray2",
+        MediaSource::Tray3 => "Tray3",
+        MediaSource::Envelope => "Envelope",
+        MediaSource::LargeCapacity => "LargeCapacity",
+        MediaSource::Custom(name) => name.as_str(),
+    };
+    return (media_source.ipp_keyword(), slot);
+}
+
+/**
+ * Map an orientation-requested enum value back to the PrintOrientation
+ */
+pub fn orientation_from_value(value: &str) -> Option<PrintOrientation> {
+    return match value {
+        CUPS_ORIENTATION_PORTRAIT => Some(PrintOrientation::Portrait),
+        CUPS_ORIENTATION_LANDSCAPE => Some(PrintOrientation::Landscape),
+        _ => None,
+    };
+}
+
+/**
+ * Map a print-quality enum value back to the PrintQuality
+ */
+pub fn quality_from_value(value: &str) -> Option<PrintQuality> {
+    return match value {
+        CUPS_PRINT_QUALITY_DRAFT => Some(PrintQuality::Draft),
+        CUPS_PRINT_QUALITY_NORMAL => Some(PrintQuality::Normal),
+        CUPS_PRINT_QUALITY_HIGH => Some(PrintQuality::High),
+        _ => None,
+    };
+}
+
+/**
+ * Parse a resolution as written by CUPS, ex 600dpi or 1200x600dpi
+ */
+pub fn resolution_from_value(value: &str) -> Option<PrintResolution> {
+    let value = value.strip_suffix("dpi")?;
+    return match value.split_once('x') {
+        Some((x, y)) => Some(PrintResolution { x: x.parse().ok()?, y: y.parse().ok()? }),
+        None => value.parse().ok().map(PrintResolution::dpi),
+    };
+}
+
+/**
+ * Map a sides keyword back to the Duplex
+ */
+pub fn duplex_from_keyword(keyword: &str) -> Option<Duplex> {
+    return match keyword {
+        CUPS_SIDES_ONE_SIDED => Some(Duplex::OneSided),
+        CUPS_SIDES_TWO_SIDED_PORTRAIT => Some(Duplex::TwoSidedLongEdge),
+        CUPS_SIDES_TWO_SIDED_LANDSCAPE => Some(Duplex::TwoSidedShortEdge),
+        _ => None,
+    };
+}
+
+/**
+ * Map a print-color-mode keyword back to the ColorMode
+ */
+pub fn color_mode_from_keyword(keyword: &str) -> Option<ColorMode> {
+    return match keyword {
+        CUPS_PRINT_COLOR_MODE_COLOR => Some(ColorMode::Color),
+        CUPS_PRINT_COLOR_MODE_MONOCHROME => Some(ColorMode::Monochrome),
+        _ => None,
+    };
+}
+
+/**
+ * Map a PWG media name back to the MediaSize, unknown names are kept as MediaSize::Custom
+ */
+pub fn media_size_from_name(name: &str) -> MediaSize {
+    return STANDARD_MEDIA_SIZES
+        .iter()
+        .find(|m| m.pwg_name() == name)
+        .cloned()
+        .unwrap_or_else(|| MediaSize::Custom(name.to_string()));
+}
+
+/**
+ * Map the print options to CUPS option names and values, in the order they are applied so the
+ * raw options come last and win on conflicts
+ */
+pub fn option_pairs(options: &PrintOptions) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = vec![];
+    let mut add = |name: &str, value: &str| pairs.push((name.to_string(), value.to_string()));
+
+    if let Some(orientation) = &options.orientation {
+        let value = if *orientation == PrintOrientation::Landscape {
+            CUPS_ORIENTATION_LANDSCAPE
+        } else {
+            CUPS_ORIENTATION_PORTRAIT
+        };
+        add(CUPS_ORIENTATION, value);
+    }
+
+    if let Some(copies) = options.copies {
+        add(CUPS_COPIES, copies.to_string().as_str());
+    }
+
+    if let Some(duplex) = &options.duplex {
+        let value = match duplex {
+            Duplex::OneSided => CUPS_SIDES_ONE_SIDED,
+            Duplex::TwoSidedLongEdge => CUPS_SIDES_TWO_SIDED_PORTRAIT,
+            Duplex::TwoSidedShortEdge => CUPS_SIDES_TWO_SIDED_LANDSCAPE,
+        };
+        add(CUPS_SIDES, value);
+    }
+
+    if let Some(media_size) = &options.media_size {
+        add(CUPS_MEDIA, media_size.pwg_name().as_str());
+    }
+
+    if let Some(page_ranges) = &options.page_ranges {
+        add(CUPS_PAGE_RANGES, page_ranges.to_string().as_str());
+    }
+
+    if let Some(color_mode) = &options.color_mode {
+        let (mode, model) = match color_mode {
+            ColorMode::Color => (CUPS_PRINT_COLOR_MODE_COLOR, CUPS_COLOR_MODEL_COLOR),
+            ColorMode::Monochrome => (CUPS_PRINT_COLOR_MODE_MONOCHROME, CUPS_COLOR_MODEL_GRAY),
+        };
+        add(CUPS_PRINT_COLOR_MODE, mode);
+        add(CUPS_COLOR_MODEL, model);
+    }
+
+    if let Some(quality) = &options.quality {
+        let value = match quality {
+            PrintQuality::Draft => CUPS_PRINT_QUALITY_DRAFT,
+            PrintQuality::Normal => CUPS_PRINT_QUALITY_NORMAL,
+            PrintQuality::High => CUPS_PRINT_QUALITY_HIGH,
+        };
+        add(CUPS_PRINT_QUALITY, value);
+    }
+
+    if let Some(resolution) = &options.resolution {
+        let value = if resolution.x == resolution.y {
+            format!("{}dpi", resolution.x)
+        } else {
+            format!("{}x{}dpi", resolution.x, resolution.y)
+        };
+        add(CUPS_PRINTER_RESOLUTION, format!("{}x{}dpi", resolution.x, resolution.y).as_str());
+        add(CUPS_RESOLUTION, value.as_str());
+    }
+
+    if let Some(number_up) = options.number_up {
+        add(CUPS_NUMBER_UP, number_up.to_string().as_str());
+    }
+
+    if let Some(collate) = options.collate {
+        add(CUPS_COLLATE, if collate { "true" } else { "false" });
+    }
+
+    if let Some(output_order) = &options.output_order {
+        let value = match output_order {
+            OutputOrder::Normal => CUPS_OUTPUT_ORDER_NORMAL,
+            OutputOrder::Reverse => CUPS_OUTPUT_ORDER_REVERSE,
+        };
+        add(CUPS_OUTPUT_ORDER, value);
+    }
+
+    if !options.finishings.is_empty() {
+        let values: Vec<String> = options.finishings.iter().map(|f| f.ipp_value().to_string()).collect();
+        add(CUPS_FINISHINGS, values.join(",").as_str());
+    }
+
+    if let Some(media_source) = &options.media_source {
+        let (source, slot) = media_source_names(media_source);
+        add(CUPS_MEDIA_SOURCE, source);
+        add(CUPS_INPUT_SLOT, slot);
+    }
+
+    if let Some(output_bin) = &options.output_bin {
+        add(CUPS_OUTPUT_BIN, output_bin.ipp_keyword().as_str());
+    }
+
+    if let Some(job_sheets) = &options.job_sheets {
+        add(CUPS_JOB_SHEETS, job_sheets.to_string().as_str());
+    }
+
+    if let Some(hold_until) = &options.hold_until {
+        add(CUPS_JOB_HOLD_UNTIL, hold_until.to_string().as_str());
+    }
+
+    if let Some(priority) = options.priority {
+        add(CUPS_JOB_PRIORITY, priority.to_string().as_str());
+    }
+
+    if let Some(fit_to_page) = options.fit_to_page {
+        add(CUPS_FIT_TO_PAGE, if fit_to_page { "true" } else { "false" });
+        add(
+            CUPS_PRINT_SCALING,
+            if fit_to_page { CUPS_PRINT_SCALING_FIT } else { CUPS_PRINT_SCALING_NONE },
+        );
+    }
+
+    if let Some(scaling) = options.scaling {
+        add(CUPS_SCALING, scaling.to_string().as_str());
+    }
+
+    if let Some(margins) = &options.margins {
+        // The page-* options take whole points
+        let points = |value: f32| (value.max(0.0).round() as u32).to_string();
+        add(CUPS_PAGE_TOP, points(margins.top).as_str());
+        add(CUPS_PAGE_BOTTOM, points(margins.bottom).as_str());
+        add(CUPS_PAGE_LEFT, points(margins.left).as_str());
+        add(CUPS_PAGE_RIGHT, points(margins.right).as_str());
+    }
+
+    if let Some(job_password) = &options.job_password {
+        add(CUPS_JOB_PASSWORD, job_password);
+    }
+
+    if let Some(encryption) = &options.job_password_encryption {
+        add(CUPS_JOB_PASSWORD_ENCRYPTION, encryption.ipp_keyword());
+    }
+
+    if let Some(job_billing) = &options.job_billing {
+        add(CUPS_JOB_BILLING, job_billing);
+    }
+
+    if let Some(job_account_id) = &options.job_account_id {
+        add(CUPS_JOB_ACCOUNT_ID, job_account_id);
+    }
+
+    if let Some(job_accounting_user_id) = &options.job_accounting_user_id {
+        add(CUPS_JOB_ACCOUNTING_USER_ID, job_accounting_user_id);
+    }
+
+    if let Some(document_format) = &options.document_format {
+        add(CUPS_DOCUMENT_FORMAT, document_format.mime_type());
+    }
+
+    for (name, value) in &options.raw_options {
+        add(name, value);
+    }
+
+    return pairs;
+}
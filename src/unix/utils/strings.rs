@@ -1,4 +1,8 @@
-use std::ffi::{CStr, CString};
+use std::{
+    ffi::{CStr, CString},
+    os::unix::ffi::OsStrExt,
+    path::Path,
+};
 use libc::c_char;
 
 pub fn c_char_to_string(value: *const c_char) -> String {
@@ -14,3 +18,10 @@ pub fn str_to_cstring(value: &str) -> CString {
     let c_string = CString::new(value);
     return c_string.unwrap();
 }
+
+/**
+ * Convert the path keeping its raw bytes, so paths that are not valid UTF-8 still resolve
+ */
+pub fn path_to_cstring(value: &Path) -> Result<CString, &'static str> {
+    return CString::new(value.as_os_str().as_bytes()).map_err(|_| "path contains a nul byte");
+}
@@ -1,14 +1,19 @@
-use std::path::Path;
+use std::{fs::File, io::Read, path::Path, time::Duration};
 
-use crate::common::base::job::PrinterJobState;
-use crate::common::base::printer::{PrintOptions, PrinterState};
+use crate::common::base::attributes::Attributes;
+use crate::common::base::event::{poll_changes, EventSender};
+use crate::common::base::job::{JobDocument, JobId, JobOwner, JobProgress, JobQuery, JobState, WhichJobs};
+use crate::common::base::printer::{DocumentFormat, NewPrinter, PrintOptions, PrinterCapabilities, PrinterOption, PrinterState, Supply, UnsupportedOption};
 use crate::common::base::{job::PrinterJob, printer::Printer};
+use crate::common::render::text::TextOptions;
+#[cfg(feature = "image")]
+use crate::common::render::image::ImagePrintOptions;
 use crate::common::traits::platform::{PlatformActions, PlatformPrinterGetters};
 
 mod utils;
 mod winspool;
 
-impl PlatformActions for crate::Platform {
+impl PlatformActions for crate::Spooler {
     fn get_printers() -> Vec<Printer> {
         let data = winspool::info::enum_printers(None);
 
@@ -26,41 +31,214 @@ impl PlatformActions for crate::Platform {
         printer_system_name: &str,
         buffer: &[u8],
         job_name: Option<&str>,
-        _options: PrintOptions,
-    ) -> Result<(), &'static str> {
-        return winspool::jobs::print_buffer(printer_system_name, job_name, buffer);
+        options: PrintOptions,
+    ) -> Result<JobId, &'static str> {
+        let mut buffer = buffer;
+        return winspool::jobs::print_reader(printer_system_name, job_name, &mut buffer, &options);
+    }
+
+    fn print_reader(
+        printer_system_name: &str,
+        reader: &mut dyn Read,
+        job_name: Option<&str>,
+        options: PrintOptions,
+    ) -> Result<JobId, &'static str> {
+        return winspool::jobs::print_reader(printer_system_name, job_name, reader, &options);
     }
 
     fn print_file(
         printer_system_name: &str,
-        file_path: &str,
+        file_path: &Path,
         job_name: Option<&str>,
-        _options: PrintOptions
-    ) -> Result<(), &'static str> {
-        let buffer = utils::file::get_file_as_bytes(file_path);
-        return if buffer.is_some() {
-            let job_name = job_name.unwrap_or(Path::new(file_path).file_name().unwrap().to_str().unwrap());
-            return Self::print(printer_system_name, &buffer.unwrap(), Some(job_name));
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        let file = File::open(file_path);
+        return if let Ok(mut file) = file {
+            let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+            Self::print_reader(printer_system_name, &mut file, Some(job_name.unwrap_or(&file_name)), options)
         } else {
             Err("failed to read file")
         };
     }
 
-    fn get_printer_jobs(printer_name: &str, active_only: bool) -> Vec<PrinterJob> {
-        return winspool::jobs::enum_printer_jobs(printer_name)
-            .unwrap_or_default()
+    fn print_uri(
+        printer_system_name: &str,
+        uri: &str,
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        // The spooler has no Print-URI equivalent, so the document is always downloaded first
+        let path = utils::download::download_to_temp_file(uri)?;
+        let result = File::open(&path)
+            .map_err(|_| "failed to read file")
+            .and_then(|mut file| Self::print_reader(printer_system_name, &mut file, Some(job_name.unwrap_or(uri)), options));
+
+        let _ = std::fs::remove_file(&path);
+        return result;
+    }
+
+    fn print_files(
+        printer_system_name: &str,
+        file_paths: &[&Path],
+        job_name: Option<&str>,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        if file_paths.is_empty() {
+            return Err("no files to print");
+        }
+
+        // The files are written as one stream, which is only a valid document for the formats
+        // without a header of their own, a PDF or PostScript file would end at the first file
+        let concatenable = matches!(options.document_format, Some(DocumentFormat::Raw | DocumentFormat::PlainText));
+        if file_paths.len() > 1 && !concatenable {
+            return Err("several files make one job on windows only with the Raw or PlainText document format");
+        }
+
+        let mut files = vec![];
+        for file_path in file_paths {
+            files.push(File::open(file_path).map_err(|_| "failed to read file")?);
+        }
+
+        let mut readers: Vec<&mut dyn Read> = files.iter_mut().map(|f| f as &mut dyn Read).collect();
+        let file_name = file_paths[0].file_name().unwrap_or_default().to_string_lossy();
+        let job_name = job_name.or(Some(&file_name));
+        return winspool::jobs::print_readers(printer_system_name, job_name, &mut readers, &options);
+    }
+
+    fn print_text(
+        printer_system_name: &str,
+        text: &str,
+        job_name: Option<&str>,
+        text_options: &TextOptions,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        // Drawn by the driver, so it does not depend on the printer accepting PDF
+        return winspool::gdi::print_text(printer_system_name, text, job_name, text_options, &options);
+    }
+
+    #[cfg(feature = "image")]
+    fn print_image(
+        printer_system_name: &str,
+        image: &image::DynamicImage,
+        job_name: Option<&str>,
+        image_options: &ImagePrintOptions,
+        options: PrintOptions
+    ) -> Result<JobId, &'static str> {
+        return winspool::gdi::print_image(printer_system_name, image, job_name, image_options, &options);
+    }
+
+    fn validate_options(printer_system_name: &str, options: &PrintOptions) -> Vec<UnsupportedOption> {
+        return winspool::capabilities::validate_options(printer_system_name, options);
+    }
+
+    fn get_capabilities(printer_system_name: &str) -> Result<PrinterCapabilities, &'static str> {
+        return winspool::capabilities::get_capabilities(printer_system_name);
+    }
+
+    fn get_printer_options(printer_system_name: &str) -> Result<Vec<PrinterOption>, &'static str> {
+        return winspool::capabilities::get_printer_options(printer_system_name);
+    }
+
+    fn get_default_options(printer_system_name: &str) -> Result<PrintOptions, &'static str> {
+        return winspool::devmode::get_default_options(printer_system_name);
+    }
+
+    fn get_option_conflicts(_printer_system_name: &str, _options: &PrintOptions, _option: &str, _value: &str) -> Vec<(String, String)> {
+        return vec![];
+    }
+
+    fn get_supplies(_printer_system_name: &str) -> Result<Vec<Supply>, &'static str> {
+        // The spooler has no supply levels, they are only exposed by vendor bidi extensions
+        return Err("supplies are not supported on windows");
+    }
+
+    fn probe_device(device_uri: &str) -> Result<Duration, &'static str> {
+        // Standard TCP/IP ports are named after the device address, ex IP_10.0.0.5, and print RAW on 9100
+        return match device_uri.strip_prefix("IP_") {
+            Some(host) => crate::common::probe::probe_tcp(host, 9100),
+            None => crate::common::probe::probe_device_port(device_uri),
+        };
+    }
+
+    fn get_printer_jobs(printer_name: &str, query: &JobQuery) -> Result<Vec<PrinterJob>, &'static str> {
+        let owner = match &query.owner {
+            JobOwner::Any => None,
+            JobOwner::CurrentUser => Some(std::env::var("USERNAME").unwrap_or_default()),
+            JobOwner::User(user_name) => Some(user_name.clone()),
+        };
+
+        return Ok(winspool::jobs::enum_printer_jobs(printer_name)?
             .into_iter()
-            .map(|j| PrinterJob::from_platform_printer_job_getters(j))
             .filter(|j| {
-                return if active_only {
-                    j.state == PrinterJobState::PENDING
-                        || j.state == PrinterJobState::PROCESSING
-                        || j.state == PrinterJobState::PAUSED
-                } else {
-                    true
+                return match query.which_jobs {
+                    WhichJobs::Active => j.state.is_active(),
+                    WhichJobs::Completed => j.state.is_terminal(),
+                    WhichJobs::All => true,
                 }
             })
-            .collect();
+            // Windows user names are case insensitive
+            .filter(|j| owner.as_ref().is_none_or(|o| j.user_name.eq_ignore_ascii_case(o)))
+            .skip(query.offset.unwrap_or(0) as usize)
+            .take(query.limit.map_or(usize::MAX, |l| l as usize))
+            .collect());
+    }
+
+    fn cancel_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        return winspool::jobs::set_job(printer_name, job_id, winspool::jobs::JOB_CONTROL_DELETE);
+    }
+
+    fn hold_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        return winspool::jobs::set_job(printer_name, job_id, winspool::jobs::JOB_CONTROL_PAUSE);
+    }
+
+    fn release_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        return winspool::jobs::set_job(printer_name, job_id, winspool::jobs::JOB_CONTROL_RESUME);
+    }
+
+    fn restart_job(printer_name: &str, job_id: JobId) -> Result<(), &'static str> {
+        return winspool::jobs::set_job(printer_name, job_id, winspool::jobs::JOB_CONTROL_RESTART);
+    }
+
+    fn move_job(_printer_name: &str, _job_id: Option<JobId>, _target_printer_name: &str) -> Result<(), &'static str> {
+        // SetJob ignores the printer name of JOB_INFO, spooled jobs can not change printer
+        return Err("moving jobs is not supported on windows");
+    }
+
+    fn set_job_priority(printer_name: &str, job_id: JobId, priority: u8) -> Result<(), &'static str> {
+        return winspool::jobs::set_job_priority(printer_name, job_id, priority);
+    }
+
+    fn purge_jobs(printer_name: &str) -> Result<(), &'static str> {
+        return winspool::control::set_printer(printer_name, winspool::control::PRINTER_CONTROL_PURGE);
+    }
+
+    fn pause_printer(printer_name: &str) -> Result<(), &'static str> {
+        return winspool::control::set_printer(printer_name, winspool::control::PRINTER_CONTROL_PAUSE);
+    }
+
+    fn resume_printer(printer_name: &str) -> Result<(), &'static str> {
+        return winspool::control::set_printer(printer_name, winspool::control::PRINTER_CONTROL_RESUME);
+    }
+
+    fn accept_jobs(_printer_name: &str) -> Result<(), &'static str> {
+        // Windows queues always accept new jobs
+        return Ok(());
+    }
+
+    fn reject_jobs(_printer_name: &str, _reason: Option<&str>) -> Result<(), &'static str> {
+        return Err("rejecting jobs is not supported on windows");
+    }
+
+    fn get_job_progress(printer_name: &str, job_id: JobId) -> Result<JobProgress, &'static str> {
+        return winspool::jobs::get_job_progress(printer_name, job_id);
+    }
+
+    fn get_job_attributes(printer_name: &str, job_id: JobId) -> Result<Attributes, &'static str> {
+        return winspool::jobs::get_job_attributes(printer_name, job_id);
+    }
+
+    fn get_job_document(printer_name: &str, job_id: JobId) -> Result<JobDocument, &'static str> {
+        return winspool::jobs::read_job_document(printer_name, job_id);
     }
 
     fn get_default_printer() -> Option<Printer> {
@@ -68,16 +246,43 @@ impl PlatformActions for crate::Platform {
             .map(|p| Printer::from_platform_printer_getters(p));
     }
 
+    fn set_default_printer(printer_name: &str) -> Result<(), &'static str> {
+        return winspool::info::set_default_printer(printer_name);
+    }
+
+    fn set_default_options(printer_name: &str, options: &PrintOptions) -> Result<(), &'static str> {
+        return winspool::control::set_default_devmode(printer_name, options);
+    }
+
+    fn add_printer(printer: &NewPrinter) -> Result<(), &'static str> {
+        return winspool::info::add_printer(printer);
+    }
+
+    fn delete_printer(printer_name: &str) -> Result<(), &'static str> {
+        return winspool::control::delete_printer(printer_name);
+    }
+
     fn get_printer_by_name(name: &str) -> Option<Printer> {
+        if let Some(printer) = winspool::info::get_printer(name, |p| Printer::from_platform_printer_getters(p)) {
+            return Some(printer);
+        }
+
         return winspool::info::enum_printers(None)
-            .into_iter()
+            .iter()
             .find(|p| p.get_name() == name || p.get_system_name() == name)
             .map(|p| Printer::from_platform_printer_getters(p));
     }
 
+    fn watch(sender: EventSender) -> Result<(), &'static str> {
+        if let Err(sender) = winspool::notifications::watch(sender) {
+            poll_changes(sender);
+        }
+        return Ok(());
+    }
+
     fn parse_printer_state(platform_state: u64, state_reasons: &str) -> PrinterState {
 
-        if state_reasons.contains("offline") || state_reasons.contains("pending_deletion") {
+        if state_reasons.contains("offline") || state_reasons.contains("pending-deletion") {
             return PrinterState::OFFLINE;
         }
 
@@ -90,14 +295,17 @@ impl PlatformActions for crate::Platform {
         }
     }
 
-    fn parse_printer_job_state(platform_state: u64) -> PrinterJobState {
+    fn parse_printer_job_state(platform_state: u64) -> JobState {
+        // JOB_STATUS_* flags, several can be set at once so the most relevant one wins
         match platform_state {
-            1 | 8 => PrinterJobState::PAUSED,
-            4 | 256 => PrinterJobState::CANCELLED,
-            16 | 2048 | 8192 => PrinterJobState::PROCESSING,
-            32 | 64 | 512 | 1024 => PrinterJobState::PENDING,
-            128 | 496 => PrinterJobState::COMPLETED,
-            _ => PrinterJobState::UNKNOWN
+            s if s & (0x00000004 | 0x00000100) != 0 => JobState::CANCELED,
+            s if s & 0x00000200 != 0 => JobState::ABORTED,
+            s if s & (0x00000080 | 0x00001000 | 0x00002000) != 0 => JobState::COMPLETED,
+            s if s & 0x00000001 != 0 => JobState::HELD,
+            s if s & (0x00000002 | 0x00000020 | 0x00000040 | 0x00000400) != 0 => JobState::STOPPED,
+            s if s & (0x00000008 | 0x00000010 | 0x00000800 | 0x00004000) != 0 => JobState::PROCESSING,
+            0 => JobState::PENDING,
+            _ => JobState::UNKNOWN
         }
     }
 }
@@ -2,7 +2,7 @@ use libc::c_ushort;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 fn is_leap_year(year: c_ushort) -> bool {
-    return (year % 4 == 0 && year % 100 != 0) || (year % 400 == 0);
+    return (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400);
 }
 
 fn days_in_months(year: c_ushort, month: c_ushort) -> c_ushort {
@@ -43,7 +43,7 @@ pub fn calculate_system_time(
         + (minute as u64 * 60)
         + (second as u64);
 
-    return UNIX_EPOCH + Duration::new(total_seconds.into(), milliseconds as u32 * 1_000_000);
+    return UNIX_EPOCH + Duration::new(total_seconds, milliseconds as u32 * 1_000_000);
 }
 
 pub fn get_current_epoch() -> u128 {
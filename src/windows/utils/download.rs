@@ -0,0 +1,40 @@
+#![allow(non_snake_case)]
+
+use libc::{c_long, c_ulong, c_void, wchar_t};
+use std::{
+    path::PathBuf,
+    ptr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::windows::utils::strings::str_to_wide_string;
+
+#[link(name = "urlmon")]
+unsafe extern "system" {
+    fn URLDownloadToFileW(
+        pCaller: *mut c_void,
+        szURL: *const wchar_t,
+        szFileName: *const wchar_t,
+        dwReserved: c_ulong,
+        lpfnCB: *mut c_void,
+    ) -> c_long;
+}
+
+/**
+ * Download the uri to a new file in the temporary directory, the caller removes it once done
+ */
+pub fn download_to_temp_file(uri: &str) -> Result<PathBuf, &'static str> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+    let path = std::env::temp_dir().join(format!("printers-{}-{}", std::process::id(), nanos));
+
+    let url = str_to_wide_string(uri);
+    let file_name = str_to_wide_string(path.to_str().ok_or("invalid temporary path")?);
+    let result = unsafe { URLDownloadToFileW(ptr::null_mut(), url.as_ptr(), file_name.as_ptr(), 0, ptr::null_mut()) };
+
+    return if result == 0 {
+        Ok(path)
+    } else {
+        let _ = std::fs::remove_file(&path);
+        Err("document download failed")
+    };
+}
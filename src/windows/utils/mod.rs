@@ -1,4 +1,4 @@
 pub mod date;
-pub mod file;
+pub mod download;
 pub mod memory;
 pub mod strings;
\ No newline at end of file
@@ -9,7 +9,7 @@ pub fn wchar_t_to_string(value: *const wchar_t) -> String {
     let mut i = 0;
     unsafe {
         while *value.offset(i) != 0 {
-            vec.push(*value.offset(i) as u16);
+            vec.push(*value.offset(i));
             i += 1;
         }
     }
@@ -0,0 +1,430 @@
+#![allow(non_snake_case)]
+
+use libc::{c_int, c_long, c_ushort, wchar_t};
+use std::ptr;
+
+use crate::{
+    common::{
+        base::printer::{
+            ColorMode, DocumentFormat, Duplex, MediaSize, OptionChoice, PrintOptions, PrintOrientation, PrintResolution,
+            PrinterCapabilities, PrinterOption, UnsupportedOption, STANDARD_MEDIA_SIZES,
+        },
+        traits::platform::PlatformPrinterGetters,
+    },
+    windows::{
+        utils::strings::{str_to_wide_string, wchar_t_to_string},
+        winspool::{
+            devmode::{bin_code, get_default_options, paper_size_code, DEVMODEW},
+            info::{enum_printers, free},
+        },
+    },
+};
+
+#[link(name = "winspool")]
+unsafe extern "system" {
+    fn DeviceCapabilitiesW(
+        pDevice: *const wchar_t,
+        pPort: *const wchar_t,
+        fwCapability: c_ushort,
+        pOutput: *mut wchar_t,
+        pDevMode: *const DEVMODEW,
+    ) -> c_int;
+}
+
+const DC_PAPERS: c_ushort = 2;
+const DC_BINS: c_ushort = 6;
+const DC_DUPLEX: c_ushort = 7;
+const DC_BINNAMES: c_ushort = 12;
+const DC_ENUMRESOLUTIONS: c_ushort = 13;
+const DC_PAPERNAMES: c_ushort = 16;
+const DC_ORIENTATION: c_ushort = 17;
+const DC_COPIES: c_ushort = 18;
+const DC_COLLATE: c_ushort = 22;
+const DC_COLORDEVICE: c_ushort = 32;
+
+// Each DC_PAPERNAMES entry is a fixed 64 characters buffer
+const PAPER_NAME_LENGTH: usize = 64;
+// Each DC_BINNAMES entry is a fixed 24 characters buffer
+const BIN_NAME_LENGTH: usize = 24;
+
+/**
+ * Printer device and port, as wide strings, used to query the driver capabilities
+ */
+pub struct Device {
+    name: Vec<u16>,
+    port: Vec<u16>,
+}
+
+impl Device {
+    pub fn new(printer_system_name: &str) -> Option<Self> {
+        let printers = enum_printers(None);
+        let port = printers
+            .iter()
+            .find(|p| p.get_system_name() == printer_system_name)
+            .map(|p| p.get_port_name());
+        free(printers);
+
+        return port.map(|port| Device {
+            name: str_to_wide_string(printer_system_name),
+            port: str_to_wide_string(port.as_str()),
+        });
+    }
+
+    /**
+     * Return the capability value, or -1 when the driver does not support the query
+     */
+    pub fn capability(&self, capability: c_ushort) -> c_int {
+        return unsafe {
+            DeviceCapabilitiesW(
+                self.name.as_ptr() as *const wchar_t,
+                self.port.as_ptr() as *const wchar_t,
+                capability,
+                ptr::null_mut(),
+                ptr::null(),
+            )
+        };
+    }
+
+    /**
+     * Return the capability array, T being the type of each item
+     */
+    pub fn capability_array<T: Default + Clone>(&self, capability: c_ushort, item_len: usize) -> Vec<T> {
+        let count = self.capability(capability);
+        if count <= 0 {
+            return vec![];
+        }
+
+        let mut buffer: Vec<T> = vec![T::default(); count as usize * item_len];
+        let count = unsafe {
+            DeviceCapabilitiesW(
+                self.name.as_ptr() as *const wchar_t,
+                self.port.as_ptr() as *const wchar_t,
+                capability,
+                buffer.as_mut_ptr() as *mut wchar_t,
+                ptr::null(),
+            )
+        };
+
+        buffer.truncate(count.max(0) as usize * item_len);
+        return buffer;
+    }
+
+    pub fn papers(&self) -> Vec<c_ushort> {
+        return self.capability_array::<c_ushort>(DC_PAPERS, 1);
+    }
+
+    pub fn paper_names(&self) -> Vec<String> {
+        return self
+            .capability_array::<u16>(DC_PAPERNAMES, PAPER_NAME_LENGTH)
+            .chunks(PAPER_NAME_LENGTH)
+            .map(|name| wchar_t_to_string(name.as_ptr()))
+            .collect();
+    }
+
+    pub fn bins(&self) -> Vec<c_ushort> {
+        return self.capability_array::<c_ushort>(DC_BINS, 1);
+    }
+
+    pub fn bin_names(&self) -> Vec<String> {
+        return self
+            .capability_array::<u16>(DC_BINNAMES, BIN_NAME_LENGTH)
+            .chunks(BIN_NAME_LENGTH)
+            .map(|name| wchar_t_to_string(name.as_ptr()))
+            .collect();
+    }
+
+    /**
+     * Return the (x, y) resolutions in dpi
+     */
+    pub fn resolutions(&self) -> Vec<(c_long, c_long)> {
+        return self
+            .capability_array::<c_long>(DC_ENUMRESOLUTIONS, 2)
+            .chunks(2)
+            .map(|r| (r[0], r[1]))
+            .collect();
+    }
+
+    pub fn max_copies(&self) -> c_int {
+        return self.capability(DC_COPIES);
+    }
+
+    pub fn supports_duplex(&self) -> bool {
+        return self.capability(DC_DUPLEX) == 1;
+    }
+
+    pub fn supports_landscape(&self) -> bool {
+        return self.capability(DC_ORIENTATION) > 0;
+    }
+
+    pub fn supports_collate(&self) -> bool {
+        return self.capability(DC_COLLATE) == 1;
+    }
+
+    pub fn supports_color(&self) -> bool {
+        return self.capability(DC_COLORDEVICE) == 1;
+    }
+}
+
+fn unsupported(option: &str, value: String, reason: &'static str) -> UnsupportedOption {
+    return UnsupportedOption {
+        option: option.to_string(),
+        value,
+        reason,
+    };
+}
+
+/**
+ * Return the options the printer can not honor, using DeviceCapabilitiesW. The options kept in the
+ * DEVMODE (copies, duplex, media...) are also reported for the RAW jobs, which the driver does
+ * not process
+ */
+pub fn validate_options(printer_system_name: &str, options: &PrintOptions) -> Vec<UnsupportedOption> {
+    let device = match Device::new(printer_system_name) {
+        Some(device) => device,
+        None => {
+            return vec![unsupported(
+                "printer_name",
+                printer_system_name.to_string(),
+                "printer not found",
+            )];
+        }
+    };
+
+    let mut result = vec![];
+
+    if options.orientation == Some(PrintOrientation::Landscape) && !device.supports_landscape() {
+        result.push(unsupported("orientation", "Landscape".to_string(), "not supported by the printer"));
+    }
+
+    if let Some(copies) = options.copies
+        && copies as c_int > device.max_copies().max(1)
+    {
+        result.push(unsupported("copies", copies.to_string(), "exceeds the printer maximum copies"));
+    }
+
+    if let Some(duplex) = &options.duplex
+        && *duplex != Duplex::OneSided
+        && !device.supports_duplex()
+    {
+        result.push(unsupported("duplex", format!("{:?}", duplex), "not supported by the printer"));
+    }
+
+    if let Some(media_size) = &options.media_size {
+        let supported = match (paper_size_code(media_size), media_size) {
+            (Some(code), _) => device.papers().contains(&(code as c_ushort)),
+            (None, MediaSize::Custom(form_name)) => device.paper_names().contains(form_name),
+            (None, _) => false,
+        };
+        if !supported {
+            result.push(unsupported("media_size", format!("{:?}", media_size), "not supported by the printer"));
+        }
+    }
+
+    if options.color_mode == Some(ColorMode::Color) && !device.supports_color() {
+        result.push(unsupported("color_mode", "Color".to_string(), "not supported by the printer"));
+    }
+
+    if let Some(resolution) = &options.resolution
+        && !device
+            .resolutions()
+            .contains(&(resolution.x as c_long, resolution.y as c_long))
+    {
+        result.push(unsupported(
+            "resolution",
+            format!("{}x{}dpi", resolution.x, resolution.y),
+            "not supported by the printer",
+        ));
+    }
+
+    if options.collate == Some(true) && !device.supports_collate() {
+        result.push(unsupported("collate", "true".to_string(), "not supported by the printer"));
+    }
+
+    if let Some(media_source) = &options.media_source
+        && !bin_code(media_source).is_some_and(|bin| device.bins().contains(&(bin as c_ushort)))
+    {
+        result.push(unsupported("media_source", format!("{:?}", media_source), "not supported by the printer"));
+    }
+
+    // Options that have no DEVMODE equivalent
+    let platform_unsupported = [
+        ("page_ranges", options.page_ranges.as_ref().map(|v| v.to_string())),
+        ("number_up", options.number_up.map(|v| v.to_string())),
+        ("output_order", options.output_order.as_ref().map(|v| format!("{:?}", v))),
+        ("output_bin", options.output_bin.as_ref().map(|v| v.ipp_keyword())),
+        ("job_sheets", options.job_sheets.as_ref().map(|v| v.to_string())),
+        ("hold_until", options.hold_until.as_ref().map(|v| v.to_string())),
+        ("priority", options.priority.map(|v| v.to_string())),
+        ("fit_to_page", options.fit_to_page.map(|v| v.to_string())),
+        ("margins", options.margins.as_ref().map(|v| format!("{:?}", v))),
+        ("job_password", options.job_password.as_ref().map(|_| "***".to_string())),
+        ("job_billing", options.job_billing.clone()),
+        ("job_account_id", options.job_account_id.clone()),
+        ("job_accounting_user_id", options.job_accounting_user_id.clone()),
+        ("requesting_user", options.requesting_user.clone()),
+    ];
+
+    for (option, value) in platform_unsupported {
+        if let Some(value) = value {
+            result.push(unsupported(option, value, "not supported on windows"));
+        }
+    }
+
+    for finishing in &options.finishings {
+        result.push(unsupported("finishings", format!("{:?}", finishing), "not supported on windows"));
+    }
+
+    // RAW data reaches the port untouched by the driver, so the options it would apply from the
+    // DEVMODE are lost, they only reach the PlainText and Xps documents (and print_text and
+    // print_image, drawn by the driver)
+    if !matches!(options.document_format, Some(DocumentFormat::PlainText | DocumentFormat::Xps)) {
+        let devmode_options = [
+            ("copies", options.copies.map(|v| v.to_string())),
+            ("orientation", options.orientation.as_ref().map(|v| format!("{:?}", v))),
+            ("duplex", options.duplex.as_ref().map(|v| format!("{:?}", v))),
+            ("media_size", options.media_size.as_ref().map(|v| format!("{:?}", v))),
+            ("media_source", options.media_source.as_ref().map(|v| format!("{:?}", v))),
+            ("color_mode", options.color_mode.as_ref().map(|v| format!("{:?}", v))),
+            ("quality", options.quality.as_ref().map(|v| format!("{:?}", v))),
+            ("resolution", options.resolution.as_ref().map(|v| format!("{}x{}dpi", v.x, v.y))),
+            ("collate", options.collate.map(|v| v.to_string())),
+            ("scaling", options.scaling.map(|v| v.to_string())),
+        ];
+
+        for (option, value) in devmode_options {
+            if let Some(value) = value {
+                result.push(unsupported(option, value, "not applied to RAW data on windows"));
+            }
+        }
+    }
+
+    return result;
+}
+
+/**
+ * Return the choices supported by the printer driver, using DeviceCapabilitiesW
+ */
+pub fn get_capabilities(printer_system_name: &str) -> Result<PrinterCapabilities, &'static str> {
+    let device = Device::new(printer_system_name).ok_or("printer not found")?;
+
+    // DC_PAPERS and DC_PAPERNAMES return the same papers in the same order
+    let media_sizes = device
+        .papers()
+        .into_iter()
+        .zip(device.paper_names())
+        .map(|(code, name)| {
+            STANDARD_MEDIA_SIZES
+                .iter()
+                .find(|m| paper_size_code(m) == Some(code as i16))
+                .cloned()
+                .unwrap_or(MediaSize::Custom(name))
+        })
+        .collect();
+
+    let duplex_modes = if device.supports_duplex() {
+        vec![Duplex::OneSided, Duplex::TwoSidedLongEdge, Duplex::TwoSidedShortEdge]
+    } else {
+        vec![Duplex::OneSided]
+    };
+
+    let color_modes = if device.supports_color() {
+        vec![ColorMode::Color, ColorMode::Monochrome]
+    } else {
+        vec![ColorMode::Monochrome]
+    };
+
+    return Ok(PrinterCapabilities {
+        media_sizes,
+        duplex_modes,
+        color_modes,
+        resolutions: device
+            .resolutions()
+            .into_iter()
+            .map(|(x, y)| PrintResolution { x: x.max(0) as u32, y: y.max(0) as u32 })
+            .collect(),
+        max_copies: device.max_copies().max(1) as u32,
+    });
+}
+
+fn printer_option(name: &str, label: &str, choices: Vec<(String, String)>) -> PrinterOption {
+    return PrinterOption {
+        name: name.to_string(),
+        label: label.to_string(),
+        choices: choices.into_iter().map(|(value, label)| OptionChoice { value, label }).collect(),
+        default: None,
+    };
+}
+
+/**
+ * Return the DEVMODE options the driver supports, named after the PrintOptions fields.
+ * Paper and bin names are the values to use with MediaSize::Custom and MediaSource::Custom
+ */
+pub fn get_printer_options(printer_system_name: &str) -> Result<Vec<PrinterOption>, &'static str> {
+    let device = Device::new(printer_system_name).ok_or("printer not found")?;
+    let same = |name: String| (name.clone(), name);
+    let mut options = vec![];
+
+    options.push(printer_option("media_size", "Paper size", device.paper_names().into_iter().map(same).collect()));
+    options.push(printer_option("media_source", "Paper source", device.bin_names().into_iter().map(same).collect()));
+
+    let mut orientations = vec![("Portrait".to_string(), "Portrait".to_string())];
+    if device.supports_landscape() {
+        orientations.push(("Landscape".to_string(), "Landscape".to_string()));
+    }
+    options.push(printer_option("orientation", "Orientation", orientations));
+
+    let mut duplex_modes = vec![("OneSided".to_string(), "One sided".to_string())];
+    if device.supports_duplex() {
+        duplex_modes.push(("TwoSidedLongEdge".to_string(), "Two sided (long edge)".to_string()));
+        duplex_modes.push(("TwoSidedShortEdge".to_string(), "Two sided (short edge)".to_string()));
+    }
+    options.push(printer_option("duplex", "Two sided", duplex_modes));
+
+    let mut color_modes = vec![("Monochrome".to_string(), "Monochrome".to_string())];
+    if device.supports_color() {
+        color_modes.insert(0, ("Color".to_string(), "Color".to_string()));
+    }
+    options.push(printer_option("color_mode", "Color", color_modes));
+
+    let resolutions = device
+        .resolutions()
+        .into_iter()
+        .map(|(x, y)| (format!("{}x{}dpi", x, y), format!("{} x {} dpi", x, y)))
+        .collect();
+    options.push(printer_option("resolution", "Resolution", resolutions));
+
+    let max_copies = device.max_copies().max(1);
+    options.push(printer_option("copies", "Copies", vec![(format!("1-{}", max_copies), format!("1 to {}", max_copies))]));
+
+    if device.supports_collate() {
+        options.push(printer_option(
+            "collate",
+            "Collate",
+            vec![("true".to_string(), "Collated".to_string()), ("false".to_string(), "Uncollated".to_string())],
+        ));
+    }
+
+    if let Ok(defaults) = get_default_options(printer_system_name) {
+        for option in options.iter_mut() {
+            option.default = match option.name.as_str() {
+                "media_size" => match defaults.media_size.clone() {
+                    Some(MediaSize::Custom(name)) => Some(name),
+                    Some(media_size) => device
+                        .papers()
+                        .iter()
+                        .position(|code| paper_size_code(&media_size) == Some(*code as i16))
+                        .and_then(|i| device.paper_names().get(i).cloned()),
+                    None => None,
+                },
+                "duplex" => defaults.duplex.as_ref().map(|v| format!("{:?}", v)),
+                "color_mode" => defaults.color_mode.as_ref().map(|v| format!("{:?}", v)),
+                "resolution" => defaults.resolution.as_ref().map(|r| format!("{}x{}dpi", r.x, r.y)),
+                "copies" => defaults.copies.map(|v| v.to_string()),
+                "collate" => defaults.collate.map(|v| v.to_string()),
+                _ => None,
+            };
+        }
+    }
+
+    return Ok(options);
+}
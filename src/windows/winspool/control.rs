@@ -0,0 +1,124 @@
+#![allow(non_snake_case)]
+
+use libc::{c_int, c_ulong, c_void, wchar_t};
+use std::ptr;
+
+use crate::{
+    common::base::printer::PrintOptions,
+    windows::{utils::strings::str_to_wide_string, winspool::devmode::get_devmode},
+};
+
+#[link(name = "winspool")]
+unsafe extern "system" {
+    fn OpenPrinterW(
+        pPrinterName: *const wchar_t,
+        phPrinter: *mut *mut c_void,
+        pDefault: *mut PrinterDefaultW,
+    ) -> c_int;
+    fn DeletePrinter(hPrinter: *mut c_void) -> c_int;
+    fn SetPrinterW(hPrinter: *mut c_void, Level: c_ulong, pPrinter: *mut c_void, Command: c_ulong) -> c_int;
+    fn ClosePrinter(hPrinter: *mut c_void) -> c_int;
+}
+
+#[repr(C)]
+struct PrinterDefaultW {
+    pDatatype: *mut wchar_t,
+    pDevMode: *mut c_void,
+    DesiredAccess: c_ulong,
+}
+
+pub const PRINTER_ACCESS_USE: c_ulong = 0x00000008;
+// Printer control commands need an administrator handle
+const PRINTER_ACCESS_ADMINISTER: c_ulong = 0x00000004;
+// Deleting the printer also needs the standard DELETE right
+const PRINTER_ALL_ACCESS: c_ulong = 0x000F000C;
+
+pub const PRINTER_CONTROL_PAUSE: c_ulong = 1;
+pub const PRINTER_CONTROL_RESUME: c_ulong = 2;
+pub const PRINTER_CONTROL_PURGE: c_ulong = 3;
+
+/**
+ * Open the printer handle with the access rights, to be closed with ClosePrinter, an empty name
+ * opens the local print server
+ */
+pub fn open_printer(printer_system_name: &str, access: c_ulong) -> Result<*mut c_void, &'static str> {
+    let printer_name = str_to_wide_string(printer_system_name);
+    let printer_name_ptr = if printer_system_name.is_empty() { ptr::null() } else { printer_name.as_ptr() as *const wchar_t };
+    let mut printer_handle: *mut c_void = ptr::null_mut();
+    let mut defaults = PrinterDefaultW {
+        pDatatype: ptr::null_mut(),
+        pDevMode: ptr::null_mut(),
+        DesiredAccess: access,
+    };
+
+    return unsafe {
+        if OpenPrinterW(printer_name_ptr, &mut printer_handle, &mut defaults) == 0 {
+            Err("OpenPrinterW failed")
+        } else {
+            Ok(printer_handle)
+        }
+    };
+}
+
+/**
+ * Send a PRINTER_CONTROL_* command to the printer with SetPrinterW
+ */
+pub fn set_printer(printer_system_name: &str, command: c_ulong) -> Result<(), &'static str> {
+    let printer_handle = open_printer(printer_system_name, PRINTER_ACCESS_ADMINISTER)?;
+
+    return unsafe {
+        let result = SetPrinterW(printer_handle, 0, ptr::null_mut(), command);
+        ClosePrinter(printer_handle);
+
+        if result == 0 { Err("SetPrinterW failed") } else { Ok(()) }
+    };
+}
+
+/**
+ * The winspool PRINTER_INFO_9 structure, the per-user default DEVMODE of the printer
+ * https://learn.microsoft.com/en/windows/win32/printdocs/printer-info-9
+ */
+#[repr(C)]
+struct PRINTER_INFO_9W {
+    pDevMode: *mut c_void,
+}
+
+/**
+ * Merge the options into the printer DEVMODE and store it as the default of the current
+ * user with SetPrinterW level 9, which needs no administrator rights
+ */
+pub fn set_default_devmode(printer_system_name: &str, options: &PrintOptions) -> Result<(), &'static str> {
+    let printer_handle = open_printer(printer_system_name, PRINTER_ACCESS_USE)?;
+
+    return unsafe {
+        let result = match get_devmode(printer_handle, printer_system_name, options) {
+            Some(mut devmode) => {
+                let mut printer_info = PRINTER_INFO_9W {
+                    pDevMode: devmode.as_mut_ptr() as *mut c_void,
+                };
+                if SetPrinterW(printer_handle, 9, &mut printer_info as *mut PRINTER_INFO_9W as *mut c_void, 0) == 0 {
+                    Err("SetPrinterW failed")
+                } else {
+                    Ok(())
+                }
+            }
+            None => Err("DocumentPropertiesW failed"),
+        };
+        ClosePrinter(printer_handle);
+        result
+    };
+}
+
+/**
+ * Delete the printer with DeletePrinter, the spooler removes it once its jobs are done
+ */
+pub fn delete_printer(printer_system_name: &str) -> Result<(), &'static str> {
+    let printer_handle = open_printer(printer_system_name, PRINTER_ALL_ACCESS)?;
+
+    return unsafe {
+        let result = DeletePrinter(printer_handle);
+        ClosePrinter(printer_handle);
+
+        if result == 0 { Err("DeletePrinter failed") } else { Ok(()) }
+    };
+}
@@ -0,0 +1,372 @@
+#![allow(non_snake_case, clippy::upper_case_acronyms)]
+
+use libc::{c_int, c_long, c_short, c_ulong, c_ushort, c_void, wchar_t};
+use std::ptr;
+
+use crate::{
+    common::base::printer::{
+        ColorMode, Duplex, MediaSize, MediaSource, PrintOptions, PrintOrientation, PrintQuality, PrintResolution,
+        STANDARD_MEDIA_SIZES,
+    },
+    windows::{
+        utils::strings::{str_to_wide_string, wchar_t_to_string},
+        winspool::control::{open_printer, PRINTER_ACCESS_USE},
+    },
+};
+
+#[link(name = "winspool")]
+unsafe extern "system" {
+    fn DocumentPropertiesW(
+        hWnd: *mut c_void,
+        hPrinter: *mut c_void,
+        pDeviceName: *const wchar_t,
+        pDevModeOutput: *mut DEVMODEW,
+        pDevModeInput: *const DEVMODEW,
+        fMode: c_ulong,
+    ) -> c_long;
+    fn ClosePrinter(hPrinter: *mut c_void) -> c_int;
+}
+
+const DM_OUT_BUFFER: c_ulong = 2;
+const DM_IN_BUFFER: c_ulong = 8;
+
+const DM_ORIENTATION: c_ulong = 0x00000001;
+const DM_PAPERSIZE: c_ulong = 0x00000002;
+const DM_PAPERLENGTH: c_ulong = 0x00000004;
+const DM_PAPERWIDTH: c_ulong = 0x00000008;
+const DM_SCALE: c_ulong = 0x00000010;
+const DM_COPIES: c_ulong = 0x00000100;
+const DM_DEFAULTSOURCE: c_ulong = 0x00000200;
+const DM_PRINTQUALITY: c_ulong = 0x00000400;
+const DM_COLOR: c_ulong = 0x00000800;
+const DM_DUPLEX: c_ulong = 0x00001000;
+const DM_YRESOLUTION: c_ulong = 0x00002000;
+const DM_COLLATE: c_ulong = 0x00008000;
+const DM_FORMNAME: c_ulong = 0x00010000;
+
+pub fn bin_code(media_source: &MediaSource) -> Option<c_short> {
+    return match media_source {
+        MediaSource::Main | MediaSource::Tray1 => Some(1),
+        MediaSource::Tray2 => Some(2),
+        MediaSource::Tray3 => Some(3),
+        MediaSource::Manual => Some(4),
+        MediaSource::Envelope => Some(5),
+        MediaSource::Auto => Some(7),
+        MediaSource::LargeCapacity => Some(11),
+        MediaSource::Custom(bin) => bin.trim().parse::<c_short>().ok(),
+    };
+}
+
+const DMORIENT_PORTRAIT: c_short = 1;
+const DMORIENT_LANDSCAPE: c_short = 2;
+
+const DMCOLLATE_FALSE: c_short = 0;
+const DMCOLLATE_TRUE: c_short = 1;
+
+const DMRES_DRAFT: c_short = -1;
+const DMRES_MEDIUM: c_short = -3;
+const DMRES_HIGH: c_short = -4;
+
+const DMCOLOR_MONOCHROME: c_short = 1;
+const DMCOLOR_COLOR: c_short = 2;
+
+const DMDUP_SIMPLEX: c_short = 1;
+const DMDUP_VERTICAL: c_short = 2;
+const DMDUP_HORIZONTAL: c_short = 3;
+
+const DMPAPER_USER: c_short = 256;
+
+pub fn paper_size_code(media_size: &MediaSize) -> Option<c_short> {
+    return match media_size {
+        MediaSize::Letter => Some(1),
+        MediaSize::Tabloid => Some(3),
+        MediaSize::Legal => Some(5),
+        MediaSize::Executive => Some(7),
+        MediaSize::A3 => Some(8),
+        MediaSize::A4 => Some(9),
+        MediaSize::A5 => Some(11),
+        MediaSize::Envelope10 => Some(20),
+        MediaSize::EnvelopeDL => Some(27),
+        MediaSize::A6 => Some(70),
+        MediaSize::CustomSize { .. } => Some(DMPAPER_USER),
+        MediaSize::Custom(_) => None,
+    };
+}
+
+/**
+ * The DEVMODEW structure contains information about the initialization and environment of a printer.
+ * Only the printer variant of the unions is declared.
+ * https://learn.microsoft.com/en-us/windows/win32/api/wingdi/ns-wingdi-devmodew
+ */
+#[derive(Debug, Clone)]
+#[repr(C)]
+pub struct DEVMODEW {
+    dmDeviceName: [wchar_t; 32],
+    dmSpecVersion: c_ushort,
+    dmDriverVersion: c_ushort,
+    dmSize: c_ushort,
+    dmDriverExtra: c_ushort,
+    dmFields: c_ulong,
+    dmOrientation: c_short,
+    dmPaperSize: c_short,
+    dmPaperLength: c_short,
+    dmPaperWidth: c_short,
+    dmScale: c_short,
+    dmCopies: c_short,
+    dmDefaultSource: c_short,
+    dmPrintQuality: c_short,
+    dmColor: c_short,
+    dmDuplex: c_short,
+    dmYResolution: c_short,
+    dmTTOption: c_short,
+    dmCollate: c_short,
+    dmFormName: [wchar_t; 32],
+    dmLogPixels: c_ushort,
+    dmBitsPerPel: c_ulong,
+    dmPelsWidth: c_ulong,
+    dmPelsHeight: c_ulong,
+    dmNup: c_ulong,
+    dmDisplayFrequency: c_ulong,
+    dmICMMethod: c_ulong,
+    dmICMIntent: c_ulong,
+    dmMediaType: c_ulong,
+    dmDitherType: c_ulong,
+    dmReserved1: c_ulong,
+    dmReserved2: c_ulong,
+    dmPanningWidth: c_ulong,
+    dmPanningHeight: c_ulong,
+}
+
+impl DEVMODEW {
+    /**
+     * Read the fields set in the DEVMODE back as print options
+     */
+    fn to_options(&self) -> PrintOptions {
+        let mut options = PrintOptions::default();
+        let has = |field: c_ulong| self.dmFields & field != 0;
+
+        if has(DM_COPIES) {
+            options.copies = Some(self.dmCopies.max(1) as u32);
+        }
+
+        if has(DM_ORIENTATION) {
+            options.orientation = match self.dmOrientation {
+                DMORIENT_PORTRAIT => Some(PrintOrientation::Portrait),
+                DMORIENT_LANDSCAPE => Some(PrintOrientation::Landscape),
+                _ => None,
+            };
+        }
+
+        if has(DM_DUPLEX) {
+            options.duplex = match self.dmDuplex {
+                DMDUP_SIMPLEX => Some(Duplex::OneSided),
+                DMDUP_VERTICAL => Some(Duplex::TwoSidedLongEdge),
+                DMDUP_HORIZONTAL => Some(Duplex::TwoSidedShortEdge),
+                _ => None,
+            };
+        }
+
+        if has(DM_PAPERSIZE) {
+            options.media_size = STANDARD_MEDIA_SIZES
+                .iter()
+                .find(|m| paper_size_code(m) == Some(self.dmPaperSize))
+                .cloned();
+        }
+        if options.media_size.is_none() && has(DM_FORMNAME) {
+            options.media_size = Some(MediaSize::Custom(wchar_t_to_string(self.dmFormName.as_ptr())));
+        }
+
+        if has(DM_COLOR) {
+            options.color_mode = match self.dmColor {
+                DMCOLOR_COLOR => Some(ColorMode::Color),
+                DMCOLOR_MONOCHROME => Some(ColorMode::Monochrome),
+                _ => None,
+            };
+        }
+
+        // Positive print qualities are resolutions in dpi, negative ones are DMRES_* levels
+        if has(DM_PRINTQUALITY) {
+            match self.dmPrintQuality {
+                DMRES_DRAFT => options.quality = Some(PrintQuality::Draft),
+                DMRES_MEDIUM => options.quality = Some(PrintQuality::Normal),
+                DMRES_HIGH => options.quality = Some(PrintQuality::High),
+                x if x > 0 => {
+                    let y = if has(DM_YRESOLUTION) && self.dmYResolution > 0 { self.dmYResolution } else { x };
+                    options.resolution = Some(PrintResolution { x: x as u32, y: y as u32 });
+                }
+                _ => {}
+            }
+        }
+
+        if has(DM_COLLATE) {
+            options.collate = Some(self.dmCollate == DMCOLLATE_TRUE);
+        }
+
+        return options;
+    }
+
+    fn apply_options(&mut self, options: &PrintOptions) {
+        if let Some(copies) = options.copies {
+            self.dmCopies = copies.min(c_short::MAX as u32) as c_short;
+            self.dmFields |= DM_COPIES;
+        }
+
+        if let Some(orientation) = &options.orientation {
+            self.dmOrientation = match orientation {
+                PrintOrientation::Portrait => DMORIENT_PORTRAIT,
+                PrintOrientation::Landscape => DMORIENT_LANDSCAPE,
+            };
+            self.dmFields |= DM_ORIENTATION;
+        }
+
+        if let Some(duplex) = &options.duplex {
+            self.dmDuplex = match duplex {
+                Duplex::OneSided => DMDUP_SIMPLEX,
+                Duplex::TwoSidedLongEdge => DMDUP_VERTICAL,
+                Duplex::TwoSidedShortEdge => DMDUP_HORIZONTAL,
+            };
+            self.dmFields |= DM_DUPLEX;
+        }
+
+        if let Some(media_size) = &options.media_size {
+            if let Some(paper_size) = paper_size_code(media_size) {
+                self.dmPaperSize = paper_size;
+                self.dmFields |= DM_PAPERSIZE;
+
+                if let MediaSize::CustomSize { width, height } = media_size {
+                    // Custom dimensions are in tenths of a millimeter
+                    self.dmPaperWidth = (width.to_millimeters() * 10.0).round() as c_short;
+                    self.dmPaperLength = (height.to_millimeters() * 10.0).round() as c_short;
+                    self.dmFields |= DM_PAPERWIDTH | DM_PAPERLENGTH;
+                }
+            } else if let MediaSize::Custom(form_name) = media_size {
+                self.dmFormName = [0; 32];
+                for (i, c) in str_to_wide_string(form_name).into_iter().take(31).enumerate() {
+                    self.dmFormName[i] = c;
+                }
+                self.dmFields |= DM_FORMNAME;
+            }
+        }
+
+        if let Some(color_mode) = &options.color_mode {
+            self.dmColor = match color_mode {
+                ColorMode::Color => DMCOLOR_COLOR,
+                ColorMode::Monochrome => DMCOLOR_MONOCHROME,
+            };
+            self.dmFields |= DM_COLOR;
+        }
+
+        if let Some(quality) = &options.quality {
+            self.dmPrintQuality = match quality {
+                PrintQuality::Draft => DMRES_DRAFT,
+                PrintQuality::Normal => DMRES_MEDIUM,
+                PrintQuality::High => DMRES_HIGH,
+            };
+            self.dmFields |= DM_PRINTQUALITY;
+        }
+
+        if let Some(resolution) = &options.resolution {
+            self.dmPrintQuality = resolution.x.min(c_short::MAX as u32) as c_short;
+            self.dmYResolution = resolution.y.min(c_short::MAX as u32) as c_short;
+            self.dmFields |= DM_PRINTQUALITY | DM_YRESOLUTION;
+        }
+
+        if let Some(collate) = options.collate {
+            self.dmCollate = if collate { DMCOLLATE_TRUE } else { DMCOLLATE_FALSE };
+            self.dmFields |= DM_COLLATE;
+        }
+
+        if let Some(source) = options.media_source.as_ref().and_then(bin_code) {
+            self.dmDefaultSource = source;
+            self.dmFields |= DM_DEFAULTSOURCE;
+        }
+
+        if let Some(scaling) = options.scaling {
+            self.dmScale = scaling.min(c_short::MAX as u32) as c_short;
+            self.dmFields |= DM_SCALE;
+        }
+    }
+}
+
+/**
+ * Owned DEVMODEW buffer, including the driver private data that follows the public struct
+ */
+pub struct DevMode {
+    buffer: Vec<u64>,
+}
+
+impl DevMode {
+    pub fn as_mut_ptr(&mut self) -> *mut DEVMODEW {
+        return self.buffer.as_mut_ptr() as *mut DEVMODEW;
+    }
+}
+
+/**
+ * Build the printer DEVMODEW with the print options merged over the driver defaults using DocumentPropertiesW
+ */
+pub fn get_devmode(printer_handle: *mut c_void, printer_system_name: &str, options: &PrintOptions) -> Option<DevMode> {
+    let printer_name = str_to_wide_string(printer_system_name);
+
+    let size = unsafe {
+        DocumentPropertiesW(
+            ptr::null_mut(),
+            printer_handle,
+            printer_name.as_ptr() as *const wchar_t,
+            ptr::null_mut(),
+            ptr::null(),
+            0,
+        )
+    };
+
+    if size < size_of::<DEVMODEW>() as c_long {
+        return None;
+    }
+
+    let mut devmode = DevMode {
+        buffer: vec![0; (size as usize).div_ceil(size_of::<u64>())],
+    };
+
+    let result = unsafe {
+        DocumentPropertiesW(
+            ptr::null_mut(),
+            printer_handle,
+            printer_name.as_ptr() as *const wchar_t,
+            devmode.as_mut_ptr(),
+            ptr::null(),
+            DM_OUT_BUFFER,
+        )
+    };
+
+    if result < 0 {
+        return None;
+    }
+
+    unsafe { (*devmode.as_mut_ptr()).apply_options(options) };
+
+    let result = unsafe {
+        DocumentPropertiesW(
+            ptr::null_mut(),
+            printer_handle,
+            printer_name.as_ptr() as *const wchar_t,
+            devmode.as_mut_ptr(),
+            devmode.as_mut_ptr(),
+            DM_IN_BUFFER | DM_OUT_BUFFER,
+        )
+    };
+
+    return if result < 0 { None } else { Some(devmode) };
+}
+
+/**
+ * Return the options of the default DEVMODE of the printer, including the current user defaults
+ */
+pub fn get_default_options(printer_system_name: &str) -> Result<PrintOptions, &'static str> {
+    let printer_handle = open_printer(printer_system_name, PRINTER_ACCESS_USE)?;
+    let devmode = get_devmode(printer_handle, printer_system_name, &PrintOptions::default());
+    unsafe { ClosePrinter(printer_handle) };
+
+    return match devmode {
+        Some(mut devmode) => Ok(unsafe { (*devmode.as_mut_ptr()).to_options() }),
+        None => Err("DocumentPropertiesW failed"),
+    };
+}
@@ -0,0 +1,402 @@
+#![allow(non_snake_case, clippy::upper_case_acronyms)]
+
+use libc::{c_int, c_uint, c_ulong, c_void, wchar_t};
+#[cfg(feature = "image")]
+use libc::{c_long, c_ushort};
+use std::ptr;
+
+use crate::{
+    common::{
+        base::{job::JobId, printer::PrintOptions},
+        render::text::{layout_text, TextOptions, COURIER_CHAR_WIDTH},
+    },
+    windows::{
+        utils::{date::get_current_epoch, strings::str_to_wide_string},
+        winspool::{
+            control::{open_printer, PRINTER_ACCESS_USE},
+            devmode::get_devmode,
+        },
+    },
+};
+#[cfg(feature = "image")]
+use crate::common::render::image::{place_image, ImagePrintOptions};
+
+#[link(name = "winspool")]
+unsafe extern "system" {
+    fn ClosePrinter(hPrinter: *mut c_void) -> c_int;
+}
+
+#[link(name = "gdi32")]
+unsafe extern "system" {
+    fn CreateDCW(
+        pwszDriver: *const wchar_t,
+        pwszDevice: *const wchar_t,
+        pszPort: *const wchar_t,
+        pdm: *const c_void,
+    ) -> *mut c_void;
+    fn DeleteDC(hdc: *mut c_void) -> c_int;
+    fn StartDocW(hdc: *mut c_void, lpdi: *const DocInfoW) -> c_int;
+    fn StartPage(hdc: *mut c_void) -> c_int;
+    fn EndPage(hdc: *mut c_void) -> c_int;
+    fn EndDoc(hdc: *mut c_void) -> c_int;
+    fn AbortDoc(hdc: *mut c_void) -> c_int;
+    fn GetDeviceCaps(hdc: *mut c_void, index: c_int) -> c_int;
+    fn CreateFontW(
+        cHeight: c_int,
+        cWidth: c_int,
+        cEscapement: c_int,
+        cOrientation: c_int,
+        cWeight: c_int,
+        bItalic: c_ulong,
+        bUnderline: c_ulong,
+        bStrikeOut: c_ulong,
+        iCharSet: c_ulong,
+        iOutPrecision: c_ulong,
+        iClipPrecision: c_ulong,
+        iQuality: c_ulong,
+        iPitchAndFamily: c_ulong,
+        pszFaceName: *const wchar_t,
+    ) -> *mut c_void;
+    fn SelectObject(hdc: *mut c_void, h: *mut c_void) -> *mut c_void;
+    fn DeleteObject(ho: *mut c_void) -> c_int;
+    fn SetTextAlign(hdc: *mut c_void, align: c_uint) -> c_uint;
+    fn TextOutW(hdc: *mut c_void, x: c_int, y: c_int, lpString: *const wchar_t, c: c_int) -> c_int;
+    #[cfg(feature = "image")]
+    fn IntersectClipRect(hdc: *mut c_void, left: c_int, top: c_int, right: c_int, bottom: c_int) -> c_int;
+    #[cfg(feature = "image")]
+    fn SetStretchBltMode(hdc: *mut c_void, mode: c_int) -> c_int;
+    #[cfg(feature = "image")]
+    fn SetBrushOrgEx(hdc: *mut c_void, x: c_int, y: c_int, lppt: *mut c_void) -> c_int;
+    #[cfg(feature = "image")]
+    fn StretchDIBits(
+        hdc: *mut c_void,
+        xDest: c_int,
+        yDest: c_int,
+        DestWidth: c_int,
+        DestHeight: c_int,
+        xSrc: c_int,
+        ySrc: c_int,
+        SrcWidth: c_int,
+        SrcHeight: c_int,
+        lpBits: *const c_void,
+        lpbmi: *const BitmapInfoHeader,
+        iUsage: c_uint,
+        rop: c_ulong,
+    ) -> c_int;
+}
+
+/**
+ * The DOCINFOW structure contains the input and output file names and other information used by the StartDoc function.
+ * https://learn.microsoft.com/en-us/windows/win32/api/wingdi/ns-wingdi-docinfow
+ */
+#[repr(C)]
+struct DocInfoW {
+    cbSize: c_int,
+    lpszDocName: *const wchar_t,
+    lpszOutput: *const wchar_t,
+    lpszDatatype: *const wchar_t,
+    fwType: c_ulong,
+}
+
+/**
+ * The BITMAPINFOHEADER structure contains information about the dimensions and color format of a DIB.
+ * https://learn.microsoft.com/en-us/windows/win32/api/wingdi/ns-wingdi-bitmapinfoheader
+ */
+#[cfg(feature = "image")]
+#[repr(C)]
+struct BitmapInfoHeader {
+    biSize: c_ulong,
+    biWidth: c_long,
+    biHeight: c_long,
+    biPlanes: c_ushort,
+    biBitCount: c_ushort,
+    biCompression: c_ulong,
+    biSizeImage: c_ulong,
+    biXPelsPerMeter: c_long,
+    biYPelsPerMeter: c_long,
+    biClrUsed: c_ulong,
+    biClrImportant: c_ulong,
+}
+
+const LOGPIXELSX: c_int = 88;
+const LOGPIXELSY: c_int = 90;
+const PHYSICALOFFSETX: c_int = 112;
+const PHYSICALOFFSETY: c_int = 113;
+
+const FW_NORMAL: c_int = 400;
+const DEFAULT_CHARSET: c_ulong = 1;
+const FIXED_PITCH: c_ulong = 1;
+const FF_MODERN: c_ulong = 0x30;
+const TA_BASELINE: c_uint = 24;
+
+#[cfg(feature = "image")]
+const BI_RGB: c_ulong = 0;
+#[cfg(feature = "image")]
+const DIB_RGB_COLORS: c_uint = 0;
+#[cfg(feature = "image")]
+const SRCCOPY: c_ulong = 0x00CC0020;
+#[cfg(feature = "image")]
+const HALFTONE: c_int = 4;
+
+// Same metrics as the Courier font of the PDF documents, so the lines break at the same columns
+const FONT_FACE: &str = "Courier New";
+
+/**
+ * Printer device context, converting the points of the page to device units
+ */
+struct Device {
+    hdc: *mut c_void,
+    dpi_x: f32,
+    dpi_y: f32,
+    offset_x: c_int,
+    offset_y: c_int,
+}
+
+impl Device {
+    fn x(&self, points: f32) -> c_int {
+        return (points * self.dpi_x / 72.0).round() as c_int - self.offset_x;
+    }
+
+    fn y(&self, points: f32) -> c_int {
+        return (points * self.dpi_y / 72.0).round() as c_int - self.offset_y;
+    }
+
+    /**
+     * Only draw inside the margins, the coordinates are in points from the top left corner
+     */
+    #[cfg(feature = "image")]
+    fn clip(&self, left: f32, top: f32, right: f32, bottom: f32) {
+        unsafe { IntersectClipRect(self.hdc, self.x(left), self.y(top), self.x(right), self.y(bottom)) };
+    }
+}
+
+/**
+ * Create a device context of the printer with the options merged into its DEVMODE
+ */
+fn create_dc(printer_system_name: &str, options: &PrintOptions) -> Result<Device, &'static str> {
+    let printer_handle = open_printer(printer_system_name, PRINTER_ACCESS_USE)?;
+    let devmode = get_devmode(printer_handle, printer_system_name, options);
+    unsafe { ClosePrinter(printer_handle) };
+
+    let mut devmode = devmode.ok_or("DocumentPropertiesW failed")?;
+    let driver = str_to_wide_string("WINSPOOL");
+    let printer_name = str_to_wide_string(printer_system_name);
+
+    let hdc = unsafe {
+        CreateDCW(
+            driver.as_ptr() as *const wchar_t,
+            printer_name.as_ptr() as *const wchar_t,
+            ptr::null(),
+            devmode.as_mut_ptr() as *const c_void,
+        )
+    };
+    if hdc.is_null() {
+        return Err("CreateDCW failed");
+    }
+
+    return unsafe {
+        Ok(Device {
+            hdc,
+            dpi_x: GetDeviceCaps(hdc, LOGPIXELSX) as f32,
+            dpi_y: GetDeviceCaps(hdc, LOGPIXELSY) as f32,
+            offset_x: GetDeviceCaps(hdc, PHYSICALOFFSETX),
+            offset_y: GetDeviceCaps(hdc, PHYSICALOFFSETY),
+        })
+    };
+}
+
+/**
+ * Print a document of the given number of pages, each one drawn by draw_page, returning the id of the created job
+ */
+fn print_pages(
+    printer_system_name: &str,
+    job_name: Option<&str>,
+    options: &PrintOptions,
+    pages: usize,
+    draw_page: &mut dyn FnMut(&Device, usize) -> Result<(), &'static str>,
+) -> Result<JobId, &'static str> {
+    let device = create_dc(printer_system_name, options)?;
+    let doc_name = str_to_wide_string(job_name.unwrap_or(get_current_epoch().to_string().as_str()));
+
+    let doc_info = DocInfoW {
+        cbSize: size_of::<DocInfoW>() as c_int,
+        lpszDocName: doc_name.as_ptr() as *const wchar_t,
+        lpszOutput: ptr::null(),
+        lpszDatatype: ptr::null(),
+        fwType: 0,
+    };
+
+    let job_id = unsafe { StartDocW(device.hdc, &doc_info) };
+    if job_id <= 0 {
+        unsafe { DeleteDC(device.hdc) };
+        return Err("StartDocW failed");
+    }
+
+    let mut result = Ok(job_id as JobId);
+    for page in 0..pages {
+        if unsafe { StartPage(device.hdc) } <= 0 {
+            result = Err("StartPage failed");
+            break;
+        }
+        if let Err(e) = draw_page(&device, page) {
+            result = Err(e);
+            break;
+        }
+        if unsafe { EndPage(device.hdc) } <= 0 {
+            result = Err("EndPage failed");
+            break;
+        }
+    }
+
+    unsafe {
+        if result.is_ok() && EndDoc(device.hdc) <= 0 {
+            result = Err("EndDoc failed");
+        } else if result.is_err() {
+            AbortDoc(device.hdc);
+        }
+        DeleteDC(device.hdc);
+    }
+    return result;
+}
+
+/**
+ * Print plain text with TextOutW, laid out in the same pages and lines as render_text
+ */
+pub fn print_text(
+    printer_system_name: &str,
+    text: &str,
+    job_name: Option<&str>,
+    text_options: &TextOptions,
+    options: &PrintOptions,
+) -> Result<JobId, &'static str> {
+    let layout = layout_text(text, text_options)?;
+    let margins = &text_options.margins;
+    let font_face = str_to_wide_string(FONT_FACE);
+
+    let mut options = options.clone();
+    options.media_size.get_or_insert(text_options.media_size.clone());
+
+    let mut draw_page = |device: &Device, page: usize| -> Result<(), &'static str> {
+        let font = unsafe {
+            CreateFontW(
+                -((text_options.font_size * device.dpi_y / 72.0).round() as c_int),
+                (text_options.font_size * COURIER_CHAR_WIDTH * device.dpi_x / 72.0).round() as c_int,
+                0,
+                0,
+                FW_NORMAL,
+                0,
+                0,
+                0,
+                DEFAULT_CHARSET,
+                0,
+                0,
+                0,
+                FIXED_PITCH | FF_MODERN,
+                font_face.as_ptr() as *const wchar_t,
+            )
+        };
+        if font.is_null() {
+            return Err("CreateFontW failed");
+        }
+
+        let mut result = Ok(());
+        unsafe {
+            let previous = SelectObject(device.hdc, font);
+            SetTextAlign(device.hdc, TA_BASELINE);
+
+            for (i, line) in layout.pages[page].iter().enumerate() {
+                let baseline = margins.top + text_options.font_size + i as f32 * layout.leading;
+                let wide: Vec<u16> = line.encode_utf16().collect();
+                if !wide.is_empty()
+                    && TextOutW(device.hdc, device.x(margins.left), device.y(baseline), wide.as_ptr() as *const wchar_t, wide.len() as c_int) == 0
+                {
+                    result = Err("TextOutW failed");
+                    break;
+                }
+            }
+
+            SelectObject(device.hdc, previous);
+            DeleteObject(font);
+        }
+        return result;
+    };
+
+    return print_pages(printer_system_name, job_name, &options, layout.pages.len(), &mut draw_page);
+}
+
+/**
+ * Print an image with StretchDIBits, placed on the page as render_image does
+ */
+#[cfg(feature = "image")]
+pub fn print_image(
+    printer_system_name: &str,
+    image: &image::DynamicImage,
+    job_name: Option<&str>,
+    image_options: &ImagePrintOptions,
+    options: &PrintOptions,
+) -> Result<JobId, &'static str> {
+    let placement = place_image(image, image_options)?;
+    let margins = &image_options.margins;
+
+    // Bottom-up rows of BGR pixels, each one padded to a multiple of 4 bytes
+    let rgb = placement.image.to_rgb8();
+    let (width, height) = (rgb.width() as usize, rgb.height() as usize);
+    let stride = (width * 3).div_ceil(4) * 4;
+    let mut bits = vec![0u8; stride * height];
+    for (row, pixels) in rgb.rows().enumerate() {
+        let offset = (height - 1 - row) * stride;
+        for (column, pixel) in pixels.enumerate() {
+            let [r, g, b] = pixel.0;
+            bits[offset + column * 3..offset + column * 3 + 3].copy_from_slice(&[b, g, r]);
+        }
+    }
+
+    let header = BitmapInfoHeader {
+        biSize: size_of::<BitmapInfoHeader>() as c_ulong,
+        biWidth: width as c_long,
+        biHeight: height as c_long,
+        biPlanes: 1,
+        biBitCount: 24,
+        biCompression: BI_RGB,
+        biSizeImage: bits.len() as c_ulong,
+        biXPelsPerMeter: 0,
+        biYPelsPerMeter: 0,
+        biClrUsed: 0,
+        biClrImportant: 0,
+    };
+
+    let mut options = options.clone();
+    options.media_size.get_or_insert(image_options.media_size.clone());
+
+    let mut draw_page = |device: &Device, _page: usize| -> Result<(), &'static str> {
+        // Clip to the printable area, so filled images do not overflow the margins
+        device.clip(margins.left, margins.top, placement.width - margins.right, placement.height - margins.bottom);
+
+        let top = placement.height - placement.y - placement.draw_height;
+        let (left, right) = (device.x(placement.x), device.x(placement.x + placement.draw_width));
+        let (top, bottom) = (device.y(top), device.y(top + placement.draw_height));
+
+        let lines = unsafe {
+            SetStretchBltMode(device.hdc, HALFTONE);
+            SetBrushOrgEx(device.hdc, 0, 0, ptr::null_mut());
+            StretchDIBits(
+                device.hdc,
+                left,
+                top,
+                right - left,
+                bottom - top,
+                0,
+                0,
+                width as c_int,
+                height as c_int,
+                bits.as_ptr() as *const c_void,
+                &header,
+                DIB_RGB_COLORS,
+                SRCCOPY,
+            )
+        };
+        return if lines <= 0 { Err("StretchDIBits failed") } else { Ok(()) };
+    };
+
+    return print_pages(printer_system_name, job_name, &options, 1, &mut draw_page);
+}
@@ -4,12 +4,16 @@ use libc::{c_int, c_uint, c_ulong, c_void, wchar_t};
 use std::{ptr, slice};
 
 use crate::{
-    common::traits::platform::PlatformPrinterGetters,
+    common::{
+        base::printer::{NewPrinter, PrinterDriver, PrinterKind},
+        traits::platform::PlatformPrinterGetters,
+    },
+    windows::winspool::control::{open_printer, PRINTER_ACCESS_USE},
     windows::utils::{memory::{alloc_s, dealloc_s}, strings::{str_to_wide_string, wchar_t_to_string}}
 };
 
 #[link(name = "winspool")]
-extern "system" {
+unsafe extern "system" {
 
     fn EnumPrintersW(
         Flags: c_ulong,
@@ -22,6 +26,16 @@ extern "system" {
     ) -> c_int;
 
     fn GetDefaultPrinterW(pszBuffer: *mut wchar_t, pcchBuffer: *mut c_ulong) -> c_int;
+    fn SetDefaultPrinterW(pszPrinter: *const wchar_t) -> c_int;
+    fn AddPrinterW(pName: *mut wchar_t, Level: c_ulong, pPrinter: *mut PRINTER_INFO_2W) -> *mut c_void;
+    fn ClosePrinter(hPrinter: *mut c_void) -> c_int;
+    fn GetPrinterW(
+        hPrinter: *mut c_void,
+        Level: c_ulong,
+        pPrinter: *mut u8,
+        cbBuf: c_ulong,
+        pcbNeeded: *mut c_ulong,
+    ) -> c_int;
 
 }
 
@@ -64,8 +78,10 @@ impl PlatformPrinterGetters for PRINTER_INFO_2W {
         return unsafe {
             GetDefaultPrinterW(ptr::null_mut(), &mut name_size);
             let mut buffer: Vec<wchar_t> = vec![0; name_size as usize];
-            GetDefaultPrinterW(buffer.as_mut_ptr(), &mut name_size);
-            *self.pPrinterName == *buffer.as_ptr()
+            if GetDefaultPrinterW(buffer.as_mut_ptr(), &mut name_size) == 0 {
+                return false;
+            }
+            wchar_t_to_string(self.pPrinterName) == wchar_t_to_string(buffer.as_ptr())
         };
     }
     fn get_system_name(&self) -> String {
@@ -77,9 +93,31 @@ impl PlatformPrinterGetters for PRINTER_INFO_2W {
     fn get_is_shared(&self) -> bool {
         return (self.Attributes & 0x00000008) == 8;
     }
+    fn get_is_accepting_jobs(&self) -> bool {
+        // Windows queues have no reject state, only a printer being deleted refuses new jobs
+        return (self.Status & 0x00000004) == 0;
+    }
+    fn get_is_class(&self) -> bool {
+        // Windows printer pools are configured on the ports of a single printer
+        return false;
+    }
+    fn get_members(&self) -> Vec<String> {
+        return vec![];
+    }
     fn get_uri(&self) -> String {
         return "".to_string();
     }
+    fn get_kind(&self) -> PrinterKind {
+        // Connections to printers shared by a print server, ex \\server\printer
+        if self.Attributes & PRINTER_ATTRIBUTE_NETWORK != 0 {
+            return PrinterKind::RemoteQueue;
+        }
+        return PrinterKind::Local;
+    }
+    fn get_device_uri(&self) -> String {
+        // Windows has no device uri, the port (ex USB001 or IP_192.168.0.10) tells how the device is connected
+        return wchar_t_to_string(self.pPortName);
+    }
     fn get_location(&self) -> String {
         return wchar_t_to_string(self.pLocation);
     }
@@ -98,35 +136,41 @@ impl PlatformPrinterGetters for PRINTER_INFO_2W {
     fn get_data_type(&self) -> String {
         return wchar_t_to_string(self.pDatatype);
     }
+    fn get_attributes(&self) -> u64 {
+        return self.Attributes as u64;
+    }
+    fn get_jobs_count(&self) -> Option<u32> {
+        return Some(self.cJobs);
+    }
     fn get_state_reasons(&self) -> Vec<String> {
-        // NOTE: These reasons are virtual descriptions based on printer status
+        // NOTE: These reasons are virtual descriptions based on printer status,
+        // named after the ipp printer-state-reasons keyword when one exists
         return [
-            (0x00000000, "ready"),
             (0x00000001, "paused"),
-            (0x00000002, "error"),
-            (0x00000004, "pending_deletion"),
-            (0x00000008, "paper_jam"),
-            (0x00000010, "paper_out"),
-            (0x00000020, "manual_feed"),
-            (0x00000040, "paper_problem"),
+            (0x00000002, "other"),
+            (0x00000004, "pending-deletion"),
+            (0x00000008, "media-jam"),
+            (0x00000010, "media-empty"),
+            (0x00000020, "media-needed"),
+            (0x00000040, "paper-problem"),
             (0x00000080, "offline"),
-            (0x00000100, "io_active"),
+            (0x00000100, "io-active"),
             (0x00000200, "busy"),
             (0x00000400, "printing"),
-            (0x00000800, "output_bin_full"),
-            (0x00001000, "not_available"),
+            (0x00000800, "output-area-full"),
+            (0x00001000, "not-available"),
             (0x00002000, "waiting"),
             (0x00004000, "processing"),
             (0x00008000, "initializing"),
-            (0x00010000, "warming_up"),
-            (0x00020000, "toner_low"),
-            (0x00040000, "no_toner"),
-            (0x00080000, "page_punt"),
-            (0x00100000, "user_intervention"),
-            (0x00200000, "out_of_memory"),
-            (0x00400000, "door_open"),
-            (0x00800000, "server_unknown"),
-            (0x01000000, "power_save"),
+            (0x00010000, "warming-up"),
+            (0x00020000, "toner-low"),
+            (0x00040000, "toner-empty"),
+            (0x00080000, "page-punt"),
+            (0x00100000, "user-intervention"),
+            (0x00200000, "out-of-memory"),
+            (0x00400000, "door-open"),
+            (0x00800000, "server-unknown"),
+            (0x01000000, "power-save"),
         ].iter()
             .filter(|v| self.Status & v.0 != 0)
             .map(|v| v.1.to_string())
@@ -141,11 +185,11 @@ pub fn enum_printers(name: Option<&str>) -> &'static [PRINTER_INFO_2W] {
     let mut bytes_needed: c_ulong = 0;
     let mut count_printers: c_ulong = 0;
     let mut buffer_ptr: *mut PRINTER_INFO_2W = ptr::null_mut();
-    let name_ptr = if name.is_none() {
-        ptr::null_mut()
-    } else {
-        let value = str_to_wide_string(name.unwrap());
+    let name_value = name.map(str_to_wide_string);
+    let name_ptr = if let Some(value) = &name_value {
         value.as_ptr()
+    } else {
+        ptr::null()
     } as *const wchar_t;
 
     for _ in 0..2 {
@@ -172,6 +216,26 @@ pub fn enum_printers(name: Option<&str>) -> &'static [PRINTER_INFO_2W] {
     return unsafe { slice::from_raw_parts(buffer_ptr, count_printers as usize) };
 }
 
+/**
+ * Read the printer directly with OpenPrinterW and GetPrinterW, the info is only valid inside map
+ */
+pub fn get_printer<T>(printer_system_name: &str, map: impl FnOnce(&PRINTER_INFO_2W) -> T) -> Option<T> {
+    let printer_handle = open_printer(printer_system_name, PRINTER_ACCESS_USE).ok()?;
+
+    return unsafe {
+        let mut bytes_needed: c_ulong = 0;
+        GetPrinterW(printer_handle, 2, ptr::null_mut(), 0, &mut bytes_needed);
+
+        // u64 items keep the buffer aligned for the struct pointers
+        let mut buffer: Vec<u64> = vec![0; (bytes_needed as usize).div_ceil(size_of::<u64>())];
+        let result = bytes_needed > 0
+            && GetPrinterW(printer_handle, 2, buffer.as_mut_ptr() as *mut u8, bytes_needed, &mut bytes_needed) != 0;
+        ClosePrinter(printer_handle);
+
+        if result { Some(map(&*(buffer.as_ptr() as *const PRINTER_INFO_2W))) } else { None }
+    };
+}
+
 /**
  * Returns the defualt printer filetring all printer
  */
@@ -179,11 +243,79 @@ pub fn get_default_printer() -> Option<&'static PRINTER_INFO_2W> {
     return enum_printers(None).iter().find(|p| p.get_is_default());
 }
 
+/**
+ * Set the default printer of the current user using SetDefaultPrinterW
+ */
+pub fn set_default_printer(printer_name: &str) -> Result<(), &'static str> {
+    let printer_name = str_to_wide_string(printer_name);
+    return if unsafe { SetDefaultPrinterW(printer_name.as_ptr() as *const wchar_t) } != 0 {
+        Ok(())
+    } else {
+        Err("SetDefaultPrinterW failed")
+    };
+}
+
+const PRINTER_ATTRIBUTE_SHARED: c_ulong = 0x00000008;
+const PRINTER_ATTRIBUTE_NETWORK: c_ulong = 0x00000010;
+
+/**
+ * Create the printer with AddPrinterW, the port and the driver must already be installed
+ */
+pub fn add_printer(printer: &NewPrinter) -> Result<(), &'static str> {
+    let driver_name = match &printer.driver {
+        PrinterDriver::Everywhere => "Microsoft IPP Class Driver",
+        PrinterDriver::Model(model) => model.as_str(),
+        PrinterDriver::PpdFile(_) => return Err("ppd files are not supported on windows"),
+    };
+
+    let mut printer_name = str_to_wide_string(&printer.name);
+    let mut port_name = str_to_wide_string(&printer.device_uri);
+    let mut driver_name = str_to_wide_string(driver_name);
+    let mut comment = str_to_wide_string(printer.info.as_deref().unwrap_or_default());
+    let mut location = str_to_wide_string(printer.location.as_deref().unwrap_or_default());
+    let mut print_processor = str_to_wide_string("WinPrint");
+    let mut data_type = str_to_wide_string("RAW");
+
+    let mut printer_info = PRINTER_INFO_2W {
+        pServerName: ptr::null_mut(),
+        pPrinterName: printer_name.as_mut_ptr() as *mut wchar_t,
+        pShareName: if printer.is_shared { printer_name.as_mut_ptr() as *mut wchar_t } else { ptr::null_mut() },
+        pPortName: port_name.as_mut_ptr() as *mut wchar_t,
+        pDriverName: driver_name.as_mut_ptr() as *mut wchar_t,
+        pComment: comment.as_mut_ptr() as *mut wchar_t,
+        pLocation: location.as_mut_ptr() as *mut wchar_t,
+        pDevMode: ptr::null_mut(),
+        pSepFile: ptr::null_mut(),
+        pPrintProcessor: print_processor.as_mut_ptr() as *mut wchar_t,
+        pDatatype: data_type.as_mut_ptr() as *mut wchar_t,
+        pParameters: ptr::null_mut(),
+        pSecurityDescriptor: ptr::null_mut(),
+        Attributes: if printer.is_shared { PRINTER_ATTRIBUTE_SHARED } else { 0 },
+        Priority: 1,
+        DefaultPriority: 0,
+        StartTime: 0,
+        UntilTime: 0,
+        Status: 0,
+        cJobs: 0,
+        AveragePPM: 0,
+    };
+
+    return unsafe {
+        let printer_handle = AddPrinterW(ptr::null_mut(), 2, &mut printer_info);
+        if printer_handle.is_null() {
+            Err("AddPrinterW failed")
+        } else {
+            ClosePrinter(printer_handle);
+            Ok(())
+        }
+    };
+}
+
 /**
  * Free winspool printer memory
  */
 pub fn free(printers: &'static [PRINTER_INFO_2W]) {
-    if printers.len() > 0 {
+    if !printers.is_empty() {
         dealloc_s::<PRINTER_INFO_2W>(printers.as_ptr());
     }
 }
@@ -1,19 +1,26 @@
-#![allow(non_snake_case)]
+#![allow(non_snake_case, clippy::upper_case_acronyms)]
 
-use libc::{c_int, c_ulong, c_ushort, c_void, wchar_t};
-use std::{ptr, slice};
+use libc::{c_int, c_ulong, c_ulonglong, c_ushort, c_void, wchar_t};
+use std::{io::Read, ptr, slice, time::Duration};
 
 use crate::{
-    common::traits::platform::PlatformPrinterJobGetters,
+    common::{
+        base::{
+            attributes::{AttributeValue, Attributes},
+            job::{JobDocument, JobId, JobProgress, PrinterJob},
+            printer::{DocumentFormat, PrintOptions},
+        },
+        traits::platform::PlatformPrinterJobGetters,
+    },
+    windows::winspool::devmode::get_devmode,
     windows::utils::{
         date::{calculate_system_time, get_current_epoch},
-        memory::alloc_s,
         strings::{str_to_wide_string, wchar_t_to_string},
     },
 };
 
 #[link(name = "winspool")]
-extern "system" {
+unsafe extern "system" {
     fn OpenPrinterW(
         pPrinterName: *const wchar_t,
         phPrinter: *mut *mut c_void,
@@ -25,15 +32,45 @@ extern "system" {
         pDocInfo: *const DocInfo1,
     ) -> c_ulong;
     fn StartPagePrinter(hPrinter: *mut c_void) -> c_int;
+    fn GetPrinterDriverW(
+        hPrinter: *mut c_void,
+        pEnvironment: *const wchar_t,
+        Level: c_ulong,
+        pDriverInfo: *mut c_void,
+        cbBuf: c_ulong,
+        pcbNeeded: *mut c_ulong,
+    ) -> c_int;
     fn WritePrinter(
         hPrinter: *mut c_void,
         pBuf: *mut c_void,
         cbBuf: c_ulong,
         pcWritten: *mut c_ulong,
     ) -> c_int;
+    fn ReadPrinter(
+        hPrinter: *mut c_void,
+        pBuf: *mut c_void,
+        cbBuf: c_ulong,
+        pNoBytesRead: *mut c_ulong,
+    ) -> c_int;
     fn EndPagePrinter(hPrinter: *mut c_void) -> c_int;
     fn EndDocPrinter(hPrinter: *mut c_void) -> c_int;
+    fn AbortDocPrinter(hPrinter: *mut c_void) -> c_int;
     fn ClosePrinter(hPrinter: *mut c_void) -> c_int;
+    fn GetJobW(
+        hPrinter: *mut c_void,
+        JobId: c_ulong,
+        Level: c_ulong,
+        pJob: *mut c_void,
+        cbBuf: c_ulong,
+        pcbNeeded: *mut c_ulong,
+    ) -> c_int;
+    fn SetJobW(
+        hPrinter: *mut c_void,
+        JobId: c_ulong,
+        Level: c_ulong,
+        pJob: *mut c_void,
+        Command: c_ulong,
+    ) -> c_int;
     fn EnumJobsW(
         hPrinter: *mut c_void,
         firstJob: c_ulong,
@@ -53,6 +90,63 @@ struct PrinterDefaultW {
     DesiredAccess: c_ulong,
 }
 
+const PRINTER_ACCESS_USE: c_ulong = 0x00000008;
+
+// Highest priority accepted by the spooler (MAX_PRIORITY)
+const MAX_PRIORITY: c_ulong = 99;
+// Keep the job position when updating JOB_INFO_1W
+const JOB_POSITION_UNSPECIFIED: c_ulong = 0;
+
+pub const JOB_CONTROL_PAUSE: c_ulong = 1;
+pub const JOB_CONTROL_RESUME: c_ulong = 2;
+pub const JOB_CONTROL_RESTART: c_ulong = 4;
+// JOB_CONTROL_CANCEL is documented as obsolete, JOB_CONTROL_DELETE replaces it
+pub const JOB_CONTROL_DELETE: c_ulong = 5;
+
+#[repr(C)]
+struct FILETIME {
+    dwLowDateTime: c_ulong,
+    dwHighDateTime: c_ulong,
+}
+
+/**
+ * The DRIVER_INFO_8 structure contains all printer driver information.
+ * https://learn.microsoft.com/en-us/windows/win32/printdocs/driver-info-8
+ */
+#[repr(C)]
+struct DriverInfo8W {
+    cVersion: c_ulong,
+    pName: *mut wchar_t,
+    pEnvironment: *mut wchar_t,
+    pDriverPath: *mut wchar_t,
+    pDataFile: *mut wchar_t,
+    pConfigFile: *mut wchar_t,
+    pHelpFile: *mut wchar_t,
+    pDependentFiles: *mut wchar_t,
+    pMonitorName: *mut wchar_t,
+    pDefaultDataType: *mut wchar_t,
+    pszzPreviousNames: *mut wchar_t,
+    ftDriverDate: FILETIME,
+    dwlDriverVersion: c_ulonglong,
+    pszMfgName: *mut wchar_t,
+    pszOEMUrl: *mut wchar_t,
+    pszHardwareID: *mut wchar_t,
+    pszProvider: *mut wchar_t,
+    pszPrintProcessor: *mut wchar_t,
+    pszVendorSetup: *mut wchar_t,
+    pszzColorProfiles: *mut wchar_t,
+    pszInfPath: *mut wchar_t,
+    dwPrinterDriverAttributes: c_ulong,
+    pszzCoreDriverDependencies: *mut wchar_t,
+    ftMinInboxDriverVerDate: FILETIME,
+    dwlMinInboxDriverVerVersion: c_ulonglong,
+}
+
+// Driver version of the XPS based v4 drivers
+const DRIVER_VERSION_4: c_ulong = 4;
+// Set for the v3 drivers that also render XPS (XPSDrv)
+const PRINTER_DRIVER_XPS: c_ulong = 0x00000002;
+
 #[repr(C)]
 struct DocInfo1 {
     pDocName: *mut wchar_t,
@@ -99,7 +193,77 @@ pub struct JOB_INFO_1W {
     Submitted: SYSTEMTIME,
 }
 
-impl PlatformPrinterJobGetters for JOB_INFO_1W {
+/**
+ * The JOB_INFO_2 structure specifies detailed print-job information.
+ * https://learn.microsoft.com/en-us/windows/win32/printdocs/job-info-2
+ */
+#[derive(Debug, Clone)]
+#[repr(C)]
+pub struct JOB_INFO_2W {
+    JobId: c_ulong,
+    pPrinterName: *mut wchar_t,
+    pMachineName: *mut wchar_t,
+    pUserName: *mut wchar_t,
+    pDocument: *mut wchar_t,
+    pNotifyName: *mut wchar_t,
+    pDatatype: *mut wchar_t,
+    pPrintProcessor: *mut wchar_t,
+    pParameters: *mut wchar_t,
+    pDriverName: *mut wchar_t,
+    pDevMode: *mut c_void,
+    pStatus: *mut wchar_t,
+    pSecurityDescriptor: *mut c_void,
+    Status: c_ulong,
+    Priority: c_ulong,
+    Position: c_ulong,
+    StartTime: c_ulong,
+    UntilTime: c_ulong,
+    TotalPages: c_ulong,
+    Size: c_ulong,
+    Submitted: SYSTEMTIME,
+    Time: c_ulong,
+    PagesPrinted: c_ulong,
+}
+
+const JOB_STATUS_PRINTING: c_ulong = 0x00000010;
+
+// JOB_STATUS_* flags of JOB_INFO_1W and JOB_INFO_2W
+const JOB_STATUS_REASONS: [(c_ulong, &str); 15] = [
+    (0x00000001, "paused"),
+    (0x00000002, "error"),
+    (0x00000004, "deleting"),
+    (0x00000008, "spooling"),
+    (0x00000010, "printing"),
+    (0x00000020, "offline"),
+    (0x00000040, "paper-out"),
+    (0x00000080, "printed"),
+    (0x00000100, "deleted"),
+    (0x00000200, "blocked-devq"),
+    (0x00000400, "user-intervention"),
+    (0x00000800, "restart"),
+    (0x00001000, "complete"),
+    (0x00002000, "retained"),
+    (0x00004000, "rendering-locally"),
+];
+
+/**
+ * Name the JOB_STATUS flags of a job, followed by the status text of the driver
+ */
+fn status_reasons(status: c_ulong, status_text: *mut wchar_t) -> Vec<String> {
+    let mut reasons: Vec<String> = JOB_STATUS_REASONS
+        .iter()
+        .filter(|(flag, _)| status & flag != 0)
+        .map(|(_, reason)| reason.to_string())
+        .collect();
+
+    let status_text = wchar_t_to_string(status_text);
+    if !status_text.is_empty() {
+        reasons.push(status_text);
+    }
+    return reasons;
+}
+
+impl PlatformPrinterJobGetters for JOB_INFO_2W {
     fn get_id(&self) -> u64 {
         return self.JobId.into();
     }
@@ -112,14 +276,31 @@ impl PlatformPrinterJobGetters for JOB_INFO_1W {
         return self.Status.into();
     }
 
+    fn get_state_reasons(&self) -> Vec<String> {
+        return status_reasons(self.Status, self.pStatus);
+    }
+
     fn get_printer(&self) -> String {
         return wchar_t_to_string(self.pPrinterName);
     }
 
+    fn get_user(&self) -> String {
+        return wchar_t_to_string(self.pUserName);
+    }
+
     fn get_media_type(&self) -> String {
         return wchar_t_to_string(self.pDatatype);
     }
 
+    fn get_pages_completed(&self) -> Option<u32> {
+        return Some(self.PagesPrinted);
+    }
+
+    fn get_pages_total(&self) -> Option<u32> {
+        // The spooler reports 0 while the pages are not counted yet
+        return if self.TotalPages == 0 { None } else { Some(self.TotalPages) };
+    }
+
     fn get_created_at(&self) -> std::time::SystemTime {
         return calculate_system_time(
             self.Submitted.wYear,
@@ -133,23 +314,45 @@ impl PlatformPrinterJobGetters for JOB_INFO_1W {
     }
 
     fn get_processed_at(&self) -> Option<std::time::SystemTime> {
-        return Some(self.get_created_at());
+        // Time holds the milliseconds elapsed since the job started printing, it only dates the
+        // start while the job prints, StartTime is the start of the allowed printing window
+        if self.Status & JOB_STATUS_PRINTING != 0 {
+            return std::time::SystemTime::now().checked_sub(Duration::from_millis(self.Time.into()));
+        }
+        return None;
     }
 
     fn get_completed_at(&self) -> Option<std::time::SystemTime> {
-        return Some(self.get_created_at());
+        // The spooler keeps no completion time
+        return None;
     }
 }
 
+const WRITE_CHUNK_SIZE: usize = 64 * 1024;
+
 /**
- * Print a buffer as RAW datatype with winspool WritePrinter
+ * Stream the reader data untouched by the driver with winspool WritePrinter, returning the id of the created job
  */
-pub fn print_buffer(
+pub fn print_reader(
     printer_system_name: &str,
     job_name: Option<&str>,
-    buffer: &[u8],
-) -> Result<(), &'static str> {
-    unsafe {
+    reader: &mut dyn Read,
+    options: &PrintOptions,
+) -> Result<JobId, &'static str> {
+    return print_readers(printer_system_name, job_name, &mut [reader], options);
+}
+
+/**
+ * Stream the data of all readers, one after another, as a single RAW job, returning the id of
+ * the created job. The job is deleted when a reader or WritePrinter fails
+ */
+pub fn print_readers(
+    printer_system_name: &str,
+    job_name: Option<&str>,
+    readers: &mut [&mut dyn Read],
+    options: &PrintOptions,
+) -> Result<JobId, &'static str> {
+    return unsafe {
         let printer_name = str_to_wide_string(printer_system_name);
         let mut printer_handle: *mut c_void = ptr::null_mut();
 
@@ -162,8 +365,30 @@ pub fn print_buffer(
             return Err("OpenPrinterW failed");
         }
 
+        let mut pDatatype = str_to_wide_string(datatype(printer_handle, options));
+
+        // Reopen the printer with the options merged into its DEVMODE, so the job inherits them,
+        // the driver only applies them to TEXT and XPS data, RAW data skips it
+        if let Some(mut devmode) = get_devmode(printer_handle, printer_system_name, options) {
+            ClosePrinter(printer_handle);
+
+            let mut defaults = PrinterDefaultW {
+                pDatatype: pDatatype.as_mut_ptr() as *mut wchar_t,
+                pDevMode: devmode.as_mut_ptr() as *mut c_void,
+                DesiredAccess: PRINTER_ACCESS_USE,
+            };
+
+            if OpenPrinterW(
+                printer_name.as_ptr() as *const wchar_t,
+                &mut printer_handle,
+                &mut defaults,
+            ) == 0
+            {
+                return Err("OpenPrinterW failed");
+            }
+        }
+
         let mut pDocName = str_to_wide_string(job_name.unwrap_or(get_current_epoch().to_string().as_str()));
-        let mut pDatatype = str_to_wide_string("RAW");
 
         let doc_info = DocInfo1 {
             pDocName: pDocName.as_mut_ptr() as *mut wchar_t,
@@ -171,43 +396,117 @@ pub fn print_buffer(
             pOutputFile: ptr::null_mut(),
         };
 
-        if StartDocPrinterW(printer_handle, 1, &doc_info) == 0 {
+        let job_id = StartDocPrinterW(printer_handle, 1, &doc_info);
+        if job_id == 0 {
             ClosePrinter(printer_handle);
             return Err("StartDocPrinterW failed");
         }
 
         if StartPagePrinter(printer_handle) == 0 {
-            EndDocPrinter(printer_handle);
+            AbortDocPrinter(printer_handle);
             ClosePrinter(printer_handle);
             return Err("StartPagePrinter failed");
         }
 
-        let mut bytes_written: c_ulong = 0;
-        let write_result = WritePrinter(
-            printer_handle,
-            buffer.as_ptr() as *mut c_void,
-            buffer.len() as c_ulong,
-            &mut bytes_written,
-        );
+        let mut write_result = Ok(job_id as JobId);
+        for reader in readers.iter_mut() {
+            if let Err(e) = write_reader(printer_handle, *reader) {
+                write_result = Err(e);
+                break;
+            }
+        }
 
-        EndPagePrinter(printer_handle);
-        EndDocPrinter(printer_handle);
+        // A job that could not be written completely is deleted instead of printing truncated
+        if write_result.is_ok() {
+            EndPagePrinter(printer_handle);
+            EndDocPrinter(printer_handle);
+        } else {
+            AbortDocPrinter(printer_handle);
+        }
         ClosePrinter(printer_handle);
 
-        if write_result == 0 {
-            return Err("WritePrinter failed");
-        }
+        write_result
+    };
+}
+
+/**
+ * Return the version and the attributes of the driver of the printer
+ */
+fn driver_info(printer_handle: *mut c_void) -> Option<(c_ulong, c_ulong)> {
+    let mut bytes_needed: c_ulong = 0;
+    unsafe { GetPrinterDriverW(printer_handle, ptr::null(), 8, ptr::null_mut(), 0, &mut bytes_needed) };
+    if (bytes_needed as usize) < size_of::<DriverInfo8W>() {
+        return None;
     }
 
-    return Ok(());
+    let mut buffer: Vec<u64> = vec![0; (bytes_needed as usize).div_ceil(size_of::<u64>())];
+    if unsafe { GetPrinterDriverW(printer_handle, ptr::null(), 8, buffer.as_mut_ptr() as *mut c_void, bytes_needed, &mut bytes_needed) } == 0 {
+        return None;
+    }
+
+    let info = unsafe { &*(buffer.as_ptr() as *const DriverInfo8W) };
+    return Some((info.cVersion, info.dwPrinterDriverAttributes));
 }
 
 /**
- * Retrive print jobs of specific printer with EnumJobsW
+ * Datatype of the job, plain text is laid out by the print processor (TEXT), XPS documents
+ * are passed unconverted to the XPS filter pipeline of the XPSDrv and v4 drivers, which renders
+ * them for the printer (XPS_PASS), and are converted to GDI calls for the GDI drivers (XPS2GDI), the
+ * other documents reach the port untouched by the driver (RAW). The filters of v4 drivers only
+ * render XPS, so on their queues the RAW data also skips them and must already be in a language
+ * the printer understands
  */
-pub fn enum_printer_jobs(
-    printer_system_name: &str,
-) -> Result<&'static [JOB_INFO_1W], &'static str> {
+fn datatype(printer_handle: *mut c_void, options: &PrintOptions) -> &'static str {
+    let (version, attributes) = driver_info(printer_handle).unwrap_or_default();
+    let v4_driver = version == DRIVER_VERSION_4;
+
+    return match options.document_format {
+        Some(DocumentFormat::PlainText) => "TEXT",
+        Some(DocumentFormat::Xps) if v4_driver || attributes & PRINTER_DRIVER_XPS != 0 => "XPS_PASS",
+        Some(DocumentFormat::Xps) => "XPS2GDI",
+        _ => "RAW",
+    };
+}
+
+/**
+ * Write all the reader data to an started document with WritePrinter
+ */
+fn write_reader(printer_handle: *mut c_void, reader: &mut dyn Read) -> Result<(), &'static str> {
+    let mut chunk = vec![0u8; WRITE_CHUNK_SIZE];
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => return Ok(()),
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => return Err("failed to read print data"),
+        };
+
+        // WritePrinter may accept only part of the chunk
+        let mut offset = 0;
+        while offset < read {
+            let mut bytes_written: c_ulong = 0;
+            if unsafe {
+                WritePrinter(
+                    printer_handle,
+                    chunk[offset..].as_ptr() as *mut c_void,
+                    (read - offset) as c_ulong,
+                    &mut bytes_written,
+                )
+            } == 0
+                || bytes_written == 0
+            {
+                return Err("WritePrinter failed");
+            }
+            offset += bytes_written as usize;
+        }
+    }
+}
+
+/**
+ * Retrive print jobs of specific printer with EnumJobsW, copied into owned values so the
+ * EnumJobsW buffer is released before returning
+ */
+pub fn enum_printer_jobs(printer_system_name: &str) -> Result<Vec<PrinterJob>, &'static str> {
     let printer_name = str_to_wide_string(printer_system_name);
     let mut printer_handle: *mut c_void = ptr::null_mut();
 
@@ -223,7 +522,8 @@ pub fn enum_printer_jobs(
     }
 
     let mut enum_result = 0;
-    let mut buffer_ptr: *mut JOB_INFO_1W = ptr::null_mut();
+    // The strings of JOB_INFO_2W are stored after the structs, u64 keeps the buffer aligned
+    let mut buffer: Vec<u64> = vec![];
     let mut jobs_count: c_ulong = 0;
     let mut bytes_needed: c_ulong = 0;
 
@@ -233,9 +533,9 @@ pub fn enum_printer_jobs(
                 printer_handle,
                 0,
                 0xFFFFFFFF,
-                1,
-                buffer_ptr as *mut c_void,
-                bytes_needed,
+                2,
+                if buffer.is_empty() { ptr::null_mut() } else { buffer.as_mut_ptr() as *mut c_void },
+                (buffer.len() * 8) as c_ulong,
                 &mut bytes_needed,
                 &mut jobs_count,
             )
@@ -245,7 +545,7 @@ pub fn enum_printer_jobs(
             break;
         }
 
-        buffer_ptr = alloc_s::<JOB_INFO_1W>(bytes_needed);
+        buffer = vec![0u64; (bytes_needed as usize).div_ceil(8)];
     }
 
     unsafe { ClosePrinter(printer_handle) };
@@ -254,9 +554,200 @@ pub fn enum_printer_jobs(
         return Err("EnumJobsW failed");
     }
 
-    return Ok(if jobs_count > 0 {
-        unsafe { slice::from_raw_parts(buffer_ptr as *const JOB_INFO_1W, jobs_count as usize) }
-    } else {
-        &[]
-    });
+    if jobs_count == 0 {
+        return Ok(vec![]);
+    }
+
+    let jobs = unsafe { slice::from_raw_parts(buffer.as_ptr() as *const JOB_INFO_2W, jobs_count as usize) };
+    return Ok(jobs
+        .iter()
+        .map(|j| PrinterJob::from_platform_printer_job_getters(j))
+        .collect());
+}
+
+/**
+ * Send a JOB_CONTROL_* command to a job of the printer with SetJobW
+ */
+pub fn set_job(printer_system_name: &str, job_id: JobId, command: c_ulong) -> Result<(), &'static str> {
+    let printer_name = str_to_wide_string(printer_system_name);
+    let mut printer_handle: *mut c_void = ptr::null_mut();
+
+    return unsafe {
+        if OpenPrinterW(printer_name.as_ptr() as *const wchar_t, &mut printer_handle, ptr::null_mut()) == 0 {
+            return Err("OpenPrinterW failed");
+        }
+
+        let result = SetJobW(printer_handle, job_id as c_ulong, 0, ptr::null_mut(), command);
+        ClosePrinter(printer_handle);
+
+        if result == 0 { Err("SetJobW failed") } else { Ok(()) }
+    };
+}
+
+/**
+ * Read the JOB_INFO_1W of a job with GetJobW, the u64 buffer keeps the struct aligned and
+ * also holds the strings it points to
+ */
+unsafe fn get_job(printer_handle: *mut c_void, job_id: JobId) -> Option<Vec<u64>> {
+    let mut bytes_needed: c_ulong = 0;
+    unsafe { GetJobW(printer_handle, job_id as c_ulong, 1, ptr::null_mut(), 0, &mut bytes_needed) };
+
+    let mut buffer = vec![0u64; (bytes_needed as usize).max(size_of::<JOB_INFO_1W>()).div_ceil(8)];
+    let result = unsafe {
+        GetJobW(
+            printer_handle,
+            job_id as c_ulong,
+            1,
+            buffer.as_mut_ptr() as *mut c_void,
+            (buffer.len() * 8) as c_ulong,
+            &mut bytes_needed,
+        )
+    };
+
+    return if result == 0 { None } else { Some(buffer) };
+}
+
+/**
+ * Change the priority of a job with GetJobW and SetJobW, IPP priorities above 99 are clamped
+ */
+pub fn set_job_priority(printer_system_name: &str, job_id: JobId, priority: u8) -> Result<(), &'static str> {
+    let printer_name = str_to_wide_string(printer_system_name);
+    let mut printer_handle: *mut c_void = ptr::null_mut();
+
+    return unsafe {
+        if OpenPrinterW(printer_name.as_ptr() as *const wchar_t, &mut printer_handle, ptr::null_mut()) == 0 {
+            return Err("OpenPrinterW failed");
+        }
+
+        let Some(mut buffer) = get_job(printer_handle, job_id) else {
+            ClosePrinter(printer_handle);
+            return Err("GetJobW failed");
+        };
+
+        let job_info = &mut *(buffer.as_mut_ptr() as *mut JOB_INFO_1W);
+        job_info.Priority = (priority as c_ulong).clamp(1, MAX_PRIORITY);
+        job_info.Position = JOB_POSITION_UNSPECIFIED;
+
+        let result = SetJobW(printer_handle, job_id as c_ulong, 1, buffer.as_mut_ptr() as *mut c_void, 0);
+        ClosePrinter(printer_handle);
+
+        if result == 0 { Err("SetJobW failed") } else { Ok(()) }
+    };
+}
+
+/**
+ * Read the printed and total pages of a job, the spooler reports 0 total pages when unknown
+ */
+pub fn get_job_progress(printer_system_name: &str, job_id: JobId) -> Result<JobProgress, &'static str> {
+    let printer_name = str_to_wide_string(printer_system_name);
+    let mut printer_handle: *mut c_void = ptr::null_mut();
+
+    return unsafe {
+        if OpenPrinterW(printer_name.as_ptr() as *const wchar_t, &mut printer_handle, ptr::null_mut()) == 0 {
+            return Err("OpenPrinterW failed");
+        }
+
+        let buffer = get_job(printer_handle, job_id);
+        ClosePrinter(printer_handle);
+
+        let Some(buffer) = buffer else {
+            return Err("GetJobW failed");
+        };
+
+        let job_info = &*(buffer.as_ptr() as *const JOB_INFO_1W);
+        Ok(JobProgress {
+            pages_completed: Some(job_info.PagesPrinted),
+            pages_total: if job_info.TotalPages == 0 { None } else { Some(job_info.TotalPages) },
+            sheets_completed: None,
+            sheets_total: None,
+        })
+    };
+}
+
+/**
+ * Read a job with GetJobW and name its JOB_INFO_1W fields after the IPP job attributes
+ */
+pub fn get_job_attributes(printer_system_name: &str, job_id: JobId) -> Result<Attributes, &'static str> {
+    let printer_name = str_to_wide_string(printer_system_name);
+    let mut printer_handle: *mut c_void = ptr::null_mut();
+
+    let buffer = unsafe {
+        if OpenPrinterW(printer_name.as_ptr() as *const wchar_t, &mut printer_handle, ptr::null_mut()) == 0 {
+            return Err("OpenPrinterW failed");
+        }
+
+        let buffer = get_job(printer_handle, job_id);
+        ClosePrinter(printer_handle);
+        buffer.ok_or("GetJobW failed")?
+    };
+
+    let job_info = unsafe { &*(buffer.as_ptr() as *const JOB_INFO_1W) };
+    let string = |value: *mut wchar_t| vec![AttributeValue::String(wchar_t_to_string(value))];
+    let integer = |value: c_ulong| vec![AttributeValue::Integer(value as i32)];
+
+    let mut attributes = Attributes::new();
+    attributes.insert("job-id".to_string(), integer(job_info.JobId));
+    attributes.insert("job-name".to_string(), string(job_info.pDocument));
+    attributes.insert("job-printer-name".to_string(), string(job_info.pPrinterName));
+    attributes.insert("job-originating-host-name".to_string(), string(job_info.pMachineName));
+    attributes.insert("job-originating-user-name".to_string(), string(job_info.pUserName));
+    attributes.insert("document-format".to_string(), string(job_info.pDatatype));
+    attributes.insert("job-state".to_string(), integer(job_info.Status));
+    attributes.insert("job-state-message".to_string(), string(job_info.pStatus));
+    attributes.insert(
+        "job-state-reasons".to_string(),
+        status_reasons(job_info.Status, job_info.pStatus).into_iter().map(AttributeValue::String).collect(),
+    );
+    attributes.insert("job-priority".to_string(), integer(job_info.Priority));
+    attributes.insert("number-of-intervening-jobs".to_string(), integer(job_info.Position.saturating_sub(1)));
+    attributes.insert("job-impressions".to_string(), integer(job_info.TotalPages));
+    attributes.insert("job-impressions-completed".to_string(), integer(job_info.PagesPrinted));
+    return Ok(attributes);
+}
+
+/**
+ * Read the spool file of a job with ReadPrinter, opening the "<printer>,Job <id>" handle
+ */
+pub fn read_job_document(printer_system_name: &str, job_id: JobId) -> Result<JobDocument, &'static str> {
+    let printer_name = str_to_wide_string(printer_system_name);
+    let job_name = str_to_wide_string(&format!("{},Job {}", printer_system_name, job_id));
+    let mut printer_handle: *mut c_void = ptr::null_mut();
+    let mut job_handle: *mut c_void = ptr::null_mut();
+
+    return unsafe {
+        if OpenPrinterW(printer_name.as_ptr() as *const wchar_t, &mut printer_handle, ptr::null_mut()) == 0 {
+            return Err("OpenPrinterW failed");
+        }
+
+        let buffer = get_job(printer_handle, job_id);
+        ClosePrinter(printer_handle);
+        let buffer = buffer.ok_or("GetJobW failed")?;
+        let job_info = &*(buffer.as_ptr() as *const JOB_INFO_1W);
+
+        if OpenPrinterW(job_name.as_ptr() as *const wchar_t, &mut job_handle, ptr::null_mut()) == 0 {
+            return Err("OpenPrinterW failed");
+        }
+
+        let mut data = vec![];
+        let mut chunk = vec![0u8; WRITE_CHUNK_SIZE];
+        loop {
+            let mut bytes_read: c_ulong = 0;
+            if ReadPrinter(job_handle, chunk.as_mut_ptr() as *mut c_void, chunk.len() as c_ulong, &mut bytes_read) == 0 {
+                ClosePrinter(job_handle);
+                return Err("ReadPrinter failed");
+            }
+
+            if bytes_read == 0 {
+                break;
+            }
+            data.extend_from_slice(&chunk[..bytes_read as usize]);
+        }
+
+        ClosePrinter(job_handle);
+        Ok(JobDocument {
+            name: wchar_t_to_string(job_info.pDocument),
+            format: wchar_t_to_string(job_info.pDatatype),
+            data,
+        })
+    };
 }
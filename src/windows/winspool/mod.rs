@@ -1,2 +1,7 @@
+pub mod capabilities;
+pub mod control;
+pub mod devmode;
+pub mod gdi;
 pub mod info;
-pub mod jobs;
\ No newline at end of file
+pub mod jobs;
+pub mod notifications;
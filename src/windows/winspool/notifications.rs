@@ -0,0 +1,96 @@
+#![allow(non_snake_case)]
+
+use libc::{c_int, c_ulong, c_void};
+use std::{ptr, sync::mpsc, thread};
+
+use crate::{
+    common::base::event::{EventSender, Snapshot},
+    windows::winspool::control::{open_printer, PRINTER_ACCESS_USE},
+};
+
+#[link(name = "winspool")]
+unsafe extern "system" {
+    fn FindFirstPrinterChangeNotification(
+        hPrinter: *mut c_void,
+        fdwFilter: c_ulong,
+        fdwOptions: c_ulong,
+        pPrinterNotifyOptions: *mut c_void,
+    ) -> *mut c_void;
+    fn FindNextPrinterChangeNotification(
+        hChange: *mut c_void,
+        pdwChange: *mut c_ulong,
+        pvReserved: *mut c_void,
+        ppPrinterNotifyInfo: *mut *mut c_void,
+    ) -> c_int;
+    fn FindClosePrinterChangeNotification(hChange: *mut c_void) -> c_int;
+    fn ClosePrinter(hPrinter: *mut c_void) -> c_int;
+}
+
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn WaitForSingleObject(hHandle: *mut c_void, dwMilliseconds: c_ulong) -> c_ulong;
+}
+
+const PRINTER_CHANGE_PRINTER: c_ulong = 0x000000FF;
+const PRINTER_CHANGE_JOB: c_ulong = 0x0000FF00;
+
+const WAIT_OBJECT_0: c_ulong = 0;
+const WAIT_TIMEOUT: c_ulong = 0x00000102;
+const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+
+// How long a wait lasts before checking if the stream was dropped
+const WAIT_TIMEOUT_MILLISECONDS: c_ulong = 500;
+
+/**
+ * Wait for print server changes and deliver them as events until the stream is dropped, the
+ * notifications only tell what kind of object changed so the printers and jobs are compared
+ * with their last known state. The sender is given back when the notifications are not available
+ */
+pub fn watch(sender: EventSender) -> Result<(), EventSender> {
+    let (ready_sender, ready) = mpsc::channel();
+
+    // Handles are not sent across threads, they live on the listener thread
+    thread::spawn(move || {
+        let server_handle = match open_printer("", PRINTER_ACCESS_USE) {
+            Ok(server_handle) => server_handle,
+            Err(_) => return ready_sender.send(Err(sender)).unwrap_or_default(),
+        };
+
+        let change_handle = unsafe {
+            FindFirstPrinterChangeNotification(server_handle, PRINTER_CHANGE_PRINTER | PRINTER_CHANGE_JOB, 0, ptr::null_mut())
+        };
+        if change_handle == INVALID_HANDLE_VALUE {
+            unsafe { ClosePrinter(server_handle) };
+            return ready_sender.send(Err(sender)).unwrap_or_default();
+        }
+
+        let mut snapshot = Snapshot::default();
+        snapshot.refresh(true);
+        ready_sender.send(Ok(())).unwrap_or_default();
+
+        while sender.is_running() {
+            match unsafe { WaitForSingleObject(change_handle, WAIT_TIMEOUT_MILLISECONDS) } {
+                WAIT_TIMEOUT => continue,
+                WAIT_OBJECT_0 => {}
+                _ => break,
+            }
+
+            let mut change: c_ulong = 0;
+            if unsafe { FindNextPrinterChangeNotification(change_handle, &mut change, ptr::null_mut(), ptr::null_mut()) } == 0 {
+                break;
+            }
+
+            let events = snapshot.refresh(change & PRINTER_CHANGE_JOB != 0);
+            if !events.into_iter().all(|event| sender.send(event)) {
+                break;
+            }
+        }
+
+        unsafe {
+            FindClosePrinterChangeNotification(change_handle);
+            ClosePrinter(server_handle);
+        }
+    });
+
+    return ready.recv().unwrap_or(Ok(()));
+}